@@ -0,0 +1,2 @@
+ALTER TABLE users ADD COLUMN role CHARACTER VARYING(32) NOT NULL DEFAULT 'user';
+ALTER TABLE users ADD CONSTRAINT users_role_check CHECK (role IN ('user', 'admin'));
@@ -0,0 +1,35 @@
+ALTER TABLE users ADD COLUMN email_normalized CHARACTER VARYING(255) NULL;
+
+-- Must match EmailAddress::normalized: the whole address is lowercased, and Gmail addresses also
+-- lose the dots and anything after a `+` in the local part, with googlemail.com becoming gmail.com
+UPDATE users
+SET email_normalized = CASE
+    WHEN SUBSTRING(LOWER(email) FROM '@([^@]*)$') IN ('gmail.com', 'googlemail.com')
+    THEN REPLACE(SPLIT_PART(SUBSTRING(LOWER(email) FROM '^(.*)@[^@]*$'), '+', 1), '.', '')
+        || '@gmail.com'
+    ELSE LOWER(email)
+END;
+
+-- Existing users whose addresses normalize the same share a mailbox, and which of them to keep
+-- can't be decided here, so they have to be merged by hand before the constraint can be added
+DO $$
+DECLARE
+    duplicates TEXT;
+BEGIN
+    SELECT STRING_AGG(email_normalized, ', ' ORDER BY email_normalized)
+    INTO duplicates
+    FROM (
+        SELECT email_normalized
+        FROM users
+        GROUP BY email_normalized
+        HAVING COUNT(*) > 1
+    ) AS shared;
+
+    IF duplicates IS NOT NULL THEN
+        RAISE EXCEPTION 'Users share the normalized email addresses %, merge them before migrating',
+            duplicates;
+    END IF;
+END $$;
+
+ALTER TABLE users ALTER COLUMN email_normalized SET NOT NULL;
+ALTER TABLE users ADD CONSTRAINT users_email_normalized_key UNIQUE (email_normalized);
@@ -0,0 +1,4 @@
+ALTER TABLE users ADD COLUMN email_confirmation_expires_at TIMESTAMP WITH TIME ZONE NULL;
+UPDATE users
+SET email_confirmation_expires_at = email_confirmation_sent_at + INTERVAL '24 hours'
+WHERE email_confirmation_sent_at IS NOT NULL;
@@ -0,0 +1,2 @@
+ALTER TABLE users ADD COLUMN username CHARACTER VARYING(32) NULL;
+ALTER TABLE users ADD CONSTRAINT users_username_key UNIQUE (username);
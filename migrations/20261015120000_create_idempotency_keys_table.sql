@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS idempotency_keys
+(
+    key CHARACTER VARYING(255) NOT NULL,
+    route CHARACTER VARYING(255) NOT NULL,
+    request_fingerprint CHARACTER VARYING(64) NOT NULL,
+    response_status SMALLINT NULL,
+    response_content_type CHARACTER VARYING(255) NULL,
+    response_body BYTEA NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (key, route)
+);
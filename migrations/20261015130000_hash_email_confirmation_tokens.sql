@@ -0,0 +1,7 @@
+UPDATE users
+SET email_confirmation_token = translate(
+    encode(sha256(convert_to(email_confirmation_token, 'UTF8')), 'base64'),
+    '+/',
+    '-_'
+)
+WHERE email_confirmation_token IS NOT NULL;
@@ -0,0 +1,2 @@
+ALTER TABLE users ADD COLUMN terms_accepted_at TIMESTAMP WITH TIME ZONE NULL;
+ALTER TABLE users ADD COLUMN terms_version TEXT NULL;
@@ -0,0 +1,22 @@
+CREATE TABLE IF NOT EXISTS audit_log
+(
+    id BIGINT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    actor_id UUID NULL,
+    event_type CHARACTER VARYING(64) NOT NULL,
+    ip INET NULL,
+    occurred_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE INDEX audit_log_actor_id_idx ON audit_log (actor_id, occurred_at);
+
+CREATE OR REPLACE FUNCTION prevent_audit_log_changes()
+RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_log is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_log_append_only
+BEFORE UPDATE OR DELETE ON audit_log
+FOR EACH ROW
+EXECUTE PROCEDURE prevent_audit_log_changes();
@@ -0,0 +1,3 @@
+ALTER TABLE audit_log ADD COLUMN subject_id UUID NULL;
+
+CREATE INDEX audit_log_subject_id_idx ON audit_log (subject_id, occurred_at);
@@ -0,0 +1,3 @@
+ALTER TABLE users
+    ADD COLUMN email_confirmation_requested_ip INET NULL,
+    ADD COLUMN email_confirmation_requested_user_agent TEXT NULL;
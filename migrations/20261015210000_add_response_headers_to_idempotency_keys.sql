@@ -0,0 +1,9 @@
+ALTER TABLE idempotency_keys ADD COLUMN response_headers TEXT[] NOT NULL DEFAULT '{}';
+
+UPDATE idempotency_keys
+SET response_headers = ARRAY['content-type: ' || response_content_type]
+WHERE response_content_type IS NOT NULL;
+
+ALTER TABLE idempotency_keys DROP COLUMN response_content_type;
+
+CREATE INDEX idempotency_keys_created_at_idx ON idempotency_keys (created_at);
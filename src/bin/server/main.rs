@@ -18,18 +18,59 @@ use chrono::Utc;
 use clap::Parser;
 use rust_saas_starter::{
     domain::{
-        auth::users::UserServiceImpl, communication::email_addresses::EmailAddressServiceImpl,
+        audit::AuditSubscriber,
+        auth::{
+            tokens::AuthConfig,
+            users::{
+                PasswordHashingConfig, PasswordPolicy, SignupConfig, SignupEmailPolicy,
+                UnconfirmedUserPurge, UnconfirmedUserPurgeConfig, UserBootstrapConfig,
+                UserServiceImpl,
+            },
+        },
+        background::BackgroundTasks,
+        clock::SystemClock,
+        communication::{
+            email_addresses::{
+                EmailAddressServiceImpl, EmailConfirmationConfig, WelcomeEmailSubscriber,
+            },
+            mailer::{EmailSenders, EmailTemplateConfig, EmailTemplates},
+            webhooks::WebhookSubscriber,
+        },
+        events::EventBus,
+        health::{HealthChecks, HealthReportCache},
+        idempotency::IdempotencyKeyPurge,
     },
     infrastructure::{
+        cache::users::{CachingUserRepository, UserCacheConfig},
+        config_file::parse_with_config_file,
         db::postgres::{DatabaseConnectionDetails, PostgresDatabase},
-        email::smtp::{SMTPConfig, SMTPMailer},
+        dns::EmailDeliverabilityConfig,
+        email::{
+            circuit_breaker::{CircuitBreakerConfig, CircuitBreakerMailer},
+            health::SmtpHealthCheck,
+            smtp::SMTPConfig,
+            EmailBackend, EmailBackendConfig,
+        },
         http::{
+            access_log::AccessLogConfig,
+            allowed_hosts::AllowedHostsConfig,
+            body_log::BodyLogConfig,
+            client_ip::TrustedProxiesConfig,
+            debug::DebugConfig,
+            header_limits::HeaderLimitsConfig,
+            rate_limit::RateLimitConfig,
+            security_headers::SecurityHeadersConfig,
             servers::{http::HttpServer, https::HttpsServer},
+            shutdown_signal,
             state::{AppConfig, AppState},
+            transport_security::TransportSecurityConfig,
             HttpServerConfig, Server,
         },
+        shutdown::ShutdownCoordinator,
+        webhooks::http::{HttpWebhookNotifier, WebhookConfig},
     },
 };
+use tracing::error;
 
 /// Command-line arguments / environment variables
 #[derive(Debug, Parser)]
@@ -38,6 +79,10 @@ pub struct Args {
     #[clap(flatten)]
     pub server: HttpServerConfig,
 
+    /// Allowed hosts configuration
+    #[clap(flatten)]
+    pub allowed_hosts: AllowedHostsConfig,
+
     /// The database connection details
     #[clap(flatten)]
     pub db: DatabaseConnectionDetails,
@@ -45,15 +90,135 @@ pub struct Args {
     /// SMTP server configuration
     #[clap(flatten)]
     pub smtp: SMTPConfig,
+
+    /// Email backend configuration
+    #[clap(flatten)]
+    pub email: EmailBackendConfig,
+
+    /// Email circuit breaker configuration
+    #[clap(flatten)]
+    pub email_circuit_breaker: CircuitBreakerConfig,
+
+    /// Password policy configuration
+    #[clap(flatten)]
+    pub password_policy: PasswordPolicy,
+
+    /// Password hashing configuration
+    #[clap(flatten)]
+    pub password_hashing: PasswordHashingConfig,
+
+    /// Access token configuration
+    #[clap(flatten)]
+    pub auth: AuthConfig,
+
+    /// User bootstrap configuration
+    #[clap(flatten)]
+    pub user_bootstrap: UserBootstrapConfig,
+
+    /// User cache configuration
+    #[clap(flatten)]
+    pub user_cache: UserCacheConfig,
+
+    /// Unconfirmed user purge configuration
+    #[clap(flatten)]
+    pub unconfirmed_user_purge: UnconfirmedUserPurgeConfig,
+
+    /// Signup configuration
+    #[clap(flatten)]
+    pub signup: SignupConfig,
+
+    /// Signup email domain configuration
+    #[clap(flatten)]
+    pub signup_email_policy: SignupEmailPolicy,
+
+    /// Email deliverability configuration
+    #[clap(flatten)]
+    pub email_deliverability: EmailDeliverabilityConfig,
+
+    /// Email confirmation configuration
+    #[clap(flatten)]
+    pub email_confirmation: EmailConfirmationConfig,
+
+    /// The addresses each kind of email is sent from
+    #[clap(flatten)]
+    pub email_senders: EmailSenders,
+
+    /// Overrides of the subjects and templates emails are rendered with
+    #[clap(flatten)]
+    pub email_templates: EmailTemplateConfig,
+
+    /// Request header limits
+    #[clap(flatten)]
+    pub header_limits: HeaderLimitsConfig,
+
+    /// Access log configuration
+    #[clap(flatten)]
+    pub access_log: AccessLogConfig,
+
+    /// Body logging configuration
+    #[clap(flatten)]
+    pub body_log: BodyLogConfig,
+
+    /// Rate limiting configuration
+    #[clap(flatten)]
+    pub rate_limit: RateLimitConfig,
+
+    /// Trusted proxy configuration
+    #[clap(flatten)]
+    pub trusted_proxies: TrustedProxiesConfig,
+
+    /// HTTPS redirect and HSTS configuration
+    #[clap(flatten)]
+    pub transport_security: TransportSecurityConfig,
+
+    /// Security headers configuration
+    #[clap(flatten)]
+    pub security_headers: SecurityHeadersConfig,
+
+    /// Debugging configuration
+    #[clap(flatten)]
+    pub debug: DebugConfig,
+
+    /// Webhook configuration
+    #[clap(flatten)]
+    pub webhooks: WebhookConfig,
 }
 
+/// The section of the config file each group of arguments is read from, and the struct they're
+/// flattened from
+const CONFIG_SECTIONS: &[(&str, &str)] = &[
+    ("server", "HttpServerConfig"),
+    ("allowed_hosts", "AllowedHostsConfig"),
+    ("db", "DatabaseConnectionDetails"),
+    ("smtp", "SMTPConfig"),
+    ("email", "EmailBackendConfig"),
+    ("email_circuit_breaker", "CircuitBreakerConfig"),
+    ("password_policy", "PasswordPolicy"),
+    ("password_hashing", "PasswordHashingConfig"),
+    ("auth", "AuthConfig"),
+    ("user_bootstrap", "UserBootstrapConfig"),
+    ("user_cache", "UserCacheConfig"),
+    ("unconfirmed_user_purge", "UnconfirmedUserPurgeConfig"),
+    ("signup", "SignupConfig"),
+    ("signup_email_policy", "SignupEmailPolicy"),
+    ("email_deliverability", "EmailDeliverabilityConfig"),
+    ("email_confirmation", "EmailConfirmationConfig"),
+    ("email_senders", "EmailSenders"),
+    ("email_templates", "EmailTemplateConfig"),
+    ("header_limits", "HeaderLimitsConfig"),
+    ("access_log", "AccessLogConfig"),
+    ("body_log", "BodyLogConfig"),
+    ("rate_limit", "RateLimitConfig"),
+    ("trusted_proxies", "TrustedProxiesConfig"),
+    ("transport_security", "TransportSecurityConfig"),
+    ("security_headers", "SecurityHeadersConfig"),
+    ("debug", "DebugConfig"),
+    ("webhooks", "WebhookConfig"),
+];
+
 #[mutants::skip]
 #[tokio::main]
 async fn main() -> Result<()> {
-    rustls::crypto::ring::default_provider()
-        .install_default()
-        .expect("Failed to install rustls crypto provider");
-
     if let Err(e) = dotenvy::dotenv() {
         eprintln!("Failed to load environment: {}", e);
 
@@ -62,63 +227,230 @@ async fn main() -> Result<()> {
 
     tracing_subscriber::fmt::init();
 
-    let args = Args::parse();
+    let args: Args = parse_with_config_file(CONFIG_SECTIONS)?;
+
+    args.debug.validate()?;
+    args.email.validate(args.debug.is_production())?;
+    args.rate_limit.validate()?;
+    args.password_hashing.validate()?;
+    args.email_confirmation.validate()?;
+
+    let signup_email_policy = args.signup_email_policy.load()?;
+
+    let postgres =
+        Arc::new(PostgresDatabase::new(&args.db.connection_string, args.db.connect_retry()).await?);
+    let user_repo = Arc::new(CachingUserRepository::new(
+        postgres.clone(),
+        &args.user_cache,
+    ));
+    let tls_source = args.server.tls_source()?;
 
-    let postgres = Arc::new(PostgresDatabase::new(&args.db.connection_string).await?);
-    let mailer = Arc::new(SMTPMailer::new(args.smtp));
+    let mut health_checks = HealthChecks::new();
+    let mut admin_health_checks = HealthChecks::new();
+
+    if !args.email.dry_run && args.smtp.is_configured() {
+        admin_health_checks = admin_health_checks.with_optional(SmtpHealthCheck::new(&args.smtp));
+    }
+
+    if let Some(tls_files) = tls_source.health_check() {
+        health_checks = health_checks.with_optional(tls_files);
+    }
+
+    let mailer = Arc::new(CircuitBreakerMailer::new(
+        EmailBackend::new(&args.email, args.smtp)?,
+        args.email_circuit_breaker,
+        Arc::new(SystemClock),
+    ));
+    let email_templates = EmailTemplates::load(args.email_templates)?;
+    let background = BackgroundTasks::new();
+    let events = EventBus::new()
+        .subscribe(AuditSubscriber::new(postgres.clone()))
+        .subscribe(
+            WelcomeEmailSubscriber::new(mailer.clone(), args.email_senders.clone())
+                .with_templates(email_templates.clone()),
+        )
+        .subscribe(WebhookSubscriber::new(
+            Arc::new(HttpWebhookNotifier::new(args.webhooks)),
+            background.clone(),
+        ));
+    let clock = Arc::new(SystemClock);
+    let shutdown = ShutdownCoordinator::new();
 
     let config = AppConfig {
         base_url: args.server.base_url.clone(),
+        allowed_hosts: args.allowed_hosts,
+        password_policy: args.password_policy,
+        password_hashing: args.password_hashing,
+        auth: args.auth,
+        signup: args.signup,
+        signup_email_policy,
+        header_limits: args.header_limits,
+        access_log: args.access_log,
+        body_log: args.body_log,
+        rate_limit: args.rate_limit,
+        trusted_proxies: args.trusted_proxies,
+        transport_security: args.transport_security.clone(),
+        security_headers: args.security_headers,
+        debug: args.debug,
+        strict_trailing_slash: args.server.strict_trailing_slash,
     };
 
+    let purge = UnconfirmedUserPurge::new(
+        user_repo.clone(),
+        clock.clone(),
+        args.unconfirmed_user_purge,
+    );
+    let idempotency_purge = IdempotencyKeyPurge::new(postgres.clone());
+
     let state = AppState {
         config,
         start_time: Utc::now(),
-        users: Arc::new(UserServiceImpl::new(postgres.clone())),
-        email_addresses: Arc::new(EmailAddressServiceImpl::new(postgres, mailer)),
+        users: Arc::new(UserServiceImpl::new(
+            user_repo.clone(),
+            events.clone(),
+            clock.clone(),
+            args.user_bootstrap,
+        )),
+        email_addresses: Arc::new(
+            EmailAddressServiceImpl::new(
+                user_repo,
+                mailer,
+                args.email_senders,
+                events,
+                clock,
+                args.email_confirmation,
+            )
+            .with_templates(email_templates),
+        ),
+        idempotency: postgres.clone(),
+        health: postgres.clone(),
+        health_checks,
+        admin_health_checks,
+        health_report: HealthReportCache::default(),
+        mail_domains: args.email_deliverability.resolver()?,
     };
 
     let http_port = args.server.http_port;
     let https_port = args.server.https_port;
+    let grace_period = args.server.shutdown_grace_period();
+    let background_grace_period = args.server.shutdown_background_grace_period();
 
-    let _ = tokio::join!(
-        tokio::spawn(
-            HttpServer::new(
-                SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), http_port),
-                &args.server.base_url,
-            )
-            .await?
-            .run()
-        ),
-        tokio::spawn(
-            HttpServer::new(
-                SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), http_port),
-                &args.server.base_url,
-            )
-            .await?
-            .run()
-        ),
-        tokio::spawn(
-            HttpsServer::new(
-                SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), https_port),
-                &args.server.cert_path,
-                &args.server.key_path,
-                state.clone(),
-            )
-            .await?
-            .run()
-        ),
-        tokio::spawn(
-            HttpsServer::new(
-                SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), https_port),
-                &args.server.cert_path,
-                &args.server.key_path,
-                state,
-            )
-            .await?
-            .run()
-        ),
-    );
+    // Every listener is bound before any server starts, so if one of the ports is taken the
+    // error names it and the listeners already bound are closed, rather than left serving
+    let http_v4 = HttpServer::new(
+        SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), http_port),
+        &args.server.base_url,
+        args.transport_security.permanent_redirect,
+        shutdown.token(),
+        grace_period,
+    )
+    .await?;
+    let http_v6 = HttpServer::new(
+        SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), http_port),
+        &args.server.base_url,
+        args.transport_security.permanent_redirect,
+        shutdown.token(),
+        grace_period,
+    )
+    .await?;
+    let https_v4 = HttpsServer::new(
+        SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), https_port),
+        tls_source.clone(),
+        args.server.alpn_protocols.clone(),
+        state.clone(),
+        shutdown.token(),
+        grace_period,
+    )
+    .await?;
+    let https_v6 = HttpsServer::new(
+        SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), https_port),
+        tls_source,
+        args.server.alpn_protocols,
+        state,
+        shutdown.token(),
+        grace_period,
+    )
+    .await?;
+
+    let servers = [
+        tokio::spawn(http_v4.run()),
+        tokio::spawn(http_v6.run()),
+        tokio::spawn(https_v4.run()),
+        tokio::spawn(https_v6.run()),
+    ];
+    let purge = tokio::spawn(purge.run(shutdown.token()));
+    let idempotency_purge = tokio::spawn(idempotency_purge.run(shutdown.token()));
+
+    shutdown_signal().await;
+
+    shutdown
+        .shutdown(
+            async {
+                for server in servers {
+                    match server.await {
+                        Ok(Err(err)) => error!("server failed: {:?}", err),
+                        Err(err) => error!("server task failed: {:?}", err),
+                        Ok(Ok(())) => {}
+                    }
+                }
+
+                if let Err(err) = purge.await {
+                    error!("unconfirmed user purge task failed: {:?}", err);
+                }
+
+                if let Err(err) = idempotency_purge.await {
+                    error!("idempotency key purge task failed: {:?}", err);
+                }
+            },
+            async {
+                background.flush(background_grace_period).await;
+            },
+            postgres.connection().close(),
+        )
+        .await;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+    use rust_saas_starter::infrastructure::config_file::try_parse_with_config_file;
+
+    use super::*;
+
+    #[test]
+    fn test_example_config_file_parses() -> anyhow::Result<()> {
+        let args: Args = try_parse_with_config_file(
+            [
+                "server",
+                "--config",
+                concat!(env!("CARGO_MANIFEST_DIR"), "/config.example.toml"),
+                // The signing key is a secret, so it isn't in the example
+                "--signing-key",
+                "signing-key",
+            ],
+            CONFIG_SECTIONS,
+        )?;
+
+        assert_eq!(args.server.https_port, 3443);
+        assert_eq!(args.smtp.host, "localhost");
+        assert_eq!(args.email_confirmation.token_ttl_minutes, 1440);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_every_config_section_is_a_group_of_arguments() {
+        let command = Args::command();
+
+        for (section, group) in CONFIG_SECTIONS {
+            assert!(
+                command
+                    .get_groups()
+                    .any(|candidate| candidate.get_id() == group),
+                "config section `{section}` refers to unknown group `{group}`"
+            );
+        }
+    }
+}
@@ -1,4 +1,13 @@
 //! Domain module
 
+pub mod audit;
 pub mod auth;
+pub mod background;
+pub mod base_url;
+pub mod clock;
 pub mod communication;
+pub mod events;
+pub mod health;
+pub mod i18n;
+pub mod idempotency;
+pub mod timestamps;
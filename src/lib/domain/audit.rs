@@ -0,0 +1,278 @@
+//! Audit module
+//!
+//! An append-only trail of authentication and account events, for compliance. Recording an
+//! event must never fail the operation it records, so failures are logged rather than returned.
+
+mod errors;
+mod event;
+
+pub use {
+    errors::AuditLogError,
+    event::{AuditEvent, AuditEventType},
+};
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tracing::warn;
+
+use crate::domain::events::{DomainEvent, EventSubscriber};
+
+#[cfg(test)]
+use mockall::mock;
+
+/// Audit logger trait
+#[async_trait]
+pub trait AuditLogger: Clone + Send + Sync + 'static {
+    /// Appends an event to the audit log
+    ///
+    /// # Arguments
+    /// * `event` - The [`AuditEvent`] which occurred.
+    ///
+    /// # Returns
+    /// A [`Result`] indicating whether the event was recorded.
+    async fn record(&self, event: AuditEvent) -> Result<(), AuditLogError>;
+}
+
+/// Records an event, logging rather than returning any failure so the operation being audited
+/// still succeeds
+pub async fn record_or_warn<A: AuditLogger>(audit: &Arc<A>, event: AuditEvent) {
+    if let Err(err) = audit.record(event).await {
+        warn!("Failed to record audit event: {:?}", err);
+    }
+}
+
+/// An audit logger which discards every event
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoopAuditLogger;
+
+#[async_trait]
+impl AuditLogger for NoopAuditLogger {
+    async fn record(&self, _event: AuditEvent) -> Result<(), AuditLogError> {
+        Ok(())
+    }
+}
+
+/// Records the [`DomainEvent`]s which are audited in an audit log
+#[derive(Clone, Debug)]
+pub struct AuditSubscriber<A: AuditLogger> {
+    audit: Arc<A>,
+}
+
+impl<A: AuditLogger> AuditSubscriber<A> {
+    /// Create a new audit subscriber recording events with `audit`
+    pub fn new(audit: Arc<A>) -> Self {
+        Self { audit }
+    }
+}
+
+#[async_trait]
+impl<A: AuditLogger> EventSubscriber for AuditSubscriber<A> {
+    async fn handle(&self, event: &DomainEvent) {
+        let (event, origin) = match event {
+            DomainEvent::UserCreated {
+                user_id,
+                origin,
+                occurred_at,
+                ..
+            } => (
+                AuditEvent::new(AuditEventType::UserCreated, *user_id, *occurred_at),
+                origin,
+            ),
+            DomainEvent::EmailConfirmed {
+                user_id,
+                origin,
+                occurred_at,
+                ..
+            } => (
+                AuditEvent::new(AuditEventType::EmailConfirmed, *user_id, *occurred_at),
+                origin,
+            ),
+            DomainEvent::EmailConfirmedByAdmin {
+                user_id,
+                admin_id,
+                origin,
+                occurred_at,
+                ..
+            } => (
+                AuditEvent::new(
+                    AuditEventType::EmailConfirmedByAdmin,
+                    *admin_id,
+                    *occurred_at,
+                )
+                .with_subject(*user_id),
+                origin,
+            ),
+            DomainEvent::UserErased {
+                user_id,
+                origin,
+                occurred_at,
+            } => (
+                AuditEvent::new(AuditEventType::UserErased, *user_id, *occurred_at),
+                origin,
+            ),
+            DomainEvent::EmailConfirmationSent { .. }
+            | DomainEvent::EmailChangeRequested { .. } => return,
+        };
+
+        let event = AuditEvent {
+            ip: origin.ip,
+            ..event
+        };
+
+        record_or_warn(&self.audit, event).await;
+    }
+}
+
+#[cfg(test)]
+mock! {
+    pub AuditLogger {}
+
+    impl Clone for AuditLogger {
+        fn clone(&self) -> Self;
+    }
+
+    #[async_trait]
+    impl AuditLogger for AuditLogger {
+        async fn record(&self, event: AuditEvent) -> Result<(), AuditLogError>;
+    }
+}
+
+/// Test doubles for the audit module
+#[cfg(test)]
+pub mod tests {
+    use std::{
+        net::{IpAddr, Ipv4Addr},
+        sync::Arc,
+    };
+
+    use anyhow::anyhow;
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    use crate::domain::{
+        communication::email_addresses::{EmailAddress, RequestOrigin},
+        events::{DomainEvent, EventSubscriber},
+    };
+
+    pub use super::MockAuditLogger;
+    use super::{AuditEventType, AuditLogError, AuditSubscriber, NoopAuditLogger};
+
+    /// Create an audit logger which discards any events
+    pub fn noop_audit() -> Arc<NoopAuditLogger> {
+        Arc::new(NoopAuditLogger)
+    }
+
+    const CLIENT_IP: IpAddr = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+
+    fn user_created(user_id: Uuid) -> DomainEvent {
+        DomainEvent::UserCreated {
+            user_id,
+            email: EmailAddress::new_unchecked("email@example.com"),
+            origin: RequestOrigin {
+                ip: Some(CLIENT_IP),
+                user_agent: None,
+            },
+            occurred_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_user_created_is_recorded() {
+        let user_id = Uuid::now_v7();
+        let mut audit = MockAuditLogger::new();
+
+        audit
+            .expect_record()
+            .times(1)
+            .withf(move |event| {
+                event.event_type == AuditEventType::UserCreated
+                    && event.actor_id == Some(user_id)
+                    && event.ip == Some(CLIENT_IP)
+            })
+            .returning(|_| Ok(()));
+
+        AuditSubscriber::new(Arc::new(audit))
+            .handle(&user_created(user_id))
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_email_confirmed_by_admin_is_recorded_against_the_admin() {
+        let user_id = Uuid::now_v7();
+        let admin_id = Uuid::now_v7();
+        let mut audit = MockAuditLogger::new();
+
+        audit
+            .expect_record()
+            .times(1)
+            .withf(move |event| {
+                event.event_type == AuditEventType::EmailConfirmedByAdmin
+                    && event.actor_id == Some(admin_id)
+                    && event.subject_id == Some(user_id)
+            })
+            .returning(|_| Ok(()));
+
+        AuditSubscriber::new(Arc::new(audit))
+            .handle(&DomainEvent::EmailConfirmedByAdmin {
+                user_id,
+                admin_id,
+                email: EmailAddress::new_unchecked("email@example.com"),
+                origin: RequestOrigin::default(),
+                occurred_at: Utc::now(),
+            })
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_user_erased_is_recorded() {
+        let user_id = Uuid::now_v7();
+        let mut audit = MockAuditLogger::new();
+
+        audit
+            .expect_record()
+            .times(1)
+            .withf(move |event| {
+                event.event_type == AuditEventType::UserErased && event.actor_id == Some(user_id)
+            })
+            .returning(|_| Ok(()));
+
+        AuditSubscriber::new(Arc::new(audit))
+            .handle(&DomainEvent::UserErased {
+                user_id,
+                origin: RequestOrigin::default(),
+                occurred_at: Utc::now(),
+            })
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_failing_to_record_is_not_propagated() {
+        let mut audit = MockAuditLogger::new();
+
+        audit
+            .expect_record()
+            .times(1)
+            .returning(|_| Err(AuditLogError::UnknownError(anyhow!("database unavailable"))));
+
+        AuditSubscriber::new(Arc::new(audit))
+            .handle(&user_created(Uuid::now_v7()))
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_unaudited_events_are_not_recorded() {
+        let mut audit = MockAuditLogger::new();
+
+        audit.expect_record().times(0);
+
+        AuditSubscriber::new(Arc::new(audit))
+            .handle(&DomainEvent::EmailChangeRequested {
+                user_id: Uuid::now_v7(),
+                new_email: EmailAddress::new_unchecked("new@example.com"),
+                expires_at: Utc::now(),
+                occurred_at: Utc::now(),
+            })
+            .await;
+    }
+}
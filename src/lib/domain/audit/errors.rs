@@ -0,0 +1,21 @@
+//! Audit log errors
+
+use anyhow::anyhow;
+use thiserror::Error;
+use tracing::debug;
+
+/// Errors that can occur when recording an audit event
+#[derive(Debug, Error)]
+pub enum AuditLogError {
+    /// Unknown error
+    #[error(transparent)]
+    UnknownError(#[from] anyhow::Error),
+}
+
+impl From<sqlx::Error> for AuditLogError {
+    fn from(err: sqlx::Error) -> Self {
+        debug!("sqlxError: {:?}", err);
+
+        AuditLogError::UnknownError(anyhow!("Unknown database error: {:?}", err))
+    }
+}
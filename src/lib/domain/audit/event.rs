@@ -0,0 +1,92 @@
+//! Audit events
+
+use std::{fmt, net::IpAddr};
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// The kind of event being audited
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuditEventType {
+    /// A user was created
+    UserCreated,
+
+    /// A user confirmed their email address
+    EmailConfirmed,
+
+    /// An admin confirmed a user's email address on their behalf
+    EmailConfirmedByAdmin,
+
+    /// A user erased their account
+    UserErased,
+
+    /// A user changed their password
+    PasswordChanged,
+
+    /// An attempt to log in failed
+    LoginFailed,
+}
+
+impl AuditEventType {
+    /// The name the event type is stored as
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditEventType::UserCreated => "user_created",
+            AuditEventType::EmailConfirmed => "email_confirmed",
+            AuditEventType::EmailConfirmedByAdmin => "email_confirmed_by_admin",
+            AuditEventType::UserErased => "user_erased",
+            AuditEventType::PasswordChanged => "password_changed",
+            AuditEventType::LoginFailed => "login_failed",
+        }
+    }
+}
+
+impl fmt::Display for AuditEventType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An authentication or account event
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuditEvent {
+    /// The user who performed the action, if known
+    pub actor_id: Option<Uuid>,
+
+    /// The user the action was performed on, if it wasn't the actor
+    pub subject_id: Option<Uuid>,
+
+    /// What happened
+    pub event_type: AuditEventType,
+
+    /// The IP address the action came from, if known
+    pub ip: Option<IpAddr>,
+
+    /// When it happened
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl AuditEvent {
+    /// Create a new event performed by `actor_id` at `occurred_at`
+    pub fn new(event_type: AuditEventType, actor_id: Uuid, occurred_at: DateTime<Utc>) -> Self {
+        Self {
+            actor_id: Some(actor_id),
+            subject_id: None,
+            event_type,
+            ip: None,
+            occurred_at,
+        }
+    }
+
+    /// Set the user the action was performed on
+    pub fn with_subject(mut self, subject_id: Uuid) -> Self {
+        self.subject_id = Some(subject_id);
+        self
+    }
+
+    /// Set the IP address the action came from
+    pub fn with_ip(mut self, ip: IpAddr) -> Self {
+        self.ip = Some(ip);
+        self
+    }
+}
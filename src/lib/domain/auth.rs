@@ -1,4 +1,5 @@
 //! Auth module
 
 pub mod emails;
+pub mod tokens;
 pub mod users;
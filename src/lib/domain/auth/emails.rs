@@ -1,3 +1,4 @@
 //! Auth emails
 
 pub mod confirm_email_address;
+pub mod welcome;
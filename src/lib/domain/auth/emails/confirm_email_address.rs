@@ -2,48 +2,167 @@
 
 use anyhow::Result;
 use askama::Template;
+use url::form_urlencoded;
 use uuid::Uuid;
 
+use crate::domain::{base_url::BaseUrl, communication::mailer::MailerError, i18n::Locale};
+
+/// Returns the link confirming a user's email address, which is handled by the `confirm_email`
+/// route. The token is percent-encoded, as its base64 padding isn't safe in a query string.
+///
+/// # Arguments
+/// * `base_url` - The base URL of the application.
+/// * `user_id` - The UUID of the user confirming their email address.
+/// * `token` - The email confirmation token.
+pub fn confirmation_url(base_url: &BaseUrl, user_id: &Uuid, token: &str) -> String {
+    let token: String = form_urlencoded::byte_serialize(token.as_bytes()).collect();
+
+    format!("{base_url}/api/v1/users/{user_id}/email/confirmation?token={token}")
+}
+
 /// Confirm email address template
 #[derive(Debug, Template)]
 #[template(path = "emails/auth/confirm_email_address.html")]
 pub struct ConfirmEmailAddressTemplate {
     /// Link to confirm email address
     pub link: String,
+
+    /// The locale the email is written in
+    pub locale: Locale,
 }
 
 impl ConfirmEmailAddressTemplate {
     /// Creates a new `ConfirmEmailAddressTemplate`
-    pub fn new(base_url: &str, user_id: &Uuid, token: &str) -> Self {
+    pub fn new(base_url: &BaseUrl, user_id: &Uuid, token: &str, locale: Locale) -> Self {
         Self {
-            link: format!("{base_url}/api/v1/users/{user_id}/email/confirmation?token={token}"),
+            link: confirmation_url(base_url, user_id, token),
+            locale,
         }
     }
 
     /// Renders the plain text version of the email
     pub fn render_plain(&self) -> Result<String> {
         Ok(format!(
-            "Visit the following URL to confirm your email address: {link}",
+            "{text} {link}",
+            text = self.locale.messages().confirm_email.plain,
             link = self.link
         ))
     }
+
+    /// Renders the HTML version of the email, with its CSS inlined, and the plain text version
+    pub fn render_email(&self) -> Result<(String, String), MailerError> {
+        let html = css_inline::inline(&self.render()?)?;
+
+        Ok((html, self.render_plain()?))
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use insta::assert_snapshot;
+    use testresult::TestResult;
+
+    use crate::domain::base_url::tests::example_base_url;
+
     use super::*;
 
+    /// Renders the email asking the user to confirm their email address, as its HTML body and
+    /// plain text body
+    fn render_confirmation_email(
+        base_url: &BaseUrl,
+        user_id: &Uuid,
+        token: &str,
+        locale: Locale,
+    ) -> Result<(String, String), MailerError> {
+        ConfirmEmailAddressTemplate::new(base_url, user_id, token, locale).render_email()
+    }
+
+    /// Creates a template with fixed inputs, so its rendering can be compared with a snapshot
+    fn fixed_template() -> ConfirmEmailAddressTemplate {
+        ConfirmEmailAddressTemplate::new(
+            &example_base_url(),
+            &Uuid::from_u128(0x0191_6d3a_7c2e_7b1f_8a4d_2c6e_9f01_b3d5),
+            "f9l4Cu5Mpwxu48ITlEfh3QNCgRrda_p23dtSx-ETfkY=",
+            Locale::English,
+        )
+    }
+
     #[test]
     fn test_confirm_email_address_confirmation_url() {
-        let base_url = "https://example.com";
+        let base_url = example_base_url();
         let user_id = Uuid::now_v7();
         let token = "f9l4Cu5Mpwxu48ITlEfh3QNCgRrda_p23dtSx-ETfkY=";
 
-        let template = ConfirmEmailAddressTemplate::new(base_url, &user_id, token);
+        let template =
+            ConfirmEmailAddressTemplate::new(&base_url, &user_id, token, Locale::English);
 
         assert_eq!(
             template.link,
-            format!("https://example.com/api/v1/users/{user_id}/email/confirmation?token=f9l4Cu5Mpwxu48ITlEfh3QNCgRrda_p23dtSx-ETfkY=")
+            format!("https://example.com/api/v1/users/{user_id}/email/confirmation?token=f9l4Cu5Mpwxu48ITlEfh3QNCgRrda_p23dtSx-ETfkY%3D")
+        );
+    }
+
+    #[test]
+    fn test_confirmation_url_percent_encodes_token() {
+        let user_id = Uuid::from_u128(0x0191_6d3a_7c2e_7b1f_8a4d_2c6e_9f01_b3d5);
+
+        assert_eq!(
+            confirmation_url(&example_base_url(), &user_id, "a+b/c=="),
+            "https://example.com/api/v1/users/01916d3a-7c2e-7b1f-8a4d-2c6e9f01b3d5/email/confirmation?token=a%2Bb%2Fc%3D%3D"
         );
     }
+
+    #[test]
+    fn test_render_confirmation_email_inlines_css_and_renders_plain_text() -> TestResult {
+        let user_id = Uuid::now_v7();
+
+        let (html, plain) =
+            render_confirmation_email(&example_base_url(), &user_id, "abc", Locale::English)?;
+
+        // Outlook's conditional styles are inside a comment, so aren't inlined
+        let uncommented: String = html
+            .split("<!--")
+            .map(|part| part.split_once("-->").map_or(part, |(_, rest)| rest))
+            .collect();
+
+        assert!(!uncommented.contains("<style"));
+        assert!(html.contains(r#"style=""#));
+        assert!(html.contains(&format!(
+            r#"href="https://example.com/api/v1/users/{user_id}/email/confirmation?token=abc""#
+        )));
+        assert!(!plain.is_empty());
+        assert!(plain.contains("token=abc"));
+
+        Ok(())
+    }
+
+    /// Compares the full rendered HTML with the checked-in snapshot under `snapshots/`. When the
+    /// template is changed on purpose, run `cargo insta test --review` to accept the new snapshot.
+    #[test]
+    fn test_confirm_email_address_snapshot() -> TestResult {
+        assert_snapshot!(fixed_template().render()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_confirmation_email_in_each_locale() -> TestResult {
+        let user_id = Uuid::now_v7();
+
+        let (english_html, english_plain) =
+            render_confirmation_email(&example_base_url(), &user_id, "abc", Locale::English)?;
+        let (french_html, french_plain) =
+            render_confirmation_email(&example_base_url(), &user_id, "abc", Locale::French)?;
+
+        assert!(english_html.contains(r#"lang="en""#));
+        assert!(english_html.contains("Confirm email&nbsp;address"));
+        assert!(english_plain.starts_with("Visit the following URL"));
+
+        assert!(french_html.contains(r#"lang="fr""#));
+        assert!(french_html.contains("Confirmer l’adresse&nbsp;e-mail"));
+        assert!(french_plain.starts_with("Rendez-vous à l’adresse suivante"));
+        assert!(french_plain.ends_with("token=abc"));
+
+        Ok(())
+    }
 }
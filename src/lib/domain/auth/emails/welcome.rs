@@ -0,0 +1,45 @@
+//! Welcome email template
+
+use anyhow::Result;
+use askama::Template;
+
+use crate::domain::{communication::mailer::MailerError, i18n::Locale};
+
+/// Welcome email template, sent once a user first confirms their email address
+#[derive(Debug, Default, Template)]
+#[template(path = "emails/auth/welcome.html")]
+pub struct WelcomeEmailTemplate {
+    /// The locale the email is written in
+    pub locale: Locale,
+}
+
+impl WelcomeEmailTemplate {
+    /// Renders the plain text version of the email
+    pub fn render_plain(&self) -> Result<String> {
+        Ok(self.locale.messages().welcome.plain.to_string())
+    }
+
+    /// Renders the HTML version of the email, with its CSS inlined, and the plain text version
+    pub fn render_email(&self) -> Result<(String, String), MailerError> {
+        let html = css_inline::inline(&self.render()?)?;
+
+        Ok((html, self.render_plain()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use testresult::TestResult;
+
+    use super::*;
+
+    #[test]
+    fn test_render_welcome_email() -> TestResult {
+        let (html, plain) = WelcomeEmailTemplate::default().render_email()?;
+
+        assert!(html.contains("welcome&nbsp;aboard!"));
+        assert!(plain.starts_with("Thanks for confirming your email address"));
+
+        Ok(())
+    }
+}
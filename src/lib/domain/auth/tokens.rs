@@ -0,0 +1,9 @@
+//! Access tokens module
+
+mod access_token;
+mod config;
+mod errors;
+
+pub use access_token::{issue_access_token, validate_access_token, AccessToken, AccessTokenClaims};
+pub use config::AuthConfig;
+pub use errors::AccessTokenError;
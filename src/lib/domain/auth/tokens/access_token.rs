@@ -0,0 +1,169 @@
+//! Access token issuance and validation
+
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{AccessTokenError, AuthConfig};
+
+/// The claims embedded in an access token
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccessTokenClaims {
+    /// The ID of the user the token was issued to
+    pub sub: Uuid,
+
+    /// The issuer of the token
+    pub iss: String,
+
+    /// The time the token was issued, as a UNIX timestamp
+    pub iat: i64,
+
+    /// The time the token expires, as a UNIX timestamp
+    pub exp: i64,
+}
+
+/// A signed access token
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccessToken {
+    /// The encoded and signed token
+    pub token: String,
+
+    /// The time the token expires
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Issues a signed access token for the given user.
+///
+/// # Arguments
+/// * `config` - The [`AuthConfig`] containing the signing key, issuer and token lifetime.
+/// * `user_id` - The UUID of the user the token is issued to.
+///
+/// # Returns
+/// A [`Result`] which is [`Ok`] containing the signed [`AccessToken`],
+/// or an [`Err`] containing an [`AccessTokenError`] if the token could not be signed.
+pub fn issue_access_token(
+    config: &AuthConfig,
+    user_id: &Uuid,
+) -> Result<AccessToken, AccessTokenError> {
+    let issued_at = Utc::now();
+    let expires_at = issued_at + Duration::seconds(config.access_token_ttl_seconds);
+
+    let claims = AccessTokenClaims {
+        sub: *user_id,
+        iss: config.issuer.clone(),
+        iat: issued_at.timestamp(),
+        exp: expires_at.timestamp(),
+    };
+
+    let token = encode(
+        &Header::new(Algorithm::HS256),
+        &claims,
+        &EncodingKey::from_secret(config.signing_key.as_bytes()),
+    )
+    .map_err(|err| AccessTokenError::UnknownError(err.into()))?;
+
+    Ok(AccessToken { token, expires_at })
+}
+
+/// Validates an access token's signature, issuer and expiry.
+///
+/// # Arguments
+/// * `config` - The [`AuthConfig`] containing the signing key and expected issuer.
+/// * `token` - The encoded access token.
+///
+/// # Returns
+/// A [`Result`] which is [`Ok`] containing the token's [`AccessTokenClaims`] if it is valid,
+/// or an [`Err`] containing an [`AccessTokenError`] if it is not.
+pub fn validate_access_token(
+    config: &AuthConfig,
+    token: &str,
+) -> Result<AccessTokenClaims, AccessTokenError> {
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.set_issuer(&[&config.issuer]);
+    validation.set_required_spec_claims(&["exp", "iss", "sub"]);
+
+    let data = decode::<AccessTokenClaims>(
+        token,
+        &DecodingKey::from_secret(config.signing_key.as_bytes()),
+        &validation,
+    )?;
+
+    Ok(data.claims)
+}
+
+#[cfg(test)]
+mod tests {
+    use testresult::TestResult;
+
+    use super::*;
+
+    fn config() -> AuthConfig {
+        AuthConfig {
+            signing_key: "test-signing-key".to_string(),
+            issuer: "test-issuer".to_string(),
+            access_token_ttl_seconds: 900,
+        }
+    }
+
+    #[test]
+    fn test_issue_and_validate_access_token() -> TestResult {
+        let user_id = Uuid::now_v7();
+
+        let access_token = issue_access_token(&config(), &user_id)?;
+        let claims = validate_access_token(&config(), &access_token.token)?;
+
+        assert_eq!(claims.sub, user_id);
+        assert_eq!(claims.iss, "test-issuer");
+        assert_eq!(claims.exp, access_token.expires_at.timestamp());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_expired_access_token() -> TestResult {
+        let config = AuthConfig {
+            access_token_ttl_seconds: -3600,
+            ..config()
+        };
+
+        let access_token = issue_access_token(&config, &Uuid::now_v7())?;
+        let result = validate_access_token(&config, &access_token.token);
+
+        assert!(matches!(result, Err(AccessTokenError::Expired)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_access_token_with_tampered_signature() -> TestResult {
+        let access_token = issue_access_token(&config(), &Uuid::now_v7())?;
+
+        let other_config = AuthConfig {
+            signing_key: "another-signing-key".to_string(),
+            ..config()
+        };
+
+        let result = validate_access_token(&other_config, &access_token.token);
+
+        assert!(matches!(result, Err(AccessTokenError::Invalid)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_access_token_with_wrong_issuer() -> TestResult {
+        let access_token = issue_access_token(&config(), &Uuid::now_v7())?;
+
+        let other_config = AuthConfig {
+            issuer: "another-issuer".to_string(),
+            ..config()
+        };
+
+        let result = validate_access_token(&other_config, &access_token.token);
+
+        assert!(matches!(result, Err(AccessTokenError::Invalid)));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+//! Auth configuration
+
+use std::fmt;
+
+use clap::Parser;
+
+/// Access token signing configuration
+#[derive(Clone, PartialEq, Eq, Parser)]
+pub struct AuthConfig {
+    /// The secret used to sign access tokens (HS256)
+    #[arg(long, env = "JWT_SECRET")]
+    pub signing_key: String,
+
+    /// The issuer (`iss`) claim set on, and required of, access tokens
+    #[arg(long, env = "JWT_ISSUER", default_value = "rust-saas-starter")]
+    pub issuer: String,
+
+    /// How long an access token is valid for, in seconds
+    #[arg(long, env = "JWT_TTL_SECONDS", default_value = "900")]
+    pub access_token_ttl_seconds: i64,
+}
+
+impl fmt::Debug for AuthConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthConfig")
+            .field("signing_key", &"********")
+            .field("issuer", &self.issuer)
+            .field("access_token_ttl_seconds", &self.access_token_ttl_seconds)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auth_config_debug_obfuscates_signing_key() {
+        let config = AuthConfig {
+            signing_key: "super-secret".to_string(),
+            issuer: "issuer".to_string(),
+            access_token_ttl_seconds: 900,
+        };
+
+        assert!(!format!("{:?}", config).contains("super-secret"));
+    }
+}
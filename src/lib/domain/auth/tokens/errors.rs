@@ -0,0 +1,32 @@
+//! Access token errors
+
+use jsonwebtoken::errors::{Error, ErrorKind};
+use thiserror::Error;
+use tracing::debug;
+
+/// Errors that can occur when issuing or validating an access token
+#[derive(Debug, Error)]
+pub enum AccessTokenError {
+    /// The access token has expired
+    #[error("access token has expired")]
+    Expired,
+
+    /// The access token is malformed, has an invalid signature or has invalid claims
+    #[error("access token is invalid")]
+    Invalid,
+
+    /// Unknown error
+    #[error(transparent)]
+    UnknownError(#[from] anyhow::Error),
+}
+
+impl From<Error> for AccessTokenError {
+    fn from(err: Error) -> Self {
+        debug!("jsonwebtoken::Error {:?} -> AccessTokenError", err);
+
+        match err.kind() {
+            ErrorKind::ExpiredSignature => AccessTokenError::Expired,
+            _ => AccessTokenError::Invalid,
+        }
+    }
+}
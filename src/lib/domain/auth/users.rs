@@ -1,17 +1,32 @@
 //! This module contains the user model and its related functions.
 
+mod config;
+mod filter;
 mod password;
+mod password_hashing;
+mod purge;
 mod repository;
+mod role;
 mod service;
+mod signup_email_policy;
 mod user;
+mod username;
 
 pub mod errors;
 
-pub use password::{Password, PasswordError};
+pub use config::{SignupConfig, UnconfirmedUserPurgeConfig, UserBootstrapConfig};
+pub use filter::{UserCursor, UserFilter};
+pub use password::{CharacterClass, Password, PasswordError, PasswordPolicy};
+pub use password_hashing::{PasswordHashingConfig, PasswordHashingConfigError};
+pub use purge::UnconfirmedUserPurge;
 pub use repository::UserRepository;
+pub use role::{Role, UnknownRoleError};
 pub use service::{UserService, UserServiceImpl};
-pub use user::{NewUser, User};
+pub use signup_email_policy::{SignupEmailPolicy, SignupEmailPolicyError};
+pub use user::{NewUser, NewUserBuilder, NewUserBuilderError, User};
+pub use username::{Username, UsernameError};
 
+/// Test doubles for the users module
 #[cfg(test)]
 pub mod tests {
     pub use super::repository::MockUserRepository;
@@ -0,0 +1,99 @@
+//! User configuration
+
+use std::time::Duration as StdDuration;
+
+use chrono::Duration;
+use clap::{ArgAction, Parser};
+
+/// User bootstrap configuration
+#[derive(Clone, Debug, Default, PartialEq, Eq, Parser)]
+pub struct UserBootstrapConfig {
+    /// Whether the first user created in an empty database becomes an admin. Intended for fresh
+    /// deployments, and safe to switch off once an admin exists
+    #[arg(long, env = "FIRST_USER_ADMIN", default_value_t = false, action = ArgAction::Set)]
+    pub first_user_admin: bool,
+}
+
+/// Signup configuration
+#[derive(Clone, Debug, Default, PartialEq, Eq, Parser)]
+pub struct SignupConfig {
+    /// Whether new users must accept the terms of service to sign up
+    #[arg(long, env = "REQUIRE_TERMS_ACCEPTANCE", default_value_t = false, action = ArgAction::Set)]
+    pub require_terms_acceptance: bool,
+}
+
+/// Unconfirmed user purge configuration
+#[derive(Clone, Debug, PartialEq, Eq, Parser)]
+pub struct UnconfirmedUserPurgeConfig {
+    /// Whether users who never confirmed their email address are deleted once the grace period
+    /// is over
+    #[arg(long, env = "PURGE_UNCONFIRMED_USERS", default_value_t = true, action = ArgAction::Set)]
+    pub purge_unconfirmed_users: bool,
+
+    /// How many days a user has to confirm their email address before they are deleted
+    #[arg(
+        long,
+        env = "UNCONFIRMED_USER_GRACE_DAYS",
+        default_value = "7",
+        value_parser = clap::value_parser!(i64).range(1..)
+    )]
+    pub unconfirmed_user_grace_days: i64,
+
+    /// How often unconfirmed users past the grace period are looked for, in minutes
+    #[arg(
+        long,
+        env = "UNCONFIRMED_USER_PURGE_INTERVAL_MINUTES",
+        default_value = "60",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub unconfirmed_user_purge_interval_minutes: u64,
+}
+
+impl UnconfirmedUserPurgeConfig {
+    /// How long a user has to confirm their email address
+    pub fn grace_period(&self) -> Duration {
+        Duration::days(self.unconfirmed_user_grace_days)
+    }
+
+    /// How long to wait between purges
+    pub fn interval(&self) -> StdDuration {
+        StdDuration::from_secs(self.unconfirmed_user_purge_interval_minutes * 60)
+    }
+}
+
+impl Default for UnconfirmedUserPurgeConfig {
+    fn default() -> Self {
+        Self {
+            purge_unconfirmed_users: true,
+            unconfirmed_user_grace_days: 7,
+            unconfirmed_user_purge_interval_minutes: 60,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unconfirmed_user_grace_period_must_be_positive() {
+        for days in ["0", "-1"] {
+            let result = UnconfirmedUserPurgeConfig::try_parse_from([
+                "test",
+                "--unconfirmed-user-grace-days",
+                days,
+            ]);
+
+            assert!(result.is_err(), "{days}");
+        }
+    }
+
+    #[test]
+    fn test_unconfirmed_user_purge_defaults() {
+        let config = UnconfirmedUserPurgeConfig::default();
+
+        assert!(config.purge_unconfirmed_users);
+        assert_eq!(config.grace_period(), Duration::days(7));
+        assert_eq!(config.interval(), StdDuration::from_secs(60 * 60));
+    }
+}
@@ -11,6 +11,10 @@ pub enum CreateUserError {
     #[error("User already exists with that email address")]
     DuplicateUser,
 
+    /// User with username already exists
+    #[error("User already exists with that username")]
+    DuplicateUsername,
+
     /// Unknown error
     #[error(transparent)]
     UnknownError(#[from] anyhow::Error),
@@ -28,6 +32,34 @@ pub enum GetUserByIdError {
     UnknownError(#[from] anyhow::Error),
 }
 
+/// Errors that can occur when listing users
+#[derive(Debug, Error)]
+pub enum ListUsersError {
+    /// Unknown error
+    #[error(transparent)]
+    UnknownError(#[from] anyhow::Error),
+}
+
+/// Errors that can occur when checking whether an email address is in use
+#[derive(Debug, Error)]
+pub enum EmailExistsError {
+    /// Unknown error
+    #[error(transparent)]
+    UnknownError(#[from] anyhow::Error),
+}
+
+/// Errors that can occur when deleting a user
+#[derive(Debug, Error)]
+pub enum DeleteUserError {
+    /// User not found
+    #[error("User not found")]
+    UserNotFound,
+
+    /// Unknown error
+    #[error(transparent)]
+    UnknownError(#[from] anyhow::Error),
+}
+
 /// Errors that can occur when updating a user
 #[derive(Debug, Error)]
 pub enum UpdateUserError {
@@ -39,6 +71,18 @@ pub enum UpdateUserError {
     #[error("User's email is already in use")]
     EmailAddressInUse,
 
+    /// Email confirmation token is not the user's outstanding token
+    #[error("Email confirmation token does not match")]
+    ConfirmationTokenMismatch,
+
+    /// User has no pending email change
+    #[error("User has no pending email change")]
+    NoPendingEmailChange,
+
+    /// User has been updated since the expected version
+    #[error("User has been updated since it was read")]
+    Conflict,
+
     /// Unknown error
     #[error(transparent)]
     UnknownError(#[from] anyhow::Error),
@@ -50,7 +94,10 @@ impl From<sqlx::Error> for CreateUserError {
 
         match err {
             sqlx::Error::Database(db_err) => match db_err.kind() {
-                sqlx::error::ErrorKind::UniqueViolation => CreateUserError::DuplicateUser,
+                sqlx::error::ErrorKind::UniqueViolation => match db_err.constraint() {
+                    Some("users_username_key") => CreateUserError::DuplicateUsername,
+                    _ => CreateUserError::DuplicateUser,
+                },
                 _ => CreateUserError::UnknownError(anyhow!("Unknown database error: {:?}", db_err)),
             },
             _ => CreateUserError::UnknownError(anyhow!("Unknown database error: {:?}", err)),
@@ -60,8 +107,14 @@ impl From<sqlx::Error> for CreateUserError {
 
 impl From<sqlx::Error> for UpdateUserError {
     fn from(err: sqlx::Error) -> Self {
+        debug!("sqlxError: {:?}", err);
+
         match err {
             sqlx::Error::RowNotFound => UpdateUserError::UserNotFound,
+            sqlx::Error::Database(db_err) => match db_err.kind() {
+                sqlx::error::ErrorKind::UniqueViolation => UpdateUserError::EmailAddressInUse,
+                _ => UpdateUserError::UnknownError(anyhow!("Unknown database error: {:?}", db_err)),
+            },
             _ => UpdateUserError::UnknownError(err.into()),
         }
     }
@@ -0,0 +1,40 @@
+//! User listing filters
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::User;
+
+/// Narrows which users are listed. Each filter which is set must match
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct UserFilter {
+    /// Only users created after this date
+    pub created_after: Option<DateTime<Utc>>,
+
+    /// Only users created before this date
+    pub created_before: Option<DateTime<Utc>>,
+
+    /// Only users whose email address has, or hasn't, been confirmed
+    pub confirmed: Option<bool>,
+}
+
+/// Where a listing continues from, after the user it was taken from. Users are listed by when
+/// they were created and then by ID, so users created or deleted meanwhile don't shift later
+/// pages the way skipping a number of users would.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UserCursor {
+    /// When the last user listed was created
+    pub created_at: DateTime<Utc>,
+
+    /// The ID of the last user listed
+    pub id: Uuid,
+}
+
+impl From<&User> for UserCursor {
+    fn from(user: &User) -> Self {
+        Self {
+            created_at: user.timestamps.created_at,
+            id: user.id,
+        }
+    }
+}
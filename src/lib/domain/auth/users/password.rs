@@ -2,55 +2,245 @@
 
 use std::fmt;
 
+use clap::{Parser, ValueEnum};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
-use zxcvbn::{zxcvbn, Score};
+#[cfg(feature = "zxcvbn")]
+use zxcvbn::zxcvbn;
 
 /// Password error
 #[derive(Debug, Error)]
 pub enum PasswordError {
-    /// Password is too short
-    #[error("Your password is too short. It must be at least 8 characters long.")]
-    TooShort,
+    /// Password is too short, contains the minimum length in characters
+    #[error("Your password is too short. It must be at least {0} characters long.")]
+    TooShort(usize),
 
-    /// Password is too long
-    #[error("Your password is too long. It must be at most 100 characters long.")]
-    TooLong,
+    /// Password is too long, contains the maximum length in bytes
+    #[error("Your password is too long. It must be at most {0} bytes long.")]
+    TooLong(usize),
+
+    /// Password is missing one or more required character classes
+    #[error("Your password must contain at least one of each: {}.", describe_classes(.0))]
+    MissingCharacterClasses(Vec<CharacterClass>),
 
     /// Password is too weak
     #[error("Your password is too weak.")]
     TooWeak(Vec<String>),
 }
 
+/// A class of characters a [`PasswordPolicy`] can require
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum CharacterClass {
+    /// A lowercase letter
+    Lowercase,
+
+    /// An uppercase letter
+    Uppercase,
+
+    /// A numeric digit
+    Digit,
+
+    /// Any character that is not alphanumeric
+    Symbol,
+}
+
+impl CharacterClass {
+    /// Whether the given character belongs to this class
+    pub fn contains(&self, c: char) -> bool {
+        match self {
+            Self::Lowercase => c.is_lowercase(),
+            Self::Uppercase => c.is_uppercase(),
+            Self::Digit => c.is_numeric(),
+            Self::Symbol => !c.is_alphanumeric(),
+        }
+    }
+}
+
+impl fmt::Display for CharacterClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Lowercase => write!(f, "lowercase letter"),
+            Self::Uppercase => write!(f, "uppercase letter"),
+            Self::Digit => write!(f, "digit"),
+            Self::Symbol => write!(f, "symbol"),
+        }
+    }
+}
+
+fn describe_classes(classes: &[CharacterClass]) -> String {
+    classes
+        .iter()
+        .map(|class| class.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Password policy
+///
+/// The minimum length is counted in characters, since that is what a user perceives when
+/// choosing a password. The maximum length is counted in UTF-8 bytes, since that is what gets
+/// fed to the Argon2 hasher: Argon2 has no practical input limit, but hashing arbitrarily large
+/// inputs wastes CPU, so the bound is on the work done rather than on the visible length. A
+/// password made of multibyte characters therefore reaches the maximum with fewer characters.
+#[derive(Clone, Debug, PartialEq, Eq, Parser)]
+pub struct PasswordPolicy {
+    /// The minimum password length, in characters
+    #[arg(long, env = "PASSWORD_MIN_LENGTH", default_value = "8")]
+    pub min_length: usize,
+
+    /// The maximum password length, in UTF-8 bytes
+    #[arg(long, env = "PASSWORD_MAX_LENGTH", default_value = "100")]
+    pub max_length: usize,
+
+    /// The minimum zxcvbn strength score, from 0 (weakest) to 4 (strongest).
+    ///
+    /// When built without the `zxcvbn` feature, any score above 0 enables the basic fallback
+    /// check instead.
+    #[arg(long, env = "PASSWORD_MIN_SCORE", default_value = "3", value_parser = clap::value_parser!(u8).range(0..=4))]
+    pub min_score: u8,
+
+    /// The character classes a password must contain at least one of each of
+    #[arg(
+        long,
+        env = "PASSWORD_REQUIRED_CLASSES",
+        value_enum,
+        value_delimiter = ','
+    )]
+    pub required_classes: Vec<CharacterClass>,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            max_length: 100,
+            min_score: 3,
+            required_classes: vec![],
+        }
+    }
+}
+
+/// Checks the password's zxcvbn score against the policy's minimum
+#[cfg(feature = "zxcvbn")]
+fn check_strength(raw: &str, policy: &PasswordPolicy) -> Result<(), PasswordError> {
+    let entropy = zxcvbn(raw, &[]);
+    if u8::from(entropy.score()) < policy.min_score {
+        let suggestions = if let Some(feedback) = entropy.feedback() {
+            feedback
+                .suggestions()
+                .iter()
+                .map(|s| s.to_string())
+                .collect::<Vec<String>>()
+        } else {
+            vec!["Please choose a stronger password.".to_string()]
+        };
+
+        return Err(PasswordError::TooWeak(suggestions));
+    }
+
+    Ok(())
+}
+
+/// Passwords, and password stems once trailing digits and symbols are removed, which are
+/// rejected by the fallback strength check
+#[cfg(not(feature = "zxcvbn"))]
+const COMMON_PASSWORDS: &[&str] = &[
+    "123456789",
+    "1234567890",
+    "12345678",
+    "87654321",
+    "abc123",
+    "abcdefgh",
+    "admin",
+    "administrator",
+    "baseball",
+    "changeme",
+    "computer",
+    "dragon",
+    "football",
+    "iloveyou",
+    "letmein",
+    "master",
+    "monkey",
+    "passw0rd",
+    "password",
+    "princess",
+    "qwerty",
+    "qwertyuiop",
+    "shadow",
+    "sunshine",
+    "superman",
+    "trustno",
+    "welcome",
+];
+
+/// The fewest distinct characters a password may contain under the fallback strength check
+#[cfg(not(feature = "zxcvbn"))]
+const MIN_DISTINCT_CHARACTERS: usize = 5;
+
+/// Checks the password against a list of common passwords and rejects ones made up of only a
+/// handful of distinct characters, for builds without zxcvbn. A policy with a minimum score of
+/// 0 skips the check, as it would with zxcvbn.
+#[cfg(not(feature = "zxcvbn"))]
+fn check_strength(raw: &str, policy: &PasswordPolicy) -> Result<(), PasswordError> {
+    use std::collections::HashSet;
+
+    if policy.min_score == 0 {
+        return Ok(());
+    }
+
+    let normalized = raw.to_lowercase();
+    let stem = normalized.trim_end_matches(|c: char| !c.is_alphabetic());
+
+    if COMMON_PASSWORDS.contains(&normalized.as_str()) || COMMON_PASSWORDS.contains(&stem) {
+        return Err(PasswordError::TooWeak(vec![
+            "This is a very common password.".to_string(),
+        ]));
+    }
+
+    if normalized.chars().collect::<HashSet<char>>().len() < MIN_DISTINCT_CHARACTERS {
+        return Err(PasswordError::TooWeak(vec![
+            "Avoid repeated characters and patterns.".to_string(),
+        ]));
+    }
+
+    Ok(())
+}
+
 /// Password
 #[derive(Clone, PartialEq, Eq)]
 pub struct Password(String);
 
 impl Password {
-    /// Create a new password
+    /// Create a new password using the default [`PasswordPolicy`]
     pub fn new(raw: &str) -> Result<Self, PasswordError> {
-        if raw.len() < 8 {
-            return Err(PasswordError::TooShort);
+        Self::new_with_policy(raw, &PasswordPolicy::default())
+    }
+
+    /// Create a new password, validated against the given [`PasswordPolicy`]
+    pub fn new_with_policy(raw: &str, policy: &PasswordPolicy) -> Result<Self, PasswordError> {
+        if raw.chars().count() < policy.min_length {
+            return Err(PasswordError::TooShort(policy.min_length));
         }
 
-        if raw.len() > 100 {
-            return Err(PasswordError::TooLong);
+        if raw.len() > policy.max_length {
+            return Err(PasswordError::TooLong(policy.max_length));
         }
 
-        let entropy = zxcvbn(raw, &[]);
-        if entropy.score() < Score::Three {
-            let suggestions = if let Some(feedback) = entropy.feedback() {
-                feedback
-                    .suggestions()
-                    .iter()
-                    .map(|s| s.to_string())
-                    .collect::<Vec<String>>()
-            } else {
-                vec!["Please choose a stronger password.".to_string()]
-            };
-
-            return Err(PasswordError::TooWeak(suggestions));
+        let missing_classes = policy
+            .required_classes
+            .iter()
+            .filter(|class| !raw.chars().any(|c| class.contains(c)))
+            .copied()
+            .collect::<Vec<CharacterClass>>();
+
+        if !missing_classes.is_empty() {
+            return Err(PasswordError::MissingCharacterClasses(missing_classes));
         }
 
+        check_strength(raw, policy)?;
+
         Ok(Self(raw.to_string()))
     }
 
@@ -119,20 +309,147 @@ mod tests {
     fn test_new_password_too_short() {
         let result = Password::new("short");
         assert!(result.is_err());
-        assert!(matches!(result, Err(PasswordError::TooShort)))
+        assert!(matches!(result, Err(PasswordError::TooShort(8))))
     }
 
     #[test]
     fn test_new_password_too_long() {
         let result = Password::new(&"a".repeat(101));
         assert!(result.is_err());
-        assert!(matches!(result, Err(PasswordError::TooLong)))
+        assert!(matches!(result, Err(PasswordError::TooLong(100))))
     }
 
+    #[test]
+    fn test_new_password_missing_required_classes() {
+        let policy = PasswordPolicy {
+            required_classes: vec![
+                CharacterClass::Lowercase,
+                CharacterClass::Uppercase,
+                CharacterClass::Digit,
+                CharacterClass::Symbol,
+            ],
+            ..PasswordPolicy::default()
+        };
+
+        let result = Password::new_with_policy("correcthorse4batterystaple", &policy);
+
+        assert!(matches!(
+            result,
+            Err(PasswordError::MissingCharacterClasses(ref classes))
+                if *classes == vec![CharacterClass::Uppercase, CharacterClass::Symbol]
+        ));
+    }
+
+    #[test]
+    fn test_new_password_with_required_classes() -> TestResult {
+        let policy = PasswordPolicy {
+            required_classes: vec![CharacterClass::Uppercase, CharacterClass::Digit],
+            ..PasswordPolicy::default()
+        };
+
+        Password::new_with_policy("correctHorse4batterystaple", &policy)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_new_password_min_score_is_configurable() -> TestResult {
+        let policy = PasswordPolicy {
+            min_score: 0,
+            ..PasswordPolicy::default()
+        };
+
+        Password::new_with_policy("weakpassword", &policy)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_new_password_max_length_is_configurable() -> TestResult {
+        let policy = PasswordPolicy {
+            max_length: 200,
+            ..PasswordPolicy::default()
+        };
+
+        Password::new_with_policy(&"correcthorsebatterystaple".repeat(8), &policy)?;
+
+        let result = Password::new_with_policy(&"correcthorsebatterystaple".repeat(9), &policy);
+        assert!(matches!(result, Err(PasswordError::TooLong(200))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_new_password_max_length_counts_bytes() {
+        let policy = PasswordPolicy {
+            max_length: 20,
+            ..PasswordPolicy::default()
+        };
+
+        // 10 characters, but 30 bytes
+        let result = Password::new_with_policy(&format!("{}犬", "日本語".repeat(3)), &policy);
+        assert!(matches!(result, Err(PasswordError::TooLong(20))));
+    }
+
+    #[test]
+    fn test_new_password_min_length_counts_characters() -> TestResult {
+        // 3 characters, but 12 bytes
+        let result = Password::new("🦀🔒🐴");
+        assert!(matches!(result, Err(PasswordError::TooShort(8))));
+
+        // 9 characters, well over 8 bytes
+        Password::new("🦀🔒🐴 staple🔋")?;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "zxcvbn")]
     #[test]
     fn test_new_password_too_weak() {
         let result = Password::new("weakpassword");
         assert!(result.is_err());
         assert!(matches!(result, Err(PasswordError::TooWeak(_))));
     }
+
+    #[cfg(not(feature = "zxcvbn"))]
+    #[test]
+    fn test_fallback_rejects_weak_but_long_passwords() {
+        for raw in [
+            "password",
+            "Password123!",
+            "qwertyuiop",
+            "1234567890",
+            "letmein2024",
+            "aaaaaaaaaaaaaaaa",
+            "abababababababab",
+        ] {
+            let result = Password::new(raw);
+            assert!(
+                matches!(result, Err(PasswordError::TooWeak(_))),
+                "{raw} was accepted"
+            );
+        }
+    }
+
+    #[cfg(not(feature = "zxcvbn"))]
+    #[test]
+    fn test_fallback_still_enforces_length() {
+        assert!(matches!(
+            Password::new("short"),
+            Err(PasswordError::TooShort(8))
+        ));
+        assert!(matches!(
+            Password::new(&"correcthorse".repeat(9)),
+            Err(PasswordError::TooLong(100))
+        ));
+    }
+
+    #[cfg(not(feature = "zxcvbn"))]
+    #[test]
+    fn test_fallback_accepts_uncommon_passwords() -> TestResult {
+        Password::new("correcthorsebatterystaple")?;
+        Password::new("tr0ub4dor&3")?;
+
+        Ok(())
+    }
 }
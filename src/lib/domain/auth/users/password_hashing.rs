@@ -0,0 +1,157 @@
+//! Password hashing
+//!
+//! Passwords are hashed with Argon2id. Its cost can be raised on hardware which can afford it, or
+//! lowered where speed matters more than strength, such as in CI. Each hash records the
+//! parameters it was made with, so changing them doesn't stop existing hashes being verified.
+
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHasher, SaltString},
+    Algorithm, Argon2, Params, Version,
+};
+use clap::Parser;
+use thiserror::Error;
+
+use crate::domain::auth::users::Password;
+
+/// Errors in the password hashing configuration
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PasswordHashingConfigError {
+    /// The parameters aren't ones Argon2 accepts
+    #[error("Invalid password hashing parameters: {0}")]
+    InvalidParams(String),
+}
+
+/// Password hashing configuration
+#[derive(Clone, Debug, PartialEq, Eq, Parser)]
+pub struct PasswordHashingConfig {
+    /// The memory used to hash a password, in KiB. At least 8 KiB per lane of parallelism
+    #[arg(
+        long = "password-hash-memory-kib",
+        env = "PASSWORD_HASH_MEMORY_KIB",
+        default_value = "19456"
+    )]
+    pub memory_kib: u32,
+
+    /// The number of passes over the memory
+    #[arg(
+        long = "password-hash-iterations",
+        env = "PASSWORD_HASH_ITERATIONS",
+        default_value = "2"
+    )]
+    pub iterations: u32,
+
+    /// The number of lanes hashed in parallel
+    #[arg(
+        long = "password-hash-parallelism",
+        env = "PASSWORD_HASH_PARALLELISM",
+        default_value = "1"
+    )]
+    pub parallelism: u32,
+}
+
+impl Default for PasswordHashingConfig {
+    fn default() -> Self {
+        Self {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl PasswordHashingConfig {
+    /// Checks Argon2 accepts the parameters
+    pub fn validate(&self) -> Result<(), PasswordHashingConfigError> {
+        self.hasher().map(|_| ())
+    }
+
+    /// Hashes `password` with a random salt, into a PHC string recording the parameters used
+    pub fn hash(&self, password: &Password) -> String {
+        let salt = SaltString::generate(&mut OsRng);
+
+        self.hasher()
+            .expect("password hashing config is validated at startup")
+            .hash_password(password.as_bytes(), &salt)
+            .expect("a password can be hashed with valid parameters")
+            .to_string()
+    }
+
+    fn hasher(&self) -> Result<Argon2<'static>, PasswordHashingConfigError> {
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, None)
+            .map_err(|err| PasswordHashingConfigError::InvalidParams(err.to_string()))?;
+
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use password_auth::{generate_hash, verify_password};
+    use testresult::TestResult;
+
+    use super::*;
+
+    fn cheap() -> PasswordHashingConfig {
+        PasswordHashingConfig {
+            memory_kib: 8,
+            iterations: 1,
+            parallelism: 1,
+        }
+    }
+
+    #[test]
+    fn test_hashes_record_their_parameters() {
+        let password = Password::new_unchecked("correcthorsebatterystaple");
+
+        let default = PasswordHashingConfig::default().hash(&password);
+        let custom = cheap().hash(&password);
+
+        assert!(default.contains("m=19456,t=2,p=1"));
+        assert!(custom.contains("m=8,t=1,p=1"));
+    }
+
+    #[test]
+    fn test_hashes_verify_whatever_the_parameters() -> TestResult {
+        let password = Password::new_unchecked("correcthorsebatterystaple");
+
+        for hash in [
+            PasswordHashingConfig::default().hash(&password),
+            cheap().hash(&password),
+            generate_hash("correcthorsebatterystaple"),
+        ] {
+            verify_password("correcthorsebatterystaple", &hash)?;
+            assert!(verify_password("wrong password", &hash).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_default_parameters_are_the_library_defaults() {
+        let password = Password::new_unchecked("correcthorsebatterystaple");
+        let library = generate_hash("correcthorsebatterystaple");
+        let params = library.split('$').nth(3);
+
+        assert_eq!(
+            PasswordHashingConfig::default()
+                .hash(&password)
+                .split('$')
+                .nth(3),
+            params
+        );
+    }
+
+    #[test]
+    fn test_invalid_parameters_are_rejected() {
+        let config = PasswordHashingConfig {
+            memory_kib: 1,
+            ..PasswordHashingConfig::default()
+        };
+
+        assert!(matches!(
+            config.validate(),
+            Err(PasswordHashingConfigError::InvalidParams(_))
+        ));
+        assert_eq!(PasswordHashingConfig::default().validate(), Ok(()));
+    }
+}
@@ -0,0 +1,134 @@
+//! Unconfirmed user purge
+
+use std::sync::Arc;
+
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info};
+
+use crate::domain::{
+    auth::users::{errors::DeleteUserError, UnconfirmedUserPurgeConfig, UserRepository},
+    clock::Clock,
+};
+
+/// Periodically deletes users who never confirmed their email address within the grace period
+#[derive(Clone, Debug)]
+pub struct UnconfirmedUserPurge<R: UserRepository, C: Clock> {
+    user_repo: Arc<R>,
+    clock: Arc<C>,
+    config: UnconfirmedUserPurgeConfig,
+}
+
+impl<R: UserRepository, C: Clock> UnconfirmedUserPurge<R, C> {
+    /// Create a new purge of the unconfirmed users in `user_repo`
+    pub fn new(user_repo: Arc<R>, clock: Arc<C>, config: UnconfirmedUserPurgeConfig) -> Self {
+        Self {
+            user_repo,
+            clock,
+            config,
+        }
+    }
+
+    /// Deletes the users created before the grace period who still haven't confirmed their email
+    /// address, returning how many were deleted
+    pub async fn purge(&self) -> Result<u64, DeleteUserError> {
+        let cutoff = self.clock.now() - self.config.grace_period();
+
+        self.user_repo.delete_unconfirmed_before(&cutoff).await
+    }
+
+    /// Purges every interval, starting straight away, until `token` is cancelled. Returns
+    /// immediately if purging is disabled
+    pub async fn run(self, token: CancellationToken) {
+        if !self.config.purge_unconfirmed_users {
+            return;
+        }
+
+        let mut interval = tokio::time::interval(self.config.interval());
+
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => return,
+                _ = interval.tick() => {}
+            }
+
+            match self.purge().await {
+                Ok(deleted) => info!("purged {deleted} unconfirmed users"),
+                Err(err) => error!("failed to purge unconfirmed users: {:?}", err),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration, Utc};
+    use testresult::TestResult;
+
+    use crate::domain::{auth::users::tests::MockUserRepository, clock::tests::MockClock};
+
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        "2026-10-15T12:00:00Z".parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_purge_deletes_users_unconfirmed_for_the_grace_period() -> TestResult {
+        let mut repo = MockUserRepository::new();
+
+        repo.expect_delete_unconfirmed_before()
+            .times(1)
+            .withf(|cutoff| *cutoff == now() - Duration::days(3))
+            .returning(|_| Ok(2));
+
+        let purge = UnconfirmedUserPurge::new(
+            Arc::new(repo),
+            Arc::new(MockClock::new(now())),
+            UnconfirmedUserPurgeConfig {
+                unconfirmed_user_grace_days: 3,
+                ..UnconfirmedUserPurgeConfig::default()
+            },
+        );
+
+        assert_eq!(purge.purge().await?, 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_disabled_purge_deletes_nothing() {
+        let mut repo = MockUserRepository::new();
+
+        repo.expect_delete_unconfirmed_before().times(0);
+
+        let purge = UnconfirmedUserPurge::new(
+            Arc::new(repo),
+            Arc::new(MockClock::new(now())),
+            UnconfirmedUserPurgeConfig {
+                purge_unconfirmed_users: false,
+                ..UnconfirmedUserPurgeConfig::default()
+            },
+        );
+
+        purge.run(CancellationToken::new()).await;
+    }
+
+    #[tokio::test]
+    async fn test_run_stops_once_cancelled() {
+        let mut repo = MockUserRepository::new();
+
+        repo.expect_delete_unconfirmed_before().returning(|_| Ok(0));
+
+        let token = CancellationToken::new();
+        let purge = UnconfirmedUserPurge::new(
+            Arc::new(repo),
+            Arc::new(MockClock::new(now())),
+            UnconfirmedUserPurgeConfig::default(),
+        );
+
+        let running = tokio::spawn(purge.run(token.clone()));
+        token.cancel();
+
+        running.await.unwrap();
+    }
+}
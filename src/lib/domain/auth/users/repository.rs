@@ -1,6 +1,7 @@
 //! User repository module
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
 #[cfg(test)]
@@ -8,35 +9,96 @@ use mockall::mock;
 
 use crate::domain::{
     auth::users::{
-        errors::{CreateUserError, GetUserByIdError, UpdateUserError},
-        NewUser, User,
+        errors::{
+            CreateUserError, DeleteUserError, EmailExistsError, GetUserByIdError, ListUsersError,
+            UpdateUserError,
+        },
+        NewUser, User, UserCursor, UserFilter,
     },
-    communication::email_addresses::EmailAddress,
+    communication::email_addresses::{EmailAddress, RequestOrigin},
 };
 
 /// User repository
 #[async_trait]
 pub trait UserRepository: Clone + Send + Sync + 'static {
-    /// Create a new user
-    async fn create_user(&self, user: &NewUser) -> Result<Uuid, CreateUserError>;
+    /// Create a new user, returning them as they were stored
+    async fn create_user(&self, user: &NewUser) -> Result<User, CreateUserError>;
+
+    /// Create a new user, as an admin if there are no other users. The emptiness check and the
+    /// insert must be atomic, so that two concurrent signups can't both become admins
+    async fn create_user_admin_if_first(&self, user: &NewUser) -> Result<User, CreateUserError>;
+
+    /// Create several users in one statement, so either all of them are created or, if any of
+    /// them can't be, none are
+    async fn create_users(&self, users: &[NewUser]) -> Result<Vec<Uuid>, CreateUserError>;
 
     /// Get a user by their ID
     async fn get_user_by_id(&self, id: &Uuid) -> Result<User, GetUserByIdError>;
 
-    /// Update the email confirmation token for a user
+    /// List users matching `filter`, oldest first, continuing after the `after` cursor if given
+    async fn list_users(
+        &self,
+        filter: &UserFilter,
+        after: Option<UserCursor>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<User>, ListUsersError>;
+
+    /// Whether any user has `email`, compared by its normalized form
+    async fn email_exists(&self, email: &EmailAddress) -> Result<bool, EmailExistsError>;
+
+    /// Delete a user by their ID
+    async fn delete_user(&self, id: &Uuid) -> Result<(), DeleteUserError>;
+
+    /// Irreversibly erase a user's personal data, keeping their row so that what's retained for
+    /// legal reasons still refers to it, and mark them deleted so they are no longer found. Any
+    /// recorded idempotent responses containing them are removed along with it. Fails with
+    /// [`DeleteUserError::UserNotFound`] if there is no such user, or they are already erased
+    async fn erase_user(&self, id: &Uuid) -> Result<(), DeleteUserError>;
+
+    /// Delete every user, other than admins, who was created before `cutoff` and has never
+    /// confirmed their email address, returning how many were deleted
+    async fn delete_unconfirmed_before(
+        &self,
+        cutoff: &DateTime<Utc>,
+    ) -> Result<u64, DeleteUserError>;
+
+    /// Update the email confirmation token for a user, storing only its hash along with the
+    /// `origin` it was requested from. Fails with [`UpdateUserError::Conflict`] if the user is no
+    /// longer at `expected_version`.
     async fn initialize_email_confirmation<'a>(
         &self,
         user_id: &Uuid,
         token: &str,
+        expires_at: &DateTime<Utc>,
         new_email: Option<&'a EmailAddress>,
+        origin: &RequestOrigin,
+        expected_version: i32,
     ) -> Result<(), UpdateUserError>;
 
-    /// Update the email confirmed date for a user
+    /// Update the email confirmed date for a user, consuming their email confirmation token.
+    /// Fails with [`UpdateUserError::ConfirmationTokenMismatch`] if `token` is no longer the
+    /// user's outstanding token, so that a token can only ever be used once.
     async fn complete_email_confirmation<'a>(
         &self,
         user_id: &Uuid,
+        token: &str,
         new_email: Option<&'a EmailAddress>,
     ) -> Result<(), UpdateUserError>;
+
+    /// Confirm a user's current email address without a token, as an admin can. Does nothing if it
+    /// is already confirmed, and leaves any pending email change and its token in place. Fails
+    /// with [`UpdateUserError::UserNotFound`] if there is no such user
+    async fn force_email_confirmation(&self, user_id: &Uuid) -> Result<(), UpdateUserError>;
+
+    /// Cancel a user's pending email change, clearing the new email address and its outstanding
+    /// confirmation token. Fails with [`UpdateUserError::NoPendingEmailChange`] if there isn't one,
+    /// or [`UpdateUserError::Conflict`] if the user is no longer at `expected_version`
+    async fn cancel_email_change(
+        &self,
+        user_id: &Uuid,
+        expected_version: i32,
+    ) -> Result<(), UpdateUserError>;
 }
 
 #[cfg(test)]
@@ -49,14 +111,26 @@ mock! {
 
     #[async_trait]
     impl UserRepository for UserRepository {
-        async fn create_user(&self, user: &NewUser) -> Result<Uuid, CreateUserError>;
+        async fn create_user(&self, user: &NewUser) -> Result<User, CreateUserError>;
+        async fn create_user_admin_if_first(&self, user: &NewUser) -> Result<User, CreateUserError>;
+        async fn create_users(&self, users: &[NewUser]) -> Result<Vec<Uuid>, CreateUserError>;
         async fn get_user_by_id(&self, id: &Uuid) -> Result<User, GetUserByIdError>;
+        async fn list_users(&self, filter: &UserFilter, after: Option<UserCursor>, limit: i64, offset: i64) -> Result<Vec<User>, ListUsersError>;
+        async fn email_exists(&self, email: &EmailAddress) -> Result<bool, EmailExistsError>;
+        async fn delete_user(&self, id: &Uuid) -> Result<(), DeleteUserError>;
+        async fn erase_user(&self, id: &Uuid) -> Result<(), DeleteUserError>;
+        async fn delete_unconfirmed_before(&self, cutoff: &DateTime<Utc>) -> Result<u64, DeleteUserError>;
         async fn initialize_email_confirmation<'a>(
             &self,
             user_id: &Uuid,
             token: &str,
+            expires_at: &DateTime<Utc>,
             new_email: Option<&'a EmailAddress>,
+            origin: &RequestOrigin,
+            expected_version: i32,
         ) -> Result<(), UpdateUserError>;
-        async fn complete_email_confirmation<'a>(&self, user_id: &Uuid, new_email: Option<&'a EmailAddress>) -> Result<(), UpdateUserError>;
+        async fn complete_email_confirmation<'a>(&self, user_id: &Uuid, token: &str, new_email: Option<&'a EmailAddress>) -> Result<(), UpdateUserError>;
+        async fn force_email_confirmation(&self, user_id: &Uuid) -> Result<(), UpdateUserError>;
+        async fn cancel_email_change(&self, user_id: &Uuid, expected_version: i32) -> Result<(), UpdateUserError>;
     }
 }
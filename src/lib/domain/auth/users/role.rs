@@ -0,0 +1,92 @@
+//! User roles
+
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// An error that can occur when parsing a role
+#[derive(Debug, Error)]
+#[error("unknown role: {0}")]
+pub struct UnknownRoleError(String);
+
+/// A user's role, used for authorization
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// A regular user
+    #[default]
+    User,
+
+    /// An administrator
+    Admin,
+}
+
+impl Role {
+    /// Whether this role grants at least the permissions of the `required` role
+    pub fn satisfies(&self, required: Role) -> bool {
+        match self {
+            Self::Admin => true,
+            Self::User => required == Self::User,
+        }
+    }
+
+    /// The role as stored in the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::User => "user",
+            Self::Admin => "admin",
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Role {
+    type Err = UnknownRoleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "user" => Ok(Self::User),
+            "admin" => Ok(Self::Admin),
+            _ => Err(UnknownRoleError(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use testresult::TestResult;
+
+    use super::*;
+
+    #[test]
+    fn test_role_round_trips_through_string() -> TestResult {
+        for role in [Role::User, Role::Admin] {
+            assert_eq!(role.to_string().parse::<Role>()?, role);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_admin_satisfies_every_role() {
+        assert!(Role::Admin.satisfies(Role::Admin));
+        assert!(Role::Admin.satisfies(Role::User));
+    }
+
+    #[test]
+    fn test_user_does_not_satisfy_admin() {
+        assert!(Role::User.satisfies(Role::User));
+        assert!(!Role::User.satisfies(Role::Admin));
+    }
+
+    #[test]
+    fn test_unknown_role_is_an_error() {
+        assert!("superuser".parse::<Role>().is_err());
+    }
+}
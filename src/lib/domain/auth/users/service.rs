@@ -9,9 +9,16 @@ use uuid::Uuid;
 #[cfg(test)]
 use mockall::mock;
 
-use crate::domain::auth::users::{
-    errors::{CreateUserError, GetUserByIdError},
-    NewUser, User, UserRepository,
+use crate::domain::{
+    auth::users::{
+        errors::{
+            CreateUserError, DeleteUserError, EmailExistsError, GetUserByIdError, ListUsersError,
+        },
+        NewUser, User, UserBootstrapConfig, UserCursor, UserFilter, UserRepository,
+    },
+    clock::Clock,
+    communication::email_addresses::{EmailAddress, RequestOrigin},
+    events::{DomainEvent, EventBus},
 };
 
 /// User service
@@ -21,11 +28,47 @@ pub trait UserService: Clone + Send + Sync + 'static {
     ///
     /// # Arguments
     /// * `user` - A reference to a [`NewUser`] containing the user details.
+    /// * `origin` - The client creating the user, recorded in the audit log.
     ///
     /// # Returns
-    /// A [`Result`] which is [`Ok`] containing the user's UUID if the user is successfully created,
-    /// or an [`Err`] containing a [`CreateUserError`] if the user cannot be created.
-    async fn create_user(&self, user: &NewUser) -> Result<Uuid, CreateUserError>;
+    /// A [`Result`] which is [`Ok`] containing the [`User`] as they were stored if the user is
+    /// successfully created, or an [`Err`] containing a [`CreateUserError`] if the user cannot be
+    /// created.
+    async fn create_user(
+        &self,
+        user: &NewUser,
+        origin: &RequestOrigin,
+    ) -> Result<User, CreateUserError>;
+
+    /// Creates several users, either all of them or, if any of them can't be, none.
+    ///
+    /// # Arguments
+    /// * `users` - The [`NewUser`]s to create.
+    /// * `origin` - The client creating the users, recorded in the audit log.
+    ///
+    /// # Returns
+    /// A [`Result`] which is [`Ok`] containing the users' UUIDs, in order, if they were all
+    /// created, or an [`Err`] containing the [`CreateUserError`] which stopped them.
+    async fn create_users(
+        &self,
+        users: &[NewUser],
+        origin: &RequestOrigin,
+    ) -> Result<Vec<Uuid>, CreateUserError>;
+
+    /// Creates several users independently, so that some can fail while the rest are created.
+    ///
+    /// # Arguments
+    /// * `users` - The [`NewUser`]s to create.
+    /// * `origin` - The client creating the users, recorded in the audit log.
+    ///
+    /// # Returns
+    /// The result of creating each user, in order: [`Ok`] containing their UUID, or an [`Err`]
+    /// containing the [`CreateUserError`] if they couldn't be created.
+    async fn create_users_independently(
+        &self,
+        users: &[NewUser],
+        origin: &RequestOrigin,
+    ) -> Vec<Result<Uuid, CreateUserError>>;
 
     /// Retrieves a user by their ID.
     ///
@@ -36,6 +79,60 @@ pub trait UserService: Clone + Send + Sync + 'static {
     /// A [`Result`] which is [`Ok`] containing the [`User`] if found,
     /// or an [`Err`] containing a [`GetUserError`] if the user cannot be found.
     async fn get_user_by_id(&self, id: &Uuid) -> Result<User, GetUserByIdError>;
+
+    /// Lists users matching a filter, oldest first.
+    ///
+    /// # Arguments
+    /// * `filter` - The [`UserFilter`] users must match.
+    /// * `after` - The [`UserCursor`] of the last user on the previous page, if any.
+    /// * `limit` - The maximum number of users to return.
+    /// * `offset` - The number of users to skip.
+    ///
+    /// # Returns
+    /// A [`Result`] which is [`Ok`] containing the page of [`User`]s,
+    /// or an [`Err`] containing a [`ListUsersError`] if the users cannot be listed.
+    async fn list_users(
+        &self,
+        filter: &UserFilter,
+        after: Option<UserCursor>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<User>, ListUsersError>;
+
+    /// Checks whether an email address is already in use.
+    ///
+    /// # Arguments
+    /// * `email` - The email address to look for, which is compared by its normalized form.
+    ///
+    /// # Returns
+    /// A [`Result`] which is [`Ok`] containing whether any user has the email address,
+    /// or an [`Err`] containing an [`EmailExistsError`] if it cannot be checked.
+    async fn email_exists(&self, email: &EmailAddress) -> Result<bool, EmailExistsError>;
+
+    /// Deletes a user by their ID.
+    ///
+    /// # Arguments
+    /// * `id` - The UUID of the user to delete.
+    ///
+    /// # Returns
+    /// A [`Result`] which is [`Ok`] if the user was deleted,
+    /// or an [`Err`] containing a [`DeleteUserError`] if the user cannot be deleted.
+    async fn delete_user(&self, id: &Uuid) -> Result<(), DeleteUserError>;
+
+    /// Irreversibly erases a user's account at their own request.
+    ///
+    /// Their email addresses, password, username and any outstanding confirmation token are
+    /// scrubbed. Their ID, role, locale, timestamps and record of accepting the terms are kept,
+    /// as is the audit log, which refers to them only by ID.
+    ///
+    /// # Arguments
+    /// * `id` - The UUID of the user to erase.
+    /// * `origin` - The client erasing the user, recorded in the audit log.
+    ///
+    /// # Returns
+    /// A [`Result`] which is [`Ok`] if the user was erased,
+    /// or an [`Err`] containing a [`DeleteUserError`] if the user cannot be erased.
+    async fn erase_user(&self, id: &Uuid, origin: &RequestOrigin) -> Result<(), DeleteUserError>;
 }
 
 #[cfg(test)]
@@ -48,42 +145,155 @@ mock! {
 
     #[async_trait]
     impl UserService for UserService {
-        async fn create_user(&self, req: &NewUser) -> Result<Uuid, CreateUserError>;
+        async fn create_user(&self, req: &NewUser, origin: &RequestOrigin) -> Result<User, CreateUserError>;
+        async fn create_users(&self, users: &[NewUser], origin: &RequestOrigin) -> Result<Vec<Uuid>, CreateUserError>;
+        async fn create_users_independently(&self, users: &[NewUser], origin: &RequestOrigin) -> Vec<Result<Uuid, CreateUserError>>;
         async fn get_user_by_id(&self, id: &Uuid) -> Result<User, GetUserByIdError>;
+        async fn list_users(&self, filter: &UserFilter, after: Option<UserCursor>, limit: i64, offset: i64) -> Result<Vec<User>, ListUsersError>;
+        async fn email_exists(&self, email: &EmailAddress) -> Result<bool, EmailExistsError>;
+        async fn delete_user(&self, id: &Uuid) -> Result<(), DeleteUserError>;
+        async fn erase_user(&self, id: &Uuid, origin: &RequestOrigin) -> Result<(), DeleteUserError>;
     }
 }
 
 /// User service implementation
 #[derive(Debug, Clone)]
-pub struct UserServiceImpl<R>
+pub struct UserServiceImpl<R, C>
 where
     R: UserRepository,
+    C: Clock,
 {
     repo: Arc<R>,
+    events: EventBus,
+    clock: Arc<C>,
+    config: UserBootstrapConfig,
 }
 
-impl<R> UserServiceImpl<R>
+impl<R, C> UserServiceImpl<R, C>
 where
     R: UserRepository,
+    C: Clock,
 {
     /// Create a new user service
-    pub fn new(repo: Arc<R>) -> Self {
-        Self { repo }
+    pub fn new(repo: Arc<R>, events: EventBus, clock: Arc<C>, config: UserBootstrapConfig) -> Self {
+        Self {
+            repo,
+            events,
+            clock,
+            config,
+        }
+    }
+
+    /// Publishes that `user` was created with `id` by the client at `origin`
+    async fn notify_created(&self, id: Uuid, user: &NewUser, origin: &RequestOrigin) {
+        self.events
+            .publish(DomainEvent::UserCreated {
+                user_id: id,
+                email: user.email().clone(),
+                origin: origin.clone(),
+                occurred_at: self.clock.now(),
+            })
+            .await;
     }
 }
 
 #[async_trait]
-impl<R> UserService for UserServiceImpl<R>
+impl<R, C> UserService for UserServiceImpl<R, C>
 where
     R: UserRepository,
+    C: Clock,
 {
-    async fn create_user(&self, req: &NewUser) -> Result<Uuid, CreateUserError> {
-        self.repo.create_user(req).await
+    async fn create_user(
+        &self,
+        req: &NewUser,
+        origin: &RequestOrigin,
+    ) -> Result<User, CreateUserError> {
+        let user = if self.config.first_user_admin {
+            self.repo.create_user_admin_if_first(req).await?
+        } else {
+            self.repo.create_user(req).await?
+        };
+
+        self.notify_created(user.id, req, origin).await;
+
+        Ok(user)
+    }
+
+    async fn create_users(
+        &self,
+        users: &[NewUser],
+        origin: &RequestOrigin,
+    ) -> Result<Vec<Uuid>, CreateUserError> {
+        let ids = self.repo.create_users(users).await?;
+
+        for (id, user) in ids.iter().zip(users) {
+            self.notify_created(*id, user, origin).await;
+        }
+
+        Ok(ids)
+    }
+
+    async fn create_users_independently(
+        &self,
+        users: &[NewUser],
+        origin: &RequestOrigin,
+    ) -> Vec<Result<Uuid, CreateUserError>> {
+        // Usually none of them conflict, so they're created in one statement, and only one at a
+        // time to find out which ones do if that fails
+        if let Ok(ids) = self.create_users(users, origin).await {
+            return ids.into_iter().map(Ok).collect();
+        }
+
+        let mut results = Vec::with_capacity(users.len());
+
+        for user in users {
+            let result = self.repo.create_user(user).await.map(|created| created.id);
+
+            if let Ok(id) = result {
+                self.notify_created(id, user, origin).await;
+            }
+
+            results.push(result);
+        }
+
+        results
     }
 
     async fn get_user_by_id(&self, id: &Uuid) -> Result<User, GetUserByIdError> {
         self.repo.get_user_by_id(id).await
     }
+
+    async fn list_users(
+        &self,
+        filter: &UserFilter,
+        after: Option<UserCursor>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<User>, ListUsersError> {
+        self.repo.list_users(filter, after, limit, offset).await
+    }
+
+    async fn email_exists(&self, email: &EmailAddress) -> Result<bool, EmailExistsError> {
+        self.repo.email_exists(email).await
+    }
+
+    async fn delete_user(&self, id: &Uuid) -> Result<(), DeleteUserError> {
+        self.repo.delete_user(id).await
+    }
+
+    async fn erase_user(&self, id: &Uuid, origin: &RequestOrigin) -> Result<(), DeleteUserError> {
+        self.repo.erase_user(id).await?;
+
+        self.events
+            .publish(DomainEvent::UserErased {
+                user_id: *id,
+                origin: origin.clone(),
+                occurred_at: self.clock.now(),
+            })
+            .await;
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -97,8 +307,12 @@ mod tests {
     use uuid::Uuid;
 
     use crate::domain::{
-        auth::users::{tests::MockUserRepository, NewUser, Password},
-        communication::email_addresses::EmailAddress,
+        auth::users::{tests::MockUserRepository, NewUser, Password, Role, UserBootstrapConfig},
+        clock::SystemClock,
+        communication::email_addresses::{EmailAddress, RequestOrigin},
+        events::tests::recording_bus,
+        i18n::Locale,
+        timestamps::Timestamps,
     };
 
     use super::*;
@@ -111,20 +325,154 @@ mod tests {
             EmailAddress::new_unchecked("email@example.com"),
             Password::new("correcthorsebatterystaple")?,
         );
-        let expected_id = user.id().clone();
+        let expected_id = *user.id();
 
         let mut mock = MockUserRepository::new();
 
         mock.expect_create_user()
             .times(1)
             .with(eq(user.clone()))
-            .returning(move |_| Ok(expected_id));
+            .returning(move |_| {
+                Ok(User {
+                    id: expected_id,
+                    ..User::default()
+                })
+            });
+
+        let service = UserServiceImpl::new(
+            Arc::new(mock),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            UserBootstrapConfig::default(),
+        );
+
+        let created = service
+            .create_user(&user, &RequestOrigin::default())
+            .await?;
+
+        assert_eq!(&created.id, user.id());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_user_publishes_user_created() -> TestResult {
+        let user = NewUser::new(
+            Uuid::now_v7(),
+            EmailAddress::new_unchecked("email@example.com"),
+            Password::new("correcthorsebatterystaple")?,
+        );
+        let expected_id = *user.id();
+
+        let mut mock = MockUserRepository::new();
+
+        mock.expect_create_user().times(1).returning(move |_| {
+            Ok(User {
+                id: expected_id,
+                ..User::default()
+            })
+        });
+
+        let (events, recorder) = recording_bus();
+
+        let service = UserServiceImpl::new(
+            Arc::new(mock),
+            events,
+            Arc::new(SystemClock),
+            UserBootstrapConfig::default(),
+        );
+
+        service
+            .create_user(&user, &RequestOrigin::default())
+            .await?;
+
+        let published = recorder.events();
+
+        assert_eq!(published.len(), 1);
+        assert!(matches!(
+            &published[0],
+            DomainEvent::UserCreated { user_id, email, .. }
+                if *user_id == expected_id && email.to_string() == "email@example.com"
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_user_with_first_user_admin_enabled() -> TestResult {
+        let user = NewUser::new(
+            Uuid::now_v7(),
+            EmailAddress::new_unchecked("email@example.com"),
+            Password::new("correcthorsebatterystaple")?,
+        );
+        let expected_id = *user.id();
+
+        let mut mock = MockUserRepository::new();
+
+        mock.expect_create_user().times(0);
+        mock.expect_create_user_admin_if_first()
+            .times(1)
+            .with(eq(user.clone()))
+            .returning(move |_| {
+                Ok(User {
+                    id: expected_id,
+                    ..User::default()
+                })
+            });
+
+        let service = UserServiceImpl::new(
+            Arc::new(mock),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            UserBootstrapConfig {
+                first_user_admin: true,
+            },
+        );
+
+        assert_eq!(
+            service
+                .create_user(&user, &RequestOrigin::default())
+                .await?
+                .id,
+            expected_id
+        );
 
-        let service = UserServiceImpl::new(Arc::new(mock));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_user_with_first_user_admin_disabled() -> TestResult {
+        let user = NewUser::new(
+            Uuid::now_v7(),
+            EmailAddress::new_unchecked("email@example.com"),
+            Password::new("correcthorsebatterystaple")?,
+        );
+        let expected_id = *user.id();
+
+        let mut mock = MockUserRepository::new();
 
-        let user_id = service.create_user(&user).await?;
+        mock.expect_create_user_admin_if_first().times(0);
+        mock.expect_create_user().times(1).returning(move |_| {
+            Ok(User {
+                id: expected_id,
+                ..User::default()
+            })
+        });
+
+        let service = UserServiceImpl::new(
+            Arc::new(mock),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            UserBootstrapConfig::default(),
+        );
 
-        assert_eq!(&user_id, user.id());
+        assert_eq!(
+            service
+                .create_user(&user, &RequestOrigin::default())
+                .await?
+                .id,
+            expected_id
+        );
 
         Ok(())
     }
@@ -145,9 +493,14 @@ mod tests {
             .with(eq(user.clone()))
             .returning(move |_req| Err(CreateUserError::DuplicateUser));
 
-        let service = UserServiceImpl::new(Arc::new(mock));
+        let service = UserServiceImpl::new(
+            Arc::new(mock),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            UserBootstrapConfig::default(),
+        );
 
-        let result = service.create_user(&user).await;
+        let result = service.create_user(&user, &RequestOrigin::default()).await;
 
         assert!(result.is_err());
         assert!(matches!(result, Err(CreateUserError::DuplicateUser)));
@@ -171,9 +524,14 @@ mod tests {
             .with(eq(user.clone()))
             .returning(move |_req| Err(CreateUserError::UnknownError(anyhow!("Unknown error"))));
 
-        let service = UserServiceImpl::new(Arc::new(mock));
+        let service = UserServiceImpl::new(
+            Arc::new(mock),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            UserBootstrapConfig::default(),
+        );
 
-        let result = service.create_user(&user).await;
+        let result = service.create_user(&user, &RequestOrigin::default()).await;
 
         assert!(result.is_err());
         assert!(matches!(result, Err(CreateUserError::UnknownError { .. })));
@@ -186,14 +544,19 @@ mod tests {
         let user_id = Uuid::now_v7();
 
         let user = User {
-            id: user_id.clone(),
+            id: user_id,
             email: EmailAddress::new_unchecked("mdcpepper@gmail.com"),
             new_email: None,
             email_confirmed_at: None,
             email_confirmation_token: None,
             email_confirmation_sent_at: None,
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
+            email_confirmation_expires_at: None,
+            role: Role::User,
+            terms_accepted_at: None,
+            terms_version: None,
+            locale: Locale::English,
+            timestamps: Timestamps::created(Utc::now()),
+            version: 1,
         };
 
         let expected_user = user.clone();
@@ -202,10 +565,15 @@ mod tests {
 
         repo.expect_get_user_by_id()
             .times(1)
-            .with(eq(user_id.clone()))
+            .with(eq(user_id))
             .returning(move |_| Ok(user.clone()));
 
-        let service = UserServiceImpl::new(Arc::new(repo));
+        let service = UserServiceImpl::new(
+            Arc::new(repo),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            UserBootstrapConfig::default(),
+        );
 
         let found_user = service.get_user_by_id(&user_id).await?;
 
@@ -214,6 +582,33 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_list_users_passes_filter_to_repository() -> TestResult {
+        let filter = UserFilter {
+            created_after: Some(Utc::now() - chrono::Duration::days(7)),
+            created_before: Some(Utc::now()),
+            confirmed: Some(true),
+        };
+
+        let mut repo = MockUserRepository::new();
+
+        repo.expect_list_users()
+            .times(1)
+            .with(eq(filter), eq(None), eq(10), eq(20))
+            .returning(|_, _, _, _| Ok(vec![User::default()]));
+
+        let service = UserServiceImpl::new(
+            Arc::new(repo),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            UserBootstrapConfig::default(),
+        );
+
+        assert_eq!(service.list_users(&filter, None, 10, 20).await?.len(), 1);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_get_user_by_id_not_found() -> TestResult {
         let user_id = Uuid::now_v7();
@@ -222,10 +617,15 @@ mod tests {
 
         mock.expect_get_user_by_id()
             .times(1)
-            .with(eq(user_id.clone()))
+            .with(eq(user_id))
             .returning(move |_| Err(GetUserByIdError::UserNotFound));
 
-        let service = UserServiceImpl::new(Arc::new(mock));
+        let service = UserServiceImpl::new(
+            Arc::new(mock),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            UserBootstrapConfig::default(),
+        );
 
         let result = service.get_user_by_id(&user_id).await;
 
@@ -234,4 +634,172 @@ mod tests {
 
         Ok(())
     }
+
+    fn new_users(emails: &[&str]) -> TestResult<Vec<NewUser>> {
+        emails
+            .iter()
+            .map(|email| {
+                Ok(NewUser::new(
+                    Uuid::now_v7(),
+                    EmailAddress::new(email)?,
+                    Password::new("correcthorsebatterystaple")?,
+                ))
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_create_users_independently_in_one_statement() -> TestResult {
+        let users = new_users(&["a@example.com", "b@example.com"])?;
+        let ids: Vec<Uuid> = users.iter().map(|user| *user.id()).collect();
+        let expected_ids = ids.clone();
+
+        let mut mock = MockUserRepository::new();
+
+        mock.expect_create_users()
+            .times(1)
+            .returning(move |_| Ok(ids.clone()));
+        mock.expect_create_user().times(0);
+
+        let service = UserServiceImpl::new(
+            Arc::new(mock),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            UserBootstrapConfig::default(),
+        );
+
+        let results = service
+            .create_users_independently(&users, &RequestOrigin::default())
+            .await;
+
+        assert_eq!(
+            results.into_iter().collect::<Result<Vec<Uuid>, _>>()?,
+            expected_ids
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_users_independently_falls_back_to_one_at_a_time() -> TestResult {
+        let users = new_users(&["a@example.com", "taken@example.com", "c@example.com"])?;
+        let first_id = *users[0].id();
+        let third_id = *users[2].id();
+
+        let mut mock = MockUserRepository::new();
+
+        mock.expect_create_users()
+            .times(1)
+            .returning(|_| Err(CreateUserError::DuplicateUser));
+        mock.expect_create_user().times(3).returning(|user| {
+            if user.email().to_string() == "taken@example.com" {
+                Err(CreateUserError::DuplicateUser)
+            } else {
+                Ok(User {
+                    id: *user.id(),
+                    ..User::default()
+                })
+            }
+        });
+
+        let service = UserServiceImpl::new(
+            Arc::new(mock),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            UserBootstrapConfig::default(),
+        );
+
+        let results = service
+            .create_users_independently(&users, &RequestOrigin::default())
+            .await;
+
+        assert!(matches!(results[0], Ok(id) if id == first_id));
+        assert!(matches!(results[1], Err(CreateUserError::DuplicateUser)));
+        assert!(matches!(results[2], Ok(id) if id == third_id));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_users_creates_none_if_any_conflict() -> TestResult {
+        let users = new_users(&["a@example.com", "taken@example.com"])?;
+
+        let mut mock = MockUserRepository::new();
+
+        mock.expect_create_users()
+            .times(1)
+            .returning(|_| Err(CreateUserError::DuplicateUser));
+        mock.expect_create_user().times(0);
+
+        let (events, recorder) = recording_bus();
+
+        let service = UserServiceImpl::new(
+            Arc::new(mock),
+            events,
+            Arc::new(SystemClock),
+            UserBootstrapConfig::default(),
+        );
+
+        let result = service
+            .create_users(&users, &RequestOrigin::default())
+            .await;
+
+        assert!(matches!(result, Err(CreateUserError::DuplicateUser)));
+        assert!(recorder.events().is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_erase_user_publishes_user_erased() -> TestResult {
+        let id = Uuid::now_v7();
+        let mut mock = MockUserRepository::new();
+
+        mock.expect_erase_user()
+            .times(1)
+            .with(eq(id))
+            .returning(|_| Ok(()));
+
+        let (events, recorder) = recording_bus();
+
+        let service = UserServiceImpl::new(
+            Arc::new(mock),
+            events,
+            Arc::new(SystemClock),
+            UserBootstrapConfig::default(),
+        );
+
+        service.erase_user(&id, &RequestOrigin::default()).await?;
+
+        assert!(matches!(
+            recorder.events().as_slice(),
+            [DomainEvent::UserErased { user_id, .. }] if *user_id == id
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_erase_missing_user_publishes_nothing() {
+        let mut mock = MockUserRepository::new();
+
+        mock.expect_erase_user()
+            .returning(|_| Err(DeleteUserError::UserNotFound));
+
+        let (events, recorder) = recording_bus();
+
+        let service = UserServiceImpl::new(
+            Arc::new(mock),
+            events,
+            Arc::new(SystemClock),
+            UserBootstrapConfig::default(),
+        );
+
+        let result = service
+            .erase_user(&Uuid::now_v7(), &RequestOrigin::default())
+            .await;
+
+        assert!(matches!(result, Err(DeleteUserError::UserNotFound)));
+        assert!(recorder.events().is_empty());
+    }
 }
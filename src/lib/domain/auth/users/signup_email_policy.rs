@@ -0,0 +1,250 @@
+//! Signup email policy
+
+use std::{fs, io, path::PathBuf};
+
+use clap::Parser;
+use thiserror::Error;
+
+use crate::domain::communication::email_addresses::{EmailAddress, EmailAddressError};
+
+/// Error loading a [`SignupEmailPolicy`]
+#[derive(Debug, Error)]
+pub enum SignupEmailPolicyError {
+    /// The blocked domains file couldn't be read
+    #[error("failed to read blocked email domains from {path}")]
+    UnreadableBlockedDomainsFile {
+        /// The path of the file
+        path: PathBuf,
+
+        /// Why it couldn't be read
+        #[source]
+        source: io::Error,
+    },
+}
+
+/// Which email domains new users may sign up with
+///
+/// A domain also covers its subdomains, so blocking `example.com` blocks `mail.example.com`
+/// too. Blocked domains win over allowed ones.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Parser)]
+pub struct SignupEmailPolicy {
+    /// The only email domains new users may sign up with. Any domain is allowed if empty
+    #[arg(
+        long = "signup-allowed-email-domains",
+        env = "SIGNUP_ALLOWED_EMAIL_DOMAINS",
+        value_delimiter = ','
+    )]
+    pub allowed_domains: Vec<String>,
+
+    /// Email domains new users may not sign up with
+    #[arg(
+        long = "signup-blocked-email-domains",
+        env = "SIGNUP_BLOCKED_EMAIL_DOMAINS",
+        value_delimiter = ','
+    )]
+    pub blocked_domains: Vec<String>,
+
+    /// A file of further blocked email domains, such as a list of disposable email providers,
+    /// with one domain per line. Blank lines and lines starting with `#` are ignored
+    #[arg(
+        long = "signup-blocked-email-domains-file",
+        env = "SIGNUP_BLOCKED_EMAIL_DOMAINS_FILE"
+    )]
+    pub blocked_domains_file: Option<PathBuf>,
+}
+
+impl SignupEmailPolicy {
+    /// Reads the blocked domains file into the blocked domains, and normalizes every domain
+    pub fn load(mut self) -> Result<Self, SignupEmailPolicyError> {
+        if let Some(path) = &self.blocked_domains_file {
+            let contents = fs::read_to_string(path).map_err(|source| {
+                SignupEmailPolicyError::UnreadableBlockedDomainsFile {
+                    path: path.clone(),
+                    source,
+                }
+            })?;
+
+            self.blocked_domains.extend(
+                contents
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(str::to_string),
+            );
+        }
+
+        self.allowed_domains = normalize(self.allowed_domains);
+        self.blocked_domains = normalize(self.blocked_domains);
+
+        Ok(self)
+    }
+
+    /// Checks that a new user may sign up with `email`
+    ///
+    /// # Returns
+    /// A [`Result`] which is [`Ok`] if the domain is allowed, or an [`Err`] containing
+    /// [`EmailAddressError::DomainNotAllowed`] if it is blocked or not on the allowlist.
+    pub fn check(&self, email: &EmailAddress) -> Result<(), EmailAddressError> {
+        let domain = email.domain();
+
+        let blocked = self
+            .blocked_domains
+            .iter()
+            .any(|entry| covers(entry, domain));
+        let allowed = self.allowed_domains.is_empty()
+            || self
+                .allowed_domains
+                .iter()
+                .any(|entry| covers(entry, domain));
+
+        if blocked || !allowed {
+            return Err(EmailAddressError::DomainNotAllowed);
+        }
+
+        Ok(())
+    }
+}
+
+/// Lowercases domains and strips the `@` or `.` they may have been written with
+fn normalize(domains: Vec<String>) -> Vec<String> {
+    domains
+        .into_iter()
+        .map(|domain| {
+            domain
+                .trim()
+                .trim_start_matches(['@', '.'])
+                .trim_end_matches('.')
+                .to_lowercase()
+        })
+        .filter(|domain| !domain.is_empty())
+        .collect()
+}
+
+/// Whether `entry` is `domain` or one of its parent domains
+fn covers(entry: &str, domain: &str) -> bool {
+    domain
+        .strip_suffix(entry)
+        .is_some_and(|rest| rest.is_empty() || rest.ends_with('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use testresult::TestResult;
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn email(raw: &str) -> EmailAddress {
+        EmailAddress::new(raw).unwrap()
+    }
+
+    #[test]
+    fn test_any_domain_is_allowed_without_a_policy() {
+        let policy = SignupEmailPolicy::default();
+
+        assert!(matches!(policy.check(&email("jane@example.com")), Ok(())));
+    }
+
+    #[test]
+    fn test_blocked_domain_is_rejected() -> TestResult {
+        let policy = SignupEmailPolicy {
+            blocked_domains: vec!["mailinator.com".to_string()],
+            ..SignupEmailPolicy::default()
+        }
+        .load()?;
+
+        assert!(matches!(
+            policy.check(&email("jane@mailinator.com")),
+            Err(EmailAddressError::DomainNotAllowed)
+        ));
+        assert!(matches!(
+            policy.check(&email("jane@eu.mailinator.com")),
+            Err(EmailAddressError::DomainNotAllowed)
+        ));
+        assert!(matches!(
+            policy.check(&email("jane@notmailinator.com")),
+            Ok(())
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_allowlist_rejects_other_domains() -> TestResult {
+        let policy = SignupEmailPolicy {
+            allowed_domains: vec!["@Example.com".to_string()],
+            ..SignupEmailPolicy::default()
+        }
+        .load()?;
+
+        assert!(matches!(policy.check(&email("jane@example.com")), Ok(())));
+        assert!(matches!(
+            policy.check(&email("jane@eng.example.com")),
+            Ok(())
+        ));
+        assert!(matches!(
+            policy.check(&email("jane@example.org")),
+            Err(EmailAddressError::DomainNotAllowed)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_blocked_domain_wins_over_allowed_domain() -> TestResult {
+        let policy = SignupEmailPolicy {
+            allowed_domains: vec!["example.com".to_string()],
+            blocked_domains: vec!["contractors.example.com".to_string()],
+            ..SignupEmailPolicy::default()
+        }
+        .load()?;
+
+        assert!(matches!(
+            policy.check(&email("jane@contractors.example.com")),
+            Err(EmailAddressError::DomainNotAllowed)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_blocked_domains_are_loaded_from_file() -> TestResult {
+        let path = env::temp_dir().join(format!("blocked-domains-{}.txt", Uuid::now_v7()));
+        fs::write(
+            &path,
+            "# Disposable providers\n\nmailinator.com\n  YOPmail.com  \n",
+        )?;
+
+        let policy = SignupEmailPolicy {
+            blocked_domains_file: Some(path.clone()),
+            ..SignupEmailPolicy::default()
+        }
+        .load();
+        fs::remove_file(&path)?;
+
+        let policy = policy?;
+
+        assert_eq!(policy.blocked_domains, ["mailinator.com", "yopmail.com"]);
+        assert!(matches!(
+            policy.check(&email("jane@yopmail.com")),
+            Err(EmailAddressError::DomainNotAllowed)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_blocked_domains_file_is_an_error() {
+        let policy = SignupEmailPolicy {
+            blocked_domains_file: Some(env::temp_dir().join(format!("{}.txt", Uuid::now_v7()))),
+            ..SignupEmailPolicy::default()
+        };
+
+        assert!(matches!(
+            policy.load(),
+            Err(SignupEmailPolicyError::UnreadableBlockedDomainsFile { .. })
+        ));
+    }
+}
@@ -1,10 +1,15 @@
 //! User model
 
 use chrono::{DateTime, Utc};
-use password_auth::generate_hash;
+use thiserror::Error;
 use uuid::Uuid;
 
-use crate::domain::{auth::users::Password, communication::email_addresses::EmailAddress};
+use crate::domain::{
+    auth::users::{Password, PasswordHashingConfig, Role, Username},
+    communication::email_addresses::EmailAddress,
+    i18n::Locale,
+    timestamps::Timestamps,
+};
 
 /// User model
 #[derive(Clone, Default, Debug, PartialEq, Eq)]
@@ -21,17 +26,34 @@ pub struct User {
     /// User email confirmed at date in UTC
     pub email_confirmed_at: Option<DateTime<Utc>>,
 
-    /// User email confirmation token
+    /// Hash of the user's email confirmation token, see [`hash_confirmation_token`]
+    ///
+    /// [`hash_confirmation_token`]: crate::domain::communication::email_addresses::hash_confirmation_token
     pub email_confirmation_token: Option<String>,
 
     /// User email confirmation sent at date in UTC
     pub email_confirmation_sent_at: Option<DateTime<Utc>>,
 
-    /// User created at date in UTC
-    pub created_at: DateTime<Utc>,
+    /// User email confirmation token expiry date in UTC
+    pub email_confirmation_expires_at: Option<DateTime<Utc>>,
 
-    /// User last updated at date in UTC
-    pub updated_at: DateTime<Utc>,
+    /// User role
+    pub role: Role,
+
+    /// When the user accepted the terms of service, if they did
+    pub terms_accepted_at: Option<DateTime<Utc>>,
+
+    /// The version of the terms of service the user accepted
+    pub terms_version: Option<String>,
+
+    /// The locale emails to the user are written in
+    pub locale: Locale,
+
+    /// When the user was created and last updated
+    pub timestamps: Timestamps,
+
+    /// Incremented each time the user is updated, so concurrent updates can be detected
+    pub version: i32,
 }
 
 /// Create user request
@@ -43,20 +65,36 @@ pub struct NewUser {
     /// New user's email address
     email: EmailAddress,
 
+    /// New user's username, if they chose one
+    username: Option<Username>,
+
     /// New user's password
     password_hash: String,
+
+    /// Whether the new user accepted the terms of service
+    accepted_terms: bool,
+
+    /// The version of the terms of service the new user accepted
+    terms_version: Option<String>,
+
+    /// The locale emails to the new user are written in
+    locale: Locale,
 }
 
 impl NewUser {
-    /// Create a new user request
-    pub fn new(id: Uuid, email: EmailAddress, password: Password) -> Self {
-        let password_hash = generate_hash(password.as_bytes());
+    /// Start building a new user request
+    pub fn builder() -> NewUserBuilder {
+        NewUserBuilder::default()
+    }
 
-        Self {
-            id,
-            email,
-            password_hash,
-        }
+    /// Create a new user request, hashing the password with the default parameters
+    pub fn new(id: Uuid, email: EmailAddress, password: Password) -> Self {
+        Self::builder()
+            .id(id)
+            .email(email)
+            .password(password)
+            .build()
+            .expect("every required field is set")
     }
 
     /// Get the new user's ID
@@ -69,20 +107,138 @@ impl NewUser {
         &self.email
     }
 
+    /// Get the new user's username
+    pub fn username(&self) -> Option<&Username> {
+        self.username.as_ref()
+    }
+
+    /// Get whether the new user accepted the terms of service
+    pub fn accepted_terms(&self) -> bool {
+        self.accepted_terms
+    }
+
+    /// Get the version of the terms of service the new user accepted
+    pub fn terms_version(&self) -> Option<&str> {
+        self.terms_version.as_deref()
+    }
+
+    /// Get the locale emails to the new user are written in
+    pub fn locale(&self) -> Locale {
+        self.locale
+    }
+
     /// Get the new user's password hash
     pub fn password_hash(&self) -> &str {
         &self.password_hash
     }
 }
 
+/// An error building a [`NewUser`]
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum NewUserBuilderError {
+    /// No email address was set
+    #[error("new user has no email address")]
+    MissingEmail,
+
+    /// No password was set
+    #[error("new user has no password")]
+    MissingPassword,
+}
+
+/// Builds a [`NewUser`], so optional fields can be added without changing how users are created
+///
+/// The email address and password are required. The ID defaults to a new UUIDv7, the password is
+/// hashed with the default parameters, no username or terms acceptance is recorded, and emails
+/// are written in the default locale.
+#[derive(Clone, Debug, Default)]
+pub struct NewUserBuilder {
+    id: Option<Uuid>,
+    email: Option<EmailAddress>,
+    password: Option<Password>,
+    hashing: PasswordHashingConfig,
+    username: Option<Username>,
+    accepted_terms: bool,
+    terms_version: Option<String>,
+    locale: Locale,
+}
+
+impl NewUserBuilder {
+    /// Set the new user's ID
+    pub fn id(mut self, id: Uuid) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    /// Set the new user's email address
+    pub fn email(mut self, email: EmailAddress) -> Self {
+        self.email = Some(email);
+        self
+    }
+
+    /// Set the new user's password
+    pub fn password(mut self, password: Password) -> Self {
+        self.password = Some(password);
+        self
+    }
+
+    /// Set the parameters the password is hashed with
+    pub fn hashing(mut self, hashing: PasswordHashingConfig) -> Self {
+        self.hashing = hashing;
+        self
+    }
+
+    /// Set the new user's username
+    pub fn username(mut self, username: Username) -> Self {
+        self.username = Some(username);
+        self
+    }
+
+    /// Record that the new user accepted the terms of service, and which version of them
+    pub fn accepted_terms(mut self, version: Option<String>) -> Self {
+        self.accepted_terms = true;
+        self.terms_version = version;
+        self
+    }
+
+    /// Set the locale emails to the new user are written in
+    pub fn locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Build the new user request, hashing the password
+    ///
+    /// # Returns
+    /// A [`Result`] containing the [`NewUser`], or an [`Err`] containing a
+    /// [`NewUserBuilderError`] if a required field wasn't set.
+    pub fn build(self) -> Result<NewUser, NewUserBuilderError> {
+        let email = self.email.ok_or(NewUserBuilderError::MissingEmail)?;
+        let password = self.password.ok_or(NewUserBuilderError::MissingPassword)?;
+
+        Ok(NewUser {
+            id: self.id.unwrap_or_else(Uuid::now_v7),
+            email,
+            username: self.username,
+            password_hash: self.hashing.hash(&password),
+            accepted_terms: self.accepted_terms,
+            terms_version: self.terms_version,
+            locale: self.locale,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use testresult::TestResult;
     use uuid::Uuid;
 
-    use crate::domain::{auth::users::Password, communication::email_addresses::EmailAddress};
+    use crate::domain::{
+        auth::users::{Password, Username},
+        communication::email_addresses::EmailAddress,
+        i18n::Locale,
+    };
 
-    use super::NewUser;
+    use super::{NewUser, NewUserBuilderError};
 
     #[test]
     fn create_user_request_hashes_password() -> TestResult {
@@ -96,4 +252,81 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn differently_cased_new_users_have_the_same_normalized_email() -> TestResult {
+        let a = NewUser::new(
+            Uuid::now_v7(),
+            EmailAddress::new("Email@Example.com")?,
+            Password::new("correcthorsebatterystaple")?,
+        );
+        let b = NewUser::new(
+            Uuid::now_v7(),
+            EmailAddress::new("email@example.com")?,
+            Password::new("correcthorsebatterystaple")?,
+        );
+
+        assert_eq!(a.email().normalized(), b.email().normalized());
+
+        Ok(())
+    }
+
+    #[test]
+    fn built_user_without_optional_fields_has_defaults() -> TestResult {
+        let user = NewUser::builder()
+            .email(EmailAddress::new("email@example.com")?)
+            .password(Password::new("correcthorsebatterystaple")?)
+            .build()?;
+
+        assert_eq!(user.email().to_string(), "email@example.com");
+        assert_eq!(user.username(), None);
+        assert!(!user.accepted_terms());
+        assert_eq!(user.terms_version(), None);
+        assert_eq!(user.locale(), Locale::default());
+        assert_eq!(user.id().get_version_num(), 7);
+        assert!(user.password_hash().starts_with("$argon2id$"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn built_user_with_optional_fields() -> TestResult {
+        let id = Uuid::now_v7();
+        let locale = Locale::French;
+
+        let user = NewUser::builder()
+            .id(id)
+            .email(EmailAddress::new("email@example.com")?)
+            .password(Password::new("correcthorsebatterystaple")?)
+            .username(Username::new("jane_doe")?)
+            .accepted_terms(Some("2024-08-01".to_string()))
+            .locale(locale)
+            .build()?;
+
+        assert_eq!(user.id(), &id);
+        assert_eq!(user.username(), Some(&Username::new("jane_doe")?));
+        assert!(user.accepted_terms());
+        assert_eq!(user.terms_version(), Some("2024-08-01"));
+        assert_eq!(user.locale(), locale);
+
+        Ok(())
+    }
+
+    #[test]
+    fn building_user_requires_email_and_password() -> TestResult {
+        assert_eq!(
+            NewUser::builder()
+                .password(Password::new("correcthorsebatterystaple")?)
+                .build(),
+            Err(NewUserBuilderError::MissingEmail)
+        );
+        assert_eq!(
+            NewUser::builder()
+                .email(EmailAddress::new("email@example.com")?)
+                .build(),
+            Err(NewUserBuilderError::MissingPassword)
+        );
+
+        Ok(())
+    }
 }
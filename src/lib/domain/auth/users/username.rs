@@ -0,0 +1,158 @@
+//! Username
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// The minimum length of a username, in characters
+pub const MIN_USERNAME_LENGTH: usize = 3;
+
+/// The maximum length of a username, in characters
+pub const MAX_USERNAME_LENGTH: usize = 32;
+
+/// Usernames which could be mistaken for the service itself, or clash with routes
+const RESERVED_USERNAMES: [&str; 16] = [
+    "about",
+    "account",
+    "admin",
+    "administrator",
+    "api",
+    "help",
+    "me",
+    "null",
+    "root",
+    "security",
+    "settings",
+    "support",
+    "system",
+    "undefined",
+    "user",
+    "www",
+];
+
+/// Username error
+#[derive(Debug, Error)]
+pub enum UsernameError {
+    /// Username is too short, contains the minimum length
+    #[error("Your username is too short. It must be at least {0} characters long.")]
+    TooShort(usize),
+
+    /// Username is too long, contains the maximum length
+    #[error("Your username is too long. It must be at most {0} characters long.")]
+    TooLong(usize),
+
+    /// Username contains characters other than lowercase letters, digits, `_` and `-`
+    #[error("Your username may only contain lowercase letters, digits, underscores and hyphens.")]
+    InvalidCharacters,
+
+    /// Username is reserved
+    #[error("That username is reserved.")]
+    Reserved,
+}
+
+/// A username
+///
+/// Usernames are lowercased before validation, so they are unique regardless of case, and may
+/// then only contain `a-z`, `0-9`, `_` and `-`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Username(String);
+
+impl Username {
+    /// Create a new username
+    pub fn new(raw: &str) -> Result<Self, UsernameError> {
+        let username = raw.trim().to_lowercase();
+
+        if username.len() < MIN_USERNAME_LENGTH {
+            return Err(UsernameError::TooShort(MIN_USERNAME_LENGTH));
+        }
+
+        if username.len() > MAX_USERNAME_LENGTH {
+            return Err(UsernameError::TooLong(MAX_USERNAME_LENGTH));
+        }
+
+        if !username
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+        {
+            return Err(UsernameError::InvalidCharacters);
+        }
+
+        if RESERVED_USERNAMES.contains(&username.as_str()) {
+            return Err(UsernameError::Reserved);
+        }
+
+        Ok(Self(username))
+    }
+
+    /// Create a new username without validation
+    pub fn new_unchecked(username: &str) -> Self {
+        Self(username.to_string())
+    }
+}
+
+impl fmt::Display for Username {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<Username> for String {
+    fn from(username: Username) -> Self {
+        username.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use testresult::TestResult;
+
+    use super::*;
+
+    #[test]
+    fn test_new_username() -> TestResult {
+        for raw in ["abc", "jane_doe", "jane-doe-99", &"a".repeat(32)] {
+            assert_eq!(Username::new(raw)?.to_string(), raw);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_new_username_is_lowercased() -> TestResult {
+        assert_eq!(Username::new(" Jane_Doe ")?.to_string(), "jane_doe");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_new_username_too_short() {
+        let result = Username::new("ab");
+        assert!(matches!(result, Err(UsernameError::TooShort(3))));
+    }
+
+    #[test]
+    fn test_new_username_too_long() {
+        let result = Username::new(&"a".repeat(33));
+        assert!(matches!(result, Err(UsernameError::TooLong(32))));
+    }
+
+    #[test]
+    fn test_new_username_invalid_characters() {
+        for raw in ["jane doe", "jane.doe", "jane@doe", "jänedoe"] {
+            let result = Username::new(raw);
+            assert!(
+                matches!(result, Err(UsernameError::InvalidCharacters)),
+                "{raw}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_new_username_reserved() {
+        for raw in ["admin", "Root", "support"] {
+            let result = Username::new(raw);
+            assert!(matches!(result, Err(UsernameError::Reserved)), "{raw}");
+        }
+    }
+}
@@ -0,0 +1,92 @@
+//! Background tasks module
+//!
+//! Work which outlives the request that started it, such as webhook deliveries, is spawned on
+//! [`BackgroundTasks`] rather than with [`tokio::spawn`], so it can be flushed before the process
+//! exits.
+
+use std::{future::Future, time::Duration};
+
+use tokio_util::task::TaskTracker;
+use tracing::warn;
+
+/// Tracks the background tasks spawned on it, so they can be waited for
+#[derive(Clone, Debug, Default)]
+pub struct BackgroundTasks {
+    tracker: TaskTracker,
+}
+
+impl BackgroundTasks {
+    /// Create a new, empty set of background tasks
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Spawns a background task
+    pub fn spawn<F>(&self, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.tracker.spawn(task);
+    }
+
+    /// Waits up to `grace_period` for every background task to finish, then abandons those still
+    /// running. Tasks spawned while waiting are waited for too.
+    ///
+    /// # Returns
+    /// Whether every task finished in time.
+    pub async fn flush(&self, grace_period: Duration) -> bool {
+        self.tracker.close();
+
+        if tokio::time::timeout(grace_period, self.tracker.wait())
+            .await
+            .is_ok()
+        {
+            return true;
+        }
+
+        warn!(
+            abandoned = self.tracker.len(),
+            grace_period_seconds = grace_period.as_secs(),
+            "background tasks didn't finish in time, abandoning them"
+        );
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_flush_waits_for_tasks_to_finish() {
+        let tasks = BackgroundTasks::new();
+        let finished = Arc::new(AtomicBool::new(false));
+
+        tasks.spawn({
+            let finished = finished.clone();
+
+            async move {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                finished.store(true, Ordering::SeqCst);
+            }
+        });
+
+        assert!(tasks.flush(Duration::from_secs(5)).await);
+        assert!(finished.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_flush_abandons_tasks_after_grace_period() {
+        let tasks = BackgroundTasks::new();
+
+        tasks.spawn(std::future::pending());
+
+        assert!(!tasks.flush(Duration::from_millis(20)).await);
+    }
+}
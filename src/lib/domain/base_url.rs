@@ -0,0 +1,105 @@
+//! Base URL module
+//!
+//! Links back to the application are built by appending a path to its base URL, so the base URL
+//! is validated and normalized once at startup rather than wherever a link is built.
+
+use std::{fmt, str::FromStr};
+
+use thiserror::Error;
+use url::Url;
+
+/// Errors in a base URL
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum BaseUrlError {
+    /// The base URL isn't a valid URL
+    #[error("Invalid base URL: {0}")]
+    Invalid(#[from] url::ParseError),
+
+    /// The base URL isn't http or https
+    #[error("Base URL must be http or https, not {0}")]
+    UnsupportedScheme(String),
+}
+
+/// The absolute http(s) URL the application is served from, without a trailing slash, so a path
+/// can be appended to it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BaseUrl(String);
+
+impl BaseUrl {
+    /// Parses and normalizes a base URL
+    pub fn parse(base_url: &str) -> Result<Self, BaseUrlError> {
+        let url = Url::parse(base_url.trim())?;
+
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(BaseUrlError::UnsupportedScheme(url.scheme().to_string()));
+        }
+
+        Ok(Self(url.as_str().trim_end_matches('/').to_string()))
+    }
+
+    /// Get the base URL as a string slice
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for BaseUrl {
+    type Err = BaseUrlError;
+
+    fn from_str(base_url: &str) -> Result<Self, Self::Err> {
+        Self::parse(base_url)
+    }
+}
+
+impl fmt::Display for BaseUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Test doubles for the base URL module
+#[cfg(test)]
+pub mod tests {
+    use testresult::TestResult;
+
+    use super::*;
+
+    /// The base URL the application is served from in tests
+    pub fn example_base_url() -> BaseUrl {
+        BaseUrl("https://example.com".to_string())
+    }
+
+    #[test]
+    fn test_trailing_slash_is_stripped() -> TestResult {
+        assert_eq!(
+            BaseUrl::parse("https://example.com/")?.as_str(),
+            "https://example.com"
+        );
+        assert_eq!(
+            BaseUrl::parse("https://example.com/app/")?.as_str(),
+            "https://example.com/app"
+        );
+        assert_eq!(
+            BaseUrl::parse("http://localhost:3000")?.as_str(),
+            "http://localhost:3000"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_non_http_scheme_is_rejected() {
+        assert_eq!(
+            BaseUrl::parse("ftp://example.com"),
+            Err(BaseUrlError::UnsupportedScheme("ftp".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_relative_url_is_rejected() {
+        assert!(matches!(
+            BaseUrl::parse("example.com"),
+            Err(BaseUrlError::Invalid(_))
+        ));
+    }
+}
@@ -0,0 +1,58 @@
+//! Clock module
+//!
+//! Time-dependent domain logic reads the current time through a [`Clock`] rather than calling
+//! [`Utc::now`] directly, so tests can control it.
+
+use chrono::{DateTime, Utc};
+
+/// A source of the current time
+pub trait Clock: Clone + Send + Sync + 'static {
+    /// Gets the current time
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// A [`Clock`] backed by the system time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Test doubles for the clock module
+#[cfg(test)]
+pub mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use chrono::{DateTime, Duration, Utc};
+
+    use super::Clock;
+
+    /// A [`Clock`] which stands still until it is moved by hand
+    #[derive(Debug, Clone)]
+    pub struct MockClock {
+        now: Arc<Mutex<DateTime<Utc>>>,
+    }
+
+    impl MockClock {
+        /// Creates a clock stopped at `now`
+        pub fn new(now: DateTime<Utc>) -> Self {
+            Self {
+                now: Arc::new(Mutex::new(now)),
+            }
+        }
+
+        /// Moves the clock forward by `duration`
+        pub fn advance(&self, duration: Duration) {
+            *self.now.lock().unwrap() += duration;
+        }
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> DateTime<Utc> {
+            *self.now.lock().unwrap()
+        }
+    }
+}
@@ -2,3 +2,4 @@
 
 pub mod email_addresses;
 pub mod mailer;
+pub mod webhooks;
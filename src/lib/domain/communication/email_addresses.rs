@@ -1,14 +1,28 @@
 //! Email addresses module.
 
+mod config;
+mod deliverability;
 mod email_address;
 mod errors;
+mod origin;
 mod service;
+mod token;
+mod welcome;
 
+pub use config::{EmailConfirmationConfig, EmailConfirmationConfigError};
+pub use deliverability::MailDomainResolver;
 pub use email_address::{EmailAddress, EmailAddressError};
 pub use errors::EmailConfirmationError;
-pub use service::{EmailAddressService, EmailAddressServiceImpl, EmailConfirmationType};
+pub use origin::RequestOrigin;
+pub use service::{
+    EmailAddressService, EmailAddressServiceImpl, EmailConfirmationType, SentEmailConfirmation,
+};
+pub use token::hash_confirmation_token;
+pub use welcome::WelcomeEmailSubscriber;
 
+/// Test doubles for the email addresses module
 #[cfg(test)]
 pub mod tests {
+    pub use super::deliverability::MockMailDomainResolver;
     pub use super::service::MockEmailAddressService;
 }
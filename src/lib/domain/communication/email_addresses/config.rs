@@ -0,0 +1,128 @@
+//! Email confirmation configuration
+
+use chrono::Duration;
+use clap::Parser;
+use thiserror::Error;
+
+/// Errors in the email confirmation configuration
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum EmailConfirmationConfigError {
+    /// The jitter could move a token's expiry to when it was issued, or before
+    #[error(
+        "Email confirmation expiry jitter ({jitter_minutes} minutes) must be less than the token \
+         TTL ({ttl_minutes} minutes)"
+    )]
+    JitterNotLessThanTtl {
+        /// The configured jitter, in minutes
+        jitter_minutes: i64,
+        /// The configured TTL, in minutes
+        ttl_minutes: i64,
+    },
+}
+
+/// Email confirmation configuration
+#[derive(Clone, Debug, PartialEq, Eq, Parser)]
+pub struct EmailConfirmationConfig {
+    /// How long a confirmation token is valid for, in minutes. The expiry is stored with the
+    /// token when it is issued, and that stored expiry is what confirmation checks against
+    #[arg(
+        long,
+        env = "EMAIL_CONFIRMATION_TOKEN_TTL_MINUTES",
+        default_value = "1440",
+        value_parser = clap::value_parser!(i64).range(1..)
+    )]
+    pub token_ttl_minutes: i64,
+
+    /// The maximum number of minutes a confirmation token's expiry is randomly moved earlier or
+    /// later by, so that tokens issued together don't all expire together. Must be less than the
+    /// TTL, so no token expires as soon as it is issued
+    #[arg(
+        long,
+        env = "EMAIL_CONFIRMATION_EXPIRY_JITTER_MINUTES",
+        default_value = "0",
+        value_parser = clap::value_parser!(i64).range(0..)
+    )]
+    pub expiry_jitter_minutes: i64,
+}
+
+impl EmailConfirmationConfig {
+    /// How long a confirmation token is valid for
+    pub fn token_ttl(&self) -> Duration {
+        Duration::minutes(self.token_ttl_minutes)
+    }
+
+    /// Checks the jitter is less than the TTL, which clap can't check as it spans two fields
+    pub fn validate(&self) -> Result<(), EmailConfirmationConfigError> {
+        if self.expiry_jitter_minutes >= self.token_ttl_minutes {
+            return Err(EmailConfirmationConfigError::JitterNotLessThanTtl {
+                jitter_minutes: self.expiry_jitter_minutes,
+                ttl_minutes: self.token_ttl_minutes,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for EmailConfirmationConfig {
+    fn default() -> Self {
+        Self {
+            token_ttl_minutes: 24 * 60,
+            expiry_jitter_minutes: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_ttl_must_be_positive() {
+        for ttl in ["0", "-60"] {
+            let result =
+                EmailConfirmationConfig::try_parse_from(["test", "--token-ttl-minutes", ttl]);
+
+            assert!(result.is_err(), "{ttl}");
+        }
+    }
+
+    #[test]
+    fn test_expiry_jitter_must_not_be_negative() {
+        let result =
+            EmailConfirmationConfig::try_parse_from(["test", "--expiry-jitter-minutes", "-5"]);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_expiry_jitter_must_be_less_than_token_ttl() {
+        let config = |expiry_jitter_minutes| EmailConfirmationConfig {
+            token_ttl_minutes: 60,
+            expiry_jitter_minutes,
+        };
+
+        assert_eq!(config(0).validate(), Ok(()));
+        assert_eq!(config(59).validate(), Ok(()));
+
+        for jitter in [60, 90] {
+            assert_eq!(
+                config(jitter).validate(),
+                Err(EmailConfirmationConfigError::JitterNotLessThanTtl {
+                    jitter_minutes: jitter,
+                    ttl_minutes: 60,
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_token_ttl() {
+        let config = EmailConfirmationConfig {
+            token_ttl_minutes: 60,
+            ..EmailConfirmationConfig::default()
+        };
+
+        assert_eq!(config.token_ttl(), Duration::hours(1));
+    }
+}
@@ -0,0 +1,40 @@
+//! Email address deliverability
+
+use async_trait::async_trait;
+
+#[cfg(test)]
+use mockall::mock;
+
+/// Looks up the DNS records which decide whether a domain can receive email
+#[async_trait]
+pub trait MailDomainResolver: Send + Sync + 'static {
+    /// The hosts `domain`'s MX records point at, or an empty list if it has none
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to look up.
+    ///
+    /// # Returns
+    /// A [`Result`] containing the mail exchangers, or an [`Err`] if the lookup itself failed.
+    async fn mail_exchangers(&self, domain: &str) -> anyhow::Result<Vec<String>>;
+
+    /// Whether `domain` has any A or AAAA records, which mail is delivered to when it has no MX
+    /// records (RFC 5321 section 5.1)
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to look up.
+    ///
+    /// # Returns
+    /// A [`Result`] containing whether there are any, or an [`Err`] if the lookup itself failed.
+    async fn has_address_records(&self, domain: &str) -> anyhow::Result<bool>;
+}
+
+#[cfg(test)]
+mock! {
+    pub MailDomainResolver {}
+
+    #[async_trait]
+    impl MailDomainResolver for MailDomainResolver {
+        async fn mail_exchangers(&self, domain: &str) -> anyhow::Result<Vec<String>>;
+        async fn has_address_records(&self, domain: &str) -> anyhow::Result<bool>;
+    }
+}
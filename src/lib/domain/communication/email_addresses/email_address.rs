@@ -1,16 +1,15 @@
 //! Email Address
 
-use lazy_static::lazy_static;
-use regex::Regex;
-use serde::{Deserialize, Serialize};
-
-lazy_static! {
-    static ref EMAIL_REGEX: Regex = Regex::new(r"^[^@\s]*?@[^@\s]*?\.[^@\s]*$").unwrap();
-}
-
-use std::fmt;
+use std::{
+    fmt,
+    net::{Ipv4Addr, Ipv6Addr},
+};
 
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use tracing::warn;
+
+use super::MailDomainResolver;
 
 use EmailAddressError::*;
 
@@ -24,10 +23,55 @@ pub enum EmailAddressError {
     /// The email address is invalid
     #[error("email is invalid")]
     InvalidEmailAddress,
+
+    /// The email address's domain can't receive email
+    #[error("email domain can't receive email")]
+    UndeliverableEmailAddress,
+
+    /// The email address's domain isn't allowed to sign up with
+    #[error("email domain is not allowed")]
+    DomainNotAllowed,
 }
 
+/// The maximum length of a local part, in octets (RFC 5321 section 4.5.3.1.1)
+const MAX_LOCAL_PART_LENGTH: usize = 64;
+
+/// The maximum length of a domain, in octets (RFC 5321 section 4.5.3.1.2)
+const MAX_DOMAIN_LENGTH: usize = 255;
+
+/// The maximum length of a domain label, in octets (RFC 1035 section 2.3.4)
+const MAX_LABEL_LENGTH: usize = 63;
+
+/// The maximum length of an address, in octets, as constrained by the `Path` limit of RFC 5321
+/// section 4.5.3.1.3 less its angle brackets
+const MAX_EMAIL_ADDRESS_LENGTH: usize = 254;
+
+/// Domains whose mailboxes ignore dots and `+tag` suffixes in the local part
+const GMAIL_DOMAINS: [&str; 2] = ["gmail.com", "googlemail.com"];
+
 /// An email address
+///
+/// Addresses are validated against the `Mailbox` production of RFC 5321 section 4.1.2, extended
+/// with the UTF-8 local parts and domains of RFC 6531:
+///
+/// * the local part is either a dot-atom (no leading, trailing or consecutive dots) or a quoted
+///   string, and is at most 64 octets;
+/// * the domain is either a hostname of at least two labels, each 1 to 63 octets of letters,
+///   digits and hyphens without a leading or trailing hyphen, and a top-level label which is
+///   not entirely numeric; or an IPv4 or `IPv6:` address literal in square brackets;
+/// * the whole address is at most 254 octets.
+///
+/// Comments, folding whitespace, obsolete syntax, general address literals and trailing
+/// (fully-qualified) dots in the domain are not supported.
+///
+/// The domain is always stored lowercased, since domain names are case-insensitive. The local
+/// part is stored as entered, since RFC 5321 allows it to be case-sensitive and it is what the
+/// user will recognise, but [`EmailAddress::normalized`] should be used whenever two addresses
+/// are compared, e.g. for uniqueness.
+///
+/// It is serialized as a plain string, and validated when deserialized.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
 pub struct EmailAddress(String);
 
 impl EmailAddress {
@@ -39,11 +83,85 @@ impl EmailAddress {
             return Err(EmptyEmailAddress);
         }
 
-        if !EMAIL_REGEX.is_match(trimmed) {
-            return Err(EmailAddressError::InvalidEmailAddress);
+        let (local, domain) = trimmed.rsplit_once('@').ok_or(InvalidEmailAddress)?;
+
+        if trimmed.len() > MAX_EMAIL_ADDRESS_LENGTH
+            || !is_valid_local_part(local)
+            || !is_valid_domain(domain)
+        {
+            return Err(InvalidEmailAddress);
         }
 
-        Ok(Self(trimmed.to_string()))
+        Ok(Self(format!("{local}@{}", domain.to_lowercase())))
+    }
+
+    /// Returns the canonical form of this email address, used to decide whether two addresses
+    /// refer to the same mailbox.
+    ///
+    /// Although RFC 5321 allows the local part to be case-sensitive, no mainstream provider
+    /// treats it that way, so the whole address is lowercased. Gmail additionally ignores dots
+    /// and anything after a `+` in the local part, and treats `googlemail.com` as `gmail.com`,
+    /// so those are stripped for Gmail addresses only.
+    pub fn normalized(&self) -> EmailAddress {
+        let lowercased = self.0.to_lowercase();
+        let (local, domain) = split(&lowercased);
+
+        if !GMAIL_DOMAINS.contains(&domain) {
+            return Self(lowercased);
+        }
+
+        let local = local.split('+').next().unwrap_or(local).replace('.', "");
+
+        Self(format!("{local}@gmail.com"))
+    }
+
+    /// Returns the domain, after the last `@`
+    pub fn domain(&self) -> &str {
+        split(&self.0).1
+    }
+
+    /// Checks that the domain can receive email, by having either MX records which don't
+    /// explicitly decline mail (RFC 7505), or address records to deliver to directly. This is a
+    /// network lookup, so unlike the format checks in [`EmailAddress::new`] it is up to the caller
+    /// whether to make it.
+    ///
+    /// Address literals aren't looked up, and an address is assumed deliverable if the lookup
+    /// fails, so that a DNS outage doesn't turn away addresses which are fine.
+    ///
+    /// # Arguments
+    /// * `resolver` - The [`MailDomainResolver`] to look the domain up with.
+    ///
+    /// # Returns
+    /// A [`Result`] which is [`Ok`] if the address may be deliverable, or an [`Err`] containing
+    /// [`EmailAddressError::UndeliverableEmailAddress`] if it definitely isn't.
+    pub async fn validate_deliverable<R: MailDomainResolver + ?Sized>(
+        &self,
+        resolver: &R,
+    ) -> Result<(), EmailAddressError> {
+        let (_, domain) = split(&self.0);
+
+        if domain.starts_with('[') {
+            return Ok(());
+        }
+
+        let deliverable = match resolver.mail_exchangers(domain).await {
+            // An MX record for the root domain is a "null MX", declining all mail
+            Ok(exchangers) if !exchangers.is_empty() => Ok(!exchangers
+                .iter()
+                .all(|exchanger| exchanger.trim_end_matches('.').is_empty())),
+            Ok(_) => resolver.has_address_records(domain).await,
+            Err(err) => Err(err),
+        };
+
+        match deliverable {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(UndeliverableEmailAddress),
+            Err(err) => {
+                warn!("Failed to look up mail records for {domain}: {:?}", err);
+
+                Ok(())
+            }
+        }
     }
 
     /// Create a new email address without validation
@@ -52,6 +170,92 @@ impl EmailAddress {
     }
 }
 
+/// Splits an address into its local part and domain at the last `@`
+fn split(email: &str) -> (&str, &str) {
+    email.rsplit_once('@').unwrap_or((email, ""))
+}
+
+/// Whether the local part is a valid dot-atom or quoted string
+fn is_valid_local_part(local: &str) -> bool {
+    if local.is_empty() || local.len() > MAX_LOCAL_PART_LENGTH {
+        return false;
+    }
+
+    match local
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+    {
+        Some(quoted) => is_valid_quoted_content(quoted),
+        None => local
+            .split('.')
+            .all(|atom| !atom.is_empty() && atom.chars().all(|c| !c.is_ascii() || is_atext(c))),
+    }
+}
+
+/// Whether `c` is an `atext` character (RFC 5322 section 3.2.3)
+fn is_atext(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!#$%&'*+-/=?^_`{|}~".contains(c)
+}
+
+/// Whether the content of a quoted string is made up of printable characters, with any quote
+/// or backslash escaped
+fn is_valid_quoted_content(quoted: &str) -> bool {
+    let mut chars = quoted.chars();
+
+    while let Some(c) = chars.next() {
+        let valid = match c {
+            '\\' => chars
+                .next()
+                .is_some_and(|escaped| matches!(escaped, ' '..='~')),
+            '"' => false,
+            c => c == ' ' || c.is_ascii_graphic() || !c.is_ascii(),
+        };
+
+        if !valid {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Whether the domain is a valid hostname or address literal
+fn is_valid_domain(domain: &str) -> bool {
+    if let Some(literal) = domain
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+    {
+        return match literal.get(..5) {
+            Some(tag) if tag.eq_ignore_ascii_case("IPv6:") => {
+                literal[5..].parse::<Ipv6Addr>().is_ok()
+            }
+            _ => literal.parse::<Ipv4Addr>().is_ok(),
+        };
+    }
+
+    if domain.is_empty() || domain.len() > MAX_DOMAIN_LENGTH {
+        return false;
+    }
+
+    let labels: Vec<&str> = domain.split('.').collect();
+
+    labels.len() >= 2
+        && labels.iter().all(|label| is_valid_label(label))
+        && labels
+            .last()
+            .is_some_and(|tld| !tld.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Whether the label is 1 to 63 octets of letters, digits and hyphens, without a leading or
+/// trailing hyphen
+fn is_valid_label(label: &str) -> bool {
+    !label.is_empty()
+        && label.len() <= MAX_LABEL_LENGTH
+        && !label.starts_with('-')
+        && !label.ends_with('-')
+        && label.chars().all(|c| c.is_alphanumeric() || c == '-')
+}
+
 impl Default for EmailAddress {
     fn default() -> Self {
         Self::new_unchecked("email@example.com")
@@ -70,10 +274,20 @@ impl From<EmailAddress> for String {
     }
 }
 
+impl TryFrom<String> for EmailAddress {
+    type Error = EmailAddressError;
+
+    fn try_from(raw: String) -> Result<Self, Self::Error> {
+        Self::new(&raw)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use testresult::TestResult;
 
+    use crate::domain::communication::email_addresses::tests::MockMailDomainResolver;
+
     use super::*;
 
     #[test]
@@ -99,6 +313,200 @@ mod tests {
         assert!(matches!(result.unwrap_err(), InvalidEmailAddress));
     }
 
+    #[test]
+    fn test_email_address_validation() {
+        let long_local = "a".repeat(65);
+        let long_label = "a".repeat(64);
+        let long_address = format!(
+            "{}@{}.com",
+            "a".repeat(64),
+            vec!["b".repeat(63); 3].join(".")
+        );
+
+        let cases = [
+            // Dot-atom local parts
+            ("email@example.com", true),
+            ("first.last@example.com", true),
+            ("first.last+tag@example.com", true),
+            ("o'brien@example.com", true),
+            ("!#$%&'*+-/=?^_`{|}~@example.com", true),
+            (".first@example.com", false),
+            ("first.@example.com", false),
+            ("first..last@example.com", false),
+            ("first last@example.com", false),
+            ("first(comment)@example.com", false),
+            // Quoted local parts
+            ("\"first last\"@example.com", true),
+            ("\"first..last\"@example.com", true),
+            ("\"first@last\"@example.com", true),
+            ("\"first\\\"last\"@example.com", true),
+            ("\"first\"last\"@example.com", false),
+            ("\"unterminated@example.com", false),
+            ("\"\"@example.com", true),
+            // Hostname domains
+            ("email@sub.example.co.uk", true),
+            ("email@my-domain.com", true),
+            ("email@example", false),
+            ("email@example.", false),
+            ("email@example.com.", false),
+            ("email@.example.com", false),
+            ("email@example..com", false),
+            ("email@-example.com", false),
+            ("email@example-.com", false),
+            ("email@exa_mple.com", false),
+            ("email@192.0.2.1", false),
+            // Address literals
+            ("email@[192.0.2.1]", true),
+            ("email@[IPv6:2001:db8::1]", true),
+            ("email@[ipv6:2001:db8::1]", true),
+            ("email@[300.0.2.1]", false),
+            ("email@[2001:db8::1]", false),
+            ("email@[example.com]", false),
+            // Internationalized addresses
+            ("email@bücher.de", true),
+            ("email@例え.jp", true),
+            ("ñoño@example.com", true),
+            ("email@xn--bcher-kva.de", true),
+            // Structure and length
+            ("email", false),
+            ("@example.com", false),
+            ("email@", false),
+            ("a@b.", false),
+            ("email@@example.com", false),
+            (&format!("{long_local}@example.com"), false),
+            (&format!("email@{long_label}.com"), false),
+            (&long_address, false),
+        ];
+
+        for (raw, valid) in cases {
+            assert_eq!(EmailAddress::new(raw).is_ok(), valid, "{raw}");
+        }
+    }
+
+    #[test]
+    fn test_oversized_email_address_is_invalid() {
+        let raw = format!("{}@example.com", "a".repeat(10 * 1024));
+
+        assert!(matches!(EmailAddress::new(&raw), Err(InvalidEmailAddress)));
+    }
+
+    fn resolver(
+        exchangers: &'static [&'static str],
+        address_records: bool,
+    ) -> MockMailDomainResolver {
+        let mut resolver = MockMailDomainResolver::new();
+
+        resolver
+            .expect_mail_exchangers()
+            .returning(move |_| Ok(exchangers.iter().map(ToString::to_string).collect()));
+        resolver
+            .expect_has_address_records()
+            .returning(move |_| Ok(address_records));
+
+        resolver
+    }
+
+    #[tokio::test]
+    async fn test_domain_with_mx_records_is_deliverable() -> TestResult {
+        let email = EmailAddress::new("email@example.com")?;
+
+        email
+            .validate_deliverable(&resolver(&["mx.example.com."], false))
+            .await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_domain_without_mx_records_falls_back_to_address_records() -> TestResult {
+        let email = EmailAddress::new("email@example.com")?;
+
+        email.validate_deliverable(&resolver(&[], true)).await?;
+
+        assert!(matches!(
+            email.validate_deliverable(&resolver(&[], false)).await,
+            Err(UndeliverableEmailAddress)
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_domain_with_null_mx_is_undeliverable() -> TestResult {
+        let email = EmailAddress::new("email@example.com")?;
+
+        assert!(matches!(
+            email.validate_deliverable(&resolver(&["."], true)).await,
+            Err(UndeliverableEmailAddress)
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_failed_lookup_or_address_literal_is_assumed_deliverable() -> TestResult {
+        let mut failing = MockMailDomainResolver::new();
+
+        failing
+            .expect_mail_exchangers()
+            .returning(|_| Err(anyhow::anyhow!("timed out")));
+
+        EmailAddress::new("email@example.com")?
+            .validate_deliverable(&failing)
+            .await?;
+        EmailAddress::new("email@[192.0.2.1]")?
+            .validate_deliverable(&MockMailDomainResolver::new())
+            .await?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_new_email_address_lowercases_domain() -> TestResult {
+        let email = EmailAddress::new("User.Name@Example.COM")?;
+
+        assert_eq!(email.to_string(), "User.Name@example.com");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_differently_cased_email_addresses_normalize_equal() -> TestResult {
+        let a = EmailAddress::new("User@Example.com")?;
+        let b = EmailAddress::new("user@example.com")?;
+
+        assert_ne!(a, b);
+        assert_eq!(a.normalized(), b.normalized());
+        assert_eq!(a.normalized().to_string(), "user@example.com");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_gmail_addresses_normalize_dots_and_tags() -> TestResult {
+        for raw in [
+            "first.last@gmail.com",
+            "First.Last+newsletter@gmail.com",
+            "firstlast@googlemail.com",
+        ] {
+            assert_eq!(
+                EmailAddress::new(raw)?.normalized().to_string(),
+                "firstlast@gmail.com"
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_non_gmail_addresses_keep_dots_and_tags() -> TestResult {
+        let email = EmailAddress::new("first.last+tag@example.com")?;
+
+        assert_eq!(email.normalized().to_string(), "first.last+tag@example.com");
+
+        Ok(())
+    }
+
     #[test]
     fn test_valid_email_to_string() -> TestResult {
         let email = EmailAddress::new("email@example.com")?;
@@ -107,4 +515,32 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_email_address_deserializes_valid_email() -> TestResult {
+        let email: EmailAddress = serde_json::from_str(r#""Email@Example.COM""#)?;
+
+        assert_eq!(email.to_string(), "Email@example.com");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_email_address_rejects_invalid_email_when_deserialized() {
+        let result = serde_json::from_str::<EmailAddress>(r#""not an email""#);
+
+        assert_eq!(
+            result.map_err(|err| err.to_string()),
+            Err("email is invalid".to_string())
+        );
+    }
+
+    #[test]
+    fn test_email_address_serializes_as_string() -> TestResult {
+        let email = EmailAddress::new("email@example.com")?;
+
+        assert_eq!(serde_json::to_string(&email)?, r#""email@example.com""#);
+
+        Ok(())
+    }
 }
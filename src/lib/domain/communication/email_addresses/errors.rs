@@ -1,4 +1,3 @@
-use css_inline::InlineError;
 use thiserror::Error;
 use tracing::debug;
 
@@ -22,6 +21,15 @@ pub enum EmailConfirmationError {
     #[error("could not send confirmation email")]
     CouldNotSendEmail,
 
+    /// The recipient's email address can't receive email. This is permanent: retrying won't help,
+    /// the user has to provide a different address
+    #[error("email address can't receive email")]
+    InvalidRecipient,
+
+    /// The confirmation email couldn't be sent right now, but may be if retried later
+    #[error("email delivery is temporarily unavailable")]
+    MailerUnavailable,
+
     /// Email is already confirmed
     #[error("email is already confirmed")]
     EmailAlreadyConfirmed,
@@ -34,6 +42,14 @@ pub enum EmailConfirmationError {
     #[error("confirmation token mismatch")]
     ConfirmationTokenMismatch,
 
+    /// No email change is pending
+    #[error("no email change is pending")]
+    NoPendingEmailChange,
+
+    /// The user has been updated since it was read
+    #[error("user has been updated since it was read")]
+    Conflict,
+
     /// Unknown error
     #[error(transparent)]
     UnknownError(#[from] anyhow::Error),
@@ -58,6 +74,11 @@ impl From<UpdateUserError> for EmailConfirmationError {
             UpdateUserError::UserNotFound => EmailConfirmationError::UserNotFound,
             UpdateUserError::UnknownError(e) => EmailConfirmationError::UnknownError(e),
             UpdateUserError::EmailAddressInUse => EmailConfirmationError::EmailAddressInUse,
+            UpdateUserError::ConfirmationTokenMismatch => {
+                EmailConfirmationError::ConfirmationTokenMismatch
+            }
+            UpdateUserError::NoPendingEmailChange => EmailConfirmationError::NoPendingEmailChange,
+            UpdateUserError::Conflict => EmailConfirmationError::Conflict,
         }
     }
 }
@@ -67,26 +88,10 @@ impl From<MailerError> for EmailConfirmationError {
         debug!("MailerError -> EmailConfirmationError");
 
         match err {
-            MailerError::SendError | MailerError::InvalidEmail => {
-                EmailConfirmationError::CouldNotSendEmail
-            }
+            MailerError::InvalidEmail => EmailConfirmationError::InvalidRecipient,
+            MailerError::SendError => EmailConfirmationError::MailerUnavailable,
+            MailerError::RenderError => EmailConfirmationError::CouldNotSendEmail,
             MailerError::UnknownError(e) => EmailConfirmationError::UnknownError(e),
         }
     }
 }
-
-impl From<InlineError> for EmailConfirmationError {
-    fn from(_err: InlineError) -> Self {
-        debug!("InlineError -> EmailConfirmationError");
-
-        EmailConfirmationError::CouldNotSendEmail
-    }
-}
-
-impl From<askama::Error> for EmailConfirmationError {
-    fn from(_err: askama::Error) -> Self {
-        debug!("askama::Error -> EmailConfirmationError");
-
-        EmailConfirmationError::CouldNotSendEmail
-    }
-}
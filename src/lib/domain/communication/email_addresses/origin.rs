@@ -0,0 +1,14 @@
+//! Where requests come from
+
+use std::net::IpAddr;
+
+/// The client a request came from. Kept alongside confirmation tokens so abuse of the
+/// confirmation emails can be traced back to it, and recorded in the audit log
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RequestOrigin {
+    /// The client's IP address, if known
+    pub ip: Option<IpAddr>,
+
+    /// The client's `User-Agent`, if it sent one
+    pub user_agent: Option<String>,
+}
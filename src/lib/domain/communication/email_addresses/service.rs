@@ -3,7 +3,6 @@
 use std::sync::Arc;
 
 use anyhow::Result;
-use askama::Template;
 use async_trait::async_trait;
 use base64::{engine::general_purpose::URL_SAFE, Engine as _};
 use chrono::{DateTime, Duration, Utc};
@@ -20,10 +19,16 @@ use crate::domain::{
         emails::confirm_email_address::ConfirmEmailAddressTemplate,
         users::{User, UserRepository},
     },
-    communication::mailer::{Mailer, Message},
+    base_url::BaseUrl,
+    clock::Clock,
+    communication::mailer::{EmailContext, EmailKind, EmailSenders, EmailTemplates, Mailer},
+    events::{DomainEvent, EventBus},
 };
 
-use super::{errors::EmailConfirmationError, EmailAddress};
+use super::{
+    errors::EmailConfirmationError, hash_confirmation_token, EmailAddress, EmailConfirmationConfig,
+    RequestOrigin,
+};
 
 /// The type of email confirmation
 #[derive(Debug, PartialEq, Eq)]
@@ -36,15 +41,25 @@ pub enum EmailConfirmationType {
 }
 
 impl EmailConfirmationType {
-    /// Gets the subject of the email confirmation
-    pub fn subject(&self) -> &str {
+    /// Gets the kind of email the confirmation is sent as
+    pub fn email_kind(&self) -> EmailKind {
         match self {
-            Self::CurrentEmail => "Please confirm your email address",
-            Self::NewEmail(_) => "Please confirm your new email address",
+            Self::CurrentEmail => EmailKind::Confirmation,
+            Self::NewEmail(_) => EmailKind::NewEmailConfirmation,
         }
     }
 }
 
+/// An email confirmation which has been sent
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SentEmailConfirmation {
+    /// When the confirmation token expires
+    pub expires_at: DateTime<Utc>,
+
+    /// The confirmation link the email contains
+    pub link: String,
+}
+
 /// Email address service
 #[async_trait]
 pub trait EmailAddressService: Clone + Send + Sync + 'static {
@@ -53,26 +68,80 @@ pub trait EmailAddressService: Clone + Send + Sync + 'static {
     /// # Arguments
     /// * `user` - The user to send the email confirmation to.
     /// * `base_url` - The base URL of the application.
+    /// * `origin` - The client which requested the confirmation, stored with its token.
     ///
     /// # Returns
-    /// - [`Ok`] with a [`DateTime<Utc>`] representing the token's expiration time if successful.
+    /// - [`Ok`] with the [`SentEmailConfirmation`], holding the token's expiration time and the
+    ///   confirmation link, if successful.
     /// - [`Err`] containing an [`EmailConfirmationError`] if the email confirmation could not be sent.
     async fn send_email_confirmation(
         &self,
         user: &User,
         confirmation_type: EmailConfirmationType,
-        base_url: &str,
-    ) -> Result<DateTime<Utc>, EmailConfirmationError>;
+        base_url: &BaseUrl,
+        origin: &RequestOrigin,
+    ) -> Result<SentEmailConfirmation, EmailConfirmationError>;
 
     /// Confirms the user's email address.
     ///
     /// # Arguments
     /// * `user_id` - The UUID of the user to confirm the email address for.
     /// * `token` - The email confirmation token.
+    /// * `origin` - The client confirming the email address, recorded in the audit log.
     ///
     /// # Returns
     /// A [`Result`] which is [`Ok`] if the email address was confirmed successfully,
-    async fn confirm_email(&self, user: &User, token: &str) -> Result<(), EmailConfirmationError>;
+    async fn confirm_email(
+        &self,
+        user: &User,
+        token: &str,
+        origin: &RequestOrigin,
+    ) -> Result<(), EmailConfirmationError>;
+
+    /// Checks a confirmation token the way [`EmailAddressService::confirm_email`] does, without
+    /// consuming it.
+    ///
+    /// # Arguments
+    /// * `user` - The user the token was sent to.
+    /// * `token` - The email confirmation token.
+    ///
+    /// # Returns
+    /// A [`Result`] which is [`Ok`] if the token would confirm the user's email address, or an
+    /// [`Err`] containing the [`EmailConfirmationError`] confirming it would fail with.
+    fn validate_confirmation_token(
+        &self,
+        user: &User,
+        token: &str,
+    ) -> Result<(), EmailConfirmationError>;
+
+    /// Confirms the user's current email address on behalf of an admin, without a token, for
+    /// users who can't receive the confirmation email. Confirming an address which is already
+    /// confirmed succeeds without doing anything.
+    ///
+    /// # Arguments
+    /// * `user` - The user to confirm the email address of.
+    /// * `admin_id` - The UUID of the admin confirming it.
+    /// * `origin` - The client the admin is confirming it from, recorded in the audit log.
+    ///
+    /// # Returns
+    /// A [`Result`] which is [`Ok`] once the email address is confirmed, or an [`Err`]
+    /// containing an [`EmailConfirmationError`] if it couldn't be.
+    async fn force_confirm_email(
+        &self,
+        user: &User,
+        admin_id: &Uuid,
+        origin: &RequestOrigin,
+    ) -> Result<(), EmailConfirmationError>;
+
+    /// Cancels the user's pending email change before it is confirmed.
+    ///
+    /// # Arguments
+    /// * `user` - The user to cancel the email change for.
+    ///
+    /// # Returns
+    /// A [`Result`] which is [`Ok`] if the email change was cancelled,
+    /// or an [`Err`] containing [`EmailConfirmationError::NoPendingEmailChange`] if there wasn't one.
+    async fn cancel_email_change(&self, user: &User) -> Result<(), EmailConfirmationError>;
 }
 
 #[cfg(test)]
@@ -89,37 +158,84 @@ mock! {
             &self,
             user: &User,
             confirmation_type: EmailConfirmationType,
-            base_url: &str,
-        ) -> Result<DateTime<Utc>, EmailConfirmationError>;
-        async fn confirm_email(&self, user: &User, token: &str) -> Result<(), EmailConfirmationError>;
+            base_url: &BaseUrl,
+            origin: &RequestOrigin,
+        ) -> Result<SentEmailConfirmation, EmailConfirmationError>;
+        async fn confirm_email(&self, user: &User, token: &str, origin: &RequestOrigin) -> Result<(), EmailConfirmationError>;
+        fn validate_confirmation_token(&self, user: &User, token: &str) -> Result<(), EmailConfirmationError>;
+        async fn force_confirm_email(&self, user: &User, admin_id: &Uuid, origin: &RequestOrigin) -> Result<(), EmailConfirmationError>;
+        async fn cancel_email_change(&self, user: &User) -> Result<(), EmailConfirmationError>;
     }
 }
 
 /// Email address service implementation
 #[derive(Debug, Clone)]
-pub struct EmailAddressServiceImpl<R, M>
+pub struct EmailAddressServiceImpl<R, M, C>
 where
     R: UserRepository,
     M: Mailer,
+    C: Clock,
 {
     user_repo: Arc<R>,
     mailer: Arc<M>,
+    senders: EmailSenders,
+    templates: EmailTemplates,
+    events: EventBus,
+    clock: Arc<C>,
+    config: EmailConfirmationConfig,
 }
 
-impl<R, M> EmailAddressServiceImpl<R, M>
+impl<R, M, C> EmailAddressServiceImpl<R, M, C>
 where
     R: UserRepository,
     M: Mailer,
+    C: Clock,
 {
     /// Creates a new email address service.
-    pub fn new(user_repo: Arc<R>, mailer: Arc<M>) -> Self {
-        Self { user_repo, mailer }
+    pub fn new(
+        user_repo: Arc<R>,
+        mailer: Arc<M>,
+        senders: EmailSenders,
+        events: EventBus,
+        clock: Arc<C>,
+        config: EmailConfirmationConfig,
+    ) -> Self {
+        Self {
+            user_repo,
+            mailer,
+            senders,
+            templates: EmailTemplates::default(),
+            events,
+            clock,
+            config,
+        }
+    }
+
+    /// Renders emails with `templates` instead of the embedded templates
+    pub fn with_templates(mut self, templates: EmailTemplates) -> Self {
+        self.templates = templates;
+        self
+    }
+
+    /// Calculates when a token issued at `issued_at` expires, after the configured TTL moved
+    /// earlier or later by a random amount of up to the configured jitter.
+    fn token_expiry(&self, issued_at: DateTime<Utc>) -> DateTime<Utc> {
+        let max_jitter_seconds = self.config.expiry_jitter_minutes * 60;
+
+        let jitter_seconds = if max_jitter_seconds > 0 {
+            rand::thread_rng().gen_range(-max_jitter_seconds..=max_jitter_seconds)
+        } else {
+            0
+        };
+
+        issued_at + self.config.token_ttl() + Duration::seconds(jitter_seconds)
     }
 
     async fn generate_email_confirmation_token(
         &self,
-        user_id: &Uuid,
+        user: &User,
         new_email: Option<&EmailAddress>,
+        origin: &RequestOrigin,
     ) -> Result<(String, DateTime<Utc>), EmailConfirmationError> {
         let salt: String = rand::thread_rng()
             .sample_iter(&Alphanumeric)
@@ -127,32 +243,47 @@ where
             .map(char::from)
             .collect();
 
-        let data = format!("{}{}{}", user_id, salt, Utc::now().timestamp());
+        let issued_at = self.clock.now();
+
+        let data = format!("{}{}{}", user.id, salt, issued_at.timestamp());
         let mut hasher = Sha256::new();
         hasher.update(data.as_bytes());
         let hash_result = hasher.finalize();
         let token = URL_SAFE.encode(hash_result);
+        let expires_at = self.token_expiry(issued_at);
 
         self.user_repo
-            .initialize_email_confirmation(user_id, &token, new_email)
+            .initialize_email_confirmation(
+                &user.id,
+                &token,
+                &expires_at,
+                new_email,
+                origin,
+                user.version,
+            )
             .await?;
 
-        Ok((token, Utc::now() + Duration::hours(24)))
+        Ok((token, expires_at))
     }
 }
 
 #[async_trait]
-impl<R, M> EmailAddressService for EmailAddressServiceImpl<R, M>
+impl<R, M, C> EmailAddressService for EmailAddressServiceImpl<R, M, C>
 where
     R: UserRepository,
     M: Mailer,
+    C: Clock,
 {
     async fn send_email_confirmation(
         &self,
         user: &User,
         confirmation_type: EmailConfirmationType,
-        base_url: &str,
-    ) -> Result<DateTime<Utc>, EmailConfirmationError> {
+        base_url: &BaseUrl,
+        origin: &RequestOrigin,
+    ) -> Result<SentEmailConfirmation, EmailConfirmationError> {
+        // Only re-confirming the current address is blocked. A confirmed user starting an email
+        // change must get through: `new_email` is only stored by the repository below, so it is
+        // still `None` (or the previous pending address) at this point and can't be relied on.
         if user.email_confirmed_at.is_some()
             && confirmation_type == EmailConfirmationType::CurrentEmail
         {
@@ -165,50 +296,141 @@ where
         };
 
         let (token, expires_at) = self
-            .generate_email_confirmation_token(&user.id, new_email)
+            .generate_email_confirmation_token(user, new_email, origin)
             .await?;
 
-        let template = ConfirmEmailAddressTemplate::new(base_url, &user.id, &token);
+        let link = ConfirmEmailAddressTemplate::new(base_url, &user.id, &token, user.locale).link;
 
-        let message = Message {
-            to: recipient,
-            from: None,
-            subject: confirmation_type.subject().to_string(),
-            html_body: css_inline::inline(&template.render()?)?,
-            plain_body: template.render_plain()?,
-        };
+        let kind = confirmation_type.email_kind();
+
+        self.mailer
+            .send_templated(
+                &self.templates,
+                recipient,
+                self.senders.sender(kind),
+                kind,
+                EmailContext {
+                    link: link.clone(),
+                    locale: user.locale,
+                },
+            )
+            .await?;
 
-        self.mailer.send_email(message).await?;
+        let occurred_at = self.clock.now();
+
+        self.events
+            .publish(match confirmation_type {
+                EmailConfirmationType::CurrentEmail => DomainEvent::EmailConfirmationSent {
+                    user_id: user.id,
+                    email: user.email.clone(),
+                    expires_at,
+                    occurred_at,
+                },
+                EmailConfirmationType::NewEmail(new_email) => DomainEvent::EmailChangeRequested {
+                    user_id: user.id,
+                    new_email,
+                    expires_at,
+                    occurred_at,
+                },
+            })
+            .await;
 
-        Ok(expires_at)
+        Ok(SentEmailConfirmation { expires_at, link })
     }
 
-    async fn confirm_email(&self, user: &User, token: &str) -> Result<(), EmailConfirmationError> {
-        if user.email_confirmed_at.is_some() && user.new_email.is_none() {
-            return Err(EmailConfirmationError::EmailAlreadyConfirmed);
-        }
+    async fn confirm_email(
+        &self,
+        user: &User,
+        token: &str,
+        origin: &RequestOrigin,
+    ) -> Result<(), EmailConfirmationError> {
+        self.validate_confirmation_token(user, token)?;
+
+        let now = self.clock.now();
 
-        let expected_token = user
+        self.user_repo
+            .complete_email_confirmation(&user.id, token, user.new_email.as_ref())
+            .await?;
+
+        self.events
+            .publish(DomainEvent::EmailConfirmed {
+                user_id: user.id,
+                email: user.new_email.clone().unwrap_or_else(|| user.email.clone()),
+                first_confirmation: user.email_confirmed_at.is_none(),
+                locale: user.locale,
+                origin: origin.clone(),
+                occurred_at: now,
+            })
+            .await;
+
+        Ok(())
+    }
+
+    fn validate_confirmation_token(
+        &self,
+        user: &User,
+        token: &str,
+    ) -> Result<(), EmailConfirmationError> {
+        // Tokens are cleared once used, so check for one first: replaying a consumed token is a
+        // mismatch, whether or not it left the user's email confirmed.
+        let expected_token_hash = user
             .email_confirmation_token
             .as_ref()
             .ok_or_else(|| EmailConfirmationError::ConfirmationTokenMismatch)?;
 
-        let confirmation_sent_at = user
-            .email_confirmation_sent_at
-            .ok_or_else(|| EmailConfirmationError::ConfirmationTokenMismatch)?;
+        if user.email_confirmed_at.is_some() && user.new_email.is_none() {
+            return Err(EmailConfirmationError::EmailAlreadyConfirmed);
+        }
 
-        let expires_at = confirmation_sent_at + Duration::hours(24);
+        let expires_at = user
+            .email_confirmation_expires_at
+            .ok_or_else(|| EmailConfirmationError::ConfirmationTokenMismatch)?;
 
-        if Utc::now() > expires_at {
+        if self.clock.now() > expires_at {
             return Err(EmailConfirmationError::ConfirmationTokenExpired);
         }
 
-        if !constant_time_eq(token.as_bytes(), expected_token.as_bytes()) {
+        let token_hash = hash_confirmation_token(token);
+
+        if !constant_time_eq(token_hash.as_bytes(), expected_token_hash.as_bytes()) {
             return Err(EmailConfirmationError::ConfirmationTokenMismatch);
         }
 
+        Ok(())
+    }
+
+    async fn force_confirm_email(
+        &self,
+        user: &User,
+        admin_id: &Uuid,
+        origin: &RequestOrigin,
+    ) -> Result<(), EmailConfirmationError> {
+        if user.email_confirmed_at.is_some() {
+            return Ok(());
+        }
+
+        self.user_repo.force_email_confirmation(&user.id).await?;
+
+        self.events
+            .publish(DomainEvent::EmailConfirmedByAdmin {
+                user_id: user.id,
+                admin_id: *admin_id,
+                email: user.email.clone(),
+                origin: origin.clone(),
+                occurred_at: self.clock.now(),
+            })
+            .await;
+
+        Ok(())
+    }
+
+    async fn cancel_email_change(&self, user: &User) -> Result<(), EmailConfirmationError> {
+        if user.new_email.is_none() {
+            return Err(EmailConfirmationError::NoPendingEmailChange);
+        }
+
         self.user_repo
-            .complete_email_confirmation(&user.id, user.new_email.as_ref())
+            .cancel_email_change(&user.id, user.version)
             .await?;
 
         Ok(())
@@ -218,13 +440,22 @@ where
 #[cfg(test)]
 mod tests {
     use testresult::TestResult;
+    use uuid::Uuid;
 
     use crate::domain::{
-        auth::users::{errors::UpdateUserError, tests::MockUserRepository},
+        auth::users::{errors::UpdateUserError, tests::MockUserRepository, Role},
+        base_url::tests::example_base_url,
+        clock::{tests::MockClock, SystemClock},
         communication::{
             email_addresses::EmailAddress,
-            mailer::{tests::MockMailer, MailerError},
+            mailer::{
+                tests::{any_mailer, MockMailer},
+                MailerError,
+            },
         },
+        events::tests::recording_bus,
+        i18n::Locale,
+        timestamps::Timestamps,
     };
 
     use super::*;
@@ -237,12 +468,26 @@ mod tests {
 
         repo.expect_initialize_email_confirmation()
             .times(1)
-            .returning(move |_, _, _| Ok(()));
+            .returning(move |_, _, _, _, _, _| Ok(()));
 
-        let service = EmailAddressServiceImpl::new(Arc::new(repo), Arc::new(MockMailer::new()));
+        let service = EmailAddressServiceImpl::new(
+            Arc::new(repo),
+            Arc::new(MockMailer::new()),
+            EmailSenders::default(),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            EmailConfirmationConfig::default(),
+        );
 
         let (token, expires_at) = service
-            .generate_email_confirmation_token(&user_id, None)
+            .generate_email_confirmation_token(
+                &User {
+                    id: user_id,
+                    ..User::default()
+                },
+                None,
+                &RequestOrigin::default(),
+            )
             .await?;
 
         assert_eq!(44, token.len());
@@ -258,14 +503,19 @@ mod tests {
         let mut users = MockUserRepository::new();
 
         let user = User {
-            id: user_id.clone(),
+            id: user_id,
             email: EmailAddress::new_unchecked("email@example.com"),
             new_email: None,
             email_confirmed_at: None,
             email_confirmation_token: None,
             email_confirmation_sent_at: None,
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
+            email_confirmation_expires_at: None,
+            role: Role::User,
+            terms_accepted_at: None,
+            terms_version: None,
+            locale: Locale::English,
+            timestamps: Timestamps::created(Utc::now()),
+            version: 1,
         };
 
         let expected_user = user.clone();
@@ -273,7 +523,7 @@ mod tests {
         users
             .expect_initialize_email_confirmation()
             .times(1)
-            .returning(move |_, _, _| Ok(()));
+            .returning(move |_, _, _, _, _, _| Ok(()));
 
         let mut mailer = MockMailer::new();
 
@@ -282,17 +532,157 @@ mod tests {
             .times(1)
             .returning(move |_| Ok(()));
 
-        let service = EmailAddressServiceImpl::new(Arc::new(users), Arc::new(mailer));
+        let service = EmailAddressServiceImpl::new(
+            Arc::new(users),
+            Arc::new(mailer),
+            EmailSenders::default(),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            EmailConfirmationConfig::default(),
+        );
 
-        let expires_at = service
+        let sent = service
             .send_email_confirmation(
                 &expected_user,
                 EmailConfirmationType::CurrentEmail,
-                "https://localhost:3443",
+                &example_base_url(),
+                &RequestOrigin::default(),
             )
             .await?;
 
-        assert!(expires_at > Utc::now());
+        assert!(sent.expires_at > Utc::now());
+        assert!(sent.link.starts_with(&format!(
+            "https://example.com/api/v1/users/{user_id}/email/confirmation?token="
+        )));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_email_confirmation_stores_origin() -> TestResult {
+        let origin = RequestOrigin {
+            ip: Some("2001:db8::1".parse()?),
+            user_agent: Some("Mozilla/5.0".to_string()),
+        };
+        let expected_origin = origin.clone();
+
+        let mut users = MockUserRepository::new();
+
+        users
+            .expect_initialize_email_confirmation()
+            .times(1)
+            .withf(move |_, _, _, _, origin, _| *origin == expected_origin)
+            .returning(|_, _, _, _, _, _| Ok(()));
+
+        let service = EmailAddressServiceImpl::new(
+            Arc::new(users),
+            any_mailer(),
+            EmailSenders::default(),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            EmailConfirmationConfig::default(),
+        );
+
+        service
+            .send_email_confirmation(
+                &User::default(),
+                EmailConfirmationType::CurrentEmail,
+                &example_base_url(),
+                &origin,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_email_confirmation_in_users_locale() -> TestResult {
+        let user = User {
+            locale: Locale::French,
+            ..User::default()
+        };
+
+        let mut users = MockUserRepository::new();
+
+        users
+            .expect_initialize_email_confirmation()
+            .times(1)
+            .returning(|_, _, _, _, _, _| Ok(()));
+
+        let mut mailer = MockMailer::new();
+
+        mailer
+            .expect_send_email()
+            .times(1)
+            .withf(|message| {
+                message.subject == "Veuillez confirmer votre adresse e-mail"
+                    && message
+                        .plain_body
+                        .starts_with("Rendez-vous à l’adresse suivante")
+            })
+            .returning(|_| Ok(()));
+
+        let service = EmailAddressServiceImpl::new(
+            Arc::new(users),
+            Arc::new(mailer),
+            EmailSenders::default(),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            EmailConfirmationConfig::default(),
+        );
+
+        service
+            .send_email_confirmation(
+                &user,
+                EmailConfirmationType::CurrentEmail,
+                &example_base_url(),
+                &RequestOrigin::default(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_email_confirmation_from_configured_sender() -> TestResult {
+        let mut users = MockUserRepository::new();
+
+        users
+            .expect_initialize_email_confirmation()
+            .times(1)
+            .returning(|_, _, _, _, _, _| Ok(()));
+
+        let mut mailer = MockMailer::new();
+
+        mailer
+            .expect_send_email()
+            .times(1)
+            .withf(|message| {
+                message.from.as_ref().map(ToString::to_string).as_deref()
+                    == Some(r#""Example" <verify@example.com>"#)
+            })
+            .returning(|_| Ok(()));
+
+        let service = EmailAddressServiceImpl::new(
+            Arc::new(users),
+            Arc::new(mailer),
+            EmailSenders {
+                confirmation: Some("Example <verify@example.com>".parse()?),
+                ..EmailSenders::default()
+            },
+            EventBus::new(),
+            Arc::new(SystemClock),
+            EmailConfirmationConfig::default(),
+        );
+
+        service
+            .send_email_confirmation(
+                &User::default(),
+                EmailConfirmationType::CurrentEmail,
+                &example_base_url(),
+                &RequestOrigin::default(),
+            )
+            .await?;
 
         Ok(())
     }
@@ -307,20 +697,28 @@ mod tests {
         users
             .expect_initialize_email_confirmation()
             .times(1)
-            .returning(|_, _, _| Ok(()));
+            .returning(|_, _, _, _, _, _| Ok(()));
 
         mailer
             .expect_send_email()
             .times(1)
             .returning(|_| Err(MailerError::SendError));
 
-        let service = EmailAddressServiceImpl::new(Arc::new(users), Arc::new(mailer));
+        let service = EmailAddressServiceImpl::new(
+            Arc::new(users),
+            Arc::new(mailer),
+            EmailSenders::default(),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            EmailConfirmationConfig::default(),
+        );
 
         let result = service
             .send_email_confirmation(
                 &user,
                 EmailConfirmationType::CurrentEmail,
-                "https://localhost:3443",
+                &example_base_url(),
+                &RequestOrigin::default(),
             )
             .await;
 
@@ -339,17 +737,25 @@ mod tests {
         user_repository
             .expect_initialize_email_confirmation()
             .times(1)
-            .returning(|_, _, _| Err(UpdateUserError::EmailAddressInUse));
+            .returning(|_, _, _, _, _, _| Err(UpdateUserError::EmailAddressInUse));
 
         mailer.expect_send_email().times(0);
 
-        let service = EmailAddressServiceImpl::new(Arc::new(user_repository), Arc::new(mailer));
+        let service = EmailAddressServiceImpl::new(
+            Arc::new(user_repository),
+            Arc::new(mailer),
+            EmailSenders::default(),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            EmailConfirmationConfig::default(),
+        );
 
         let result = service
             .send_email_confirmation(
                 &user,
                 EmailConfirmationType::NewEmail(EmailAddress::new_unchecked("email@example.com")),
-                "https://localhost:3443",
+                &example_base_url(),
+                &RequestOrigin::default(),
             )
             .await;
 
@@ -358,6 +764,141 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_send_email_confirmation_confirmed_user_starting_email_change() -> TestResult {
+        let user = User {
+            id: Uuid::now_v7(),
+            email: EmailAddress::new_unchecked("email@example.com"),
+            email_confirmed_at: Some(Utc::now() - Duration::days(30)),
+            ..User::default()
+        };
+
+        let mut users = MockUserRepository::new();
+
+        users
+            .expect_initialize_email_confirmation()
+            .times(1)
+            .withf(|_, _, _, new_email, _, _| {
+                new_email.map(|email| email.to_string()) == Some("new@example.com".to_string())
+            })
+            .returning(|_, _, _, _, _, _| Ok(()));
+
+        let mut mailer = MockMailer::new();
+
+        mailer
+            .expect_send_email()
+            .times(1)
+            .withf(|message| message.to.to_string() == "new@example.com")
+            .returning(|_| Ok(()));
+
+        let service = EmailAddressServiceImpl::new(
+            Arc::new(users),
+            Arc::new(mailer),
+            EmailSenders::default(),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            EmailConfirmationConfig::default(),
+        );
+
+        let result = service
+            .send_email_confirmation(
+                &user,
+                EmailConfirmationType::NewEmail(EmailAddress::new_unchecked("new@example.com")),
+                &example_base_url(),
+                &RequestOrigin::default(),
+            )
+            .await;
+
+        assert!(result.is_ok());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_email_confirmation_confirmed_user_reconfirming_current_email() -> TestResult
+    {
+        let user = User {
+            id: Uuid::now_v7(),
+            email: EmailAddress::new_unchecked("email@example.com"),
+            email_confirmed_at: Some(Utc::now() - Duration::days(30)),
+            ..User::default()
+        };
+
+        let mut users = MockUserRepository::new();
+        let mut mailer = MockMailer::new();
+
+        users.expect_initialize_email_confirmation().times(0);
+        mailer.expect_send_email().times(0);
+
+        let service = EmailAddressServiceImpl::new(
+            Arc::new(users),
+            Arc::new(mailer),
+            EmailSenders::default(),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            EmailConfirmationConfig::default(),
+        );
+
+        let result = service
+            .send_email_confirmation(
+                &user,
+                EmailConfirmationType::CurrentEmail,
+                &example_base_url(),
+                &RequestOrigin::default(),
+            )
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(EmailConfirmationError::EmailAlreadyConfirmed)
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_email_confirmation_confirmed_user_with_pending_change_reconfirming_current_email(
+    ) -> TestResult {
+        let user = User {
+            id: Uuid::now_v7(),
+            email: EmailAddress::new_unchecked("email@example.com"),
+            new_email: Some(EmailAddress::new_unchecked("new@example.com")),
+            email_confirmed_at: Some(Utc::now() - Duration::days(30)),
+            ..User::default()
+        };
+
+        let mut users = MockUserRepository::new();
+        let mut mailer = MockMailer::new();
+
+        users.expect_initialize_email_confirmation().times(0);
+        mailer.expect_send_email().times(0);
+
+        let service = EmailAddressServiceImpl::new(
+            Arc::new(users),
+            Arc::new(mailer),
+            EmailSenders::default(),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            EmailConfirmationConfig::default(),
+        );
+
+        let result = service
+            .send_email_confirmation(
+                &user,
+                EmailConfirmationType::CurrentEmail,
+                &example_base_url(),
+                &RequestOrigin::default(),
+            )
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(EmailConfirmationError::EmailAlreadyConfirmed)
+        ));
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_confirm_email_success() -> TestResult {
         let user_id = Uuid::now_v7();
@@ -366,14 +907,19 @@ mod tests {
         let mut users = MockUserRepository::new();
 
         let user = User {
-            id: user_id.clone(),
+            id: user_id,
             email: EmailAddress::new_unchecked("email@example.com"),
             new_email: None,
             email_confirmed_at: None,
-            email_confirmation_token: Some("token".to_string()),
-            email_confirmation_sent_at: Some(yesterday.clone() + Duration::hours(12)),
-            created_at: yesterday.clone(),
-            updated_at: yesterday.clone(),
+            email_confirmation_token: Some(hash_confirmation_token("token")),
+            email_confirmation_sent_at: Some(yesterday + Duration::hours(12)),
+            email_confirmation_expires_at: Some(yesterday + Duration::hours(36)),
+            role: Role::User,
+            terms_accepted_at: None,
+            terms_version: None,
+            locale: Locale::English,
+            timestamps: Timestamps::created(yesterday),
+            version: 1,
         };
 
         let expected_user = user.clone();
@@ -381,14 +927,105 @@ mod tests {
         users
             .expect_complete_email_confirmation()
             .times(1)
-            .withf(move |user_id, new_email| *user_id == user.id && new_email.is_none())
-            .returning(|_, _| Ok(()));
+            .withf(move |user_id, token, new_email| {
+                *user_id == user.id && token == "token" && new_email.is_none()
+            })
+            .returning(|_, _, _| Ok(()));
+
+        let (events, recorder) = recording_bus();
 
-        let service = EmailAddressServiceImpl::new(Arc::new(users), Arc::new(MockMailer::new()));
+        let service = EmailAddressServiceImpl::new(
+            Arc::new(users),
+            Arc::new(MockMailer::new()),
+            EmailSenders::default(),
+            events,
+            Arc::new(SystemClock),
+            EmailConfirmationConfig::default(),
+        );
 
-        let result = service.confirm_email(&expected_user, "token").await;
+        let result = service
+            .confirm_email(&expected_user, "token", &RequestOrigin::default())
+            .await;
 
         assert!(result.is_ok());
+        assert!(matches!(
+            &recorder.events()[..],
+            [DomainEvent::EmailConfirmed { user_id: id, email, first_confirmation: true, .. }]
+                if *id == user_id && email.to_string() == "email@example.com"
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_force_confirm_email_confirms_without_a_token() -> TestResult {
+        let user = User {
+            id: Uuid::now_v7(),
+            email: EmailAddress::new_unchecked("email@example.com"),
+            ..User::default()
+        };
+        let user_id = user.id;
+        let admin_id = Uuid::now_v7();
+
+        let mut users = MockUserRepository::new();
+
+        users
+            .expect_force_email_confirmation()
+            .times(1)
+            .withf(move |id| *id == user_id)
+            .returning(|_| Ok(()));
+
+        let (events, recorder) = recording_bus();
+
+        let service = EmailAddressServiceImpl::new(
+            Arc::new(users),
+            Arc::new(MockMailer::new()),
+            EmailSenders::default(),
+            events,
+            Arc::new(SystemClock),
+            EmailConfirmationConfig::default(),
+        );
+
+        service
+            .force_confirm_email(&user, &admin_id, &RequestOrigin::default())
+            .await?;
+
+        assert!(matches!(
+            &recorder.events()[..],
+            [DomainEvent::EmailConfirmedByAdmin { user_id: id, admin_id: by, .. }]
+                if *id == user_id && *by == admin_id
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_force_confirm_email_already_confirmed_does_nothing() -> TestResult {
+        let user = User {
+            email_confirmed_at: Some(Utc::now()),
+            ..User::default()
+        };
+
+        let mut users = MockUserRepository::new();
+
+        users.expect_force_email_confirmation().times(0);
+
+        let (events, recorder) = recording_bus();
+
+        let service = EmailAddressServiceImpl::new(
+            Arc::new(users),
+            Arc::new(MockMailer::new()),
+            EmailSenders::default(),
+            events,
+            Arc::new(SystemClock),
+            EmailConfirmationConfig::default(),
+        );
+
+        service
+            .force_confirm_email(&user, &Uuid::now_v7(), &RequestOrigin::default())
+            .await?;
+
+        assert!(recorder.events().is_empty());
 
         Ok(())
     }
@@ -401,24 +1038,36 @@ mod tests {
         let mut users = MockUserRepository::new();
 
         let user = User {
-            id: user_id.clone(),
+            id: user_id,
             email: EmailAddress::new_unchecked("email@example.com"),
             new_email: None,
             email_confirmed_at: None,
-            email_confirmation_token: Some("token".to_string()),
-            email_confirmation_sent_at: Some(yesterday.clone()),
-            created_at: yesterday.clone(),
-            updated_at: yesterday.clone(),
+            email_confirmation_token: Some(hash_confirmation_token("token")),
+            email_confirmation_sent_at: Some(yesterday),
+            email_confirmation_expires_at: Some(yesterday + Duration::hours(24)),
+            role: Role::User,
+            terms_accepted_at: None,
+            terms_version: None,
+            locale: Locale::English,
+            timestamps: Timestamps::created(yesterday),
+            version: 1,
         };
 
         let expected_user = user.clone();
 
         users.expect_complete_email_confirmation().times(0);
 
-        let service = EmailAddressServiceImpl::new(Arc::new(users), Arc::new(MockMailer::new()));
+        let service = EmailAddressServiceImpl::new(
+            Arc::new(users),
+            Arc::new(MockMailer::new()),
+            EmailSenders::default(),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            EmailConfirmationConfig::default(),
+        );
 
         let result = service
-            .confirm_email(&expected_user, "incorrect token")
+            .confirm_email(&expected_user, "incorrect token", &RequestOrigin::default())
             .await;
 
         assert!(result.is_err());
@@ -434,26 +1083,714 @@ mod tests {
         let mut users = MockUserRepository::new();
 
         let user = User {
-            id: user_id.clone(),
+            id: user_id,
             email: EmailAddress::new_unchecked("email@example.com"),
             new_email: None,
             email_confirmed_at: None,
-            email_confirmation_token: Some("token".to_string()),
-            email_confirmation_sent_at: Some(last_week.clone()),
-            created_at: last_week.clone(),
-            updated_at: last_week.clone(),
+            email_confirmation_token: Some(hash_confirmation_token("token")),
+            email_confirmation_sent_at: Some(last_week),
+            email_confirmation_expires_at: Some(last_week + Duration::hours(24)),
+            role: Role::User,
+            terms_accepted_at: None,
+            terms_version: None,
+            locale: Locale::English,
+            timestamps: Timestamps::created(last_week),
+            version: 1,
         };
 
         let expected_user = user.clone();
 
         users.expect_complete_email_confirmation().times(0);
 
-        let service = EmailAddressServiceImpl::new(Arc::new(users), Arc::new(MockMailer::new()));
+        let service = EmailAddressServiceImpl::new(
+            Arc::new(users),
+            Arc::new(MockMailer::new()),
+            EmailSenders::default(),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            EmailConfirmationConfig::default(),
+        );
 
-        let result = service.confirm_email(&expected_user, "token").await;
+        let result = service
+            .confirm_email(&expected_user, "token", &RequestOrigin::default())
+            .await;
 
         assert!(result.is_err());
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_confirm_new_email_taken_before_confirmation() -> TestResult {
+        let yesterday = Utc::now() - Duration::days(1);
+
+        let mut users = MockUserRepository::new();
+
+        let user = User {
+            id: Uuid::now_v7(),
+            email: EmailAddress::new_unchecked("email@example.com"),
+            new_email: Some(EmailAddress::new_unchecked("taken@example.com")),
+            email_confirmed_at: Some(yesterday),
+            email_confirmation_token: Some(hash_confirmation_token("token")),
+            email_confirmation_sent_at: Some(Utc::now()),
+            email_confirmation_expires_at: Some(Utc::now() + Duration::hours(24)),
+            role: Role::User,
+            terms_accepted_at: None,
+            terms_version: None,
+            locale: Locale::English,
+            timestamps: Timestamps::created(yesterday),
+            version: 1,
+        };
+
+        let expected_id = user.id;
+
+        users
+            .expect_complete_email_confirmation()
+            .times(1)
+            .withf(move |user_id, _, new_email| {
+                *user_id == expected_id
+                    && new_email.map(|email| email.to_string())
+                        == Some("taken@example.com".to_string())
+            })
+            .returning(|_, _, _| Err(UpdateUserError::EmailAddressInUse));
+
+        let service = EmailAddressServiceImpl::new(
+            Arc::new(users),
+            Arc::new(MockMailer::new()),
+            EmailSenders::default(),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            EmailConfirmationConfig::default(),
+        );
+
+        let result = service
+            .confirm_email(&user, "token", &RequestOrigin::default())
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(EmailConfirmationError::EmailAddressInUse)
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_generate_email_confirmation_token_with_jitter() -> TestResult {
+        let stored_expiry = Arc::new(std::sync::Mutex::new(None));
+        let captured_expiry = stored_expiry.clone();
+
+        let mut repo = MockUserRepository::new();
+
+        repo.expect_initialize_email_confirmation()
+            .times(1)
+            .returning(move |_, _, expires_at, _, _, _| {
+                *captured_expiry.lock().unwrap() = Some(*expires_at);
+                Ok(())
+            });
+
+        let config = EmailConfirmationConfig {
+            expiry_jitter_minutes: 30,
+            ..EmailConfirmationConfig::default()
+        };
+
+        let service = EmailAddressServiceImpl::new(
+            Arc::new(repo),
+            Arc::new(MockMailer::new()),
+            EmailSenders::default(),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            config,
+        );
+
+        let before = Utc::now();
+
+        let (_, expires_at) = service
+            .generate_email_confirmation_token(&User::default(), None, &RequestOrigin::default())
+            .await?;
+
+        let after = Utc::now();
+
+        assert!(expires_at >= before + Duration::hours(24) - Duration::minutes(30));
+        assert!(expires_at <= after + Duration::hours(24) + Duration::minutes(30));
+        assert_eq!(*stored_expiry.lock().unwrap(), Some(expires_at));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_token_expiry_without_jitter() {
+        let service = EmailAddressServiceImpl::new(
+            Arc::new(MockUserRepository::new()),
+            Arc::new(MockMailer::new()),
+            EmailSenders::default(),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            EmailConfirmationConfig::default(),
+        );
+
+        let issued_at = Utc::now();
+
+        assert_eq!(
+            service.token_expiry(issued_at),
+            issued_at + Duration::hours(24)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_token_generated_with_one_hour_ttl() -> TestResult {
+        let stored_expiry = Arc::new(std::sync::Mutex::new(None));
+        let captured_expiry = stored_expiry.clone();
+
+        let mut repo = MockUserRepository::new();
+
+        repo.expect_initialize_email_confirmation()
+            .times(1)
+            .returning(move |_, _, expires_at, _, _, _| {
+                *captured_expiry.lock().unwrap() = Some(*expires_at);
+                Ok(())
+            });
+
+        let config = EmailConfirmationConfig {
+            token_ttl_minutes: 60,
+            ..EmailConfirmationConfig::default()
+        };
+
+        let service = EmailAddressServiceImpl::new(
+            Arc::new(repo),
+            Arc::new(MockMailer::new()),
+            EmailSenders::default(),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            config,
+        );
+
+        let before = Utc::now();
+
+        let (token, expires_at) = service
+            .generate_email_confirmation_token(&User::default(), None, &RequestOrigin::default())
+            .await?;
+
+        let after = Utc::now();
+
+        assert!(expires_at >= before + Duration::hours(1));
+        assert!(expires_at <= after + Duration::hours(1));
+        assert_eq!(*stored_expiry.lock().unwrap(), Some(expires_at));
+
+        // Simulate the token being used within the hour, then after it, by moving its issue
+        // time back so the stored expiry is that far from now
+        for (elapsed, accepted) in [
+            (Duration::minutes(59), true),
+            (Duration::minutes(61), false),
+        ] {
+            let user = User {
+                id: Uuid::now_v7(),
+                email_confirmation_token: Some(hash_confirmation_token(&token)),
+                email_confirmation_expires_at: Some(expires_at - elapsed),
+                ..User::default()
+            };
+
+            let mut users = MockUserRepository::new();
+
+            users
+                .expect_complete_email_confirmation()
+                .times(usize::from(accepted))
+                .returning(|_, _, _| Ok(()));
+
+            let service = EmailAddressServiceImpl::new(
+                Arc::new(users),
+                any_mailer(),
+                EmailSenders::default(),
+                EventBus::new(),
+                Arc::new(SystemClock),
+                EmailConfirmationConfig::default(),
+            );
+
+            let result = service
+                .confirm_email(&user, &token, &RequestOrigin::default())
+                .await;
+
+            if accepted {
+                assert!(result.is_ok());
+            } else {
+                assert!(matches!(
+                    result,
+                    Err(EmailConfirmationError::ConfirmationTokenExpired)
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_confirm_email_uses_stored_expiry() -> TestResult {
+        let last_week = Utc::now() - Duration::weeks(1);
+
+        let mut users = MockUserRepository::new();
+
+        let user = User {
+            id: Uuid::now_v7(),
+            email: EmailAddress::new_unchecked("email@example.com"),
+            new_email: None,
+            email_confirmed_at: None,
+            email_confirmation_token: Some(hash_confirmation_token("token")),
+            email_confirmation_sent_at: Some(last_week),
+            email_confirmation_expires_at: Some(Utc::now() + Duration::minutes(5)),
+            role: Role::User,
+            terms_accepted_at: None,
+            terms_version: None,
+            locale: Locale::English,
+            timestamps: Timestamps::created(last_week),
+            version: 1,
+        };
+
+        users
+            .expect_complete_email_confirmation()
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+
+        let service = EmailAddressServiceImpl::new(
+            Arc::new(users),
+            any_mailer(),
+            EmailSenders::default(),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            EmailConfirmationConfig::default(),
+        );
+
+        service
+            .confirm_email(&user, "token", &RequestOrigin::default())
+            .await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_confirm_email_one_second_past_expiry() -> TestResult {
+        let issued_at = Utc::now();
+        let clock = MockClock::new(issued_at);
+
+        let mut users = MockUserRepository::new();
+
+        users
+            .expect_initialize_email_confirmation()
+            .times(1)
+            .returning(|_, _, _, _, _, _| Ok(()));
+
+        users.expect_complete_email_confirmation().times(0);
+
+        let service = EmailAddressServiceImpl::new(
+            Arc::new(users),
+            Arc::new(MockMailer::new()),
+            EmailSenders::default(),
+            EventBus::new(),
+            Arc::new(clock.clone()),
+            EmailConfirmationConfig::default(),
+        );
+
+        let user_id = Uuid::now_v7();
+
+        let (token, expires_at) = service
+            .generate_email_confirmation_token(
+                &User {
+                    id: user_id,
+                    ..User::default()
+                },
+                None,
+                &RequestOrigin::default(),
+            )
+            .await?;
+
+        assert_eq!(expires_at, issued_at + Duration::hours(24));
+
+        let user = User {
+            id: user_id,
+            email_confirmation_token: Some(hash_confirmation_token(&token)),
+            email_confirmation_expires_at: Some(expires_at),
+            ..User::default()
+        };
+
+        clock.advance(Duration::hours(24) + Duration::seconds(1));
+
+        let result = service
+            .confirm_email(&user, &token, &RequestOrigin::default())
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(EmailConfirmationError::ConfirmationTokenExpired)
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_confirm_email_token_is_single_use() -> TestResult {
+        let user = User {
+            id: Uuid::now_v7(),
+            email_confirmation_token: Some(hash_confirmation_token("token")),
+            email_confirmation_expires_at: Some(Utc::now() + Duration::hours(1)),
+            ..User::default()
+        };
+
+        let mut users = MockUserRepository::new();
+
+        users
+            .expect_complete_email_confirmation()
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+
+        let service = EmailAddressServiceImpl::new(
+            Arc::new(users),
+            any_mailer(),
+            EmailSenders::default(),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            EmailConfirmationConfig::default(),
+        );
+
+        service
+            .confirm_email(&user, "token", &RequestOrigin::default())
+            .await?;
+
+        // The user as it is stored once the token has been consumed
+        let confirmed_user = User {
+            email_confirmed_at: Some(Utc::now()),
+            email_confirmation_token: None,
+            ..user
+        };
+
+        let result = service
+            .confirm_email(&confirmed_user, "token", &RequestOrigin::default())
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(EmailConfirmationError::ConfirmationTokenMismatch)
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_confirm_email_token_consumed_concurrently() -> TestResult {
+        let user = User {
+            id: Uuid::now_v7(),
+            email_confirmation_token: Some(hash_confirmation_token("token")),
+            email_confirmation_expires_at: Some(Utc::now() + Duration::hours(1)),
+            ..User::default()
+        };
+
+        let mut users = MockUserRepository::new();
+
+        users
+            .expect_complete_email_confirmation()
+            .times(1)
+            .returning(|_, _, _| Err(UpdateUserError::ConfirmationTokenMismatch));
+
+        let service = EmailAddressServiceImpl::new(
+            Arc::new(users),
+            Arc::new(MockMailer::new()),
+            EmailSenders::default(),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            EmailConfirmationConfig::default(),
+        );
+
+        let result = service
+            .confirm_email(&user, "token", &RequestOrigin::default())
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(EmailConfirmationError::ConfirmationTokenMismatch)
+        ));
+
+        Ok(())
+    }
+
+    /// A service which fails the test if validating a token touches the repository or mailer
+    fn validating_service(now: DateTime<Utc>) -> impl EmailAddressService {
+        let mut users = MockUserRepository::new();
+
+        users.expect_complete_email_confirmation().times(0);
+
+        EmailAddressServiceImpl::new(
+            Arc::new(users),
+            Arc::new(MockMailer::new()),
+            EmailSenders::default(),
+            EventBus::new(),
+            Arc::new(MockClock::new(now)),
+            EmailConfirmationConfig::default(),
+        )
+    }
+
+    fn user_with_token(token: &str, expires_at: DateTime<Utc>) -> User {
+        User {
+            email_confirmation_token: Some(hash_confirmation_token(token)),
+            email_confirmation_expires_at: Some(expires_at),
+            ..User::default()
+        }
+    }
+
+    #[test]
+    fn test_validate_confirmation_token_valid() {
+        let now = Utc::now();
+        let service = validating_service(now);
+
+        let result = service.validate_confirmation_token(
+            &user_with_token("token", now + Duration::hours(1)),
+            "token",
+        );
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_validate_confirmation_token_expired() {
+        let now = Utc::now();
+        let service = validating_service(now);
+
+        let result = service.validate_confirmation_token(
+            &user_with_token("token", now - Duration::seconds(1)),
+            "token",
+        );
+
+        assert!(matches!(
+            result,
+            Err(EmailConfirmationError::ConfirmationTokenExpired)
+        ));
+    }
+
+    #[test]
+    fn test_validate_confirmation_token_mismatch() {
+        let now = Utc::now();
+        let service = validating_service(now);
+
+        let result = service.validate_confirmation_token(
+            &user_with_token("token", now + Duration::hours(1)),
+            "incorrect token",
+        );
+
+        assert!(matches!(
+            result,
+            Err(EmailConfirmationError::ConfirmationTokenMismatch)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_email_change() -> TestResult {
+        let user = User {
+            id: Uuid::now_v7(),
+            new_email: Some(EmailAddress::new_unchecked("new@example.com")),
+            email_confirmation_token: Some(hash_confirmation_token("token")),
+            version: 3,
+            ..User::default()
+        };
+
+        let expected_id = user.id;
+        let mut users = MockUserRepository::new();
+
+        users
+            .expect_cancel_email_change()
+            .times(1)
+            .withf(move |user_id, version| *user_id == expected_id && *version == 3)
+            .returning(|_, _| Ok(()));
+
+        let service = EmailAddressServiceImpl::new(
+            Arc::new(users),
+            Arc::new(MockMailer::new()),
+            EmailSenders::default(),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            EmailConfirmationConfig::default(),
+        );
+
+        service.cancel_email_change(&user).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cancel_email_change_without_pending_change() -> TestResult {
+        let mut users = MockUserRepository::new();
+
+        users.expect_cancel_email_change().times(0);
+
+        let service = EmailAddressServiceImpl::new(
+            Arc::new(users),
+            Arc::new(MockMailer::new()),
+            EmailSenders::default(),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            EmailConfirmationConfig::default(),
+        );
+
+        let result = service.cancel_email_change(&User::default()).await;
+
+        assert!(matches!(
+            result,
+            Err(EmailConfirmationError::NoPendingEmailChange)
+        ));
+
+        Ok(())
+    }
+
+    async fn send_email_confirmation_failing_with(
+        mailer_error: MailerError,
+    ) -> Result<SentEmailConfirmation, EmailConfirmationError> {
+        let mut users = MockUserRepository::new();
+        let mut mailer = MockMailer::new();
+
+        users
+            .expect_initialize_email_confirmation()
+            .returning(|_, _, _, _, _, _| Ok(()));
+
+        let mut mailer_error = Some(mailer_error);
+
+        mailer
+            .expect_send_email()
+            .times(1)
+            .returning(move |_| Err(mailer_error.take().expect("send_email called twice")));
+
+        let service = EmailAddressServiceImpl::new(
+            Arc::new(users),
+            Arc::new(mailer),
+            EmailSenders::default(),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            EmailConfirmationConfig::default(),
+        );
+
+        service
+            .send_email_confirmation(
+                &User::default(),
+                EmailConfirmationType::CurrentEmail,
+                &example_base_url(),
+                &RequestOrigin::default(),
+            )
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_send_email_confirmation_separates_mailer_failures() {
+        assert!(matches!(
+            send_email_confirmation_failing_with(MailerError::InvalidEmail).await,
+            Err(EmailConfirmationError::InvalidRecipient)
+        ));
+        assert!(matches!(
+            send_email_confirmation_failing_with(MailerError::SendError).await,
+            Err(EmailConfirmationError::MailerUnavailable)
+        ));
+        assert!(matches!(
+            send_email_confirmation_failing_with(MailerError::RenderError).await,
+            Err(EmailConfirmationError::CouldNotSendEmail)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_confirm_email_change_is_not_a_first_confirmation() -> TestResult {
+        let user = User {
+            id: Uuid::now_v7(),
+            new_email: Some(EmailAddress::new_unchecked("new@example.com")),
+            email_confirmed_at: Some(Utc::now() - Duration::days(30)),
+            email_confirmation_token: Some(hash_confirmation_token("token")),
+            email_confirmation_expires_at: Some(Utc::now() + Duration::hours(1)),
+            ..User::default()
+        };
+
+        let mut users = MockUserRepository::new();
+
+        users
+            .expect_complete_email_confirmation()
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+
+        let (events, recorder) = recording_bus();
+
+        let service = EmailAddressServiceImpl::new(
+            Arc::new(users),
+            Arc::new(MockMailer::new()),
+            EmailSenders::default(),
+            events,
+            Arc::new(SystemClock),
+            EmailConfirmationConfig::default(),
+        );
+
+        service
+            .confirm_email(&user, "token", &RequestOrigin::default())
+            .await?;
+
+        assert!(matches!(
+            &recorder.events()[..],
+            [DomainEvent::EmailConfirmed { email, first_confirmation: false, .. }]
+                if email.to_string() == "new@example.com"
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_email_confirmation_publishes_what_was_sent() -> TestResult {
+        let user = User {
+            id: Uuid::now_v7(),
+            email: EmailAddress::new_unchecked("email@example.com"),
+            ..User::default()
+        };
+
+        let mut users = MockUserRepository::new();
+
+        users
+            .expect_initialize_email_confirmation()
+            .times(2)
+            .returning(|_, _, _, _, _, _| Ok(()));
+
+        let (events, recorder) = recording_bus();
+
+        let service = EmailAddressServiceImpl::new(
+            Arc::new(users),
+            any_mailer(),
+            EmailSenders::default(),
+            events,
+            Arc::new(SystemClock),
+            EmailConfirmationConfig::default(),
+        );
+
+        let current = service
+            .send_email_confirmation(
+                &user,
+                EmailConfirmationType::CurrentEmail,
+                &example_base_url(),
+                &RequestOrigin::default(),
+            )
+            .await?;
+        let change = service
+            .send_email_confirmation(
+                &user,
+                EmailConfirmationType::NewEmail(EmailAddress::new_unchecked("new@example.com")),
+                &example_base_url(),
+                &RequestOrigin::default(),
+            )
+            .await?;
+
+        let published = recorder.events();
+
+        assert!(matches!(
+            &published[0],
+            DomainEvent::EmailConfirmationSent { user_id, email, expires_at, .. }
+                if *user_id == user.id
+                    && email.to_string() == "email@example.com"
+                    && *expires_at == current.expires_at
+        ));
+        assert!(matches!(
+            &published[1],
+            DomainEvent::EmailChangeRequested { user_id, new_email, expires_at, .. }
+                if *user_id == user.id
+                    && new_email.to_string() == "new@example.com"
+                    && *expires_at == change.expires_at
+        ));
+
+        Ok(())
+    }
 }
@@ -0,0 +1,32 @@
+//! Email confirmation tokens
+
+use base64::{engine::general_purpose::URL_SAFE, Engine as _};
+use sha2::{Digest, Sha256};
+
+/// Hashes an email confirmation token for storage.
+///
+/// Only the hash is kept in the database, so a leaked copy of it can't be used to confirm an
+/// email address: the raw token only ever appears in the link emailed to the user.
+///
+/// # Arguments
+/// * `token` - The raw email confirmation token.
+///
+/// # Returns
+/// The URL-safe base64 encoded SHA-256 hash of the token.
+pub fn hash_confirmation_token(token: &str) -> String {
+    URL_SAFE.encode(Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_confirmation_token() {
+        let hash = hash_confirmation_token("token");
+
+        assert_ne!(hash, "token");
+        assert_eq!(hash, hash_confirmation_token("token"));
+        assert_ne!(hash, hash_confirmation_token("other-token"));
+    }
+}
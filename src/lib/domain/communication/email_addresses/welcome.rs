@@ -0,0 +1,171 @@
+//! Welcome email subscriber
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tracing::warn;
+
+use crate::domain::{
+    communication::mailer::{EmailContext, EmailKind, EmailSenders, EmailTemplates, Mailer},
+    events::{DomainEvent, EventSubscriber},
+};
+
+/// Welcomes users once they first confirm their email address. Confirming a change of address
+/// doesn't welcome them again.
+#[derive(Clone, Debug)]
+pub struct WelcomeEmailSubscriber<M: Mailer> {
+    mailer: Arc<M>,
+    senders: EmailSenders,
+    templates: EmailTemplates,
+}
+
+impl<M: Mailer> WelcomeEmailSubscriber<M> {
+    /// Create a new welcome email subscriber sending emails with `mailer`, from the welcome sender
+    pub fn new(mailer: Arc<M>, senders: EmailSenders) -> Self {
+        Self {
+            mailer,
+            senders,
+            templates: EmailTemplates::default(),
+        }
+    }
+
+    /// Renders welcome emails with `templates` instead of the embedded template
+    pub fn with_templates(mut self, templates: EmailTemplates) -> Self {
+        self.templates = templates;
+        self
+    }
+}
+
+#[async_trait]
+impl<M: Mailer> EventSubscriber for WelcomeEmailSubscriber<M> {
+    async fn handle(&self, event: &DomainEvent) {
+        let DomainEvent::EmailConfirmed {
+            email,
+            first_confirmation: true,
+            locale,
+            ..
+        } = event
+        else {
+            return;
+        };
+
+        if let Err(err) = self
+            .mailer
+            .send_templated(
+                &self.templates,
+                email.clone(),
+                self.senders.sender(EmailKind::Welcome),
+                EmailKind::Welcome,
+                EmailContext {
+                    locale: *locale,
+                    ..EmailContext::default()
+                },
+            )
+            .await
+        {
+            warn!("Failed to send welcome email: {:?}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use testresult::TestResult;
+    use uuid::Uuid;
+
+    use crate::domain::{
+        communication::{
+            email_addresses::{EmailAddress, RequestOrigin},
+            mailer::{tests::MockMailer, MailerError},
+        },
+        i18n::Locale,
+    };
+
+    use super::*;
+
+    fn email_confirmed(first_confirmation: bool) -> DomainEvent {
+        DomainEvent::EmailConfirmed {
+            user_id: Uuid::now_v7(),
+            email: EmailAddress::new_unchecked("email@example.com"),
+            first_confirmation,
+            locale: Locale::English,
+            origin: RequestOrigin::default(),
+            occurred_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_first_confirmation_sends_welcome_email() {
+        let mut mailer = MockMailer::new();
+
+        mailer
+            .expect_send_email()
+            .times(1)
+            .withf(|message| {
+                message.to.to_string() == "email@example.com"
+                    && message.from.is_none()
+                    && message.subject == "Welcome!"
+            })
+            .returning(|_| Ok(()));
+
+        WelcomeEmailSubscriber::new(Arc::new(mailer), EmailSenders::default())
+            .handle(&email_confirmed(true))
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_welcome_email_sent_from_configured_sender() -> TestResult {
+        let mut mailer = MockMailer::new();
+
+        mailer
+            .expect_send_email()
+            .times(1)
+            .withf(|message| {
+                message
+                    .from
+                    .as_ref()
+                    .map(|from| from.email.to_string())
+                    .as_deref()
+                    == Some("hello@example.com")
+            })
+            .returning(|_| Ok(()));
+
+        let senders = EmailSenders {
+            confirmation: Some("verify@example.com".parse()?),
+            welcome: Some("hello@example.com".parse()?),
+            ..EmailSenders::default()
+        };
+
+        WelcomeEmailSubscriber::new(Arc::new(mailer), senders)
+            .handle(&email_confirmed(true))
+            .await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_email_change_does_not_send_welcome_email() {
+        let mut mailer = MockMailer::new();
+
+        mailer.expect_send_email().times(0);
+
+        WelcomeEmailSubscriber::new(Arc::new(mailer), EmailSenders::default())
+            .handle(&email_confirmed(false))
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_welcome_email_failure_is_not_propagated() {
+        let mut mailer = MockMailer::new();
+
+        mailer
+            .expect_send_email()
+            .times(1)
+            .returning(|_| Err(MailerError::SendError));
+
+        WelcomeEmailSubscriber::new(Arc::new(mailer), EmailSenders::default())
+            .handle(&email_confirmed(true))
+            .await;
+    }
+}
@@ -2,12 +2,24 @@
 
 mod errors;
 mod message;
+mod senders;
+mod templates;
 
-pub use {errors::MailerError, message::Message};
+pub use {
+    errors::MailerError,
+    message::{Mailbox, Message},
+    senders::EmailSenders,
+    templates::{
+        EmailContext, EmailKind, EmailTemplateConfig, EmailTemplateError, EmailTemplates,
+        RenderedEmail,
+    },
+};
 
 use async_trait::async_trait;
 use mockall::mock;
 
+use crate::domain::communication::email_addresses::EmailAddress;
+
 /// Mailer trait
 #[async_trait]
 pub trait Mailer: Clone + Send + Sync + 'static {
@@ -23,6 +35,37 @@ pub trait Mailer: Clone + Send + Sync + 'static {
     /// # Returns
     /// A [`Result`] indicating success or failure.
     async fn send_email(&self, message: Message) -> Result<(), MailerError>;
+
+    /// Render an email from the [`EmailTemplates`] registry and send it
+    ///
+    /// # Arguments
+    /// * `templates` - The [`EmailTemplates`] to render the email with.
+    /// * `to` - The [`EmailAddress`] to send the email to.
+    /// * `from` - The [`Mailbox`] to send the email from, or [`None`] for the default sender.
+    /// * `kind` - The [`EmailKind`] of email to send.
+    /// * `context` - The [`EmailContext`] interpolated into the template.
+    ///
+    /// # Returns
+    /// A [`Result`] indicating success or failure.
+    async fn send_templated(
+        &self,
+        templates: &EmailTemplates,
+        to: EmailAddress,
+        from: Option<Mailbox>,
+        kind: EmailKind,
+        context: EmailContext,
+    ) -> Result<(), MailerError> {
+        let rendered = templates.render(kind, &context)?;
+
+        self.send_email(Message {
+            to,
+            from,
+            subject: rendered.subject,
+            html_body: rendered.html_body,
+            plain_body: rendered.plain_body,
+        })
+        .await
+    }
 }
 
 mock! {
@@ -38,7 +81,58 @@ mock! {
     }
 }
 
+/// Test doubles for the mailer module
 #[cfg(test)]
 pub mod tests {
     pub use super::MockMailer;
+
+    use std::sync::Arc;
+
+    use testresult::TestResult;
+
+    use super::*;
+
+    /// Create a mailer which accepts any emails
+    pub fn any_mailer() -> Arc<MockMailer> {
+        let mut mailer = MockMailer::new();
+
+        mailer.expect_send_email().returning(|_| Ok(()));
+
+        Arc::new(mailer)
+    }
+
+    #[tokio::test]
+    async fn test_send_templated_confirmation() -> TestResult {
+        let mut mailer = MockMailer::new();
+
+        mailer
+            .expect_send_email()
+            .times(1)
+            .withf(|message| {
+                message.to.to_string() == "email@example.com"
+                    && message.from.is_none()
+                    && message.subject == "Please confirm your email address"
+                    && message
+                        .html_body
+                        .contains(r#"href="https://example.com/confirm?token=abc""#)
+                    && message.plain_body
+                        == "Visit the following URL to confirm your email address: https://example.com/confirm?token=abc"
+            })
+            .returning(|_| Ok(()));
+
+        mailer
+            .send_templated(
+                &EmailTemplates::default(),
+                EmailAddress::new("email@example.com")?,
+                None,
+                EmailKind::Confirmation,
+                EmailContext {
+                    link: "https://example.com/confirm?token=abc".to_string(),
+                    ..EmailContext::default()
+                },
+            )
+            .await?;
+
+        Ok(())
+    }
 }
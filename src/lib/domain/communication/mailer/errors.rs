@@ -1,15 +1,21 @@
 //! Mailer errors
 
+use css_inline::InlineError;
 use thiserror::Error;
+use tracing::debug;
 
 /// Mailer errors
 #[derive(Debug, Error)]
 pub enum MailerError {
-    /// An error occurred while sending the email
+    /// An error occurred while sending the email, which may not recur if it is sent again later
     #[error("An error occurred while sending the email")]
     SendError,
 
-    /// Invalid email address
+    /// An error occurred while rendering the email's template
+    #[error("An error occurred while rendering the email")]
+    RenderError,
+
+    /// The recipient's email address is invalid, or was rejected by the mail server
     #[error("Invalid email address")]
     InvalidEmail,
 
@@ -23,3 +29,19 @@ impl From<anyhow::Error> for MailerError {
         MailerError::UnknownError(err)
     }
 }
+
+impl From<askama::Error> for MailerError {
+    fn from(err: askama::Error) -> Self {
+        debug!("askama::Error {:?} -> MailerError", err);
+
+        MailerError::RenderError
+    }
+}
+
+impl From<InlineError> for MailerError {
+    fn from(err: InlineError) -> Self {
+        debug!("InlineError {:?} -> MailerError", err);
+
+        MailerError::RenderError
+    }
+}
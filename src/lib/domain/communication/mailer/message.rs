@@ -1,6 +1,90 @@
 //! Email message
 
-use crate::domain::communication::email_addresses::EmailAddress;
+use std::{fmt, str::FromStr};
+
+use crate::domain::communication::email_addresses::{EmailAddress, EmailAddressError};
+
+/// An email address with an optional display name, e.g. `"Example" <noreply@example.com>`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mailbox {
+    /// The display name shown alongside the address
+    pub name: Option<String>,
+
+    /// The email address
+    pub email: EmailAddress,
+}
+
+impl Mailbox {
+    /// Create a new mailbox
+    pub fn new(name: Option<String>, email: EmailAddress) -> Self {
+        Self { name, email }
+    }
+}
+
+impl From<EmailAddress> for Mailbox {
+    fn from(email: EmailAddress) -> Self {
+        Self::new(None, email)
+    }
+}
+
+impl FromStr for Mailbox {
+    type Err = EmailAddressError;
+
+    /// Parses a bare address, or one with a display name such as `"Example" <noreply@example.com>`
+    fn from_str(mailbox: &str) -> Result<Self, Self::Err> {
+        let mailbox = mailbox.trim();
+
+        let Some((name, email)) = mailbox
+            .strip_suffix('>')
+            .and_then(|mailbox| mailbox.rsplit_once('<'))
+        else {
+            return Ok(Self::from(EmailAddress::new(mailbox)?));
+        };
+
+        let name = name.trim();
+        let name = match name
+            .strip_prefix('"')
+            .and_then(|name| name.strip_suffix('"'))
+        {
+            Some(quoted) => unescape(quoted),
+            None => name.to_string(),
+        };
+
+        Ok(Self::new(
+            Some(name).filter(|name| !name.is_empty()),
+            EmailAddress::new(email)?,
+        ))
+    }
+}
+
+/// Removes the backslashes escaping characters in a quoted display name
+fn unescape(quoted: &str) -> String {
+    let mut chars = quoted.chars();
+    let mut name = String::with_capacity(quoted.len());
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => name.extend(chars.next()),
+            c => name.push(c),
+        }
+    }
+
+    name
+}
+
+impl fmt::Display for Mailbox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            // The name is quoted so it can hold characters which are special in a header
+            Some(name) => {
+                let name = name.replace('\\', "\\\\").replace('"', "\\\"");
+
+                write!(f, "\"{name}\" <{}>", self.email)
+            }
+            None => write!(f, "{}", self.email),
+        }
+    }
+}
 
 /// Email message
 #[derive(Debug)]
@@ -8,8 +92,8 @@ pub struct Message {
     /// The recipient of the email
     pub to: EmailAddress,
 
-    /// The sender of the email
-    pub from: Option<EmailAddress>,
+    /// The sender of the email, or the mailer's default sender if [`None`]
+    pub from: Option<Mailbox>,
 
     /// The subject of the email
     pub subject: String,
@@ -20,3 +104,69 @@ pub struct Message {
     /// The plain text body of the email
     pub plain_body: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use testresult::TestResult;
+
+    use super::*;
+
+    #[test]
+    fn test_mailbox_display_without_name() -> TestResult {
+        let mailbox = Mailbox::from(EmailAddress::new("noreply@example.com")?);
+
+        assert_eq!(mailbox.to_string(), "noreply@example.com");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mailbox_display_with_name() -> TestResult {
+        let mailbox = Mailbox::new(
+            Some(r#"The "Example" Team"#.to_string()),
+            EmailAddress::new("noreply@example.com")?,
+        );
+
+        assert_eq!(
+            mailbox.to_string(),
+            r#""The \"Example\" Team" <noreply@example.com>"#
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mailbox_parses_bare_address() -> TestResult {
+        assert_eq!(
+            "noreply@example.com".parse::<Mailbox>()?,
+            Mailbox::from(EmailAddress::new("noreply@example.com")?)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mailbox_parses_display_name() -> TestResult {
+        let expected = Mailbox::new(
+            Some(r#"The "Example" Team"#.to_string()),
+            EmailAddress::new("support@example.com")?,
+        );
+
+        assert_eq!(
+            r#""The \"Example\" Team" <support@example.com>"#.parse::<Mailbox>()?,
+            expected
+        );
+        assert_eq!(expected.to_string().parse::<Mailbox>()?, expected);
+        assert_eq!(
+            "Support <support@example.com>".parse::<Mailbox>()?.name,
+            Some("Support".to_string())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mailbox_rejects_invalid_address() {
+        assert!("Support <not an address>".parse::<Mailbox>().is_err());
+    }
+}
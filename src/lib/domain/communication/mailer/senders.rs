@@ -0,0 +1,112 @@
+//! Email senders configuration
+
+use clap::Parser;
+
+use super::{EmailKind, Mailbox};
+
+/// The addresses each kind of email is sent from. A kind without one is sent from the mailer's
+/// default sender
+#[derive(Clone, Debug, Default, PartialEq, Eq, Parser)]
+pub struct EmailSenders {
+    /// The address email confirmations are sent from, such as `"Example" <verify@example.com>`
+    #[arg(long = "email-from-confirmation", env = "EMAIL_FROM_CONFIRMATION")]
+    pub confirmation: Option<Mailbox>,
+
+    /// The address confirmations of a new email address are sent from. Defaults to the address
+    /// email confirmations are sent from
+    #[arg(
+        long = "email-from-new-email-confirmation",
+        env = "EMAIL_FROM_NEW_EMAIL_CONFIRMATION"
+    )]
+    pub new_email_confirmation: Option<Mailbox>,
+
+    /// The address welcome emails are sent from
+    #[arg(long = "email-from-welcome", env = "EMAIL_FROM_WELCOME")]
+    pub welcome: Option<Mailbox>,
+}
+
+impl EmailSenders {
+    /// The address `kind` of email is sent from, or [`None`] for the mailer's default sender
+    pub fn sender(&self, kind: EmailKind) -> Option<Mailbox> {
+        match kind {
+            EmailKind::Confirmation => self.confirmation.clone(),
+            EmailKind::NewEmailConfirmation => self
+                .new_email_confirmation
+                .clone()
+                .or_else(|| self.confirmation.clone()),
+            EmailKind::Welcome => self.welcome.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use testresult::TestResult;
+
+    use super::*;
+
+    #[test]
+    fn test_senders_are_parsed_per_kind() -> TestResult {
+        let senders = EmailSenders::try_parse_from([
+            "test",
+            "--email-from-confirmation",
+            "Example <verify@example.com>",
+            "--email-from-welcome",
+            "hello@example.com",
+        ])?;
+
+        assert_eq!(
+            senders.sender(EmailKind::Confirmation),
+            Some("Example <verify@example.com>".parse()?)
+        );
+        assert_eq!(
+            senders.sender(EmailKind::Welcome),
+            Some("hello@example.com".parse()?)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_new_email_confirmation_falls_back_to_confirmation_sender() -> TestResult {
+        let mut senders = EmailSenders {
+            confirmation: Some("verify@example.com".parse()?),
+            ..EmailSenders::default()
+        };
+
+        assert_eq!(
+            senders.sender(EmailKind::NewEmailConfirmation),
+            senders.confirmation
+        );
+
+        senders.new_email_confirmation = Some("change@example.com".parse()?);
+
+        assert_eq!(
+            senders.sender(EmailKind::NewEmailConfirmation),
+            senders.new_email_confirmation
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_sender_is_rejected() {
+        assert!(
+            EmailSenders::try_parse_from(["test", "--email-from-welcome", "not an address"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_every_kind_uses_default_sender_unless_configured() {
+        let senders = EmailSenders::default();
+
+        for kind in [
+            EmailKind::Confirmation,
+            EmailKind::NewEmailConfirmation,
+            EmailKind::Welcome,
+        ] {
+            assert_eq!(senders.sender(kind), None);
+        }
+    }
+}
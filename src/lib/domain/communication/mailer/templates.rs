@@ -0,0 +1,580 @@
+//! Email template registry
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use clap::Parser;
+use thiserror::Error;
+
+use crate::domain::{
+    auth::emails::{
+        confirm_email_address::ConfirmEmailAddressTemplate, welcome::WelcomeEmailTemplate,
+    },
+    i18n::Locale,
+};
+
+use super::MailerError;
+
+/// The kinds of email which are rendered from a template
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EmailKind {
+    /// Asks the user to confirm their current email address
+    Confirmation,
+
+    /// Asks the user to confirm the new email address they are changing to
+    NewEmailConfirmation,
+
+    /// Welcomes a user who has confirmed their email address for the first time
+    Welcome,
+}
+
+/// Errors loading the templates overriding the embedded ones
+#[derive(Debug, Error)]
+pub enum EmailTemplateError {
+    /// A template couldn't be read
+    #[error("Failed to read email template {path}: {source}")]
+    Read {
+        /// The template's path
+        path: PathBuf,
+
+        /// The error reading it
+        source: io::Error,
+    },
+
+    /// A template for an email with a link doesn't say where the link goes
+    #[error("Email template {0} must contain a {{{{ link }}}} placeholder")]
+    MissingLinkPlaceholder(PathBuf),
+}
+
+/// Overrides of the subjects and templates emails are rendered with, so emails can be branded
+/// without changing the embedded templates
+#[derive(Clone, Debug, Default, PartialEq, Eq, Parser)]
+pub struct EmailTemplateConfig {
+    /// The subject of email confirmations, in every locale
+    #[arg(
+        long = "email-subject-confirmation",
+        env = "EMAIL_SUBJECT_CONFIRMATION"
+    )]
+    pub confirmation_subject: Option<String>,
+
+    /// The subject of confirmations of a new email address, in every locale
+    #[arg(
+        long = "email-subject-new-email-confirmation",
+        env = "EMAIL_SUBJECT_NEW_EMAIL_CONFIRMATION"
+    )]
+    pub new_email_confirmation_subject: Option<String>,
+
+    /// The subject of welcome emails, in every locale
+    #[arg(long = "email-subject-welcome", env = "EMAIL_SUBJECT_WELCOME")]
+    pub welcome_subject: Option<String>,
+
+    /// A directory of HTML templates used instead of the embedded ones, read once at startup.
+    /// `confirm_email_address.html` and `welcome.html` are looked for, with `{{ link }}` replaced
+    /// by the email's link, which the confirmation template must contain. A kind of email without
+    /// a template here uses the embedded one
+    #[arg(long = "template-dir", env = "EMAIL_TEMPLATE_DIR")]
+    pub template_dir: Option<PathBuf>,
+}
+
+impl EmailTemplateConfig {
+    /// The configured subject of `kind` of email, if it is overridden
+    fn subject(&self, kind: EmailKind) -> Option<&str> {
+        match kind {
+            EmailKind::Confirmation => self.confirmation_subject.as_deref(),
+            EmailKind::NewEmailConfirmation => self.new_email_confirmation_subject.as_deref(),
+            EmailKind::Welcome => self.welcome_subject.as_deref(),
+        }
+        .filter(|subject| !subject.trim().is_empty())
+    }
+}
+
+impl EmailKind {
+    /// The subject of emails of this kind, in the given locale
+    pub fn subject(&self, locale: Locale) -> &'static str {
+        let messages = locale.messages();
+
+        match self {
+            Self::Confirmation => messages.confirm_email.subject,
+            Self::NewEmailConfirmation => messages.confirm_email.new_email_subject,
+            Self::Welcome => messages.welcome.subject,
+        }
+    }
+
+    /// The name of the file in the template directory which overrides this kind's template
+    fn template_file(&self) -> &'static str {
+        match self {
+            Self::Confirmation | Self::NewEmailConfirmation => "confirm_email_address.html",
+            Self::Welcome => "welcome.html",
+        }
+    }
+
+    /// Whether emails of this kind ask the user to follow a link
+    fn has_link(&self) -> bool {
+        match self {
+            Self::Confirmation | Self::NewEmailConfirmation => true,
+            Self::Welcome => false,
+        }
+    }
+}
+
+/// A template overriding an embedded one, split around its `{{ link }}` placeholders
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct OverrideTemplate {
+    parts: Vec<String>,
+}
+
+impl OverrideTemplate {
+    /// Splits `template` around each `{{link}}` placeholder, however it is spaced inside the
+    /// braces
+    fn parse(template: &str) -> Self {
+        let mut parts = Vec::new();
+        let mut current = String::new();
+        let mut rest = template;
+
+        while let Some(start) = rest.find("{{") {
+            current.push_str(&rest[..start]);
+
+            let inner = &rest[start + 2..];
+            let placeholder_end = inner
+                .trim_start()
+                .strip_prefix("link")
+                .map(str::trim_start)
+                .and_then(|after| after.strip_prefix("}}"));
+
+            match placeholder_end {
+                Some(after) => {
+                    parts.push(std::mem::take(&mut current));
+                    rest = after;
+                }
+                // Braces which aren't around a link placeholder are kept as they are
+                None => {
+                    current.push_str("{{");
+                    rest = inner;
+                }
+            }
+        }
+
+        current.push_str(rest);
+        parts.push(current);
+
+        Self { parts }
+    }
+
+    /// Whether the template contains a link placeholder
+    fn has_link(&self) -> bool {
+        self.parts.len() > 1
+    }
+
+    /// The template with its placeholders replaced by `link`, escaped for HTML
+    fn render(&self, link: &str) -> String {
+        self.parts.join(&escape_html(link))
+    }
+}
+
+/// The values interpolated into an email template
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EmailContext {
+    /// The link the email asks the user to follow, if it has one
+    pub link: String,
+
+    /// The locale the email is written in, which is the recipient's
+    pub locale: Locale,
+}
+
+/// A rendered email, ready to be sent
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RenderedEmail {
+    /// The subject of the email
+    pub subject: String,
+
+    /// The HTML body of the email, with its CSS inlined
+    pub html_body: String,
+
+    /// The plain text body of the email
+    pub plain_body: String,
+}
+
+/// Renders each [`EmailKind`] from its template, or the template overriding it
+#[derive(Clone, Debug, Default)]
+pub struct EmailTemplates {
+    config: Arc<EmailTemplateConfig>,
+    confirmation: Option<Arc<OverrideTemplate>>,
+    welcome: Option<Arc<OverrideTemplate>>,
+}
+
+impl EmailTemplates {
+    /// Create a new template registry, with the overrides in `config`. The templates in its
+    /// template directory are read now, so a missing placeholder stops the server starting rather
+    /// than an email being sent without its link.
+    ///
+    /// # Returns
+    /// A [`Result`] which is [`Ok`] containing the registry,
+    /// or an [`Err`] containing an [`EmailTemplateError`] if a template can't be used.
+    pub fn load(config: EmailTemplateConfig) -> Result<Self, EmailTemplateError> {
+        let confirmation = load_override(&config, EmailKind::Confirmation)?;
+        let welcome = load_override(&config, EmailKind::Welcome)?;
+
+        Ok(Self {
+            config: Arc::new(config),
+            confirmation,
+            welcome,
+        })
+    }
+
+    /// Renders an email of the given kind.
+    ///
+    /// # Arguments
+    /// * `kind` - The [`EmailKind`] to render.
+    /// * `context` - The [`EmailContext`] interpolated into the template.
+    ///
+    /// # Returns
+    /// A [`Result`] which is [`Ok`] containing the [`RenderedEmail`],
+    /// or an [`Err`] containing a [`MailerError`] if the template could not be rendered.
+    pub fn render(
+        &self,
+        kind: EmailKind,
+        context: &EmailContext,
+    ) -> Result<RenderedEmail, MailerError> {
+        let (html_body, plain_body) = match kind {
+            EmailKind::Confirmation | EmailKind::NewEmailConfirmation => {
+                ConfirmEmailAddressTemplate {
+                    link: context.link.clone(),
+                    locale: context.locale,
+                }
+                .render_email()?
+            }
+            EmailKind::Welcome => WelcomeEmailTemplate {
+                locale: context.locale,
+            }
+            .render_email()?,
+        };
+
+        let html_body = match self.override_template(kind) {
+            Some(template) => css_inline::inline(&template.render(&context.link))?,
+            None => html_body,
+        };
+
+        let subject = self
+            .config
+            .subject(kind)
+            .unwrap_or_else(|| kind.subject(context.locale));
+
+        Ok(RenderedEmail {
+            subject: subject.to_string(),
+            html_body,
+            plain_body,
+        })
+    }
+
+    /// The template overriding `kind` of email, or [`None`] if it isn't overridden
+    fn override_template(&self, kind: EmailKind) -> Option<&OverrideTemplate> {
+        match kind {
+            EmailKind::Confirmation | EmailKind::NewEmailConfirmation => {
+                self.confirmation.as_deref()
+            }
+            EmailKind::Welcome => self.welcome.as_deref(),
+        }
+    }
+}
+
+/// Reads the template in the configured directory overriding `kind` of email, or [`None`] if it
+/// isn't overridden
+fn load_override(
+    config: &EmailTemplateConfig,
+    kind: EmailKind,
+) -> Result<Option<Arc<OverrideTemplate>>, EmailTemplateError> {
+    let Some(dir) = &config.template_dir else {
+        return Ok(None);
+    };
+
+    let path = dir.join(kind.template_file());
+
+    let Some(template) = read_template(&path)? else {
+        return Ok(None);
+    };
+
+    let template = OverrideTemplate::parse(&template);
+
+    if kind.has_link() && !template.has_link() {
+        return Err(EmailTemplateError::MissingLinkPlaceholder(path));
+    }
+
+    Ok(Some(Arc::new(template)))
+}
+
+/// Reads the template at `path`, or [`None`] if there isn't one
+fn read_template(path: &Path) -> Result<Option<String>, EmailTemplateError> {
+    match fs::read_to_string(path) {
+        Ok(template) => Ok(Some(template)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(source) => Err(EmailTemplateError::Read {
+            path: path.to_path_buf(),
+            source,
+        }),
+    }
+}
+
+/// Escapes `text` for use in HTML, including inside a quoted attribute
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#x27;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use testresult::TestResult;
+    use uuid::Uuid;
+
+    use super::*;
+
+    #[test]
+    fn test_render_confirmation() -> TestResult {
+        let context = EmailContext {
+            link: "https://example.com/confirm?token=abc".to_string(),
+            ..EmailContext::default()
+        };
+
+        let rendered = EmailTemplates::default().render(EmailKind::Confirmation, &context)?;
+
+        assert_eq!(rendered.subject, "Please confirm your email address");
+        assert!(rendered
+            .html_body
+            .contains(r#"href="https://example.com/confirm?token=abc""#));
+        assert_eq!(
+            rendered.plain_body,
+            "Visit the following URL to confirm your email address: https://example.com/confirm?token=abc"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_new_email_confirmation_subject() -> TestResult {
+        let rendered = EmailTemplates::default()
+            .render(EmailKind::NewEmailConfirmation, &EmailContext::default())?;
+
+        assert_eq!(rendered.subject, "Please confirm your new email address");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_welcome() -> TestResult {
+        let rendered =
+            EmailTemplates::default().render(EmailKind::Welcome, &EmailContext::default())?;
+
+        assert_eq!(rendered.subject, "Welcome!");
+        assert!(!rendered.html_body.is_empty());
+        assert!(!rendered.plain_body.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_confirmation_in_french() -> TestResult {
+        let context = EmailContext {
+            link: "https://example.com/confirm?token=abc".to_string(),
+            locale: Locale::French,
+        };
+
+        let rendered = EmailTemplates::default().render(EmailKind::Confirmation, &context)?;
+
+        assert_eq!(rendered.subject, "Veuillez confirmer votre adresse e-mail");
+        assert!(rendered
+            .html_body
+            .contains("Veuillez confirmer votre adresse e-mail en cliquant"));
+        assert_eq!(
+            rendered.plain_body,
+            "Rendez-vous à l’adresse suivante pour confirmer votre adresse e-mail : https://example.com/confirm?token=abc"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_welcome_in_french() -> TestResult {
+        let context = EmailContext {
+            locale: Locale::French,
+            ..EmailContext::default()
+        };
+
+        let rendered = EmailTemplates::default().render(EmailKind::Welcome, &context)?;
+
+        assert_eq!(rendered.subject, "Bienvenue !");
+        assert!(rendered.plain_body.starts_with("Merci d’avoir confirmé"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_configured_subject_overrides_default() -> TestResult {
+        let templates = EmailTemplates::load(EmailTemplateConfig {
+            confirmation_subject: Some("Confirm your Example account".to_string()),
+            ..EmailTemplateConfig::default()
+        })?;
+
+        let confirmation = templates.render(EmailKind::Confirmation, &EmailContext::default())?;
+        let welcome = templates.render(EmailKind::Welcome, &EmailContext::default())?;
+
+        assert_eq!(confirmation.subject, "Confirm your Example account");
+        assert_eq!(welcome.subject, "Welcome!");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_blank_subject_falls_back_to_default() -> TestResult {
+        let templates = EmailTemplates::load(EmailTemplateConfig {
+            welcome_subject: Some(" ".to_string()),
+            ..EmailTemplateConfig::default()
+        })?;
+
+        let rendered = templates.render(EmailKind::Welcome, &EmailContext::default())?;
+
+        assert_eq!(rendered.subject, "Welcome!");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_template_in_template_dir_overrides_embedded_template() -> TestResult {
+        let dir = env::temp_dir().join(format!("email-templates-{}", Uuid::now_v7()));
+        fs::create_dir_all(&dir)?;
+        fs::write(
+            dir.join("confirm_email_address.html"),
+            r#"<style>a { color: red; }</style><a href="{{ link }}">Confirm with Example</a>"#,
+        )?;
+
+        let templates = EmailTemplates::load(EmailTemplateConfig {
+            template_dir: Some(dir.clone()),
+            ..EmailTemplateConfig::default()
+        })?;
+        let context = EmailContext {
+            link: "https://example.com/confirm?token=abc".to_string(),
+            ..EmailContext::default()
+        };
+
+        let confirmation = templates.render(EmailKind::Confirmation, &context);
+        let welcome = templates.render(EmailKind::Welcome, &context);
+        fs::remove_dir_all(&dir)?;
+
+        let confirmation = confirmation?;
+
+        assert!(confirmation.html_body.contains(
+            r#"<a href="https://example.com/confirm?token=abc" style="color: red;">Confirm with Example</a>"#
+        ));
+        assert!(confirmation.plain_body.ends_with("token=abc"));
+        assert_eq!(
+            welcome?.html_body,
+            EmailTemplates::default()
+                .render(EmailKind::Welcome, &context)?
+                .html_body
+        );
+
+        Ok(())
+    }
+
+    /// Creates a template directory holding a confirmation template of `contents`
+    fn template_dir(contents: &str) -> TestResult<PathBuf> {
+        let dir = env::temp_dir().join(format!("email-templates-{}", Uuid::now_v7()));
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("confirm_email_address.html"), contents)?;
+
+        Ok(dir)
+    }
+
+    #[test]
+    fn test_template_is_read_once_when_loaded() -> TestResult {
+        let dir = template_dir(r#"<a href="{{ link }}">Confirm with Example</a>"#)?;
+
+        let templates = EmailTemplates::load(EmailTemplateConfig {
+            template_dir: Some(dir.clone()),
+            ..EmailTemplateConfig::default()
+        });
+        fs::remove_dir_all(&dir)?;
+
+        let rendered = templates?.render(EmailKind::Confirmation, &EmailContext::default())?;
+
+        assert!(rendered.html_body.contains("Confirm with Example"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_confirmation_template_without_link_is_rejected() -> TestResult {
+        let dir = template_dir("<p>Confirm with Example</p>")?;
+
+        let result = EmailTemplates::load(EmailTemplateConfig {
+            template_dir: Some(dir.clone()),
+            ..EmailTemplateConfig::default()
+        });
+        fs::remove_dir_all(&dir)?;
+
+        assert!(matches!(
+            result,
+            Err(EmailTemplateError::MissingLinkPlaceholder(path))
+                if path.ends_with("confirm_email_address.html")
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_link_placeholder_spacing_variants() {
+        for template in ["<{{link}}>", "<{{ link }}>", "<{{  link\t}}>"] {
+            assert_eq!(
+                OverrideTemplate::parse(template).render("https://example.com"),
+                "<https://example.com>",
+                "{template}"
+            );
+        }
+
+        let template = OverrideTemplate::parse("{{ linked }} {{ link }} {{");
+
+        assert_eq!(
+            template.render("https://example.com"),
+            "{{ linked }} https://example.com {{"
+        );
+    }
+
+    #[test]
+    fn test_link_is_escaped() {
+        let template = OverrideTemplate::parse(r#"<a href="{{ link }}">Confirm</a>"#);
+
+        assert_eq!(
+            template.render(r#"https://example.com/?a=1&b="><script>"#),
+            r#"<a href="https://example.com/?a=1&amp;b=&quot;&gt;&lt;script&gt;">Confirm</a>"#
+        );
+    }
+
+    #[test]
+    fn test_missing_template_dir_falls_back_to_embedded_template() -> TestResult {
+        let templates = EmailTemplates::load(EmailTemplateConfig {
+            template_dir: Some(env::temp_dir().join(Uuid::now_v7().to_string())),
+            ..EmailTemplateConfig::default()
+        })?;
+
+        let rendered = templates.render(EmailKind::Confirmation, &EmailContext::default())?;
+
+        assert!(rendered.html_body.contains("Confirm email&nbsp;address"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,233 @@
+//! Webhooks module
+
+mod errors;
+mod event;
+
+pub use {errors::WebhookError, event::UserEvent};
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tracing::warn;
+
+use crate::domain::{
+    background::BackgroundTasks,
+    events::{DomainEvent, EventSubscriber},
+};
+
+#[cfg(test)]
+use mockall::mock;
+
+/// Webhook notifier trait
+#[async_trait]
+pub trait WebhookNotifier: Clone + Send + Sync + 'static {
+    /// Notify integrators of a user lifecycle event
+    ///
+    /// # Arguments
+    /// * `event` - The [`UserEvent`] which occurred.
+    ///
+    /// # Returns
+    /// A [`Result`] indicating whether the event was delivered.
+    async fn notify(&self, event: UserEvent) -> Result<(), WebhookError>;
+}
+
+/// Delivers an event on a background task, so the caller doesn't wait on the webhook endpoint.
+/// Delivery failures are logged rather than returned.
+pub fn notify_in_background<W: WebhookNotifier>(
+    webhooks: &Arc<W>,
+    background: &BackgroundTasks,
+    event: UserEvent,
+) {
+    let webhooks = Arc::clone(webhooks);
+
+    background.spawn(async move {
+        if let Err(err) = webhooks.notify(event).await {
+            warn!("Failed to deliver webhook: {:?}", err);
+        }
+    });
+}
+
+/// Notifies integrators of the [`DomainEvent`]s which have a webhook, in the background
+#[derive(Clone, Debug)]
+pub struct WebhookSubscriber<W: WebhookNotifier> {
+    webhooks: Arc<W>,
+    background: BackgroundTasks,
+}
+
+impl<W: WebhookNotifier> WebhookSubscriber<W> {
+    /// Create a new webhook subscriber delivering events with `webhooks`, on `background`
+    pub fn new(webhooks: Arc<W>, background: BackgroundTasks) -> Self {
+        Self {
+            webhooks,
+            background,
+        }
+    }
+}
+
+#[async_trait]
+impl<W: WebhookNotifier> EventSubscriber for WebhookSubscriber<W> {
+    async fn handle(&self, event: &DomainEvent) {
+        let event = match event {
+            DomainEvent::UserCreated {
+                user_id,
+                email,
+                occurred_at,
+                ..
+            } => UserEvent::UserCreated {
+                user_id: *user_id,
+                email: email.clone(),
+                occurred_at: *occurred_at,
+            },
+            DomainEvent::EmailConfirmed {
+                user_id,
+                email,
+                occurred_at,
+                ..
+            }
+            | DomainEvent::EmailConfirmedByAdmin {
+                user_id,
+                email,
+                occurred_at,
+                ..
+            } => UserEvent::EmailConfirmed {
+                user_id: *user_id,
+                email: email.clone(),
+                occurred_at: *occurred_at,
+            },
+            DomainEvent::EmailConfirmationSent { .. }
+            | DomainEvent::EmailChangeRequested { .. }
+            | DomainEvent::UserErased { .. } => return,
+        };
+
+        notify_in_background(&self.webhooks, &self.background, event);
+    }
+}
+
+#[cfg(test)]
+mock! {
+    pub WebhookNotifier {}
+
+    impl Clone for WebhookNotifier {
+        fn clone(&self) -> Self;
+    }
+
+    #[async_trait]
+    impl WebhookNotifier for WebhookNotifier {
+        async fn notify(&self, event: UserEvent) -> Result<(), WebhookError>;
+    }
+}
+
+/// Test doubles for the webhooks module
+#[cfg(test)]
+pub mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use async_trait::async_trait;
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    use crate::domain::{
+        background::BackgroundTasks,
+        communication::email_addresses::{EmailAddress, RequestOrigin},
+        events::{DomainEvent, EventSubscriber},
+        i18n::Locale,
+    };
+
+    pub use super::MockWebhookNotifier;
+    use super::{UserEvent, WebhookError, WebhookNotifier, WebhookSubscriber};
+
+    /// A notifier which records every event it's asked to deliver
+    #[derive(Clone, Debug, Default)]
+    struct RecordingNotifier {
+        delivered: Arc<Mutex<Vec<UserEvent>>>,
+    }
+
+    #[async_trait]
+    impl WebhookNotifier for RecordingNotifier {
+        async fn notify(&self, event: UserEvent) -> Result<(), WebhookError> {
+            self.delivered.lock().unwrap().push(event);
+
+            Ok(())
+        }
+    }
+
+    /// Handles `event` with a webhook subscriber, and returns the events it delivered
+    async fn deliver(event: DomainEvent) -> Vec<UserEvent> {
+        let webhooks = RecordingNotifier::default();
+        let background = BackgroundTasks::new();
+
+        WebhookSubscriber::new(Arc::new(webhooks.clone()), background.clone())
+            .handle(&event)
+            .await;
+
+        assert!(background.flush(Duration::from_secs(5)).await);
+
+        let delivered = webhooks.delivered.lock().unwrap().clone();
+
+        delivered
+    }
+
+    #[tokio::test]
+    async fn test_user_created_is_delivered() {
+        let user_id = Uuid::now_v7();
+        let occurred_at = Utc::now();
+
+        let delivered = deliver(DomainEvent::UserCreated {
+            user_id,
+            email: EmailAddress::new_unchecked("email@example.com"),
+            origin: RequestOrigin::default(),
+            occurred_at,
+        })
+        .await;
+
+        assert_eq!(
+            delivered,
+            vec![UserEvent::UserCreated {
+                user_id,
+                email: EmailAddress::new_unchecked("email@example.com"),
+                occurred_at,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_email_confirmed_is_delivered() {
+        let user_id = Uuid::now_v7();
+        let occurred_at = Utc::now();
+
+        let delivered = deliver(DomainEvent::EmailConfirmed {
+            user_id,
+            email: EmailAddress::new_unchecked("new@example.com"),
+            first_confirmation: false,
+            locale: Locale::English,
+            origin: RequestOrigin::default(),
+            occurred_at,
+        })
+        .await;
+
+        assert_eq!(
+            delivered,
+            vec![UserEvent::EmailConfirmed {
+                user_id,
+                email: EmailAddress::new_unchecked("new@example.com"),
+                occurred_at,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_events_without_a_webhook_are_not_delivered() {
+        let delivered = deliver(DomainEvent::EmailConfirmationSent {
+            user_id: Uuid::now_v7(),
+            email: EmailAddress::new_unchecked("email@example.com"),
+            expires_at: Utc::now(),
+            occurred_at: Utc::now(),
+        })
+        .await;
+
+        assert_eq!(delivered, vec![]);
+    }
+}
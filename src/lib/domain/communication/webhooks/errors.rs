@@ -0,0 +1,15 @@
+//! Webhook errors
+
+use thiserror::Error;
+
+/// Webhook errors
+#[derive(Debug, Error)]
+pub enum WebhookError {
+    /// The webhook endpoint responded with an unsuccessful status code
+    #[error("Webhook endpoint responded with status {0}")]
+    UnexpectedStatus(u16),
+
+    /// Unknown error
+    #[error(transparent)]
+    UnknownError(#[from] anyhow::Error),
+}
@@ -0,0 +1,68 @@
+//! User lifecycle events
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::communication::email_addresses::EmailAddress;
+
+/// A user lifecycle event, serialized as the webhook payload
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum UserEvent {
+    /// A user signed up
+    UserCreated {
+        /// The new user's ID
+        user_id: Uuid,
+
+        /// The new user's email address
+        email: EmailAddress,
+
+        /// When the user was created
+        occurred_at: DateTime<Utc>,
+    },
+
+    /// A user confirmed their email address
+    EmailConfirmed {
+        /// The user's ID
+        user_id: Uuid,
+
+        /// The email address which was confirmed
+        email: EmailAddress,
+
+        /// When the email address was confirmed
+        occurred_at: DateTime<Utc>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use testresult::TestResult;
+
+    use super::*;
+
+    #[test]
+    fn test_user_created_payload() -> TestResult {
+        let user_id = Uuid::now_v7();
+        let occurred_at = Utc::now();
+
+        let event = UserEvent::UserCreated {
+            user_id,
+            email: EmailAddress::new("email@example.com")?,
+            occurred_at,
+        };
+
+        assert_eq!(
+            serde_json::to_value(&event)?,
+            json!({
+                "event": "user_created",
+                "user_id": user_id,
+                "email": "email@example.com",
+                "occurred_at": occurred_at,
+            })
+        );
+
+        Ok(())
+    }
+}
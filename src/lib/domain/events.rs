@@ -0,0 +1,241 @@
+//! Domain events module
+//!
+//! Services publish what happened as [`DomainEvent`]s instead of carrying out its side effects
+//! themselves, and each [`EventSubscriber`] on the [`EventBus`] reacts to them independently, so
+//! adding a side effect, such as another notification, doesn't mean changing the services.
+//! Subscribers can't fail the operation which published the event: they log their own failures,
+//! and spawn anything slow as a [`background`](crate::domain::background) task.
+
+use std::{fmt, sync::Arc};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::domain::{
+    communication::email_addresses::{EmailAddress, RequestOrigin},
+    i18n::Locale,
+};
+
+/// Something which happened to a user
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DomainEvent {
+    /// A user was created
+    UserCreated {
+        /// The new user's ID
+        user_id: Uuid,
+
+        /// The new user's email address
+        email: EmailAddress,
+
+        /// The client which created the user
+        origin: RequestOrigin,
+
+        /// When the user was created
+        occurred_at: DateTime<Utc>,
+    },
+
+    /// An email confirmation was sent to a user's current email address
+    EmailConfirmationSent {
+        /// The user's ID
+        user_id: Uuid,
+
+        /// The email address the confirmation was sent to
+        email: EmailAddress,
+
+        /// When the confirmation token expires
+        expires_at: DateTime<Utc>,
+
+        /// When the confirmation was sent
+        occurred_at: DateTime<Utc>,
+    },
+
+    /// A user asked to change their email address, and a confirmation was sent to the new one
+    EmailChangeRequested {
+        /// The user's ID
+        user_id: Uuid,
+
+        /// The email address the user is changing to
+        new_email: EmailAddress,
+
+        /// When the confirmation token expires
+        expires_at: DateTime<Utc>,
+
+        /// When the change was requested
+        occurred_at: DateTime<Utc>,
+    },
+
+    /// A user confirmed their email address, or the new one they are changing to
+    EmailConfirmed {
+        /// The user's ID
+        user_id: Uuid,
+
+        /// The email address which was confirmed
+        email: EmailAddress,
+
+        /// Whether this was the user's first confirmation, rather than a change of address
+        first_confirmation: bool,
+
+        /// The user's locale, for anything sent to them in response
+        locale: Locale,
+
+        /// The client which confirmed it
+        origin: RequestOrigin,
+
+        /// When the email address was confirmed
+        occurred_at: DateTime<Utc>,
+    },
+
+    /// An admin confirmed a user's email address on their behalf, without a token
+    EmailConfirmedByAdmin {
+        /// The user's ID
+        user_id: Uuid,
+
+        /// The ID of the admin who confirmed it
+        admin_id: Uuid,
+
+        /// The email address which was confirmed
+        email: EmailAddress,
+
+        /// The client the admin confirmed it from
+        origin: RequestOrigin,
+
+        /// When the email address was confirmed
+        occurred_at: DateTime<Utc>,
+    },
+
+    /// A user erased their account, scrubbing their personal data
+    UserErased {
+        /// The erased user's ID
+        user_id: Uuid,
+
+        /// The client which erased the user
+        origin: RequestOrigin,
+
+        /// When the user was erased
+        occurred_at: DateTime<Utc>,
+    },
+}
+
+/// Reacts to the events published on an [`EventBus`]
+#[async_trait]
+pub trait EventSubscriber: Send + Sync + 'static {
+    /// Handles an event, logging rather than returning any failure
+    ///
+    /// # Arguments
+    /// * `event` - The [`DomainEvent`] which was published.
+    async fn handle(&self, event: &DomainEvent);
+}
+
+/// Delivers published events to every subscriber, in the order they subscribed
+#[derive(Clone, Default)]
+pub struct EventBus {
+    subscribers: Vec<Arc<dyn EventSubscriber>>,
+}
+
+impl EventBus {
+    /// Create an event bus without any subscribers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a subscriber to the bus
+    pub fn subscribe(mut self, subscriber: impl EventSubscriber) -> Self {
+        self.subscribers.push(Arc::new(subscriber));
+        self
+    }
+
+    /// Publishes an event to every subscriber
+    pub async fn publish(&self, event: DomainEvent) {
+        for subscriber in &self.subscribers {
+            subscriber.handle(&event).await;
+        }
+    }
+}
+
+impl fmt::Debug for EventBus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventBus")
+            .field("subscribers", &self.subscribers.len())
+            .finish()
+    }
+}
+
+/// Test doubles for the domain events module
+#[cfg(test)]
+pub mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    /// A subscriber which records every event published to it
+    #[derive(Clone, Debug, Default)]
+    pub struct RecordingSubscriber {
+        events: Arc<Mutex<Vec<DomainEvent>>>,
+    }
+
+    impl RecordingSubscriber {
+        /// The events published so far, in order
+        pub fn events(&self) -> Vec<DomainEvent> {
+            self.events.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl EventSubscriber for RecordingSubscriber {
+        async fn handle(&self, event: &DomainEvent) {
+            self.events.lock().unwrap().push(event.clone());
+        }
+    }
+
+    /// Create an event bus which records the events published on it
+    pub fn recording_bus() -> (EventBus, RecordingSubscriber) {
+        let recorder = RecordingSubscriber::default();
+
+        (EventBus::new().subscribe(recorder.clone()), recorder)
+    }
+
+    #[tokio::test]
+    async fn test_every_subscriber_receives_each_event_in_order() {
+        let first = RecordingSubscriber::default();
+        let second = RecordingSubscriber::default();
+
+        let bus = EventBus::new()
+            .subscribe(first.clone())
+            .subscribe(second.clone());
+
+        let events = [
+            DomainEvent::UserCreated {
+                user_id: Uuid::now_v7(),
+                email: EmailAddress::new_unchecked("a@example.com"),
+                origin: RequestOrigin::default(),
+                occurred_at: Utc::now(),
+            },
+            DomainEvent::UserCreated {
+                user_id: Uuid::now_v7(),
+                email: EmailAddress::new_unchecked("b@example.com"),
+                origin: RequestOrigin::default(),
+                occurred_at: Utc::now(),
+            },
+        ];
+
+        for event in events.clone() {
+            bus.publish(event).await;
+        }
+
+        assert_eq!(first.events(), events);
+        assert_eq!(second.events(), events);
+    }
+
+    #[tokio::test]
+    async fn test_publishing_without_subscribers_does_nothing() {
+        EventBus::new()
+            .publish(DomainEvent::UserCreated {
+                user_id: Uuid::now_v7(),
+                email: EmailAddress::new_unchecked("a@example.com"),
+                origin: RequestOrigin::default(),
+                occurred_at: Utc::now(),
+            })
+            .await;
+    }
+}
@@ -0,0 +1,22 @@
+//! Health module
+//!
+//! Lets the readiness check ask whether the database can be reached and is migrated to the
+//! schema version the binary expects, so traffic isn't served against one which isn't, and the
+//! health check run a [`HealthCheck`] against each dependency the application has. Checks run
+//! concurrently, and their report is cached briefly by [`HealthReportCache`].
+
+mod cache;
+mod check;
+mod database;
+mod errors;
+
+pub use cache::HealthReportCache;
+pub use check::{CheckResult, HealthCheck, HealthChecks, HealthReport, HealthStatus};
+pub use database::{DatabaseCheck, DatabaseHealth};
+pub use errors::HealthError;
+
+/// Test doubles for the health module
+#[cfg(test)]
+pub mod tests {
+    pub use super::database::MockDatabaseHealth;
+}
@@ -0,0 +1,99 @@
+//! Health report caching
+
+use std::{sync::Arc, time::Duration};
+
+use tokio::{sync::Mutex, time::Instant};
+
+use crate::domain::health::{HealthChecks, HealthReport};
+
+/// How long a health report is reused for
+const REPORT_TTL: Duration = Duration::from_secs(5);
+
+/// Reuses the last health report for a short while, so polling the health endpoint doesn't run
+/// checks which reach out to other services on every request. Clones share the same report.
+#[derive(Clone, Debug)]
+pub struct HealthReportCache {
+    ttl: Duration,
+    report: Arc<Mutex<Option<(Instant, HealthReport)>>>,
+}
+
+impl Default for HealthReportCache {
+    fn default() -> Self {
+        Self::new(REPORT_TTL)
+    }
+}
+
+impl HealthReportCache {
+    /// Create a cache which reuses each report for `ttl`
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            report: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// The last report, if it was made less than the TTL ago, otherwise a new one from running
+    /// `checks`. Requests arriving while the checks run wait for that report rather than running
+    /// them again.
+    pub async fn get_or_run(&self, checks: &HealthChecks) -> HealthReport {
+        let mut cached = self.report.lock().await;
+
+        if let Some((ran_at, report)) = cached.as_ref() {
+            if ran_at.elapsed() < self.ttl {
+                return report.clone();
+            }
+        }
+
+        let report = checks.run().await;
+
+        *cached = Some((Instant::now(), report.clone()));
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use async_trait::async_trait;
+
+    use crate::domain::health::{HealthCheck, HealthStatus};
+
+    use super::*;
+
+    /// A check which counts how many times it has run
+    #[derive(Clone, Default)]
+    struct CountingCheck(Arc<AtomicUsize>);
+
+    #[async_trait]
+    impl HealthCheck for CountingCheck {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        async fn check(&self) -> HealthStatus {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            HealthStatus::Up
+        }
+    }
+
+    #[tokio::test]
+    async fn test_report_is_reused_until_it_expires() {
+        let check = CountingCheck::default();
+        let checks = HealthChecks::new().with_required(check.clone());
+        let cache = HealthReportCache::new(Duration::from_millis(50));
+
+        cache.get_or_run(&checks).await;
+        cache.get_or_run(&checks).await;
+
+        assert_eq!(check.0.load(Ordering::SeqCst), 1);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+
+        let report = cache.get_or_run(&checks).await;
+
+        assert_eq!(report.status, HealthStatus::Up);
+        assert_eq!(check.0.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,338 @@
+//! Composable health checks
+
+use std::{fmt, sync::Arc};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinSet;
+
+/// The health of one dependency, or of the application as a whole
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    /// Working normally
+    Up,
+
+    /// Working, but not as well as it should be, such as an optional dependency being down
+    Degraded,
+
+    /// Not working
+    Down,
+}
+
+/// A check of something the application depends on
+#[async_trait]
+pub trait HealthCheck: Send + Sync + 'static {
+    /// The name the check's result is reported under
+    fn name(&self) -> &str;
+
+    /// Checks the dependency's health
+    async fn check(&self) -> HealthStatus;
+}
+
+/// The result of running a single [`HealthCheck`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CheckResult {
+    /// The name of the check
+    pub name: String,
+
+    /// How the check went
+    pub status: HealthStatus,
+
+    /// Whether the application is down when this check is
+    pub required: bool,
+}
+
+/// The results of running every check in [`HealthChecks`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HealthReport {
+    /// The overall health: down if any required check is, degraded if any other check isn't up,
+    /// and up otherwise
+    pub status: HealthStatus,
+
+    /// The result of each check, in the order they were added
+    pub checks: Vec<CheckResult>,
+}
+
+impl HealthReport {
+    /// Aggregate the results of each check into a report
+    pub fn new(checks: Vec<CheckResult>) -> Self {
+        let status = if checks
+            .iter()
+            .any(|check| check.required && check.status == HealthStatus::Down)
+        {
+            HealthStatus::Down
+        } else if checks.iter().any(|check| check.status != HealthStatus::Up) {
+            HealthStatus::Degraded
+        } else {
+            HealthStatus::Up
+        };
+
+        Self { status, checks }
+    }
+}
+
+/// A check, and whether the application is down when it is
+#[derive(Clone)]
+struct RegisteredCheck {
+    check: Arc<dyn HealthCheck>,
+    required: bool,
+}
+
+/// A set of health checks, which are run together
+#[derive(Clone, Default)]
+pub struct HealthChecks {
+    checks: Vec<RegisteredCheck>,
+}
+
+impl HealthChecks {
+    /// Create an empty set of health checks
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a check the application is down without
+    pub fn with_required(self, check: impl HealthCheck) -> Self {
+        self.with_check(Arc::new(check), true)
+    }
+
+    /// Add a check the application is only degraded without
+    pub fn with_optional(self, check: impl HealthCheck) -> Self {
+        self.with_check(Arc::new(check), false)
+    }
+
+    /// Add every check in `other`, after these ones
+    pub fn with_checks(mut self, other: &HealthChecks) -> Self {
+        self.checks.extend(other.checks.iter().cloned());
+        self
+    }
+
+    fn with_check(mut self, check: Arc<dyn HealthCheck>, required: bool) -> Self {
+        self.checks.push(RegisteredCheck { check, required });
+        self
+    }
+
+    /// Run every check at once, so the report takes as long as the slowest check rather than all
+    /// of them added together. Results are in the order the checks were added, and a check which
+    /// panics is reported as down.
+    pub async fn run(&self) -> HealthReport {
+        let mut tasks = JoinSet::new();
+
+        for (index, registered) in self.checks.iter().enumerate() {
+            let check = registered.check.clone();
+
+            tasks.spawn(async move { (index, check.check().await) });
+        }
+
+        let mut statuses = vec![HealthStatus::Down; self.checks.len()];
+
+        while let Some(result) = tasks.join_next().await {
+            if let Ok((index, status)) = result {
+                statuses[index] = status;
+            }
+        }
+
+        HealthReport::new(
+            self.checks
+                .iter()
+                .zip(statuses)
+                .map(
+                    |(RegisteredCheck { check, required }, status)| CheckResult {
+                        name: check.name().to_string(),
+                        status,
+                        required: *required,
+                    },
+                )
+                .collect(),
+        )
+    }
+}
+
+impl fmt::Debug for HealthChecks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.checks.iter().map(|registered| registered.check.name()))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    /// A check which always reports `status`
+    struct FixedCheck {
+        name: &'static str,
+        status: HealthStatus,
+    }
+
+    #[async_trait]
+    impl HealthCheck for FixedCheck {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        async fn check(&self) -> HealthStatus {
+            self.status
+        }
+    }
+
+    /// A check which takes a moment to report up
+    struct SlowCheck;
+
+    #[async_trait]
+    impl HealthCheck for SlowCheck {
+        fn name(&self) -> &str {
+            "slow"
+        }
+
+        async fn check(&self) -> HealthStatus {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            HealthStatus::Up
+        }
+    }
+
+    /// A check which panics
+    struct PanickingCheck;
+
+    #[async_trait]
+    impl HealthCheck for PanickingCheck {
+        fn name(&self) -> &str {
+            "panicking"
+        }
+
+        async fn check(&self) -> HealthStatus {
+            panic!("check panicked")
+        }
+    }
+
+    fn up() -> FixedCheck {
+        FixedCheck {
+            name: "up",
+            status: HealthStatus::Up,
+        }
+    }
+
+    fn down() -> FixedCheck {
+        FixedCheck {
+            name: "down",
+            status: HealthStatus::Down,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_every_check_up_is_up() {
+        let report = HealthChecks::new()
+            .with_required(up())
+            .with_optional(up())
+            .run()
+            .await;
+
+        assert_eq!(report.status, HealthStatus::Up);
+    }
+
+    #[tokio::test]
+    async fn test_required_check_down_is_down() {
+        let report = HealthChecks::new()
+            .with_required(up())
+            .with_required(down())
+            .run()
+            .await;
+
+        assert_eq!(report.status, HealthStatus::Down);
+        assert_eq!(
+            report.checks,
+            vec![
+                CheckResult {
+                    name: "up".to_string(),
+                    status: HealthStatus::Up,
+                    required: true,
+                },
+                CheckResult {
+                    name: "down".to_string(),
+                    status: HealthStatus::Down,
+                    required: true,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_optional_check_down_is_degraded() {
+        let report = HealthChecks::new()
+            .with_required(up())
+            .with_optional(down())
+            .run()
+            .await;
+
+        assert_eq!(report.status, HealthStatus::Degraded);
+    }
+
+    #[tokio::test]
+    async fn test_degraded_required_check_is_degraded() {
+        let report = HealthChecks::new()
+            .with_required(FixedCheck {
+                name: "degraded",
+                status: HealthStatus::Degraded,
+            })
+            .run()
+            .await;
+
+        assert_eq!(report.status, HealthStatus::Degraded);
+    }
+
+    #[tokio::test]
+    async fn test_checks_run_concurrently() {
+        let started = Instant::now();
+
+        let report = HealthChecks::new()
+            .with_required(SlowCheck)
+            .with_optional(SlowCheck)
+            .with_optional(SlowCheck)
+            .run()
+            .await;
+
+        assert_eq!(report.status, HealthStatus::Up);
+        assert!(started.elapsed() < Duration::from_millis(500));
+    }
+
+    #[tokio::test]
+    async fn test_panicking_check_is_down() {
+        let report = HealthChecks::new()
+            .with_required(up())
+            .with_optional(PanickingCheck)
+            .run()
+            .await;
+
+        assert_eq!(
+            report
+                .checks
+                .iter()
+                .map(|check| (check.name.as_str(), check.status))
+                .collect::<Vec<_>>(),
+            vec![("up", HealthStatus::Up), ("panicking", HealthStatus::Down)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_with_checks_appends_other_checks() {
+        let others = HealthChecks::new().with_optional(down());
+
+        let report = HealthChecks::new()
+            .with_required(up())
+            .with_checks(&others)
+            .run()
+            .await;
+
+        assert_eq!(
+            report
+                .checks
+                .iter()
+                .map(|check| (check.name.as_str(), check.required))
+                .collect::<Vec<_>>(),
+            vec![("up", true), ("down", false)]
+        );
+    }
+}
@@ -0,0 +1,74 @@
+//! Database health
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tracing::warn;
+
+#[cfg(test)]
+use mockall::mock;
+
+use super::{HealthCheck, HealthError, HealthStatus};
+
+/// Database health checks
+#[async_trait]
+pub trait DatabaseHealth: Clone + Send + Sync + 'static {
+    /// Checks the database can be reached.
+    ///
+    /// # Returns
+    /// A [`Result`] which is [`Ok`] if the database answered,
+    /// or an [`Err`] containing a [`HealthError`] if it could not be reached.
+    async fn ping(&self) -> Result<(), HealthError>;
+
+    /// Lists the migrations embedded in the binary which haven't been applied to the database.
+    ///
+    /// # Returns
+    /// A [`Result`] which is [`Ok`] containing the versions of the pending migrations, oldest
+    /// first, or an [`Err`] containing a [`HealthError`] if they could not be listed.
+    async fn pending_migrations(&self) -> Result<Vec<i64>, HealthError>;
+}
+
+/// A [`HealthCheck`] that the database can be reached
+#[derive(Clone, Debug)]
+pub struct DatabaseCheck<H: DatabaseHealth> {
+    health: Arc<H>,
+}
+
+impl<H: DatabaseHealth> DatabaseCheck<H> {
+    /// Create a new database check, pinging through `health`
+    pub fn new(health: Arc<H>) -> Self {
+        Self { health }
+    }
+}
+
+#[async_trait]
+impl<H: DatabaseHealth> HealthCheck for DatabaseCheck<H> {
+    fn name(&self) -> &str {
+        "database"
+    }
+
+    async fn check(&self) -> HealthStatus {
+        match self.health.ping().await {
+            Ok(()) => HealthStatus::Up,
+            Err(err) => {
+                warn!("Database health check failed: {:?}", err);
+                HealthStatus::Down
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mock! {
+    pub DatabaseHealth {}
+
+    impl Clone for DatabaseHealth {
+        fn clone(&self) -> Self;
+    }
+
+    #[async_trait]
+    impl DatabaseHealth for DatabaseHealth {
+        async fn ping(&self) -> Result<(), HealthError>;
+        async fn pending_migrations(&self) -> Result<Vec<i64>, HealthError>;
+    }
+}
@@ -0,0 +1,21 @@
+//! Health errors
+
+use anyhow::anyhow;
+use thiserror::Error;
+use tracing::debug;
+
+/// Errors that can occur when checking the health of the database
+#[derive(Debug, Error)]
+pub enum HealthError {
+    /// Unknown error
+    #[error(transparent)]
+    UnknownError(#[from] anyhow::Error),
+}
+
+impl From<sqlx::Error> for HealthError {
+    fn from(err: sqlx::Error) -> Self {
+        debug!("sqlxError: {:?}", err);
+
+        HealthError::UnknownError(anyhow!("Unknown database error: {:?}", err))
+    }
+}
@@ -0,0 +1,11 @@
+//! Internationalization module
+//!
+//! Emails are written in the language stored against their recipient, and pages in the one their
+//! request's `Accept-Language` header prefers. Anything missing, or in a language there are no
+//! translations for, falls back to English.
+
+mod locale;
+mod messages;
+
+pub use locale::{Locale, UnknownLocaleError};
+pub use messages::{ConfirmEmailMessages, Messages, PageMessages, WelcomeMessages};
@@ -0,0 +1,124 @@
+//! Locales
+
+use std::{fmt, str::FromStr};
+
+use thiserror::Error;
+
+use super::messages::{Messages, ENGLISH, FRENCH};
+
+/// An error that can occur when parsing a locale
+#[derive(Debug, Error)]
+#[error("unknown locale: {0}")]
+pub struct UnknownLocaleError(String);
+
+/// A language there are translations for
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Locale {
+    /// English
+    #[default]
+    English,
+
+    /// French
+    French,
+}
+
+impl Locale {
+    /// The locale as stored in the database, and used in `lang` attributes
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::English => "en",
+            Self::French => "fr",
+        }
+    }
+
+    /// The strings translated into this locale
+    pub fn messages(&self) -> &'static Messages {
+        match self {
+            Self::English => &ENGLISH,
+            Self::French => &FRENCH,
+        }
+    }
+
+    /// Picks the locale an `Accept-Language` header prefers most, or English if it doesn't
+    /// accept any there are translations for.
+    ///
+    /// # Arguments
+    /// * `header` - The value of the header, e.g. `fr-CH, fr;q=0.9, en;q=0.8, *;q=0.5`.
+    pub fn from_accept_language(header: &str) -> Self {
+        let mut preferred: Option<(Self, f32)> = None;
+
+        for range in header.split(',') {
+            let mut params = range.split(';');
+            let tag = params.next().unwrap_or_default().trim();
+
+            let quality = params
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|quality| quality.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+
+            let Ok(locale) = tag.parse::<Self>() else {
+                continue;
+            };
+
+            if quality > 0.0 && preferred.is_none_or(|(_, best)| quality > best) {
+                preferred = Some((locale, quality));
+            }
+        }
+
+        preferred.map(|(locale, _)| locale).unwrap_or_default()
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Locale {
+    type Err = UnknownLocaleError;
+
+    /// Parses a language tag such as `fr` or `fr-CA`, ignoring any region or script
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let language = s.split(['-', '_']).next().unwrap_or_default();
+
+        match language.to_ascii_lowercase().as_str() {
+            "en" => Ok(Self::English),
+            "fr" => Ok(Self::French),
+            _ => Err(UnknownLocaleError(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_language_tags() {
+        assert_eq!("en".parse::<Locale>().ok(), Some(Locale::English));
+        assert_eq!("fr-CA".parse::<Locale>().ok(), Some(Locale::French));
+        assert_eq!("FR_fr".parse::<Locale>().ok(), Some(Locale::French));
+        assert!("de".parse::<Locale>().is_err());
+        assert!("".parse::<Locale>().is_err());
+    }
+
+    #[test]
+    fn test_accept_language_picks_highest_quality() {
+        assert_eq!(
+            Locale::from_accept_language("en;q=0.8, fr-CH, de;q=0.9"),
+            Locale::French
+        );
+        assert_eq!(
+            Locale::from_accept_language("fr;q=0.5, en;q=0.7"),
+            Locale::English
+        );
+    }
+
+    #[test]
+    fn test_accept_language_falls_back_to_english() {
+        assert_eq!(Locale::from_accept_language(""), Locale::English);
+        assert_eq!(Locale::from_accept_language("de, *;q=0.5"), Locale::English);
+        assert_eq!(Locale::from_accept_language("fr;q=0"), Locale::English);
+    }
+}
@@ -0,0 +1,130 @@
+//! Translated strings
+//!
+//! Strings used in HTML are trusted markup, so may contain entities such as `&nbsp;`. Plain text
+//! strings must not.
+
+/// The strings for every email and page, in one locale
+#[derive(Debug)]
+pub struct Messages {
+    /// The confirm email address email
+    pub confirm_email: ConfirmEmailMessages,
+
+    /// The welcome email
+    pub welcome: WelcomeMessages,
+
+    /// The pages shown in the browser
+    pub pages: PageMessages,
+}
+
+/// The strings for the confirm email address email
+#[derive(Debug)]
+pub struct ConfirmEmailMessages {
+    /// The subject when confirming the current email address
+    pub subject: &'static str,
+
+    /// The subject when confirming a new email address
+    pub new_email_subject: &'static str,
+
+    /// The HTML paragraph asking the user to follow the link
+    pub instructions: &'static str,
+
+    /// The HTML paragraph explaining why
+    pub reason: &'static str,
+
+    /// The HTML text of the link
+    pub action: &'static str,
+
+    /// The plain text preceding the link
+    pub plain: &'static str,
+}
+
+/// The strings for the welcome email
+#[derive(Debug)]
+pub struct WelcomeMessages {
+    /// The subject
+    pub subject: &'static str,
+
+    /// The HTML paragraph welcoming the user
+    pub greeting: &'static str,
+
+    /// The HTML paragraph inviting questions
+    pub questions: &'static str,
+
+    /// The plain text body
+    pub plain: &'static str,
+}
+
+/// The strings for the pages shown in the browser
+#[derive(Debug)]
+pub struct PageMessages {
+    /// An email address has been confirmed
+    pub email_confirmed: &'static str,
+
+    /// A new email address is already in use by another account
+    pub email_address_in_use: &'static str,
+
+    /// Something wasn't found
+    pub not_found: &'static str,
+
+    /// Something couldn't be processed
+    pub unprocessable_entity: &'static str,
+
+    /// Something went wrong on the server
+    pub internal_server_error: &'static str,
+}
+
+pub(super) static ENGLISH: Messages = Messages {
+    confirm_email: ConfirmEmailMessages {
+        subject: "Please confirm your email address",
+        new_email_subject: "Please confirm your new email address",
+        instructions: "Please confirm your email address by clicking the link&nbsp;below.",
+        reason: "We may need to send you critical information about our service and it is \
+            important that we have an accurate email&nbsp;address.",
+        action: "Confirm email&nbsp;address",
+        plain: "Visit the following URL to confirm your email address:",
+    },
+    welcome: WelcomeMessages {
+        subject: "Welcome!",
+        greeting: "Thanks for confirming your email address, and welcome&nbsp;aboard!",
+        questions: "If you have any questions, just reply to this&nbsp;email.",
+        plain: "Thanks for confirming your email address, and welcome aboard! \
+            If you have any questions, just reply to this email.",
+    },
+    pages: PageMessages {
+        email_confirmed: "Your email address has been confirmed.",
+        email_address_in_use: "That email address is already in use by another account. \
+            Your email address has not been changed.",
+        not_found: "Not found.",
+        unprocessable_entity: "Unprocessable entity.",
+        internal_server_error: "Something went wrong.",
+    },
+};
+
+pub(super) static FRENCH: Messages = Messages {
+    confirm_email: ConfirmEmailMessages {
+        subject: "Veuillez confirmer votre adresse e-mail",
+        new_email_subject: "Veuillez confirmer votre nouvelle adresse e-mail",
+        instructions: "Veuillez confirmer votre adresse e-mail en cliquant sur le \
+            lien&nbsp;ci-dessous.",
+        reason: "Nous pourrions avoir besoin de vous envoyer des informations importantes sur \
+            notre service, il est donc essentiel que votre adresse e-mail soit&nbsp;exacte.",
+        action: "Confirmer l’adresse&nbsp;e-mail",
+        plain: "Rendez-vous à l’adresse suivante pour confirmer votre adresse e-mail :",
+    },
+    welcome: WelcomeMessages {
+        subject: "Bienvenue !",
+        greeting:
+            "Merci d’avoir confirmé votre adresse e-mail, et bienvenue&nbsp;à&nbsp;bord&nbsp;!",
+        questions: "Si vous avez des questions, il vous suffit de répondre à cet&nbsp;e-mail.",
+        plain: "Merci d’avoir confirmé votre adresse e-mail, et bienvenue à bord ! \
+            Si vous avez des questions, il vous suffit de répondre à cet e-mail.",
+    },
+    pages: PageMessages {
+        email_confirmed: "Votre adresse e-mail a été confirmée.",
+        email_address_in_use: "Cette adresse e-mail est déjà utilisée par un autre compte. \
+            Votre adresse e-mail n’a pas été modifiée.",
+        not_found: "Introuvable.",
+        unprocessable_entity: "Requête impossible à traiter.",
+        internal_server_error: "Une erreur s’est produite.",
+    },
+};
@@ -0,0 +1,20 @@
+//! Idempotency module
+//!
+//! Lets clients safely retry a non-idempotent request by sending the same `Idempotency-Key`:
+//! the first request is executed and its response recorded, and repeats are answered with the
+//! recorded response instead of being executed again. Keys, and the responses recorded against
+//! them, expire after 24 hours and are then purged.
+
+mod errors;
+mod purge;
+mod store;
+
+pub use errors::IdempotencyError;
+pub use purge::IdempotencyKeyPurge;
+pub use store::{IdempotencyStore, IdempotentRequest, RecordedResponse, StartOutcome};
+
+/// Test doubles for the idempotency module
+#[cfg(test)]
+pub mod tests {
+    pub use super::store::MockIdempotencyStore;
+}
@@ -0,0 +1,21 @@
+//! Idempotency errors
+
+use anyhow::anyhow;
+use thiserror::Error;
+use tracing::debug;
+
+/// Errors that can occur when recording idempotent requests
+#[derive(Debug, Error)]
+pub enum IdempotencyError {
+    /// Unknown error
+    #[error(transparent)]
+    UnknownError(#[from] anyhow::Error),
+}
+
+impl From<sqlx::Error> for IdempotencyError {
+    fn from(err: sqlx::Error) -> Self {
+        debug!("sqlxError: {:?}", err);
+
+        IdempotencyError::UnknownError(anyhow!("Unknown database error: {:?}", err))
+    }
+}
@@ -0,0 +1,79 @@
+//! Expired idempotency key purge
+
+use std::{sync::Arc, time::Duration};
+
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info};
+
+use crate::domain::idempotency::{IdempotencyError, IdempotencyStore};
+
+/// How often expired keys are looked for
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Periodically deletes expired idempotency keys, so their recorded responses aren't kept forever
+#[derive(Clone, Debug)]
+pub struct IdempotencyKeyPurge<S: IdempotencyStore> {
+    store: Arc<S>,
+}
+
+impl<S: IdempotencyStore> IdempotencyKeyPurge<S> {
+    /// Create a new purge of the expired keys in `store`
+    pub fn new(store: Arc<S>) -> Self {
+        Self { store }
+    }
+
+    /// Deletes the expired keys, returning how many were deleted
+    pub async fn purge(&self) -> Result<u64, IdempotencyError> {
+        self.store.purge_expired().await
+    }
+
+    /// Purges every hour, starting straight away, until `token` is cancelled
+    pub async fn run(self, token: CancellationToken) {
+        let mut interval = tokio::time::interval(PURGE_INTERVAL);
+
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => return,
+                _ = interval.tick() => {}
+            }
+
+            match self.purge().await {
+                Ok(deleted) => info!("purged {deleted} expired idempotency keys"),
+                Err(err) => error!("failed to purge expired idempotency keys: {:?}", err),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use testresult::TestResult;
+
+    use crate::domain::idempotency::tests::MockIdempotencyStore;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_purge_deletes_expired_keys() -> TestResult {
+        let mut store = MockIdempotencyStore::new();
+
+        store.expect_purge_expired().times(1).returning(|| Ok(3));
+
+        assert_eq!(IdempotencyKeyPurge::new(Arc::new(store)).purge().await?, 3);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_stops_once_cancelled() {
+        let mut store = MockIdempotencyStore::new();
+
+        store.expect_purge_expired().returning(|| Ok(0));
+
+        let token = CancellationToken::new();
+        let running = tokio::spawn(IdempotencyKeyPurge::new(Arc::new(store)).run(token.clone()));
+        token.cancel();
+
+        running.await.unwrap();
+    }
+}
@@ -0,0 +1,97 @@
+//! Idempotency store
+
+use async_trait::async_trait;
+
+#[cfg(test)]
+use mockall::mock;
+
+use super::IdempotencyError;
+
+/// A request made with an idempotency key
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IdempotentRequest {
+    /// The client-supplied idempotency key
+    pub key: String,
+
+    /// The method and route the key was used on, e.g. `POST /api/v1/users`, so the same key can
+    /// be used on different routes
+    pub route: String,
+
+    /// A hash of the request body, used to detect a key being reused for a different request
+    pub fingerprint: String,
+}
+
+/// A response recorded against an idempotency key
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecordedResponse {
+    /// The HTTP status code
+    pub status: u16,
+
+    /// The response headers, as names and values, in order
+    pub headers: Vec<(String, String)>,
+
+    /// The response body
+    pub body: Vec<u8>,
+}
+
+/// The outcome of starting an idempotent request
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StartOutcome {
+    /// The key has not been used (or its previous use has expired), so the request should be
+    /// executed and its response recorded
+    Started,
+
+    /// Another request with the key is still being executed
+    InProgress,
+
+    /// The key has already been used for a different request
+    FingerprintMismatch,
+
+    /// The key has already been used for this request, which responded with this response
+    Completed(RecordedResponse),
+}
+
+/// Idempotency store
+///
+/// Keys are held for 24 hours, after which they may be reused.
+#[async_trait]
+pub trait IdempotencyStore: Clone + Send + Sync + 'static {
+    /// Claims the request's key, or reports how it was previously used. Claiming must be atomic,
+    /// so that of several concurrent requests with the same key only one is [`StartOutcome::Started`].
+    async fn start(&self, request: &IdempotentRequest) -> Result<StartOutcome, IdempotencyError>;
+
+    /// Records the response to a started request, to be replayed to later requests with its key
+    async fn complete(
+        &self,
+        request: &IdempotentRequest,
+        response: &RecordedResponse,
+    ) -> Result<(), IdempotencyError>;
+
+    /// Releases a started request's key without recording a response, so it can be retried
+    async fn release(&self, request: &IdempotentRequest) -> Result<(), IdempotencyError>;
+
+    /// Deletes every key which has expired, along with its recorded response, returning how many
+    /// were deleted
+    async fn purge_expired(&self) -> Result<u64, IdempotencyError>;
+}
+
+#[cfg(test)]
+mock! {
+    pub IdempotencyStore {}
+
+    impl Clone for IdempotencyStore {
+        fn clone(&self) -> Self;
+    }
+
+    #[async_trait]
+    impl IdempotencyStore for IdempotencyStore {
+        async fn start(&self, request: &IdempotentRequest) -> Result<StartOutcome, IdempotencyError>;
+        async fn complete(
+            &self,
+            request: &IdempotentRequest,
+            response: &RecordedResponse,
+        ) -> Result<(), IdempotencyError>;
+        async fn release(&self, request: &IdempotentRequest) -> Result<(), IdempotencyError>;
+        async fn purge_expired(&self) -> Result<u64, IdempotencyError>;
+    }
+}
@@ -0,0 +1,78 @@
+//! Timestamps module
+//!
+//! Every record keeps when it was created and last updated. They are kept together in
+//! [`Timestamps`], rather than repeated as fields of each record, so the helpers reading them are
+//! shared.
+
+use chrono::{DateTime, Duration, Utc};
+
+/// When a record was created and last updated
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Timestamps {
+    /// Created at date in UTC
+    pub created_at: DateTime<Utc>,
+
+    /// Last updated at date in UTC
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Timestamps {
+    /// The timestamps of a record created at `at`, and not updated since
+    pub fn created(at: DateTime<Utc>) -> Self {
+        Self {
+            created_at: at,
+            updated_at: at,
+        }
+    }
+
+    /// How long ago the record was created, as of `now`
+    pub fn age(&self, now: DateTime<Utc>) -> Duration {
+        now - self.created_at
+    }
+
+    /// How long ago the record was last updated, as of `now`
+    pub fn since_update(&self, now: DateTime<Utc>) -> Duration {
+        now - self.updated_at
+    }
+
+    /// Whether the record was updated within `period` before `now`
+    pub fn updated_within(&self, period: Duration, now: DateTime<Utc>) -> bool {
+        self.since_update(now) <= period
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        "2026-10-15T12:00:00Z".parse().unwrap()
+    }
+
+    #[test]
+    fn test_age_and_time_since_update() {
+        let timestamps = Timestamps {
+            created_at: now() - Duration::days(3),
+            updated_at: now() - Duration::hours(2),
+        };
+
+        assert_eq!(timestamps.age(now()), Duration::days(3));
+        assert_eq!(timestamps.since_update(now()), Duration::hours(2));
+    }
+
+    #[test]
+    fn test_updated_within() {
+        let timestamps = Timestamps::created(now() - Duration::hours(1));
+
+        assert!(timestamps.updated_within(Duration::hours(1), now()));
+        assert!(!timestamps.updated_within(Duration::minutes(59), now()));
+    }
+
+    #[test]
+    fn test_created_record_has_not_been_updated() {
+        let timestamps = Timestamps::created(now());
+
+        assert_eq!(timestamps.created_at, timestamps.updated_at);
+        assert_eq!(timestamps.age(now()), Duration::zero());
+    }
+}
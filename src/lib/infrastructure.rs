@@ -1,5 +1,10 @@
 //! Infrastructure module
 
+pub mod cache;
+pub mod config_file;
 pub mod db;
+pub mod dns;
 pub mod email;
 pub mod http;
+pub mod shutdown;
+pub mod webhooks;
@@ -0,0 +1,3 @@
+//! Cache module
+
+pub mod users;
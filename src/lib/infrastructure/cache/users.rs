@@ -0,0 +1,328 @@
+//! Cached user repository
+
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use clap::Parser;
+use moka::future::Cache;
+use uuid::Uuid;
+
+use crate::domain::{
+    auth::users::{
+        errors::{
+            CreateUserError, DeleteUserError, EmailExistsError, GetUserByIdError, ListUsersError,
+            UpdateUserError,
+        },
+        NewUser, User, UserCursor, UserFilter, UserRepository,
+    },
+    communication::email_addresses::{EmailAddress, RequestOrigin},
+};
+
+/// User cache configuration
+#[derive(Clone, Debug, PartialEq, Eq, Parser)]
+pub struct UserCacheConfig {
+    /// The most users kept in the cache. Users aren't cached if 0
+    #[arg(
+        long = "user-cache-capacity",
+        env = "USER_CACHE_CAPACITY",
+        default_value = "0"
+    )]
+    pub capacity: u64,
+
+    /// How many seconds a user is cached for. Each instance only evicts the users it updates
+    /// itself, so this bounds how stale another instance's copy can be
+    #[arg(
+        long = "user-cache-ttl-seconds",
+        env = "USER_CACHE_TTL_SECONDS",
+        default_value = "30"
+    )]
+    pub ttl_seconds: u64,
+}
+
+impl Default for UserCacheConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 0,
+            ttl_seconds: 30,
+        }
+    }
+}
+
+impl UserCacheConfig {
+    /// Whether users are cached at all
+    pub fn enabled(&self) -> bool {
+        self.capacity > 0 && self.ttl_seconds > 0
+    }
+}
+
+/// A [`UserRepository`] which caches users looked up by ID in memory, in front of another.
+///
+/// A user is evicted whenever they are updated or deleted through it, so it must be shared by
+/// everything which updates users. Only successful lookups are cached, and if the cache is
+/// disabled every call goes straight through.
+#[derive(Clone, Debug)]
+pub struct CachingUserRepository<R: UserRepository> {
+    inner: Arc<R>,
+    cache: Option<Cache<Uuid, User>>,
+}
+
+impl<R: UserRepository> CachingUserRepository<R> {
+    /// Create a new caching user repository in front of `inner`
+    pub fn new(inner: Arc<R>, config: &UserCacheConfig) -> Self {
+        let cache = config.enabled().then(|| {
+            Cache::builder()
+                .max_capacity(config.capacity)
+                .time_to_live(Duration::from_secs(config.ttl_seconds))
+                .build()
+        });
+
+        Self { inner, cache }
+    }
+
+    async fn evict(&self, id: &Uuid) {
+        if let Some(cache) = &self.cache {
+            cache.invalidate(id).await;
+        }
+    }
+}
+
+#[async_trait]
+impl<R: UserRepository> UserRepository for CachingUserRepository<R> {
+    async fn create_user(&self, user: &NewUser) -> Result<User, CreateUserError> {
+        self.inner.create_user(user).await
+    }
+
+    async fn create_user_admin_if_first(&self, user: &NewUser) -> Result<User, CreateUserError> {
+        self.inner.create_user_admin_if_first(user).await
+    }
+
+    async fn create_users(&self, users: &[NewUser]) -> Result<Vec<Uuid>, CreateUserError> {
+        self.inner.create_users(users).await
+    }
+
+    async fn get_user_by_id(&self, id: &Uuid) -> Result<User, GetUserByIdError> {
+        let Some(cache) = &self.cache else {
+            return self.inner.get_user_by_id(id).await;
+        };
+
+        if let Some(user) = cache.get(id).await {
+            return Ok(user);
+        }
+
+        let user = self.inner.get_user_by_id(id).await?;
+        cache.insert(*id, user.clone()).await;
+
+        Ok(user)
+    }
+
+    async fn list_users(
+        &self,
+        filter: &UserFilter,
+        after: Option<UserCursor>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<User>, ListUsersError> {
+        self.inner.list_users(filter, after, limit, offset).await
+    }
+
+    async fn email_exists(&self, email: &EmailAddress) -> Result<bool, EmailExistsError> {
+        self.inner.email_exists(email).await
+    }
+
+    async fn delete_user(&self, id: &Uuid) -> Result<(), DeleteUserError> {
+        let result = self.inner.delete_user(id).await;
+        self.evict(id).await;
+
+        result
+    }
+
+    async fn erase_user(&self, id: &Uuid) -> Result<(), DeleteUserError> {
+        let result = self.inner.erase_user(id).await;
+        self.evict(id).await;
+
+        result
+    }
+
+    async fn delete_unconfirmed_before(
+        &self,
+        cutoff: &DateTime<Utc>,
+    ) -> Result<u64, DeleteUserError> {
+        let result = self.inner.delete_unconfirmed_before(cutoff).await;
+
+        // Which users were deleted isn't known, so none of them are left behind
+        if let Some(cache) = &self.cache {
+            cache.invalidate_all();
+        }
+
+        result
+    }
+
+    async fn initialize_email_confirmation<'a>(
+        &self,
+        user_id: &Uuid,
+        token: &str,
+        expires_at: &DateTime<Utc>,
+        new_email: Option<&'a EmailAddress>,
+        origin: &RequestOrigin,
+        expected_version: i32,
+    ) -> Result<(), UpdateUserError> {
+        let result = self
+            .inner
+            .initialize_email_confirmation(
+                user_id,
+                token,
+                expires_at,
+                new_email,
+                origin,
+                expected_version,
+            )
+            .await;
+        self.evict(user_id).await;
+
+        result
+    }
+
+    async fn complete_email_confirmation<'a>(
+        &self,
+        user_id: &Uuid,
+        token: &str,
+        new_email: Option<&'a EmailAddress>,
+    ) -> Result<(), UpdateUserError> {
+        let result = self
+            .inner
+            .complete_email_confirmation(user_id, token, new_email)
+            .await;
+        self.evict(user_id).await;
+
+        result
+    }
+
+    async fn force_email_confirmation(&self, user_id: &Uuid) -> Result<(), UpdateUserError> {
+        let result = self.inner.force_email_confirmation(user_id).await;
+        self.evict(user_id).await;
+
+        result
+    }
+
+    async fn cancel_email_change(
+        &self,
+        user_id: &Uuid,
+        expected_version: i32,
+    ) -> Result<(), UpdateUserError> {
+        let result = self
+            .inner
+            .cancel_email_change(user_id, expected_version)
+            .await;
+        self.evict(user_id).await;
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use testresult::TestResult;
+
+    use crate::domain::auth::users::tests::MockUserRepository;
+
+    use super::*;
+
+    fn enabled() -> UserCacheConfig {
+        UserCacheConfig {
+            capacity: 100,
+            ttl_seconds: 60,
+        }
+    }
+
+    fn user() -> User {
+        User {
+            id: Uuid::now_v7(),
+            ..User::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_second_read_within_ttl_is_cached() -> TestResult {
+        let user = user();
+        let id = user.id;
+        let mut repo = MockUserRepository::new();
+
+        repo.expect_get_user_by_id()
+            .times(1)
+            .returning(move |_| Ok(user.clone()));
+
+        let cached = CachingUserRepository::new(Arc::new(repo), &enabled());
+
+        assert_eq!(cached.get_user_by_id(&id).await?.id, id);
+        assert_eq!(cached.get_user_by_id(&id).await?.id, id);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_update_evicts_user() -> TestResult {
+        let user = user();
+        let id = user.id;
+        let mut repo = MockUserRepository::new();
+
+        repo.expect_get_user_by_id()
+            .times(3)
+            .returning(move |_| Ok(user.clone()));
+        repo.expect_complete_email_confirmation()
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+        repo.expect_cancel_email_change()
+            .times(1)
+            .returning(|_, _| Err(UpdateUserError::NoPendingEmailChange));
+
+        let cached = CachingUserRepository::new(Arc::new(repo), &enabled());
+
+        cached.get_user_by_id(&id).await?;
+        cached
+            .complete_email_confirmation(&id, "token", None)
+            .await?;
+        cached.get_user_by_id(&id).await?;
+
+        // Evicted even though the update failed, as it can't be told whether it was applied
+        assert!(cached.cancel_email_change(&id, 1).await.is_err());
+        cached.get_user_by_id(&id).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_missing_user_is_not_cached() -> TestResult {
+        let id = Uuid::now_v7();
+        let mut repo = MockUserRepository::new();
+
+        repo.expect_get_user_by_id()
+            .times(2)
+            .returning(|_| Err(GetUserByIdError::UserNotFound));
+
+        let cached = CachingUserRepository::new(Arc::new(repo), &enabled());
+
+        assert!(cached.get_user_by_id(&id).await.is_err());
+        assert!(cached.get_user_by_id(&id).await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_disabled_by_default() -> TestResult {
+        let user = user();
+        let id = user.id;
+        let mut repo = MockUserRepository::new();
+
+        repo.expect_get_user_by_id()
+            .times(2)
+            .returning(move |_| Ok(user.clone()));
+
+        let cached = CachingUserRepository::new(Arc::new(repo), &UserCacheConfig::default());
+
+        cached.get_user_by_id(&id).await?;
+        cached.get_user_by_id(&id).await?;
+
+        Ok(())
+    }
+}
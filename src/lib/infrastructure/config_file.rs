@@ -0,0 +1,465 @@
+//! Configuration files
+//!
+//! Arguments can be read from a TOML file named by `--config` (or `CONFIG_FILE`), with one
+//! table per group of arguments:
+//!
+//! ```toml
+//! [server]
+//! https_port = 8443
+//!
+//! [smtp]
+//! host = "smtp.example.com"
+//! ```
+//!
+//! Keys are either the arguments' field names or their long names, such as `host` or `smtp-host`,
+//! with hyphens or underscores. The file's values become the
+//! arguments' defaults, so flags take precedence over environment variables, which take
+//! precedence over the file, which takes precedence over the built-in defaults.
+
+use std::{
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use clap::{Arg, Command, Id, Parser};
+use thiserror::Error;
+use toml::{Table, Value};
+
+/// The id of the argument naming the configuration file
+const CONFIG_ARG: &str = "config";
+
+/// Errors loading the arguments from a configuration file
+#[derive(Debug, Error)]
+pub enum ConfigFileError {
+    /// The configuration file couldn't be read
+    #[error("failed to read config file {path}: {source}")]
+    Read {
+        /// The configuration file's path
+        path: PathBuf,
+
+        /// The error reading it
+        source: std::io::Error,
+    },
+
+    /// The configuration file isn't valid TOML
+    #[error("failed to parse config file {path}: {source}")]
+    Parse {
+        /// The configuration file's path
+        path: PathBuf,
+
+        /// The error parsing it
+        source: toml::de::Error,
+    },
+
+    /// A top-level key in the configuration file isn't a table of a known group of arguments
+    #[error("unknown section `{0}` in config file, expected one of: {1}")]
+    UnknownSection(String, String),
+
+    /// A key in the configuration file isn't an argument in its section
+    #[error("unknown key `{0}.{1}` in config file")]
+    UnknownKey(String, String),
+
+    /// A value in the configuration file can't be used as an argument
+    #[error("`{0}.{1}` in config file must be a string, number, boolean, or an array of them")]
+    InvalidValue(String, String),
+
+    /// The arguments themselves are invalid
+    #[error(transparent)]
+    Args(#[from] clap::Error),
+}
+
+/// Parses the arguments from the process's command line, environment, and configuration file,
+/// exiting with a usage message if they are invalid, as [`Parser::parse`] does.
+///
+/// # Arguments
+/// * `sections` - Each section of the configuration file, and the id of the group of arguments
+///   it holds, which is the name of the struct they are flattened from.
+pub fn parse_with_config_file<P: Parser>(sections: &[(&str, &str)]) -> Result<P, ConfigFileError> {
+    match try_parse_with_config_file(std::env::args_os(), sections) {
+        Err(ConfigFileError::Args(err)) => err.exit(),
+        result => result,
+    }
+}
+
+/// Parses the arguments from the given command line, the environment, and configuration file.
+///
+/// # Arguments
+/// * `args` - The command line, starting with the binary's name.
+/// * `sections` - Each section of the configuration file, and the id of the group of arguments
+///   it holds.
+///
+/// # Returns
+/// A [`Result`] which is [`Ok`] containing the arguments,
+/// or an [`Err`] containing a [`ConfigFileError`] if they or the configuration file are invalid.
+pub fn try_parse_with_config_file<P, I, T>(
+    args: I,
+    sections: &[(&str, &str)],
+) -> Result<P, ConfigFileError>
+where
+    P: Parser,
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+
+    let mut command = P::command().arg(config_arg());
+
+    if let Some(path) = config_path(&command, &args) {
+        command = with_defaults_from(command, &path, sections)?;
+    }
+
+    let matches = command.try_get_matches_from(args)?;
+
+    Ok(P::from_arg_matches(&matches)?)
+}
+
+/// The argument naming the configuration file
+fn config_arg() -> Arg {
+    Arg::new(CONFIG_ARG)
+        .long(CONFIG_ARG)
+        .env("CONFIG_FILE")
+        .value_name("PATH")
+        .value_parser(clap::value_parser!(PathBuf))
+        .help("A TOML file to read arguments from, which flags and environment variables override")
+}
+
+/// Finds the configuration file's path, before the rest of the arguments can be parsed
+fn config_path(command: &Command, args: &[OsString]) -> Option<PathBuf> {
+    Command::new(command.get_name().to_string())
+        .arg(config_arg())
+        .disable_help_flag(true)
+        .disable_version_flag(true)
+        .ignore_errors(true)
+        .try_get_matches_from(args)
+        .ok()?
+        .get_one::<PathBuf>(CONFIG_ARG)
+        .cloned()
+}
+
+/// Sets the default of each argument in the configuration file to its value there
+fn with_defaults_from(
+    mut command: Command,
+    path: &Path,
+    sections: &[(&str, &str)],
+) -> Result<Command, ConfigFileError> {
+    let contents = fs::read_to_string(path).map_err(|source| ConfigFileError::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    let file: Table = contents.parse().map_err(|source| ConfigFileError::Parse {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    for (section, values) in file {
+        let group = sections
+            .iter()
+            .find(|(name, _)| *name == section)
+            .map(|(_, group)| *group)
+            .ok_or_else(|| {
+                let expected = sections
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                ConfigFileError::UnknownSection(section.clone(), expected)
+            })?;
+
+        let Value::Table(values) = values else {
+            return Err(ConfigFileError::UnknownSection(
+                section,
+                "a table of arguments".to_string(),
+            ));
+        };
+
+        let ids = command
+            .get_groups()
+            .find(|candidate| candidate.get_id() == group)
+            .map(|group| group.get_args().cloned().collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        for (key, value) in values {
+            let Some(id) = arg_id(&command, &ids, &key) else {
+                return Err(ConfigFileError::UnknownKey(section, key));
+            };
+
+            let defaults = to_defaults(&value)
+                .ok_or_else(|| ConfigFileError::InvalidValue(section.clone(), key.clone()))?;
+
+            // A default never leaves a required argument missing, but clap still insists on it
+            // being passed unless it's no longer marked required
+            command = command.mut_arg(id, |arg| arg.default_values(defaults).required(false));
+        }
+    }
+
+    Ok(command)
+}
+
+/// Finds the id of the argument among `ids` which `key` names, by either its id or its long name
+fn arg_id(command: &Command, ids: &[Id], key: &str) -> Option<String> {
+    let id = key.replace('-', "_");
+    let long = key.replace('_', "-");
+
+    command
+        .get_arguments()
+        .filter(|arg| ids.contains(arg.get_id()))
+        .find(|arg| arg.get_id() == id.as_str() || arg.get_long() == Some(long.as_str()))
+        .map(|arg| arg.get_id().to_string())
+}
+
+/// Converts a value in the configuration file to the argument values it stands for
+fn to_defaults(value: &Value) -> Option<Vec<String>> {
+    match value {
+        Value::String(value) => Some(vec![value.clone()]),
+        Value::Integer(value) => Some(vec![value.to_string()]),
+        Value::Float(value) => Some(vec![value.to_string()]),
+        Value::Boolean(value) => Some(vec![value.to_string()]),
+        Value::Datetime(value) => Some(vec![value.to_string()]),
+        Value::Array(values) => values
+            .iter()
+            .map(|value| match value {
+                Value::Array(_) | Value::Table(_) => None,
+                value => to_defaults(value).and_then(|mut values| values.pop()),
+            })
+            .collect(),
+        Value::Table(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::{ArgAction, Parser};
+    use testresult::TestResult;
+    use uuid::Uuid;
+
+    use super::*;
+
+    #[derive(Debug, Parser)]
+    struct ServerArgs {
+        #[arg(long, default_value = "3443")]
+        port: u16,
+
+        #[arg(long, default_value = "localhost")]
+        host: String,
+    }
+
+    #[derive(Debug, Parser)]
+    struct SmtpArgs {
+        #[arg(long, env = "CONFIG_FILE_TEST_SMTP_HOST", default_value = "localhost")]
+        smtp_host: String,
+
+        #[arg(long = "smtp-timeout-seconds", default_value = "30")]
+        timeout: u64,
+
+        #[arg(long, default_value_t = false, action = ArgAction::Set)]
+        smtp_tls: bool,
+
+        #[arg(long, value_delimiter = ',')]
+        smtp_recipients: Vec<String>,
+    }
+
+    #[derive(Debug, Parser)]
+    struct Args {
+        #[clap(flatten)]
+        server: ServerArgs,
+
+        #[clap(flatten)]
+        smtp: SmtpArgs,
+    }
+
+    const SECTIONS: &[(&str, &str)] = &[("server", "ServerArgs"), ("smtp", "SmtpArgs")];
+
+    /// A configuration file which is deleted when dropped
+    struct ConfigFile(PathBuf);
+
+    impl ConfigFile {
+        fn path(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for ConfigFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    fn config_file(contents: &str) -> TestResult<ConfigFile> {
+        let path = std::env::temp_dir().join(format!("config-{}.toml", Uuid::now_v7()));
+        fs::write(&path, contents)?;
+
+        Ok(ConfigFile(path))
+    }
+
+    fn parse(args: &[&str]) -> Result<Args, ConfigFileError> {
+        try_parse_with_config_file(
+            std::iter::once("server").chain(args.iter().copied()),
+            SECTIONS,
+        )
+    }
+
+    #[test]
+    fn test_file_values_override_defaults() -> TestResult {
+        let file = config_file(
+            r#"
+            [server]
+            port = 8443
+
+            [smtp]
+            smtp-tls = true
+            smtp_recipients = ["a@example.com", "b@example.com"]
+            "#,
+        )?;
+
+        let args = parse(&["--config", file.path().to_str().ok_or("path")?])?;
+
+        assert_eq!(args.server.port, 8443);
+        assert_eq!(args.server.host, "localhost");
+        assert!(args.smtp.smtp_tls);
+        assert_eq!(
+            args.smtp.smtp_recipients,
+            vec!["a@example.com", "b@example.com"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_flags_override_file_values() -> TestResult {
+        let file = config_file("[server]\nport = 8443\nhost = \"example.com\"\n")?;
+
+        let args = parse(&[
+            "--config",
+            file.path().to_str().ok_or("path")?,
+            "--port",
+            "9443",
+        ])?;
+
+        assert_eq!(args.server.port, 9443);
+        assert_eq!(args.server.host, "example.com");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_environment_overrides_file_values() -> TestResult {
+        let file = config_file("[smtp]\nsmtp_host = \"smtp.example.com\"\n")?;
+
+        std::env::set_var("CONFIG_FILE_TEST_SMTP_HOST", "mail.example.com");
+
+        let args = parse(&["--config", file.path().to_str().ok_or("path")?]);
+
+        std::env::remove_var("CONFIG_FILE_TEST_SMTP_HOST");
+
+        assert_eq!(args?.smtp.smtp_host, "mail.example.com");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_without_config_file() -> TestResult {
+        let args = parse(&["--port", "9443"])?;
+
+        assert_eq!(args.server.port, 9443);
+        assert_eq!(args.smtp.smtp_host, "localhost");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_file_values_satisfy_required_arguments() -> TestResult {
+        #[derive(Debug, Parser)]
+        struct DatabaseArgs {
+            #[arg(long)]
+            url: String,
+        }
+
+        #[derive(Debug, Parser)]
+        struct RequiredArgs {
+            #[clap(flatten)]
+            db: DatabaseArgs,
+        }
+
+        let sections = &[("db", "DatabaseArgs")];
+        let file = config_file("[db]\nurl = \"postgres://localhost\"\n")?;
+
+        let args: RequiredArgs = try_parse_with_config_file(
+            ["server", "--config", file.path().to_str().ok_or("path")?],
+            sections,
+        )?;
+
+        assert_eq!(args.db.url, "postgres://localhost");
+        assert!(try_parse_with_config_file::<RequiredArgs, _, _>(["server"], sections).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_keys_can_be_field_or_long_names() -> TestResult {
+        let file = config_file("[smtp]\ntimeout = 10\n")?;
+        let args = parse(&["--config", file.path().to_str().ok_or("path")?])?;
+
+        assert_eq!(args.smtp.timeout, 10);
+
+        let file = config_file("[smtp]\nsmtp-timeout-seconds = 10\n")?;
+        let args = parse(&["--config", file.path().to_str().ok_or("path")?])?;
+
+        assert_eq!(args.smtp.timeout, 10);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_key_is_rejected() -> TestResult {
+        let file = config_file("[server]\nprot = 8443\n")?;
+
+        let err = parse(&["--config", file.path().to_str().ok_or("path")?])
+            .err()
+            .ok_or("expected an error")?;
+
+        assert!(matches!(
+            &err,
+            ConfigFileError::UnknownKey(section, key) if section == "server" && key == "prot"
+        ));
+        assert_eq!(err.to_string(), "unknown key `server.prot` in config file");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_in_the_wrong_section_is_rejected() -> TestResult {
+        let file = config_file("[server]\nsmtp_host = \"smtp.example.com\"\n")?;
+
+        let result = parse(&["--config", file.path().to_str().ok_or("path")?]);
+
+        assert!(matches!(result, Err(ConfigFileError::UnknownKey(_, _))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_section_is_rejected() -> TestResult {
+        let file = config_file("[database]\nurl = \"postgres://localhost\"\n")?;
+
+        let err = parse(&["--config", file.path().to_str().ok_or("path")?])
+            .err()
+            .ok_or("expected an error")?;
+
+        assert_eq!(
+            err.to_string(),
+            "unknown section `database` in config file, expected one of: server, smtp"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_file_is_an_error() {
+        let result = parse(&["--config", "/nonexistent/config.toml"]);
+
+        assert!(matches!(result, Err(ConfigFileError::Read { .. })));
+    }
+}
@@ -0,0 +1,107 @@
+//! Postgres implementation of the AuditLogger trait
+
+use async_trait::async_trait;
+use sqlx::query;
+
+use crate::{
+    domain::audit::{AuditEvent, AuditLogError, AuditLogger},
+    infrastructure::db::postgres::PostgresDatabase,
+};
+
+#[async_trait]
+impl AuditLogger for PostgresDatabase {
+    #[mutants::skip]
+    async fn record(&self, event: AuditEvent) -> Result<(), AuditLogError> {
+        query!(
+            r#"
+            INSERT INTO audit_log (actor_id, subject_id, event_type, ip, occurred_at)
+            VALUES ($1, $2, $3, $4::text::inet, $5)
+            "#,
+            event.actor_id,
+            event.subject_id,
+            event.event_type.as_str(),
+            event.ip.map(|ip| ip.to_string()),
+            event.occurred_at,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{IpAddr, Ipv4Addr},
+        sync::Arc,
+    };
+
+    use chrono::Utc;
+    use sqlx::{query, PgPool};
+    use testresult::TestResult;
+    use uuid::Uuid;
+
+    use crate::{
+        domain::{
+            audit::{AuditEvent, AuditEventType, AuditLogger, AuditSubscriber},
+            communication::email_addresses::{EmailAddress, RequestOrigin},
+            events::{DomainEvent, EventSubscriber},
+        },
+        infrastructure::db::postgres::PostgresDatabase,
+    };
+
+    #[sqlx::test]
+    #[ignore = "requires a Postgres server at DATABASE_URL"]
+    async fn test_audit_log_is_append_only(pool: PgPool) -> TestResult {
+        let db = PostgresDatabase { pool };
+        let actor_id = Uuid::now_v7();
+
+        db.record(
+            AuditEvent::new(AuditEventType::UserCreated, actor_id, Utc::now())
+                .with_ip(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1))),
+        )
+        .await?;
+
+        let row = query!(r#"SELECT actor_id, event_type, ip::text AS "ip!" FROM audit_log"#)
+            .fetch_one(&db.pool)
+            .await?;
+
+        assert_eq!(row.actor_id, Some(actor_id));
+        assert_eq!(row.event_type, "user_created");
+        assert_eq!(row.ip, "192.0.2.1/32");
+
+        assert!(query!("DELETE FROM audit_log")
+            .execute(&db.pool)
+            .await
+            .is_err());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a Postgres server at DATABASE_URL"]
+    async fn test_subscriber_records_client_ip(pool: PgPool) -> TestResult {
+        let db = PostgresDatabase { pool };
+
+        AuditSubscriber::new(Arc::new(db.clone()))
+            .handle(&DomainEvent::UserCreated {
+                user_id: Uuid::now_v7(),
+                email: EmailAddress::new_unchecked("email@example.com"),
+                origin: RequestOrigin {
+                    ip: Some(IpAddr::V4(Ipv4Addr::new(198, 51, 100, 7))),
+                    user_agent: None,
+                },
+                occurred_at: Utc::now(),
+            })
+            .await;
+
+        let row = query!(r#"SELECT ip::text AS ip FROM audit_log"#)
+            .fetch_one(&db.pool)
+            .await?;
+
+        assert_eq!(row.ip.as_deref(), Some("198.51.100.7/32"));
+
+        Ok(())
+    }
+}
@@ -3,16 +3,20 @@
 use anyhow::{anyhow, Error};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use sqlx::{query, query_as, Error::RowNotFound};
+use sqlx::{query, query_as, query_scalar, Error::RowNotFound};
 use uuid::Uuid;
 
 use crate::{
     domain::{
         auth::users::{
-            errors::{CreateUserError, GetUserByIdError, UpdateUserError},
-            NewUser, User, UserRepository,
+            errors::{
+                CreateUserError, DeleteUserError, EmailExistsError, GetUserByIdError,
+                ListUsersError, UpdateUserError,
+            },
+            NewUser, User, UserCursor, UserFilter, UserRepository,
         },
-        communication::email_addresses::EmailAddress,
+        communication::email_addresses::{hash_confirmation_token, EmailAddress, RequestOrigin},
+        timestamps::Timestamps,
     },
     infrastructure::db::postgres::PostgresDatabase,
 };
@@ -24,8 +28,14 @@ struct UserRecord {
     email_confirmed_at: Option<DateTime<Utc>>,
     email_confirmation_token: Option<String>,
     email_confirmation_sent_at: Option<DateTime<Utc>>,
+    email_confirmation_expires_at: Option<DateTime<Utc>>,
+    role: String,
+    terms_accepted_at: Option<DateTime<Utc>>,
+    terms_version: Option<String>,
+    locale: String,
     created_at: DateTime<Utc>,
     updated_at: DateTime<Utc>,
+    version: i32,
 }
 
 impl TryFrom<UserRecord> for User {
@@ -41,30 +51,200 @@ impl TryFrom<UserRecord> for User {
             email_confirmed_at: record.email_confirmed_at,
             email_confirmation_token: record.email_confirmation_token,
             email_confirmation_sent_at: record.email_confirmation_sent_at,
-            created_at: record.created_at,
-            updated_at: record.updated_at,
+            email_confirmation_expires_at: record.email_confirmation_expires_at,
+            role: record.role.parse()?,
+            terms_accepted_at: record.terms_accepted_at,
+            terms_version: record.terms_version,
+            // Locales there are no longer translations for fall back to the default
+            locale: record.locale.parse().unwrap_or_default(),
+            timestamps: Timestamps {
+                created_at: record.created_at,
+                updated_at: record.updated_at,
+            },
+            version: record.version,
         })
     }
 }
 
+impl PostgresDatabase {
+    /// Get the user's current version, to tell why an update guarded by one matched no rows
+    async fn user_version(&self, user_id: &Uuid) -> Result<Option<i32>, UpdateUserError> {
+        Ok(query!(
+            r#"
+            SELECT version
+            FROM users
+            WHERE id = $1
+            "#,
+            user_id,
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .map(|row| row.version))
+    }
+}
+
 #[async_trait]
 impl UserRepository for PostgresDatabase {
     #[mutants::skip]
-    async fn create_user(&self, user: &NewUser) -> Result<Uuid, CreateUserError> {
-        let result = query!(
+    async fn create_user(&self, user: &NewUser) -> Result<User, CreateUserError> {
+        let record = query_as!(
+            UserRecord,
             r#"
-            INSERT INTO users (id, email, password)
-            VALUES ($1, $2, $3)
-            RETURNING id
+            INSERT INTO users (
+                id, email, email_normalized, username, password, terms_accepted_at, terms_version,
+                locale
+            )
+            VALUES ($1, $2, $3, $4, $5, CASE WHEN $6 THEN NOW() END, $7, $8)
+            RETURNING
+                id,
+                email,
+                new_email,
+                email_confirmed_at,
+                email_confirmation_token,
+                email_confirmation_sent_at,
+                email_confirmation_expires_at,
+                role,
+                terms_accepted_at,
+                terms_version,
+                locale,
+                created_at,
+                updated_at,
+                version
             "#,
             user.id(),
             user.email().to_string(),
-            user.password_hash().to_string()
+            user.email().normalized().to_string(),
+            user.username().map(|username| username.to_string()),
+            user.password_hash().to_string(),
+            user.accepted_terms(),
+            user.terms_version(),
+            user.locale().as_str(),
         )
         .fetch_one(&self.pool)
         .await?;
 
-        Ok(result.id)
+        Ok(record.try_into()?)
+    }
+
+    #[mutants::skip]
+    async fn create_user_admin_if_first(&self, user: &NewUser) -> Result<User, CreateUserError> {
+        let any_users = query_scalar!(r#"SELECT EXISTS (SELECT 1 FROM users) AS "exists!""#)
+            .fetch_one(&self.pool)
+            .await?;
+
+        // Once there is a user there can't be a first one again, so later signups skip the lock
+        if any_users {
+            return self.create_user(user).await;
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        // SHARE ROW EXCLUSIVE conflicts with itself, so concurrent first signups are serialized
+        // and only one of them can see an empty table
+        query!("LOCK TABLE users IN SHARE ROW EXCLUSIVE MODE")
+            .execute(&mut *tx)
+            .await?;
+
+        let record = query_as!(
+            UserRecord,
+            r#"
+            INSERT INTO users (
+                id, email, email_normalized, username, password, terms_accepted_at, terms_version,
+                locale, role
+            )
+            VALUES (
+                $1, $2, $3, $4, $5, CASE WHEN $6 THEN NOW() END, $7, $8,
+                CASE WHEN EXISTS (SELECT 1 FROM users) THEN 'user' ELSE 'admin' END
+            )
+            RETURNING
+                id,
+                email,
+                new_email,
+                email_confirmed_at,
+                email_confirmation_token,
+                email_confirmation_sent_at,
+                email_confirmation_expires_at,
+                role,
+                terms_accepted_at,
+                terms_version,
+                locale,
+                created_at,
+                updated_at,
+                version
+            "#,
+            user.id(),
+            user.email().to_string(),
+            user.email().normalized().to_string(),
+            user.username().map(|username| username.to_string()),
+            user.password_hash().to_string(),
+            user.accepted_terms(),
+            user.terms_version(),
+            user.locale().as_str(),
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(record.try_into()?)
+    }
+
+    #[mutants::skip]
+    async fn create_users(&self, users: &[NewUser]) -> Result<Vec<Uuid>, CreateUserError> {
+        let ids: Vec<Uuid> = users.iter().map(|user| *user.id()).collect();
+        let emails: Vec<String> = users.iter().map(|user| user.email().to_string()).collect();
+        let normalized_emails: Vec<String> = users
+            .iter()
+            .map(|user| user.email().normalized().to_string())
+            .collect();
+        let usernames: Vec<Option<String>> = users
+            .iter()
+            .map(|user| user.username().map(|username| username.to_string()))
+            .collect();
+        let passwords: Vec<String> = users
+            .iter()
+            .map(|user| user.password_hash().to_string())
+            .collect();
+        let accepted_terms: Vec<bool> = users.iter().map(|user| user.accepted_terms()).collect();
+        let terms_versions: Vec<Option<String>> = users
+            .iter()
+            .map(|user| user.terms_version().map(str::to_string))
+            .collect();
+        let locales: Vec<String> = users
+            .iter()
+            .map(|user| user.locale().as_str().to_string())
+            .collect();
+
+        query!(
+            r#"
+            INSERT INTO users (
+                id, email, email_normalized, username, password, terms_accepted_at, terms_version,
+                locale
+            )
+            SELECT
+                id, email, email_normalized, username, password,
+                CASE WHEN accepted_terms THEN NOW() END, terms_version, locale
+            FROM UNNEST(
+                $1::uuid[], $2::text[], $3::text[], $4::text[], $5::text[], $6::bool[],
+                $7::text[], $8::text[]
+            ) AS new_users (
+                id, email, email_normalized, username, password, accepted_terms, terms_version,
+                locale
+            )
+            "#,
+            &ids,
+            &emails,
+            &normalized_emails,
+            &usernames as &[Option<String>],
+            &passwords,
+            &accepted_terms,
+            &terms_versions as &[Option<String>],
+            &locales,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(ids)
     }
 
     #[mutants::skip]
@@ -79,10 +259,17 @@ impl UserRepository for PostgresDatabase {
                 email_confirmed_at,
                 email_confirmation_token,
                 email_confirmation_sent_at,
+                email_confirmation_expires_at,
+                role,
+                terms_accepted_at,
+                terms_version,
+                locale,
                 created_at,
-                updated_at
+                updated_at,
+                version
             FROM users
             WHERE id = $1
+            AND deleted_at IS NULL
             "#,
             id
         )
@@ -95,27 +282,202 @@ impl UserRepository for PostgresDatabase {
         .try_into()?)
     }
 
+    #[mutants::skip]
+    async fn list_users(
+        &self,
+        filter: &UserFilter,
+        after: Option<UserCursor>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<User>, ListUsersError> {
+        query_as!(
+            UserRecord,
+            r#"
+            SELECT
+                id,
+                email,
+                new_email,
+                email_confirmed_at,
+                email_confirmation_token,
+                email_confirmation_sent_at,
+                email_confirmation_expires_at,
+                role,
+                terms_accepted_at,
+                terms_version,
+                locale,
+                created_at,
+                updated_at,
+                version
+            FROM users
+            WHERE deleted_at IS NULL
+                AND ($3::timestamptz IS NULL OR created_at > $3)
+                AND ($4::timestamptz IS NULL OR created_at < $4)
+                AND ($5::boolean IS NULL OR (email_confirmed_at IS NOT NULL) = $5)
+                AND ($6::timestamptz IS NULL OR (created_at, id) > ($6, $7::uuid))
+            ORDER BY created_at, id
+            LIMIT $1
+            OFFSET $2
+            "#,
+            limit,
+            offset,
+            filter.created_after,
+            filter.created_before,
+            filter.confirmed,
+            after.map(|cursor| cursor.created_at),
+            after.map(|cursor| cursor.id),
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|err| anyhow!("Unknown database error: {:?}", err))?
+        .into_iter()
+        .map(|record| Ok(record.try_into()?))
+        .collect()
+    }
+
+    #[mutants::skip]
+    async fn email_exists(&self, email: &EmailAddress) -> Result<bool, EmailExistsError> {
+        let exists = query_scalar!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1
+                FROM users
+                WHERE email_normalized = $1
+            ) AS "exists!"
+            "#,
+            email.normalized().to_string(),
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|err| anyhow!("Unknown database error: {:?}", err))?;
+
+        Ok(exists)
+    }
+
+    #[mutants::skip]
+    async fn delete_user(&self, id: &Uuid) -> Result<(), DeleteUserError> {
+        let result = query!(
+            r#"
+            DELETE FROM users
+            WHERE id = $1
+            "#,
+            id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|err| anyhow!("Unknown database error: {:?}", err))?;
+
+        if result.rows_affected() == 0 {
+            return Err(DeleteUserError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[mutants::skip]
+    async fn erase_user(&self, id: &Uuid) -> Result<(), DeleteUserError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|err| anyhow!("Unknown database error: {:?}", err))?;
+
+        // The placeholder address is unique to the user, so it can't collide with anyone signing
+        // up, and uses a reserved domain so nothing can ever be delivered to it
+        let result = query!(
+            r#"
+            UPDATE users
+            SET email = 'erased-' || id || '@erased.invalid',
+                email_normalized = 'erased-' || id || '@erased.invalid',
+                password = '',
+                username = NULL,
+                new_email = NULL,
+                email_confirmation_token = NULL,
+                email_confirmation_sent_at = NULL,
+                email_confirmation_expires_at = NULL,
+                email_confirmation_requested_ip = NULL,
+                email_confirmation_requested_user_agent = NULL,
+                deleted_at = NOW()
+            WHERE id = $1
+            AND deleted_at IS NULL
+            "#,
+            id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|err| anyhow!("Unknown database error: {:?}", err))?;
+
+        if result.rows_affected() == 0 {
+            return Err(DeleteUserError::UserNotFound);
+        }
+
+        // Recorded responses for requests which created or returned the user hold their personal
+        // data, and every one of them includes the user's id
+        query!(
+            r#"
+            DELETE FROM idempotency_keys
+            WHERE POSITION(CONVERT_TO($1::text, 'UTF8') IN response_body) > 0
+            "#,
+            id.to_string()
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|err| anyhow!("Unknown database error: {:?}", err))?;
+
+        tx.commit()
+            .await
+            .map_err(|err| anyhow!("Unknown database error: {:?}", err))?;
+
+        Ok(())
+    }
+
+    async fn delete_unconfirmed_before(
+        &self,
+        cutoff: &DateTime<Utc>,
+    ) -> Result<u64, DeleteUserError> {
+        // Admins are kept, so purging an unconfirmed bootstrap admin can't leave the table empty
+        // for the next signup to become admin
+        let result = query!(
+            r#"
+            DELETE FROM users
+            WHERE email_confirmed_at IS NULL
+              AND created_at < $1
+              AND role <> 'admin'
+            "#,
+            cutoff
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|err| anyhow!("Unknown database error: {:?}", err))?;
+
+        Ok(result.rows_affected())
+    }
+
     #[mutants::skip]
     async fn initialize_email_confirmation<'a>(
         &self,
         user_id: &Uuid,
         token: &str,
+        expires_at: &DateTime<Utc>,
         new_email: Option<&'a EmailAddress>,
+        origin: &RequestOrigin,
+        expected_version: i32,
     ) -> Result<(), UpdateUserError> {
+        let normalized_new_email: Option<String> =
+            new_email.map(|email| email.normalized().to_string());
         let new_email: Option<String> = new_email.as_ref().map(|email| email.to_string());
 
         let mut tx = self.pool.begin().await?;
 
-        if new_email.is_some() {
+        if normalized_new_email.is_some() {
             let existing_email = query!(
                 r#"
                 SELECT EXISTS (
                     SELECT 1
                     FROM users
-                    WHERE email = $1
+                    WHERE email_normalized = $1
                 )
                 "#,
-                new_email,
+                normalized_new_email,
             )
             .fetch_optional(&mut *tx)
             .await?;
@@ -128,21 +490,36 @@ impl UserRepository for PostgresDatabase {
             }
         }
 
-        query!(
+        let result = query!(
             r#"
             UPDATE users
             SET email_confirmation_token = $1,
             email_confirmation_sent_at = NOW(),
+            email_confirmation_expires_at = $4,
+            email_confirmation_requested_ip = $6::text::inet,
+            email_confirmation_requested_user_agent = $7,
             new_email = COALESCE($3, new_email)
             WHERE id = $2
+            AND version = $5
             "#,
-            token.to_string(),
+            hash_confirmation_token(token),
             user_id,
             new_email,
+            expires_at,
+            expected_version,
+            origin.ip.map(|ip| ip.to_string()),
+            origin.user_agent,
         )
         .execute(&mut *tx)
         .await?;
 
+        if result.rows_affected() == 0 {
+            return Err(match self.user_version(user_id).await? {
+                Some(_) => UpdateUserError::Conflict,
+                None => UpdateUserError::UserNotFound,
+            });
+        }
+
         tx.commit().await?;
 
         Ok(())
@@ -152,27 +529,824 @@ impl UserRepository for PostgresDatabase {
     async fn complete_email_confirmation<'a>(
         &self,
         user_id: &Uuid,
+        token: &str,
         new_email: Option<&'a EmailAddress>,
     ) -> Result<(), UpdateUserError> {
-        query!(
+        // This is a single statement, so if the new email address has been taken by another user
+        // since the change was requested, the unique constraint fails the whole update and the
+        // user's current email address is left untouched.
+        //
+        // Matching on the token as well as the ID means that of two requests racing to use the
+        // same token, only the first updates a row.
+        let result = query!(
             r#"
             UPDATE users
             SET email_confirmed_at = NOW(),
                 email_confirmation_token = NULL,
                 email = COALESCE($2, email),
+                email_normalized = COALESCE($3, email_normalized),
                 new_email = NULL
             WHERE id = $1
+            AND email_confirmation_token = $4
             "#,
             user_id,
             new_email.map(|email| email.to_string()),
+            new_email.map(|email| email.normalized().to_string()),
+            hash_confirmation_token(token),
         )
         .execute(&self.pool)
-        .await
-        .map_err(|err| match err {
-            RowNotFound => UpdateUserError::UserNotFound,
-            _ => UpdateUserError::UnknownError(anyhow!("Unknown database error: {:?}", err)),
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(UpdateUserError::ConfirmationTokenMismatch);
+        }
+
+        Ok(())
+    }
+
+    #[mutants::skip]
+    async fn force_email_confirmation(&self, user_id: &Uuid) -> Result<(), UpdateUserError> {
+        // An outstanding token is for confirming the current email address unless there's a
+        // pending change, in which case it's kept so the new address can still be confirmed
+        let result = query!(
+            r#"
+            UPDATE users
+            SET email_confirmed_at = NOW(),
+                email_confirmation_token = CASE
+                    WHEN new_email IS NULL THEN NULL
+                    ELSE email_confirmation_token
+                END,
+                email_confirmation_expires_at = CASE
+                    WHEN new_email IS NULL THEN NULL
+                    ELSE email_confirmation_expires_at
+                END
+            WHERE id = $1
+            AND email_confirmed_at IS NULL
+            "#,
+            user_id,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 && self.user_version(user_id).await?.is_none() {
+            return Err(UpdateUserError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[mutants::skip]
+    async fn cancel_email_change(
+        &self,
+        user_id: &Uuid,
+        expected_version: i32,
+    ) -> Result<(), UpdateUserError> {
+        let result = query!(
+            r#"
+            UPDATE users
+            SET new_email = NULL,
+                email_confirmation_token = NULL,
+                email_confirmation_expires_at = NULL
+            WHERE id = $1
+            AND new_email IS NOT NULL
+            AND version = $2
+            "#,
+            user_id,
+            expected_version,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(match self.user_version(user_id).await? {
+                Some(version) if version != expected_version => UpdateUserError::Conflict,
+                _ => UpdateUserError::NoPendingEmailChange,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{IpAddr, Ipv4Addr},
+        sync::{Arc, Mutex},
+    };
+
+    use chrono::{Duration, Utc};
+    use sqlx::PgPool;
+    use testresult::TestResult;
+    use url::form_urlencoded;
+    use uuid::Uuid;
+
+    use crate::{
+        domain::{
+            auth::users::{
+                errors::{CreateUserError, DeleteUserError, GetUserByIdError, UpdateUserError},
+                NewUser, Password, Role, User, UserCursor, UserFilter, UserRepository,
+            },
+            base_url::tests::example_base_url,
+            clock::SystemClock,
+            communication::{
+                email_addresses::{
+                    hash_confirmation_token, EmailAddress, EmailAddressService,
+                    EmailAddressServiceImpl, EmailConfirmationConfig, EmailConfirmationError,
+                    EmailConfirmationType, RequestOrigin,
+                },
+                mailer::{
+                    tests::{any_mailer, MockMailer},
+                    EmailSenders,
+                },
+            },
+            events::EventBus,
+        },
+        infrastructure::db::postgres::PostgresDatabase,
+    };
+
+    use super::UserRecord;
+
+    fn new_user(email: &str) -> TestResult<NewUser> {
+        Ok(NewUser::new(
+            Uuid::now_v7(),
+            EmailAddress::new(email)?,
+            Password::new("correcthorsebatterystaple")?,
+        ))
+    }
+
+    #[test]
+    fn test_user_record_maps_timestamps() -> TestResult {
+        let created_at = Utc::now() - Duration::days(2);
+        let updated_at = Utc::now() - Duration::hours(1);
+
+        let user = User::try_from(UserRecord {
+            id: Uuid::now_v7(),
+            email: "email@example.com".to_string(),
+            new_email: None,
+            email_confirmed_at: None,
+            email_confirmation_token: None,
+            email_confirmation_sent_at: None,
+            email_confirmation_expires_at: None,
+            role: "user".to_string(),
+            terms_accepted_at: None,
+            terms_version: None,
+            locale: "en".to_string(),
+            created_at,
+            updated_at,
+            version: 1,
         })?;
 
+        assert_eq!(user.timestamps.created_at, created_at);
+        assert_eq!(user.timestamps.updated_at, updated_at);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a Postgres server at DATABASE_URL"]
+    async fn test_only_the_first_user_becomes_admin(pool: PgPool) -> TestResult {
+        let db = PostgresDatabase { pool };
+
+        let first_id = db
+            .create_user_admin_if_first(&new_user("first@example.com")?)
+            .await?
+            .id;
+        let second_id = db
+            .create_user_admin_if_first(&new_user("second@example.com")?)
+            .await?
+            .id;
+
+        assert_eq!(db.get_user_by_id(&first_id).await?.role, Role::Admin);
+        assert_eq!(db.get_user_by_id(&second_id).await?.role, Role::User);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a Postgres server at DATABASE_URL"]
+    async fn test_concurrent_first_users_create_one_admin(pool: PgPool) -> TestResult {
+        let db = PostgresDatabase { pool };
+
+        let (a, b) = (new_user("a@example.com")?, new_user("b@example.com")?);
+        let (a, b) = tokio::join!(
+            db.create_user_admin_if_first(&a),
+            db.create_user_admin_if_first(&b)
+        );
+
+        let roles = [
+            db.get_user_by_id(&a?.id).await?.role,
+            db.get_user_by_id(&b?.id).await?.role,
+        ];
+
+        assert_eq!(roles.iter().filter(|role| **role == Role::Admin).count(), 1);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a Postgres server at DATABASE_URL"]
+    async fn test_create_users_in_one_statement(pool: PgPool) -> TestResult {
+        let db = PostgresDatabase { pool };
+
+        let users = [new_user("a@example.com")?, new_user("b@example.com")?];
+
+        let ids = db.create_users(&users).await?;
+
+        assert_eq!(ids, vec![*users[0].id(), *users[1].id()]);
+        assert_eq!(
+            db.get_user_by_id(&ids[1]).await?.email.to_string(),
+            "b@example.com"
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a Postgres server at DATABASE_URL"]
+    async fn test_create_users_creates_none_if_any_conflict(pool: PgPool) -> TestResult {
+        let db = PostgresDatabase { pool };
+
+        db.create_user(&new_user("taken@example.com")?).await?;
+
+        let users = [new_user("new@example.com")?, new_user("Taken@example.com")?];
+
+        let result = db.create_users(&users).await;
+
+        assert!(matches!(result, Err(CreateUserError::DuplicateUser)));
+        assert_eq!(
+            db.list_users(&UserFilter::default(), None, 10, 0)
+                .await?
+                .len(),
+            1
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a Postgres server at DATABASE_URL"]
+    async fn test_list_users_filters_by_confirmation_and_creation_date(pool: PgPool) -> TestResult {
+        let db = PostgresDatabase { pool };
+
+        let old = db.create_user(&new_user("old@example.com")?).await?.id;
+        let confirmed = db
+            .create_user(&new_user("confirmed@example.com")?)
+            .await?
+            .id;
+        let unconfirmed = db
+            .create_user(&new_user("unconfirmed@example.com")?)
+            .await?
+            .id;
+
+        sqlx::query("UPDATE users SET email_confirmed_at = NOW() WHERE id = ANY($1)")
+            .bind(vec![old, confirmed])
+            .execute(&db.pool)
+            .await?;
+        sqlx::query("UPDATE users SET created_at = NOW() - INTERVAL '2 days' WHERE id = $1")
+            .bind(old)
+            .execute(&db.pool)
+            .await?;
+
+        let ids = |users: Vec<User>| users.into_iter().map(|user| user.id).collect::<Vec<_>>();
+
+        let filter = UserFilter {
+            confirmed: Some(false),
+            ..UserFilter::default()
+        };
+        assert_eq!(
+            ids(db.list_users(&filter, None, 10, 0).await?),
+            vec![unconfirmed]
+        );
+
+        let filter = UserFilter {
+            created_after: Some(Utc::now() - Duration::days(1)),
+            created_before: Some(Utc::now() + Duration::days(1)),
+            confirmed: Some(true),
+        };
+        assert_eq!(
+            ids(db.list_users(&filter, None, 10, 0).await?),
+            vec![confirmed]
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a Postgres server at DATABASE_URL"]
+    async fn test_list_users_after_cursor_is_not_shifted_by_deletions(pool: PgPool) -> TestResult {
+        let db = PostgresDatabase { pool };
+
+        let first = db.create_user(&new_user("first@example.com")?).await?;
+        let second = db.create_user(&new_user("second@example.com")?).await?.id;
+        let third = db.create_user(&new_user("third@example.com")?).await?.id;
+
+        let ids = |users: Vec<User>| users.into_iter().map(|user| user.id).collect::<Vec<_>>();
+        let filter = UserFilter::default();
+
+        assert_eq!(
+            ids(db.list_users(&filter, None, 1, 0).await?),
+            vec![first.id]
+        );
+
+        db.delete_user(&first.id).await?;
+
+        let cursor = Some(UserCursor::from(&first));
+
+        assert_eq!(
+            ids(db.list_users(&filter, cursor, 10, 0).await?),
+            vec![second, third]
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a Postgres server at DATABASE_URL"]
+    async fn test_delete_unconfirmed_before_only_deletes_old_unconfirmed_users(
+        pool: PgPool,
+    ) -> TestResult {
+        let db = PostgresDatabase { pool };
+
+        let old_unconfirmed = db
+            .create_user(&new_user("old-unconfirmed@example.com")?)
+            .await?
+            .id;
+        let old_confirmed = db
+            .create_user(&new_user("old-confirmed@example.com")?)
+            .await?
+            .id;
+        let recent = db.create_user(&new_user("recent@example.com")?).await?.id;
+
+        sqlx::query("UPDATE users SET email_confirmed_at = NOW() WHERE id = $1")
+            .bind(old_confirmed)
+            .execute(&db.pool)
+            .await?;
+        sqlx::query("UPDATE users SET created_at = NOW() - INTERVAL '8 days' WHERE id = ANY($1)")
+            .bind(vec![old_unconfirmed, old_confirmed])
+            .execute(&db.pool)
+            .await?;
+
+        let deleted = db
+            .delete_unconfirmed_before(&(Utc::now() - Duration::days(7)))
+            .await?;
+
+        let remaining = db
+            .list_users(&UserFilter::default(), None, 10, 0)
+            .await?
+            .into_iter()
+            .map(|user| user.id)
+            .collect::<Vec<_>>();
+
+        assert_eq!(deleted, 1);
+        assert_eq!(remaining, vec![old_confirmed, recent]);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a Postgres server at DATABASE_URL"]
+    async fn test_delete_unconfirmed_before_keeps_unconfirmed_admins(pool: PgPool) -> TestResult {
+        let db = PostgresDatabase { pool };
+
+        let admin = db
+            .create_user_admin_if_first(&new_user("admin@example.com")?)
+            .await?
+            .id;
+
+        sqlx::query("UPDATE users SET created_at = NOW() - INTERVAL '8 days' WHERE id = $1")
+            .bind(admin)
+            .execute(&db.pool)
+            .await?;
+
+        let deleted = db
+            .delete_unconfirmed_before(&(Utc::now() - Duration::days(7)))
+            .await?;
+
+        assert_eq!(deleted, 0);
+        assert_eq!(db.get_user_by_id(&admin).await?.role, Role::Admin);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a Postgres server at DATABASE_URL"]
+    async fn test_erase_user_scrubs_personal_data(pool: PgPool) -> TestResult {
+        let db = PostgresDatabase { pool };
+
+        let erased = db.create_user(&new_user("erased@example.com")?).await?.id;
+        let kept = db.create_user(&new_user("kept@example.com")?).await?.id;
+
+        for (key, user) in [("erased-key", erased), ("kept-key", kept)] {
+            sqlx::query(
+                "INSERT INTO idempotency_keys (key, route, request_fingerprint, response_status, response_body)
+                 VALUES ($1, 'POST /api/v1/users', '', 201, $2)",
+            )
+            .bind(key)
+            .bind(format!(r#"{{"id":"{user}"}}"#).into_bytes())
+            .execute(&db.pool)
+            .await?;
+        }
+
+        db.erase_user(&erased).await?;
+
+        let keys: Vec<String> = sqlx::query_scalar("SELECT key FROM idempotency_keys")
+            .fetch_all(&db.pool)
+            .await?;
+
+        assert_eq!(keys, vec!["kept-key".to_string()]);
+
+        assert!(matches!(
+            db.get_user_by_id(&erased).await,
+            Err(GetUserByIdError::UserNotFound)
+        ));
+        assert_eq!(
+            db.list_users(&UserFilter::default(), None, 10, 0)
+                .await?
+                .into_iter()
+                .map(|user| user.id)
+                .collect::<Vec<_>>(),
+            vec![kept]
+        );
+
+        let (email, email_normalized, password): (String, String, String) =
+            sqlx::query_as("SELECT email, email_normalized, password FROM users WHERE id = $1")
+                .bind(erased)
+                .fetch_one(&db.pool)
+                .await?;
+
+        assert_eq!(email, format!("erased-{erased}@erased.invalid"));
+        assert_eq!(email_normalized, email);
+        assert!(password.is_empty());
+        assert!(
+            !db.email_exists(&EmailAddress::new("erased@example.com")?)
+                .await?
+        );
+
+        assert!(matches!(
+            db.erase_user(&erased).await,
+            Err(DeleteUserError::UserNotFound)
+        ));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a Postgres server at DATABASE_URL"]
+    async fn test_email_confirmation_origin_is_stored(pool: PgPool) -> TestResult {
+        let db = PostgresDatabase { pool };
+
+        let id = db.create_user(&new_user("email@example.com")?).await?.id;
+
+        db.initialize_email_confirmation(
+            &id,
+            "token",
+            &(Utc::now() + Duration::hours(1)),
+            None,
+            &RequestOrigin {
+                ip: Some(IpAddr::V4(Ipv4Addr::new(203, 0, 113, 7))),
+                user_agent: Some("curl/8.0".to_string()),
+            },
+            1,
+        )
+        .await?;
+
+        let (ip, user_agent): (Option<String>, Option<String>) = sqlx::query_as(
+            "SELECT host(email_confirmation_requested_ip), email_confirmation_requested_user_agent \
+             FROM users WHERE id = $1",
+        )
+        .bind(id)
+        .fetch_one(&db.pool)
+        .await?;
+
+        assert_eq!(ip.as_deref(), Some("203.0.113.7"));
+        assert_eq!(user_agent.as_deref(), Some("curl/8.0"));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a Postgres server at DATABASE_URL"]
+    async fn test_force_email_confirmation_is_idempotent(pool: PgPool) -> TestResult {
+        let db = PostgresDatabase { pool };
+
+        let id = db.create_user(&new_user("email@example.com")?).await?.id;
+
+        db.force_email_confirmation(&id).await?;
+        let confirmed_at = db.get_user_by_id(&id).await?.email_confirmed_at;
+
+        db.force_email_confirmation(&id).await?;
+        let user = db.get_user_by_id(&id).await?;
+
+        assert!(confirmed_at.is_some());
+        assert_eq!(user.email_confirmed_at, confirmed_at);
+        assert!(matches!(
+            db.force_email_confirmation(&Uuid::now_v7()).await,
+            Err(UpdateUserError::UserNotFound)
+        ));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a Postgres server at DATABASE_URL"]
+    async fn test_email_exists_compares_normalized_addresses(pool: PgPool) -> TestResult {
+        let db = PostgresDatabase { pool };
+
+        db.create_user(&new_user("taken@example.com")?).await?;
+
+        assert!(
+            db.email_exists(&EmailAddress::new("Taken@Example.com")?)
+                .await?
+        );
+        assert!(
+            !db.email_exists(&EmailAddress::new("free@example.com")?)
+                .await?
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a Postgres server at DATABASE_URL"]
+    async fn test_email_confirmation_token_is_stored_hashed(pool: PgPool) -> TestResult {
+        let db = Arc::new(PostgresDatabase { pool });
+
+        let user_id = db.create_user(&new_user("hashed@example.com")?).await?.id;
+
+        let sent_body = Arc::new(Mutex::new(String::new()));
+        let captured_body = sent_body.clone();
+
+        let mut mailer = MockMailer::new();
+
+        mailer
+            .expect_send_email()
+            .times(1)
+            .returning(move |message| {
+                *captured_body.lock().unwrap() = message.plain_body;
+
+                Ok(())
+            });
+
+        let service = EmailAddressServiceImpl::new(
+            db.clone(),
+            Arc::new(mailer),
+            EmailSenders::default(),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            EmailConfirmationConfig::default(),
+        );
+
+        service
+            .send_email_confirmation(
+                &db.get_user_by_id(&user_id).await?,
+                EmailConfirmationType::CurrentEmail,
+                &example_base_url(),
+                &RequestOrigin::default(),
+            )
+            .await?;
+
+        let body = sent_body.lock().unwrap().clone();
+        let query = body
+            .split('?')
+            .nth(1)
+            .and_then(|rest| rest.split_whitespace().next())
+            .ok_or("no link in email")?;
+
+        // The link percent-encodes the token, so it's decoded as the confirm_email route would
+        let emailed_token = form_urlencoded::parse(query.as_bytes())
+            .find_map(|(name, value)| (name == "token").then(|| value.into_owned()))
+            .ok_or("no token in email")?;
+        let emailed_token = emailed_token.as_str();
+
+        let user = db.get_user_by_id(&user_id).await?;
+        let stored_token = user
+            .email_confirmation_token
+            .clone()
+            .ok_or("no stored token")?;
+
+        assert_ne!(stored_token, emailed_token);
+        assert_eq!(stored_token, hash_confirmation_token(emailed_token));
+
+        service
+            .confirm_email(&user, emailed_token, &RequestOrigin::default())
+            .await?;
+
+        let user = db.get_user_by_id(&user_id).await?;
+
+        assert!(user.email_confirmed_at.is_some());
+        assert!(user.email_confirmation_token.is_none());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a Postgres server at DATABASE_URL"]
+    async fn test_email_confirmation_token_is_single_use(pool: PgPool) -> TestResult {
+        let db = Arc::new(PostgresDatabase { pool });
+
+        let user_id = db
+            .create_user(&new_user("single-use@example.com")?)
+            .await?
+            .id;
+        let expires_at = Utc::now() + Duration::hours(1);
+
+        db.initialize_email_confirmation(
+            &user_id,
+            "first",
+            &expires_at,
+            None,
+            &RequestOrigin::default(),
+            1,
+        )
+        .await?;
+        let superseded = db.get_user_by_id(&user_id).await?;
+
+        db.initialize_email_confirmation(
+            &user_id,
+            "second",
+            &expires_at,
+            None,
+            &RequestOrigin::default(),
+            2,
+        )
+        .await?;
+        let user = db.get_user_by_id(&user_id).await?;
+
+        let service = EmailAddressServiceImpl::new(
+            db.clone(),
+            any_mailer(),
+            EmailSenders::default(),
+            EventBus::new(),
+            Arc::new(SystemClock),
+            EmailConfirmationConfig::default(),
+        );
+
+        // Requesting a new confirmation invalidates the outstanding token, even for a request
+        // that loaded the user before it was replaced
+        assert!(matches!(
+            service
+                .confirm_email(&superseded, "first", &RequestOrigin::default())
+                .await,
+            Err(EmailConfirmationError::ConfirmationTokenMismatch)
+        ));
+
+        service
+            .confirm_email(&user, "second", &RequestOrigin::default())
+            .await?;
+
+        // Replaying the token, whether with the user as it was loaded before or after it was
+        // consumed, is a mismatch
+        assert!(matches!(
+            service
+                .confirm_email(&user, "second", &RequestOrigin::default())
+                .await,
+            Err(EmailConfirmationError::ConfirmationTokenMismatch)
+        ));
+        assert!(matches!(
+            service
+                .confirm_email(
+                    &db.get_user_by_id(&user_id).await?,
+                    "second",
+                    &RequestOrigin::default()
+                )
+                .await,
+            Err(EmailConfirmationError::ConfirmationTokenMismatch)
+        ));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a Postgres server at DATABASE_URL"]
+    async fn test_create_user_records_terms_acceptance(pool: PgPool) -> TestResult {
+        let db = PostgresDatabase { pool };
+
+        let accepted = db
+            .create_user(
+                &NewUser::builder()
+                    .email(EmailAddress::new("accepted@example.com")?)
+                    .password(Password::new("correcthorsebatterystaple")?)
+                    .accepted_terms(Some("v2".to_string()))
+                    .build()?,
+            )
+            .await?;
+        let not_accepted = db.create_user(&new_user("declined@example.com")?).await?;
+
+        // The created users are returned as they were stored
+        assert_eq!(db.get_user_by_id(&accepted.id).await?, accepted);
+        assert_eq!(db.get_user_by_id(&not_accepted.id).await?, not_accepted);
+
+        assert!(accepted.terms_accepted_at.is_some());
+        assert_eq!(accepted.terms_version.as_deref(), Some("v2"));
+        assert!(not_accepted.terms_accepted_at.is_none());
+        assert!(not_accepted.terms_version.is_none());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a Postgres server at DATABASE_URL"]
+    async fn test_cancel_email_change(pool: PgPool) -> TestResult {
+        let db = PostgresDatabase { pool };
+
+        let user_id = db.create_user(&new_user("current@example.com")?).await?.id;
+        let new_email = EmailAddress::new("new@example.com")?;
+
+        db.initialize_email_confirmation(
+            &user_id,
+            "token",
+            &(Utc::now() + Duration::hours(1)),
+            Some(&new_email),
+            &RequestOrigin::default(),
+            1,
+        )
+        .await?;
+
+        db.cancel_email_change(&user_id, 2).await?;
+
+        let user = db.get_user_by_id(&user_id).await?;
+
+        assert_eq!(user.email.to_string(), "current@example.com");
+        assert!(user.new_email.is_none());
+        assert!(user.email_confirmation_token.is_none());
+
+        assert!(matches!(
+            db.cancel_email_change(&user_id, 3).await,
+            Err(UpdateUserError::NoPendingEmailChange)
+        ));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a Postgres server at DATABASE_URL"]
+    async fn test_update_with_stale_version_is_rejected(pool: PgPool) -> TestResult {
+        let db = PostgresDatabase { pool };
+
+        let user_id = db
+            .create_user(&new_user("versioned@example.com")?)
+            .await?
+            .id;
+        let expires_at = Utc::now() + Duration::hours(1);
+        let new_email = EmailAddress::new("new@example.com")?;
+
+        assert_eq!(db.get_user_by_id(&user_id).await?.version, 1);
+
+        db.initialize_email_confirmation(
+            &user_id,
+            "first",
+            &expires_at,
+            Some(&new_email),
+            &RequestOrigin::default(),
+            1,
+        )
+        .await?;
+
+        assert_eq!(db.get_user_by_id(&user_id).await?.version, 2);
+
+        assert!(matches!(
+            db.initialize_email_confirmation(
+                &user_id,
+                "second",
+                &expires_at,
+                None,
+                &RequestOrigin::default(),
+                1
+            )
+            .await,
+            Err(UpdateUserError::Conflict)
+        ));
+        assert!(matches!(
+            db.cancel_email_change(&user_id, 1).await,
+            Err(UpdateUserError::Conflict)
+        ));
+
+        let user = db.get_user_by_id(&user_id).await?;
+
+        assert_eq!(user.version, 2);
+        assert_eq!(
+            user.email_confirmation_token,
+            Some(hash_confirmation_token("first"))
+        );
+
+        db.cancel_email_change(&user_id, 2).await?;
+
+        assert_eq!(db.get_user_by_id(&user_id).await?.version, 3);
+
+        assert!(matches!(
+            db.initialize_email_confirmation(
+                &Uuid::now_v7(),
+                "token",
+                &expires_at,
+                None,
+                &RequestOrigin::default(),
+                1
+            )
+            .await,
+            Err(UpdateUserError::UserNotFound)
+        ));
+
         Ok(())
     }
 }
@@ -0,0 +1,101 @@
+//! Postgres implementation of the DatabaseHealth trait
+
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+use sqlx::{query, query_scalar};
+
+use crate::{
+    domain::health::{DatabaseHealth, HealthError},
+    infrastructure::db::postgres::{PostgresDatabase, MIGRATOR},
+};
+
+/// The Postgres error code for a relation which doesn't exist
+const UNDEFINED_TABLE: &str = "42P01";
+
+#[async_trait]
+impl DatabaseHealth for PostgresDatabase {
+    #[mutants::skip]
+    async fn ping(&self) -> Result<(), HealthError> {
+        query!("SELECT 1 AS ping").fetch_one(&self.pool).await?;
+
+        Ok(())
+    }
+
+    #[mutants::skip]
+    async fn pending_migrations(&self) -> Result<Vec<i64>, HealthError> {
+        let applied = query_scalar!("SELECT version FROM _sqlx_migrations WHERE success")
+            .fetch_all(&self.pool)
+            .await;
+
+        // The migrations table is only created by the first migration run, so without it every
+        // migration is pending
+        let applied: HashSet<i64> = match applied {
+            Ok(applied) => applied.into_iter().collect(),
+            Err(sqlx::Error::Database(err)) if err.code().as_deref() == Some(UNDEFINED_TABLE) => {
+                HashSet::new()
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(MIGRATOR
+            .iter()
+            .filter(|migration| !migration.migration_type.is_down_migration())
+            .map(|migration| migration.version)
+            .filter(|version| !applied.contains(version))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+    use testresult::TestResult;
+
+    use super::*;
+
+    fn embedded_versions() -> Vec<i64> {
+        MIGRATOR
+            .iter()
+            .filter(|migration| !migration.migration_type.is_down_migration())
+            .map(|migration| migration.version)
+            .collect()
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a Postgres server at DATABASE_URL"]
+    async fn test_migrated_database_is_healthy(pool: PgPool) -> TestResult {
+        let db = PostgresDatabase { pool };
+
+        db.ping().await?;
+
+        assert_eq!(db.pending_migrations().await?, Vec::<i64>::new());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a Postgres server at DATABASE_URL"]
+    async fn test_missing_migration_is_pending(pool: PgPool) -> TestResult {
+        let db = PostgresDatabase { pool };
+        let latest = *embedded_versions().last().expect("no embedded migrations");
+
+        query!("DELETE FROM _sqlx_migrations WHERE version = $1", latest)
+            .execute(&db.pool)
+            .await?;
+
+        assert_eq!(db.pending_migrations().await?, vec![latest]);
+
+        Ok(())
+    }
+
+    #[sqlx::test(migrations = false)]
+    #[ignore = "requires a Postgres server at DATABASE_URL"]
+    async fn test_unmigrated_database_has_every_migration_pending(pool: PgPool) -> TestResult {
+        let db = PostgresDatabase { pool };
+
+        assert_eq!(db.pending_migrations().await?, embedded_versions());
+
+        Ok(())
+    }
+}
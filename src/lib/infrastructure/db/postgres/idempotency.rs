@@ -0,0 +1,235 @@
+//! Postgres implementation of the IdempotencyStore trait
+
+use async_trait::async_trait;
+use sqlx::query;
+
+use crate::{
+    domain::idempotency::{
+        IdempotencyError, IdempotencyStore, IdempotentRequest, RecordedResponse, StartOutcome,
+    },
+    infrastructure::db::postgres::PostgresDatabase,
+};
+
+#[async_trait]
+impl IdempotencyStore for PostgresDatabase {
+    #[mutants::skip]
+    async fn start(&self, request: &IdempotentRequest) -> Result<StartOutcome, IdempotencyError> {
+        // The upsert only takes over a key whose previous use has expired, and the primary key
+        // ensures only one of several concurrent inserts can claim a new one
+        let claimed = query!(
+            r#"
+            INSERT INTO idempotency_keys (key, route, request_fingerprint)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (key, route) DO UPDATE
+            SET request_fingerprint = EXCLUDED.request_fingerprint,
+            response_status = NULL,
+            response_headers = '{}',
+            response_body = NULL,
+            created_at = NOW()
+            WHERE idempotency_keys.created_at < NOW() - INTERVAL '24 hours'
+            RETURNING key
+            "#,
+            request.key,
+            request.route,
+            request.fingerprint
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        if claimed.is_some() {
+            return Ok(StartOutcome::Started);
+        }
+
+        let existing = query!(
+            r#"
+            SELECT request_fingerprint, response_status, response_headers, response_body
+            FROM idempotency_keys
+            WHERE key = $1 AND route = $2
+            "#,
+            request.key,
+            request.route
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        // A missing row means the key was released after our insert conflicted, and the request
+        // which released it may be retrying, so treat it as still in progress
+        let Some(existing) = existing else {
+            return Ok(StartOutcome::InProgress);
+        };
+
+        if existing.request_fingerprint != request.fingerprint {
+            return Ok(StartOutcome::FingerprintMismatch);
+        }
+
+        Ok(match existing.response_status {
+            Some(status) => StartOutcome::Completed(RecordedResponse {
+                status: status as u16,
+                headers: existing
+                    .response_headers
+                    .iter()
+                    .filter_map(|header| decode_header(header))
+                    .collect(),
+                body: existing.response_body.unwrap_or_default(),
+            }),
+            None => StartOutcome::InProgress,
+        })
+    }
+
+    #[mutants::skip]
+    async fn complete(
+        &self,
+        request: &IdempotentRequest,
+        response: &RecordedResponse,
+    ) -> Result<(), IdempotencyError> {
+        query!(
+            r#"
+            UPDATE idempotency_keys
+            SET response_status = $3,
+            response_headers = $4,
+            response_body = $5
+            WHERE key = $1 AND route = $2
+            "#,
+            request.key,
+            request.route,
+            response.status as i16,
+            &response
+                .headers
+                .iter()
+                .map(|(name, value)| format!("{name}: {value}"))
+                .collect::<Vec<_>>(),
+            response.body
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    #[mutants::skip]
+    async fn release(&self, request: &IdempotentRequest) -> Result<(), IdempotencyError> {
+        query!(
+            r#"
+            DELETE FROM idempotency_keys
+            WHERE key = $1 AND route = $2 AND response_status IS NULL
+            "#,
+            request.key,
+            request.route
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    #[mutants::skip]
+    async fn purge_expired(&self) -> Result<u64, IdempotencyError> {
+        let result = query!(
+            r#"
+            DELETE FROM idempotency_keys
+            WHERE created_at < NOW() - INTERVAL '24 hours'
+            "#
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+/// Splits a header stored as `name: value` back into its name and value
+fn decode_header(header: &str) -> Option<(String, String)> {
+    header
+        .split_once(": ")
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+    use testresult::TestResult;
+
+    use super::*;
+
+    fn request(fingerprint: &str) -> IdempotentRequest {
+        IdempotentRequest {
+            key: "key-1".to_string(),
+            route: "POST /api/v1/users".to_string(),
+            fingerprint: fingerprint.to_string(),
+        }
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a Postgres server at DATABASE_URL"]
+    async fn test_key_lifecycle(pool: PgPool) -> TestResult {
+        let db = PostgresDatabase { pool };
+        let response = RecordedResponse {
+            status: 201,
+            headers: vec![
+                ("content-type".to_string(), "application/json".to_string()),
+                ("location".to_string(), "/api/v1/users/1".to_string()),
+            ],
+            body: b"{}".to_vec(),
+        };
+
+        assert_eq!(db.start(&request("a")).await?, StartOutcome::Started);
+        assert_eq!(db.start(&request("a")).await?, StartOutcome::InProgress);
+
+        db.complete(&request("a"), &response).await?;
+
+        assert_eq!(
+            db.start(&request("a")).await?,
+            StartOutcome::Completed(response)
+        );
+        assert_eq!(
+            db.start(&request("b")).await?,
+            StartOutcome::FingerprintMismatch
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a Postgres server at DATABASE_URL"]
+    async fn test_released_key_can_be_restarted(pool: PgPool) -> TestResult {
+        let db = PostgresDatabase { pool };
+
+        assert_eq!(db.start(&request("a")).await?, StartOutcome::Started);
+
+        db.release(&request("a")).await?;
+
+        assert_eq!(db.start(&request("a")).await?, StartOutcome::Started);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    #[ignore = "requires a Postgres server at DATABASE_URL"]
+    async fn test_purge_expired_deletes_only_expired_keys(pool: PgPool) -> TestResult {
+        let db = PostgresDatabase { pool };
+
+        db.start(&request("a")).await?;
+        db.start(&IdempotentRequest {
+            key: "key-2".to_string(),
+            ..request("a")
+        })
+        .await?;
+
+        query!("UPDATE idempotency_keys SET created_at = NOW() - INTERVAL '25 hours' WHERE key = 'key-1'")
+            .execute(&db.pool)
+            .await?;
+
+        assert_eq!(db.purge_expired().await?, 1);
+
+        let remaining = query!("SELECT key FROM idempotency_keys")
+            .fetch_all(&db.pool)
+            .await?;
+
+        assert_eq!(
+            remaining.into_iter().map(|row| row.key).collect::<Vec<_>>(),
+            vec!["key-2"]
+        );
+
+        Ok(())
+    }
+}
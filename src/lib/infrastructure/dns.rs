@@ -0,0 +1,103 @@
+//! DNS module
+
+use std::{fmt, sync::Arc};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use clap::{ArgAction, Parser};
+use hickory_resolver::{error::ResolveErrorKind, TokioAsyncResolver};
+
+use crate::domain::communication::email_addresses::MailDomainResolver;
+
+/// Email deliverability configuration
+#[derive(Clone, Debug, Default, PartialEq, Eq, Parser)]
+pub struct EmailDeliverabilityConfig {
+    /// Whether new users' email addresses, and addresses users change to, are rejected if their
+    /// domain has no MX or address records to deliver to. Off by default, as it looks each domain
+    /// up in DNS
+    #[arg(
+        long = "check-email-deliverability",
+        env = "CHECK_EMAIL_DELIVERABILITY",
+        default_value_t = false,
+        action = ArgAction::Set
+    )]
+    pub check_deliverability: bool,
+}
+
+impl EmailDeliverabilityConfig {
+    /// The resolver to check addresses' domains with, if checks are on
+    pub fn resolver(&self) -> anyhow::Result<Option<Arc<dyn MailDomainResolver>>> {
+        if !self.check_deliverability {
+            return Ok(None);
+        }
+
+        Ok(Some(Arc::new(DnsMailDomainResolver::from_system_conf()?)))
+    }
+}
+
+/// A [`MailDomainResolver`] which looks domains up with the system's DNS configuration
+#[derive(Clone)]
+pub struct DnsMailDomainResolver {
+    resolver: TokioAsyncResolver,
+}
+
+impl DnsMailDomainResolver {
+    /// Create a new resolver from the system's DNS configuration, e.g. `/etc/resolv.conf`
+    pub fn from_system_conf() -> anyhow::Result<Self> {
+        let resolver = TokioAsyncResolver::tokio_from_system_conf()
+            .context("failed to read the system DNS configuration")?;
+
+        Ok(Self { resolver })
+    }
+}
+
+impl fmt::Debug for DnsMailDomainResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DnsMailDomainResolver")
+            .finish_non_exhaustive()
+    }
+}
+
+/// The domain as a fully-qualified name, so it isn't looked up under the search domains
+fn fully_qualified(domain: &str) -> String {
+    format!("{}.", domain.trim_end_matches('.'))
+}
+
+#[async_trait]
+impl MailDomainResolver for DnsMailDomainResolver {
+    async fn mail_exchangers(&self, domain: &str) -> anyhow::Result<Vec<String>> {
+        match self.resolver.mx_lookup(fully_qualified(domain)).await {
+            Ok(lookup) => Ok(lookup.iter().map(|mx| mx.exchange().to_utf8()).collect()),
+            Err(err) if matches!(err.kind(), ResolveErrorKind::NoRecordsFound { .. }) => {
+                Ok(Vec::new())
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn has_address_records(&self, domain: &str) -> anyhow::Result<bool> {
+        match self.resolver.lookup_ip(fully_qualified(domain)).await {
+            Ok(lookup) => Ok(lookup.iter().next().is_some()),
+            Err(err) if matches!(err.kind(), ResolveErrorKind::NoRecordsFound { .. }) => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_resolver_unless_checks_are_on() -> anyhow::Result<()> {
+        assert!(EmailDeliverabilityConfig::default().resolver()?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_domains_are_looked_up_fully_qualified() {
+        assert_eq!(fully_qualified("example.com"), "example.com.");
+        assert_eq!(fully_qualified("example.com."), "example.com.");
+    }
+}
@@ -1,3 +1,216 @@
 //! Email module
 
+use axum::async_trait;
+use clap::Parser;
+use thiserror::Error;
+use tracing::warn;
+
+use crate::domain::communication::mailer::{Mailer, MailerError, Message};
+
+use self::{
+    logging::LoggingMailer,
+    smtp::{SMTPConfig, SMTPMailer},
+};
+
+pub mod circuit_breaker;
+pub mod health;
+pub mod logging;
 pub mod smtp;
+
+/// Email backend configuration
+#[derive(Clone, Debug, Default, PartialEq, Eq, Parser)]
+pub struct EmailBackendConfig {
+    /// Log emails instead of sending them
+    #[arg(
+        long = "email-dry-run",
+        env = "EMAIL_DRY_RUN",
+        default_value_t = false,
+        action = clap::ArgAction::Set
+    )]
+    pub dry_run: bool,
+
+    /// Log emails instead of refusing to start when SMTP isn't configured, for local development
+    #[arg(
+        long = "email-fallback-to-logging",
+        env = "EMAIL_FALLBACK_TO_LOGGING",
+        default_value_t = false,
+        action = clap::ArgAction::Set
+    )]
+    pub fallback_to_logging: bool,
+}
+
+/// An error choosing the email backend
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum EmailBackendError {
+    /// SMTP was selected, but there's no server or sender address to send with
+    #[error(
+        "SMTP isn't configured: set SMTP_HOST, SMTP_PORT and SMTP_SENDER, or set EMAIL_DRY_RUN or \
+         EMAIL_FALLBACK_TO_LOGGING to log emails instead"
+    )]
+    SmtpNotConfigured,
+
+    /// Emails were to be logged on a production server, which would leak their contents, such as
+    /// confirmation tokens, into the logs
+    #[error(
+        "EMAIL_DRY_RUN and EMAIL_FALLBACK_TO_LOGGING can't be enabled when APP_ENV is production"
+    )]
+    LoggingInProduction,
+}
+
+impl EmailBackendConfig {
+    /// Checks emails won't be logged in production
+    ///
+    /// # Arguments
+    /// * `production` - Whether the server is running in production.
+    pub fn validate(&self, production: bool) -> Result<(), EmailBackendError> {
+        if production && (self.dry_run || self.fallback_to_logging) {
+            return Err(EmailBackendError::LoggingInProduction);
+        }
+
+        Ok(())
+    }
+}
+
+/// The mailer emails are sent with, chosen at startup
+#[derive(Debug, Clone)]
+pub enum EmailBackend {
+    /// Send emails over SMTP
+    Smtp(SMTPMailer),
+
+    /// Log emails without sending them
+    Logging(LoggingMailer),
+}
+
+impl EmailBackend {
+    /// Create the email backend selected by `config`
+    ///
+    /// # Returns
+    /// A [`Result`] containing the backend, or an [`Err`] containing
+    /// [`EmailBackendError::SmtpNotConfigured`] if SMTP is selected but not configured, and
+    /// falling back to logging isn't allowed.
+    pub fn new(config: &EmailBackendConfig, smtp: SMTPConfig) -> Result<Self, EmailBackendError> {
+        if config.dry_run {
+            return Ok(EmailBackend::Logging(LoggingMailer::new()));
+        }
+
+        if smtp.is_configured() {
+            return Ok(EmailBackend::Smtp(SMTPMailer::new(smtp)));
+        }
+
+        if !config.fallback_to_logging {
+            return Err(EmailBackendError::SmtpNotConfigured);
+        }
+
+        warn!("SMTP isn't configured, so emails will be logged instead of sent");
+
+        Ok(EmailBackend::Logging(LoggingMailer::new()))
+    }
+}
+
+#[async_trait]
+impl Mailer for EmailBackend {
+    async fn send_email(&self, message: Message) -> Result<(), MailerError> {
+        match self {
+            EmailBackend::Smtp(mailer) => mailer.send_email(message).await,
+            EmailBackend::Logging(mailer) => mailer.send_email(message).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn configured_smtp() -> SMTPConfig {
+        SMTPConfig {
+            host: "localhost".to_string(),
+            port: 9587,
+            sender: "email@example.com".to_string(),
+            ..SMTPConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_dry_run_selects_logging_mailer() {
+        let backend = EmailBackend::new(
+            &EmailBackendConfig {
+                dry_run: true,
+                ..EmailBackendConfig::default()
+            },
+            SMTPConfig::default(),
+        );
+
+        assert!(matches!(backend, Ok(EmailBackend::Logging(_))));
+    }
+
+    #[test]
+    fn test_smtp_mailer_is_selected_by_default() {
+        let backend = EmailBackend::new(&EmailBackendConfig::default(), configured_smtp());
+
+        assert!(matches!(backend, Ok(EmailBackend::Smtp(_))));
+    }
+
+    #[test]
+    fn test_blank_smtp_host_is_not_configured() {
+        assert!(configured_smtp().is_configured());
+
+        for smtp in [
+            SMTPConfig {
+                host: "  ".to_string(),
+                ..configured_smtp()
+            },
+            SMTPConfig {
+                port: 0,
+                ..configured_smtp()
+            },
+            SMTPConfig {
+                sender: String::new(),
+                ..configured_smtp()
+            },
+        ] {
+            assert!(!smtp.is_configured());
+        }
+    }
+
+    #[test]
+    fn test_unconfigured_smtp_is_refused() {
+        let backend = EmailBackend::new(&EmailBackendConfig::default(), SMTPConfig::default());
+
+        assert!(matches!(backend, Err(EmailBackendError::SmtpNotConfigured)));
+    }
+
+    #[test]
+    fn test_logging_refused_in_production() {
+        for config in [
+            EmailBackendConfig {
+                dry_run: true,
+                ..EmailBackendConfig::default()
+            },
+            EmailBackendConfig {
+                fallback_to_logging: true,
+                ..EmailBackendConfig::default()
+            },
+        ] {
+            assert_eq!(
+                config.validate(true),
+                Err(EmailBackendError::LoggingInProduction)
+            );
+            assert_eq!(config.validate(false), Ok(()));
+        }
+
+        assert_eq!(EmailBackendConfig::default().validate(true), Ok(()));
+    }
+
+    #[test]
+    fn test_unconfigured_smtp_falls_back_to_logging_mailer() {
+        let backend = EmailBackend::new(
+            &EmailBackendConfig {
+                fallback_to_logging: true,
+                ..EmailBackendConfig::default()
+            },
+            SMTPConfig::default(),
+        );
+
+        assert!(matches!(backend, Ok(EmailBackend::Logging(_))));
+    }
+}
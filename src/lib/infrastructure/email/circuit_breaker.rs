@@ -0,0 +1,426 @@
+//! Circuit breaker mailer
+//!
+//! While the mail server is down every send waits for it to time out, holding up the request
+//! which sent it. After enough failures in a row the breaker opens and sends fail immediately,
+//! until the cooldown has passed and a single probe send gets through.
+
+use std::sync::{Arc, Mutex};
+
+use axum::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use clap::Parser;
+use tracing::{info, warn};
+
+use crate::domain::{
+    clock::{Clock, SystemClock},
+    communication::mailer::{Mailer, MailerError, Message},
+};
+
+/// Circuit breaker configuration
+#[derive(Clone, Debug, PartialEq, Eq, Parser)]
+pub struct CircuitBreakerConfig {
+    /// How many sends in a row must fail before sends fail immediately. 0 never opens the circuit
+    #[arg(
+        long = "email-circuit-breaker-failure-threshold",
+        env = "EMAIL_CIRCUIT_BREAKER_FAILURE_THRESHOLD",
+        default_value = "5"
+    )]
+    pub failure_threshold: u32,
+
+    /// How many seconds sends fail immediately for, before one is tried again
+    #[arg(
+        long = "email-circuit-breaker-cooldown-seconds",
+        env = "EMAIL_CIRCUIT_BREAKER_COOLDOWN_SECONDS",
+        default_value = "30"
+    )]
+    pub cooldown_seconds: u32,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            cooldown_seconds: 30,
+        }
+    }
+}
+
+/// Whether sends are getting through to the wrapped mailer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Sends are passed on
+    Closed,
+
+    /// Sends fail immediately, until the cooldown has passed
+    Open,
+
+    /// The cooldown has passed, so the next send is let through to probe the mailer, and other
+    /// sends fail immediately until it finishes, or until another cooldown has passed without it
+    /// finishing
+    HalfOpen,
+}
+
+/// The breaker's state, and what it needs to decide when to change it
+#[derive(Clone, Copy, Debug)]
+enum Breaker {
+    Closed {
+        failures: u32,
+    },
+    Open {
+        until: DateTime<Utc>,
+    },
+    /// A probe is in flight, and another may be let through after `until` in case it never
+    /// finishes, such as its future being dropped or panicking
+    HalfOpen {
+        until: DateTime<Utc>,
+    },
+}
+
+/// A [`Mailer`] which stops trying to send through `M` while it keeps failing
+#[derive(Clone, Debug)]
+pub struct CircuitBreakerMailer<M: Mailer, C: Clock = SystemClock> {
+    inner: M,
+    config: CircuitBreakerConfig,
+    clock: Arc<C>,
+    breaker: Arc<Mutex<Breaker>>,
+}
+
+impl<M: Mailer, C: Clock> CircuitBreakerMailer<M, C> {
+    /// Create a new circuit breaker around `inner`
+    pub fn new(inner: M, config: CircuitBreakerConfig, clock: Arc<C>) -> Self {
+        Self {
+            inner,
+            config,
+            clock,
+            breaker: Arc::new(Mutex::new(Breaker::Closed { failures: 0 })),
+        }
+    }
+
+    /// Whether sends are currently getting through
+    pub fn state(&self) -> CircuitState {
+        match *self.lock() {
+            Breaker::Closed { .. } => CircuitState::Closed,
+            Breaker::Open { until } if self.clock.now() < until => CircuitState::Open,
+            Breaker::Open { .. } | Breaker::HalfOpen { .. } => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Locks the breaker's state
+    fn lock(&self) -> std::sync::MutexGuard<'_, Breaker> {
+        self.breaker.lock().expect("circuit breaker lock poisoned")
+    }
+
+    /// Whether a send may go through, moving an open circuit whose cooldown has passed to
+    /// half-open so the send can probe the mailer
+    fn allow(&self) -> bool {
+        let mut breaker = self.lock();
+
+        match *breaker {
+            Breaker::Closed { .. } => true,
+            Breaker::Open { until } | Breaker::HalfOpen { until } if self.clock.now() >= until => {
+                *breaker = Breaker::HalfOpen {
+                    until: self.cooldown_end(),
+                };
+
+                true
+            }
+            Breaker::Open { .. } | Breaker::HalfOpen { .. } => false,
+        }
+    }
+
+    /// Records how a send went
+    fn record(&self, result: &Result<(), MailerError>) {
+        let mut breaker = self.lock();
+
+        // Only failing to deliver at all says anything about the mail server being down
+        let failed = matches!(
+            result,
+            Err(MailerError::SendError | MailerError::UnknownError(_))
+        );
+
+        *breaker = match (*breaker, failed) {
+            (Breaker::HalfOpen { .. }, false) => {
+                info!("email circuit breaker closed, sends are getting through again");
+
+                Breaker::Closed { failures: 0 }
+            }
+            (_, false) => Breaker::Closed { failures: 0 },
+            (Breaker::HalfOpen { .. }, true) => self.open(),
+            (Breaker::Closed { failures }, true)
+                if self.config.failure_threshold > 0
+                    && failures + 1 >= self.config.failure_threshold =>
+            {
+                warn!(
+                    failures = failures + 1,
+                    cooldown_seconds = self.config.cooldown_seconds,
+                    "email circuit breaker opened, sends will fail until the cooldown has passed"
+                );
+
+                self.open()
+            }
+            (Breaker::Closed { failures }, true) => Breaker::Closed {
+                failures: failures + 1,
+            },
+            (open @ Breaker::Open { .. }, true) => open,
+        };
+    }
+
+    /// An open circuit, failing sends until the cooldown from now has passed
+    fn open(&self) -> Breaker {
+        Breaker::Open {
+            until: self.cooldown_end(),
+        }
+    }
+
+    /// When a cooldown starting now will have passed
+    fn cooldown_end(&self) -> DateTime<Utc> {
+        self.clock.now() + Duration::seconds(self.config.cooldown_seconds.into())
+    }
+}
+
+#[async_trait]
+impl<M: Mailer, C: Clock> Mailer for CircuitBreakerMailer<M, C> {
+    async fn send_email(&self, message: Message) -> Result<(), MailerError> {
+        if !self.allow() {
+            return Err(MailerError::SendError);
+        }
+
+        let result = self.inner.send_email(message).await;
+        self.record(&result);
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    use testresult::TestResult;
+
+    use crate::domain::{
+        clock::tests::MockClock,
+        communication::{email_addresses::EmailAddress, mailer::tests::MockMailer},
+    };
+
+    use super::*;
+
+    /// A mailer which fails every send while `failing` is set, and never finishes sending while
+    /// `hanging` is
+    #[derive(Clone, Debug, Default)]
+    struct FlakyMailer {
+        failing: Arc<AtomicBool>,
+        hanging: Arc<AtomicBool>,
+        sends: Arc<AtomicUsize>,
+    }
+
+    impl FlakyMailer {
+        fn sends(&self) -> usize {
+            self.sends.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl Mailer for FlakyMailer {
+        async fn send_email(&self, _message: Message) -> Result<(), MailerError> {
+            self.sends.fetch_add(1, Ordering::SeqCst);
+
+            if self.hanging.load(Ordering::SeqCst) {
+                std::future::pending::<()>().await;
+            }
+
+            match self.failing.load(Ordering::SeqCst) {
+                true => Err(MailerError::SendError),
+                false => Ok(()),
+            }
+        }
+    }
+
+    fn message() -> Message {
+        Message {
+            to: EmailAddress::new_unchecked("email@example.com"),
+            from: None,
+            subject: "Subject".to_string(),
+            html_body: "<p>Body</p>".to_string(),
+            plain_body: "Body".to_string(),
+        }
+    }
+
+    fn breaker(
+        inner: &FlakyMailer,
+        failure_threshold: u32,
+    ) -> (CircuitBreakerMailer<FlakyMailer, MockClock>, MockClock) {
+        let clock = MockClock::new(Utc::now());
+        let mailer = CircuitBreakerMailer::new(
+            inner.clone(),
+            CircuitBreakerConfig {
+                failure_threshold,
+                cooldown_seconds: 30,
+            },
+            Arc::new(clock.clone()),
+        );
+
+        (mailer, clock)
+    }
+
+    #[tokio::test]
+    async fn test_breaker_opens_then_closes_once_mailer_recovers() -> TestResult {
+        let inner = FlakyMailer::default();
+        let (mailer, clock) = breaker(&inner, 3);
+
+        inner.failing.store(true, Ordering::SeqCst);
+
+        for _ in 0..2 {
+            assert!(mailer.send_email(message()).await.is_err());
+            assert_eq!(mailer.state(), CircuitState::Closed);
+        }
+
+        assert!(mailer.send_email(message()).await.is_err());
+        assert_eq!(mailer.state(), CircuitState::Open);
+
+        // Open: sends fail without reaching the mailer, even once it has recovered
+        inner.failing.store(false, Ordering::SeqCst);
+
+        assert!(matches!(
+            mailer.send_email(message()).await,
+            Err(MailerError::SendError)
+        ));
+        assert_eq!(inner.sends(), 3);
+
+        // Half-open: once the cooldown has passed, a probe is let through and closes the circuit
+        clock.advance(Duration::seconds(30));
+
+        assert_eq!(mailer.state(), CircuitState::HalfOpen);
+
+        mailer.send_email(message()).await?;
+
+        assert_eq!(inner.sends(), 4);
+        assert_eq!(mailer.state(), CircuitState::Closed);
+
+        mailer.send_email(message()).await?;
+
+        assert_eq!(inner.sends(), 5);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_failed_probe_reopens_breaker() {
+        let inner = FlakyMailer::default();
+        let (mailer, clock) = breaker(&inner, 1);
+
+        inner.failing.store(true, Ordering::SeqCst);
+
+        assert!(mailer.send_email(message()).await.is_err());
+        assert_eq!(mailer.state(), CircuitState::Open);
+
+        clock.advance(Duration::seconds(30));
+
+        assert!(mailer.send_email(message()).await.is_err());
+        assert_eq!(inner.sends(), 2);
+        assert_eq!(mailer.state(), CircuitState::Open);
+
+        clock.advance(Duration::seconds(29));
+
+        assert!(mailer.send_email(message()).await.is_err());
+        assert_eq!(inner.sends(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_probe_does_not_leave_breaker_half_open() -> TestResult {
+        let inner = FlakyMailer::default();
+        let (mailer, clock) = breaker(&inner, 1);
+
+        inner.failing.store(true, Ordering::SeqCst);
+
+        assert!(mailer.send_email(message()).await.is_err());
+
+        // The probe hangs, and its future is dropped before it finishes
+        clock.advance(Duration::seconds(30));
+        inner.hanging.store(true, Ordering::SeqCst);
+
+        let probe = tokio::time::timeout(
+            std::time::Duration::from_millis(10),
+            mailer.send_email(message()),
+        )
+        .await;
+
+        assert!(probe.is_err());
+        assert_eq!(inner.sends(), 2);
+
+        // Until another cooldown has passed, sends still wait on the lost probe
+        inner.hanging.store(false, Ordering::SeqCst);
+        inner.failing.store(false, Ordering::SeqCst);
+
+        assert!(mailer.send_email(message()).await.is_err());
+        assert_eq!(inner.sends(), 2);
+
+        // After it has, another probe is let through and closes the circuit
+        clock.advance(Duration::seconds(30));
+
+        mailer.send_email(message()).await?;
+
+        assert_eq!(inner.sends(), 3);
+        assert_eq!(mailer.state(), CircuitState::Closed);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_success_resets_consecutive_failures() -> TestResult {
+        let inner = FlakyMailer::default();
+        let (mailer, _clock) = breaker(&inner, 2);
+
+        for failing in [true, false, true] {
+            inner.failing.store(failing, Ordering::SeqCst);
+            let _ = mailer.send_email(message()).await;
+        }
+
+        assert_eq!(mailer.state(), CircuitState::Closed);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rejected_recipient_is_not_a_failure() {
+        let mut inner = MockMailer::new();
+
+        inner
+            .expect_send_email()
+            .times(2)
+            .returning(|_| Err(MailerError::InvalidEmail));
+
+        let mailer = CircuitBreakerMailer::new(
+            inner,
+            CircuitBreakerConfig {
+                failure_threshold: 1,
+                cooldown_seconds: 30,
+            },
+            Arc::new(SystemClock),
+        );
+
+        for _ in 0..2 {
+            assert!(matches!(
+                mailer.send_email(message()).await,
+                Err(MailerError::InvalidEmail)
+            ));
+        }
+
+        assert_eq!(mailer.state(), CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_zero_threshold_never_opens() {
+        let inner = FlakyMailer::default();
+        let (mailer, _clock) = breaker(&inner, 0);
+
+        inner.failing.store(true, Ordering::SeqCst);
+
+        for _ in 0..10 {
+            let _ = mailer.send_email(message()).await;
+        }
+
+        assert_eq!(inner.sends(), 10);
+        assert_eq!(mailer.state(), CircuitState::Closed);
+    }
+}
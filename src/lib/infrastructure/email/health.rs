@@ -0,0 +1,144 @@
+//! SMTP health check
+
+use std::time::Duration;
+
+use axum::async_trait;
+use tokio::{net::TcpStream, time::timeout};
+use tracing::warn;
+
+use crate::{
+    domain::health::{HealthCheck, HealthStatus},
+    infrastructure::email::smtp::SMTPConfig,
+};
+
+/// How long to wait for an SMTP server to accept a connection
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A [`HealthCheck`] that the SMTP servers accept connections.
+///
+/// Up if the primary server does, degraded if only a fallback one does, and down if none do.
+#[derive(Clone, Debug)]
+pub struct SmtpHealthCheck {
+    relays: Vec<(String, u16)>,
+}
+
+impl SmtpHealthCheck {
+    /// Create a new SMTP health check of the servers in `config`
+    pub fn new(config: &SMTPConfig) -> Self {
+        Self {
+            relays: config
+                .relays()
+                .into_iter()
+                .map(|relay| (relay.host, relay.port))
+                .collect(),
+        }
+    }
+}
+
+/// Whether `host` accepts a connection on `port` within [`CONNECT_TIMEOUT`]
+async fn reachable(host: &str, port: u16) -> bool {
+    match timeout(CONNECT_TIMEOUT, TcpStream::connect((host, port))).await {
+        Ok(Ok(_)) => true,
+        Ok(Err(err)) => {
+            warn!("SMTP server {host}:{port} is unreachable: {err}");
+            false
+        }
+        Err(_) => {
+            warn!("SMTP server {host}:{port} didn't accept a connection in time");
+            false
+        }
+    }
+}
+
+#[async_trait]
+impl HealthCheck for SmtpHealthCheck {
+    fn name(&self) -> &str {
+        "smtp"
+    }
+
+    async fn check(&self) -> HealthStatus {
+        for (index, (host, port)) in self.relays.iter().enumerate() {
+            if reachable(host, *port).await {
+                return match index {
+                    0 => HealthStatus::Up,
+                    _ => HealthStatus::Degraded,
+                };
+            }
+        }
+
+        HealthStatus::Down
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+
+    use testresult::TestResult;
+    use tokio::net::TcpListener;
+
+    use crate::infrastructure::email::smtp::SmtpRelayAddress;
+
+    use super::*;
+
+    async fn listening() -> TestResult<(TcpListener, u16)> {
+        let listener = TcpListener::bind(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0)).await?;
+        let port = listener.local_addr()?.port();
+
+        Ok((listener, port))
+    }
+
+    /// A port nothing is listening on
+    async fn closed() -> TestResult<u16> {
+        let (_, port) = listening().await?;
+
+        Ok(port)
+    }
+
+    fn check(primary: u16, fallback: Option<u16>) -> SmtpHealthCheck {
+        SmtpHealthCheck::new(&SMTPConfig {
+            host: "127.0.0.1".to_string(),
+            port: primary,
+            fallback_relays: fallback
+                .map(|port| SmtpRelayAddress {
+                    host: "127.0.0.1".to_string(),
+                    port,
+                    ..SmtpRelayAddress::default()
+                })
+                .into_iter()
+                .collect(),
+            ..SMTPConfig::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_reachable_primary_is_up() -> TestResult {
+        let (_listener, port) = listening().await?;
+
+        assert_eq!(check(port, None).check().await, HealthStatus::Up);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_only_fallback_reachable_is_degraded() -> TestResult {
+        let (_listener, port) = listening().await?;
+
+        assert_eq!(
+            check(closed().await?, Some(port)).check().await,
+            HealthStatus::Degraded
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_servers_are_down() -> TestResult {
+        assert_eq!(
+            check(closed().await?, Some(closed().await?)).check().await,
+            HealthStatus::Down
+        );
+
+        Ok(())
+    }
+}
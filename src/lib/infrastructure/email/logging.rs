@@ -0,0 +1,83 @@
+//! Logging email service implementation
+
+use axum::async_trait;
+use tracing::info;
+
+use crate::domain::communication::mailer::{Mailer, MailerError, Message};
+
+/// A mailer which logs emails instead of sending them, for previewing them during development
+#[derive(Debug, Default, Clone)]
+pub struct LoggingMailer;
+
+impl LoggingMailer {
+    /// Create a new logging mailer
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl Mailer for LoggingMailer {
+    async fn send_email(&self, message: Message) -> Result<(), MailerError> {
+        let from = message
+            .from
+            .map(|from| from.to_string())
+            .unwrap_or_else(|| "(default sender)".to_string());
+
+        info!(
+            to = %message.to,
+            from,
+            subject = message.subject,
+            "email not sent (dry run)"
+        );
+        info!("HTML body:\n{}", message.html_body);
+        info!("plain text body:\n{}", message.plain_body);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use testresult::TestResult;
+
+    use crate::domain::communication::{
+        email_addresses::EmailAddress,
+        mailer::{Mailbox, Message},
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_send_email_with_empty_bodies() -> TestResult {
+        let message = Message {
+            to: EmailAddress::new("email@example.com")?,
+            from: None,
+            subject: String::new(),
+            html_body: String::new(),
+            plain_body: String::new(),
+        };
+
+        LoggingMailer::new().send_email(message).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_email_with_sender() -> TestResult {
+        let message = Message {
+            to: EmailAddress::new("email@example.com")?,
+            from: Some(Mailbox::new(
+                Some("Example".to_string()),
+                EmailAddress::new("noreply@example.com")?,
+            )),
+            subject: "Subject".to_string(),
+            html_body: "<p>Body</p>".to_string(),
+            plain_body: "Body".to_string(),
+        };
+
+        LoggingMailer::new().send_email(message).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,200 @@
+//! Access logging
+//!
+//! Every response is logged, except successful responses on quiet paths such as health checks,
+//! which would otherwise drown out everything else. Those are sampled at 1 in N instead, and
+//! errors on them are always logged.
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use clap::Parser;
+use tracing::debug;
+
+/// Access log configuration
+#[derive(Clone, Debug, PartialEq, Eq, Parser)]
+pub struct AccessLogConfig {
+    /// Paths whose successful responses are sampled rather than always logged
+    #[arg(
+        long,
+        env = "ACCESS_LOG_QUIET_PATHS",
+        value_delimiter = ',',
+        default_value = "/api/v1/uptime,/api/v1/health,/api/v1/health/ready"
+    )]
+    pub quiet_paths: Vec<String>,
+
+    /// Log 1 in this many successful responses on quiet paths, or none of them if 0
+    #[arg(long, env = "ACCESS_LOG_SAMPLE_RATE", default_value = "0")]
+    pub sample_rate: u64,
+}
+
+impl Default for AccessLogConfig {
+    fn default() -> Self {
+        Self {
+            quiet_paths: vec![
+                "/api/v1/uptime".to_string(),
+                "/api/v1/health".to_string(),
+                "/api/v1/health/ready".to_string(),
+            ],
+            sample_rate: 0,
+        }
+    }
+}
+
+/// The access log's configuration, and a count of the quiet responses it has seen
+#[derive(Clone, Debug)]
+pub struct AccessLog {
+    config: Arc<AccessLogConfig>,
+    quiet_responses: Arc<AtomicU64>,
+}
+
+impl AccessLog {
+    /// Create a new access log
+    pub fn new(config: AccessLogConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            quiet_responses: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Whether a response with `status` on `path` should be logged
+    fn should_log(&self, path: &str, status: u16) -> bool {
+        if status >= 400 || !self.config.quiet_paths.iter().any(|quiet| quiet == path) {
+            return true;
+        }
+
+        if self.config.sample_rate == 0 {
+            return false;
+        }
+
+        self.quiet_responses
+            .fetch_add(1, Ordering::Relaxed)
+            .is_multiple_of(self.config.sample_rate)
+    }
+}
+
+/// Logs the response to each request, within the request's trace span
+pub async fn access_log(State(log): State<AccessLog>, request: Request, next: Next) -> Response {
+    let path = request.uri().path().to_string();
+    let started_at = Instant::now();
+
+    let response = next.run(request).await;
+
+    let status = response.status().as_u16();
+
+    if log.should_log(&path, status) {
+        debug!(
+            status,
+            latency_ms = started_at.elapsed().as_millis() as u64,
+            "finished processing request"
+        );
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum_test::TestServer;
+    use testresult::TestResult;
+    use tracing::{
+        field::{Field, Visit},
+        Event, Subscriber,
+    };
+    use tracing_subscriber::{layer::Context, prelude::*, Layer};
+
+    use crate::infrastructure::http::{servers::https::router, state::tests::test_state};
+
+    use super::{AccessLog, AccessLogConfig};
+
+    /// Collects the status of every access log event
+    #[derive(Clone, Default)]
+    struct AccessLogStatuses(Arc<Mutex<Vec<u64>>>);
+
+    impl Visit for AccessLogStatuses {
+        fn record_u64(&mut self, field: &Field, value: u64) {
+            if field.name() == "status" {
+                self.0.lock().unwrap().push(value);
+            }
+        }
+
+        fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+    }
+
+    impl<S: Subscriber> Layer<S> for AccessLogStatuses {
+        fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+            event.record(&mut self.clone());
+        }
+    }
+
+    fn quiet(paths: &[&str], sample_rate: u64) -> AccessLog {
+        AccessLog::new(AccessLogConfig {
+            quiet_paths: paths.iter().map(|path| path.to_string()).collect(),
+            sample_rate,
+        })
+    }
+
+    #[test]
+    fn test_other_paths_are_always_logged() {
+        let log = quiet(&["/api/v1/uptime"], 0);
+
+        assert!(log.should_log("/api/v1/users", 200));
+        assert!(log.should_log("/api/v1/users", 200));
+    }
+
+    #[test]
+    fn test_errors_on_quiet_paths_are_always_logged() {
+        let log = quiet(&["/api/v1/uptime"], 0);
+
+        assert!(!log.should_log("/api/v1/uptime", 200));
+        assert!(log.should_log("/api/v1/uptime", 404));
+        assert!(log.should_log("/api/v1/uptime", 503));
+    }
+
+    #[test]
+    fn test_quiet_paths_are_sampled() {
+        let log = quiet(&["/api/v1/uptime"], 3);
+
+        let logged: Vec<bool> = (0..6)
+            .map(|_| log.should_log("/api/v1/uptime", 200))
+            .collect();
+
+        assert_eq!(logged, vec![true, false, false, true, false, false]);
+    }
+
+    #[tokio::test]
+    async fn test_quiet_path_is_only_logged_on_error() -> TestResult {
+        let statuses = AccessLogStatuses::default();
+        let _guard =
+            tracing::subscriber::set_default(tracing_subscriber::registry().with(statuses.clone()));
+
+        let mut state = test_state(None, None);
+        state.config.access_log = AccessLogConfig {
+            quiet_paths: vec!["/api/v1/uptime".to_string(), "/api/v1/users".to_string()],
+            sample_rate: 0,
+        };
+
+        let server = TestServer::new(router(state))?;
+
+        server.get("/api/v1/uptime").await.assert_status_ok();
+        server
+            .get("/api/v1/users")
+            .await
+            .assert_status_unauthorized();
+
+        assert_eq!(*statuses.0.lock().unwrap(), vec![401]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,148 @@
+//! Allowed hosts
+//!
+//! Links back to the application are built from the configured base URL, but when one deployment
+//! answers for several hosts, such as tenants' domains or preview environments, links should point
+//! back at the host the request was made to. The host comes from the client though, so it is only
+//! used if it is on the allowlist, and otherwise the configured base URL is used as before.
+
+use axum::http::uri::Authority;
+use clap::Parser;
+use url::Url;
+
+use crate::domain::base_url::BaseUrl;
+
+/// Allowed hosts configuration
+#[derive(Clone, Debug, Default, PartialEq, Eq, Parser)]
+pub struct AllowedHostsConfig {
+    /// The hosts links can point back at instead of the base URL, such as `app.example.com`, or
+    /// `*.preview.example.com` for any of its subdomains. Links always use the base URL if empty
+    #[arg(long, env = "ALLOWED_HOSTS", value_delimiter = ',')]
+    pub allowed_hosts: Vec<String>,
+}
+
+impl AllowedHostsConfig {
+    /// Whether `host`, without its port, is on the allowlist
+    pub fn is_allowed(&self, host: &str) -> bool {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+
+        self.allowed_hosts.iter().any(|allowed| {
+            let allowed = allowed.trim().to_ascii_lowercase();
+
+            match allowed.strip_prefix("*.") {
+                Some(domain) => host
+                    .strip_suffix(domain)
+                    .is_some_and(|subdomain| subdomain.len() > 1 && subdomain.ends_with('.')),
+                None => host == allowed,
+            }
+        })
+    }
+
+    /// The base URL for a request made to `host`, over `scheme` if it is known, or `configured` if
+    /// the host isn't allowed. The configured base URL's path is kept, so an application served
+    /// under a path prefix keeps it on every host.
+    pub fn base_url_for(&self, configured: &BaseUrl, host: &str, scheme: Option<&str>) -> BaseUrl {
+        self.allowed_base_url(configured, host, scheme)
+            .unwrap_or_else(|| configured.clone())
+    }
+
+    fn allowed_base_url(
+        &self,
+        configured: &BaseUrl,
+        host: &str,
+        scheme: Option<&str>,
+    ) -> Option<BaseUrl> {
+        // An authority can carry credentials, which have no place in a Host header
+        if host.contains('@') {
+            return None;
+        }
+
+        let authority = host.trim().parse::<Authority>().ok()?;
+
+        if !self.is_allowed(authority.host()) {
+            return None;
+        }
+
+        let configured = Url::parse(configured.as_str()).ok()?;
+        let scheme = match scheme.map(str::to_ascii_lowercase).as_deref() {
+            Some(scheme @ ("http" | "https")) => scheme.to_string(),
+            _ => configured.scheme().to_string(),
+        };
+
+        BaseUrl::parse(&format!("{scheme}://{authority}{}", configured.path())).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use testresult::TestResult;
+
+    use super::*;
+
+    fn allowing(hosts: &[&str]) -> AllowedHostsConfig {
+        AllowedHostsConfig {
+            allowed_hosts: hosts.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    #[test]
+    fn test_exact_and_wildcard_hosts_are_allowed() {
+        let config = allowing(&["app.example.com", "*.preview.example.com"]);
+
+        assert!(config.is_allowed("app.example.com"));
+        assert!(config.is_allowed("APP.example.com."));
+        assert!(config.is_allowed("pr-42.preview.example.com"));
+        assert!(!config.is_allowed("preview.example.com"));
+        assert!(!config.is_allowed("evilpreview.example.com"));
+        assert!(!config.is_allowed("example.com"));
+    }
+
+    #[test]
+    fn test_nothing_is_allowed_by_default() {
+        assert!(!AllowedHostsConfig::default().is_allowed("localhost"));
+    }
+
+    #[test]
+    fn test_allowed_host_replaces_configured_host() -> TestResult {
+        let configured = BaseUrl::parse("https://example.com/app")?;
+        let config = allowing(&["*.preview.example.com"]);
+
+        assert_eq!(
+            config
+                .base_url_for(&configured, "pr-42.preview.example.com:8443", None)
+                .as_str(),
+            "https://pr-42.preview.example.com:8443/app"
+        );
+        assert_eq!(
+            config
+                .base_url_for(&configured, "pr-42.preview.example.com", Some("http"))
+                .as_str(),
+            "http://pr-42.preview.example.com/app"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_disallowed_or_malformed_host_falls_back_to_configured() -> TestResult {
+        let configured = BaseUrl::parse("https://example.com")?;
+        let config = allowing(&["app.example.com"]);
+
+        for host in [
+            "evil.com",
+            "evil.com@app.example.com",
+            "app.example.com/evil",
+            "",
+        ] {
+            assert_eq!(config.base_url_for(&configured, host, None), configured);
+        }
+
+        assert_eq!(
+            config
+                .base_url_for(&configured, "app.example.com", Some("ftp"))
+                .as_str(),
+            "https://app.example.com"
+        );
+
+        Ok(())
+    }
+}
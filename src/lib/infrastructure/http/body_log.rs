@@ -0,0 +1,323 @@
+//! Request and response body logging
+//!
+//! Off by default, since even with sensitive fields redacted, bodies hold personal data. Only
+//! JSON bodies of a known length are logged, so streamed responses are passed through untouched,
+//! and bodies which can't be parsed aren't logged at all rather than risk logging a password.
+
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body, Bytes, HttpBody},
+    extract::{Request, State},
+    http::{header::CONTENT_TYPE, HeaderMap},
+    middleware::Next,
+    response::Response,
+};
+use clap::{ArgAction, Parser};
+use serde_json::Value;
+use tracing::debug;
+
+/// The replacement for the value of a redacted field
+pub const REDACTED: &str = "[redacted]";
+
+/// The largest body that is read in order to be logged. Larger bodies are passed on without being
+/// logged, rather than being held in memory
+const MAX_BUFFERED_BYTES: u64 = 1024 * 1024;
+
+/// Body logging configuration
+#[derive(Clone, Debug, PartialEq, Eq, Parser)]
+pub struct BodyLogConfig {
+    /// Whether request and response bodies are logged, for debugging
+    #[arg(
+        long = "log-bodies",
+        env = "LOG_BODIES",
+        default_value_t = false,
+        action = ArgAction::Set
+    )]
+    pub enabled: bool,
+
+    /// The most bytes of each body which are logged, after redaction
+    #[arg(
+        long = "log-body-max-bytes",
+        env = "LOG_BODY_MAX_BYTES",
+        default_value = "4096"
+    )]
+    pub max_bytes: usize,
+
+    /// The JSON fields whose values are redacted, wherever they appear in a body
+    #[arg(
+        long = "log-body-redacted-fields",
+        env = "LOG_BODY_REDACTED_FIELDS",
+        value_delimiter = ',',
+        default_value = "password,token,current_password,new_password"
+    )]
+    pub redacted_fields: Vec<String>,
+}
+
+impl Default for BodyLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_bytes: 4096,
+            redacted_fields: ["password", "token", "current_password", "new_password"]
+                .map(str::to_string)
+                .to_vec(),
+        }
+    }
+}
+
+impl BodyLogConfig {
+    /// The body as it should be logged: parsed as JSON, with the redacted fields' values replaced
+    /// and cut down to the maximum length. [`None`] if it isn't JSON
+    fn redact(&self, body: &[u8]) -> Option<String> {
+        let mut json = serde_json::from_slice::<Value>(body).ok()?;
+
+        redact_fields(&mut json, &self.redacted_fields);
+
+        let mut logged = json.to_string();
+
+        if logged.len() > self.max_bytes {
+            let mut end = self.max_bytes;
+
+            while !logged.is_char_boundary(end) {
+                end -= 1;
+            }
+
+            logged.truncate(end);
+            logged.push('…');
+        }
+
+        Some(logged)
+    }
+}
+
+/// Replaces the values of `fields` in `json` and everything nested in it
+fn redact_fields(json: &mut Value, fields: &[String]) {
+    match json {
+        Value::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                if fields.iter().any(|field| field.eq_ignore_ascii_case(key)) {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_fields(value, fields);
+                }
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                redact_fields(value, fields);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Whether a body with `headers` and `size` should be read to be logged
+fn is_loggable(headers: &HeaderMap, size: Option<u64>) -> bool {
+    let is_json = headers
+        .get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .is_some_and(|content_type| content_type.contains("json"));
+
+    is_json && size.is_some_and(|size| size <= MAX_BUFFERED_BYTES)
+}
+
+/// Reads a body to log it, returning the body to pass on in its place
+async fn log_body(
+    config: &BodyLogConfig,
+    body: Body,
+    log: impl FnOnce(&str),
+) -> Result<Body, axum::Error> {
+    let bytes: Bytes = to_bytes(body, MAX_BUFFERED_BYTES as usize).await?;
+
+    if let Some(logged) = config.redact(&bytes) {
+        log(&logged);
+    }
+
+    Ok(Body::from(bytes))
+}
+
+/// Logs the bodies of API requests and responses, if enabled
+pub async fn body_log(
+    State(config): State<Arc<BodyLogConfig>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !config.enabled || !request.uri().path().starts_with("/api/v1") {
+        return next.run(request).await;
+    }
+
+    let request = if is_loggable(request.headers(), request.body().size_hint().exact()) {
+        let (parts, body) = request.into_parts();
+
+        match log_body(&config, body, |body| debug!(body, "request body")).await {
+            Ok(body) => Request::from_parts(parts, body),
+            Err(err) => {
+                debug!("failed to read request body to log it: {err}");
+
+                Request::from_parts(parts, Body::empty())
+            }
+        }
+    } else {
+        request
+    };
+
+    let response = next.run(request).await;
+
+    if !is_loggable(response.headers(), response.body().size_hint().exact()) {
+        return response;
+    }
+
+    let status = response.status().as_u16();
+    let (parts, body) = response.into_parts();
+
+    match log_body(&config, body, |body| debug!(status, body, "response body")).await {
+        Ok(body) => Response::from_parts(parts, body),
+        Err(err) => {
+            debug!("failed to read response body to log it: {err}");
+
+            Response::from_parts(parts, Body::empty())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::http::StatusCode;
+    use axum_test::TestServer;
+    use serde_json::json;
+    use testresult::TestResult;
+    use tracing::{
+        field::{Field, Visit},
+        Event, Subscriber,
+    };
+    use tracing_subscriber::{layer::Context, prelude::*, Layer};
+
+    use crate::{
+        domain::auth::users::{tests::MockUserService, User},
+        infrastructure::http::{servers::https::router, state::tests::test_state},
+    };
+
+    use super::*;
+
+    /// Collects every logged body
+    #[derive(Clone, Default)]
+    struct LoggedBodies(Arc<Mutex<Vec<String>>>);
+
+    impl Visit for LoggedBodies {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            if field.name() == "body" {
+                self.0.lock().unwrap().push(value.to_string());
+            }
+        }
+
+        fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+    }
+
+    impl<S: Subscriber> Layer<S> for LoggedBodies {
+        fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+            event.record(&mut self.clone());
+        }
+    }
+
+    #[test]
+    fn test_sensitive_fields_are_redacted_wherever_they_are() {
+        let config = BodyLogConfig::default();
+
+        let logged = config.redact(
+            json!({
+                "email": "email@example.com",
+                "Password": "correcthorsebatterystaple",
+                "users": [{ "new_password": "hunter2", "token": 42 }],
+            })
+            .to_string()
+            .as_bytes(),
+        );
+
+        assert_eq!(
+            serde_json::from_str::<Value>(&logged.unwrap_or_default()).ok(),
+            Some(json!({
+                "email": "email@example.com",
+                "Password": REDACTED,
+                "users": [{ "new_password": REDACTED, "token": REDACTED }],
+            }))
+        );
+    }
+
+    #[test]
+    fn test_logged_body_is_capped() {
+        let config = BodyLogConfig {
+            max_bytes: 12,
+            ..BodyLogConfig::default()
+        };
+
+        assert_eq!(
+            config.redact(r#"{"name":"ééééééé"}"#.as_bytes()).as_deref(),
+            Some(r#"{"name":"é…"#)
+        );
+    }
+
+    #[test]
+    fn test_bodies_which_are_not_json_are_not_logged() {
+        assert_eq!(BodyLogConfig::default().redact(b"password=hunter2"), None);
+    }
+
+    #[tokio::test]
+    async fn test_create_user_request_is_logged_with_password_redacted() -> TestResult {
+        let logged = LoggedBodies::default();
+        let _guard =
+            tracing::subscriber::set_default(tracing_subscriber::registry().with(logged.clone()));
+
+        let mut users = MockUserService::new();
+        users
+            .expect_create_user()
+            .returning(|_, _| Ok(User::default()));
+
+        let mut state = test_state(Some(users), None);
+        state.config.body_log.enabled = true;
+
+        let request = json!({
+            "email": "email@example.com",
+            "password": "correcthorsebatterystaple",
+        });
+
+        let response = TestServer::new(router(state))?
+            .post("/api/v1/users")
+            .json(&request)
+            .await;
+
+        response.assert_status(StatusCode::CREATED);
+        assert!(response.json::<Value>()["id"].is_string());
+
+        let logged = logged.0.lock().unwrap().clone();
+
+        assert_eq!(
+            serde_json::from_str::<Value>(&logged[0])?,
+            json!({ "email": "email@example.com", "password": REDACTED })
+        );
+        assert!(logged
+            .iter()
+            .all(|body| !body.contains("correcthorsebatterystaple")));
+        assert_eq!(logged.len(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_bodies_are_not_logged_unless_enabled() -> TestResult {
+        let logged = LoggedBodies::default();
+        let _guard =
+            tracing::subscriber::set_default(tracing_subscriber::registry().with(logged.clone()));
+
+        let response = TestServer::new(router(test_state(None, None)))?
+            .get("/api/v1/auth/password-policy")
+            .await;
+
+        response.assert_status_ok();
+        assert!(logged.0.lock().unwrap().is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,327 @@
+//! Client IP addresses
+//!
+//! Behind a load balancer every connection comes from the proxy, so the client's address has to
+//! be read from the `X-Forwarded-For` or `Forwarded` header instead. Anyone can send those
+//! though, so they are only believed when the connection comes from a trusted proxy, only as far
+//! back as the chain of trusted proxies goes, and only from the one header the proxy is configured
+//! to set.
+
+use std::{
+    net::{IpAddr, Ipv6Addr, SocketAddr},
+    sync::Arc,
+};
+
+use axum::{
+    extract::ConnectInfo,
+    http::{header::FORWARDED, HeaderMap, Request},
+};
+use clap::{Parser, ValueEnum};
+use ipnet::IpNet;
+use tower_governor::{key_extractor::KeyExtractor, GovernorError};
+
+/// The `X-Forwarded-For` header
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+
+/// The header the trusted proxies forward the client's address in
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ForwardedHeader {
+    /// `X-Forwarded-For`
+    #[default]
+    XForwardedFor,
+
+    /// `Forwarded`, as standardised in RFC 7239
+    Forwarded,
+}
+
+/// Trusted proxy configuration
+#[derive(Clone, Debug, Default, PartialEq, Eq, Parser)]
+pub struct TrustedProxiesConfig {
+    /// The addresses or CIDR ranges of the proxies whose forwarded headers are trusted
+    #[arg(
+        long,
+        env = "TRUSTED_PROXIES",
+        value_delimiter = ',',
+        value_parser = parse_proxy
+    )]
+    pub trusted_proxies: Vec<IpNet>,
+
+    /// The header the trusted proxies set. The other one is ignored, since a client could send it
+    /// through the proxy untouched
+    #[arg(
+        long = "forwarded-header",
+        env = "FORWARDED_HEADER",
+        default_value = "x-forwarded-for"
+    )]
+    pub forwarded_header: ForwardedHeader,
+}
+
+/// Parses a CIDR range, or a single address as a range of one
+fn parse_proxy(value: &str) -> Result<IpNet, String> {
+    let value = value.trim();
+
+    value
+        .parse::<IpNet>()
+        .or_else(|_| value.parse::<IpAddr>().map(IpNet::from))
+        .map_err(|_| format!("invalid IP address or CIDR range: {value}"))
+}
+
+impl TrustedProxiesConfig {
+    /// Whether `ip` is one of the trusted proxies
+    pub fn is_trusted(&self, ip: &IpAddr) -> bool {
+        let ip = canonical(*ip);
+
+        self.trusted_proxies.iter().any(|proxy| proxy.contains(&ip))
+    }
+
+    /// Finds the address of the client behind a connection from `peer`.
+    ///
+    /// The forwarded addresses are walked back from the one closest to the server, and the
+    /// first which isn't a trusted proxy is the client. If the peer isn't trusted its headers
+    /// are ignored, so they can't be spoofed.
+    pub fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        if !self.is_trusted(&peer) {
+            return canonical(peer);
+        }
+
+        let forwarded = forwarded_for(headers, self.forwarded_header);
+
+        forwarded
+            .iter()
+            .rev()
+            .find(|ip| !self.is_trusted(ip))
+            .or_else(|| forwarded.first())
+            .copied()
+            .map_or(canonical(peer), canonical)
+    }
+}
+
+/// Rate limits by client IP address, behind [`TrustedProxiesConfig`]'s trusted proxies.
+///
+/// IPv6 clients are limited by their /64 network rather than their address, since they can
+/// usually pick any address within it.
+#[derive(Clone, Debug)]
+pub struct ClientIpKeyExtractor {
+    config: Arc<TrustedProxiesConfig>,
+}
+
+impl ClientIpKeyExtractor {
+    /// Create a new client IP key extractor
+    pub fn new(config: TrustedProxiesConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+        }
+    }
+}
+
+impl KeyExtractor for ClientIpKeyExtractor {
+    type Key = IpAddr;
+
+    fn extract<T>(&self, req: &Request<T>) -> Result<Self::Key, GovernorError> {
+        let peer = req
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(address)| address.ip())
+            .ok_or(GovernorError::UnableToExtractKey)?;
+
+        Ok(rate_limit_key(self.config.client_ip(peer, req.headers())))
+    }
+}
+
+/// The key a client is rate limited by: IPv4 addresses as they are, and IPv6 addresses by their
+/// /64 network
+fn rate_limit_key(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V4(_) => ip,
+        IpAddr::V6(ip) => {
+            let network = u128::from(ip) & !(u128::MAX >> 64);
+
+            IpAddr::V6(Ipv6Addr::from(network))
+        }
+    }
+}
+
+/// Treats IPv4-mapped IPv6 addresses, as dual-stack sockets report IPv4 peers, as IPv4
+fn canonical(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(ip, IpAddr::V4),
+        IpAddr::V4(_) => ip,
+    }
+}
+
+/// The forwarded addresses in the request's `header`, furthest from the server first
+fn forwarded_for(headers: &HeaderMap, header: ForwardedHeader) -> Vec<IpAddr> {
+    match header {
+        ForwardedHeader::XForwardedFor => headers
+            .get_all(X_FORWARDED_FOR)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|ip| ip.trim().parse().ok())
+            .collect(),
+        ForwardedHeader::Forwarded => headers
+            .get_all(FORWARDED)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|element| {
+                element.split(';').find_map(|pair| {
+                    let (name, value) = pair.trim().split_once('=')?;
+
+                    name.eq_ignore_ascii_case("for")
+                        .then(|| parse_node(value))
+                        .flatten()
+                })
+            })
+            .collect(),
+    }
+}
+
+/// Parses a `Forwarded` node such as `192.0.2.43`, `"192.0.2.43:4711"` or `"[2001:db8::1]:4711"`
+fn parse_node(node: &str) -> Option<IpAddr> {
+    let node = node.trim().trim_matches('"');
+
+    if let Some(rest) = node.strip_prefix('[') {
+        return rest.split_once(']')?.0.parse().ok();
+    }
+
+    node.parse()
+        .ok()
+        .or_else(|| node.split_once(':')?.0.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+    use testresult::TestResult;
+
+    use super::*;
+
+    fn config(proxies: &[&str]) -> TrustedProxiesConfig {
+        TrustedProxiesConfig {
+            trusted_proxies: proxies
+                .iter()
+                .map(|proxy| parse_proxy(proxy).unwrap())
+                .collect(),
+            forwarded_header: ForwardedHeader::XForwardedFor,
+        }
+    }
+
+    fn headers(name: &'static str, value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn test_peer_is_used_without_trusted_proxies() -> TestResult {
+        let client = config(&[]).client_ip(
+            "203.0.113.7".parse()?,
+            &headers(X_FORWARDED_FOR, "198.51.100.1"),
+        );
+
+        assert_eq!(client, "203.0.113.7".parse::<IpAddr>()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_forwarded_ip_is_used_when_peer_is_trusted() -> TestResult {
+        let config = config(&["10.0.0.0/8"]);
+
+        let client = config.client_ip(
+            "10.0.0.2".parse()?,
+            &headers(X_FORWARDED_FOR, "198.51.100.1, 10.0.0.1"),
+        );
+
+        assert_eq!(client, "198.51.100.1".parse::<IpAddr>()?);
+
+        let config = TrustedProxiesConfig {
+            forwarded_header: ForwardedHeader::Forwarded,
+            ..config
+        };
+
+        let client = config.client_ip(
+            "10.0.0.2".parse()?,
+            &headers("forwarded", r#"for="[2001:db8::1]:4711";proto=https"#),
+        );
+
+        assert_eq!(client, "2001:db8::1".parse::<IpAddr>()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_forged_x_forwarded_for_is_ignored_behind_forwarded_proxy() -> TestResult {
+        let config = TrustedProxiesConfig {
+            forwarded_header: ForwardedHeader::Forwarded,
+            ..config(&["10.0.0.0/8"])
+        };
+
+        // The proxy only sets Forwarded, passing the client's own X-Forwarded-For through as is
+        let mut headers = headers(X_FORWARDED_FOR, "192.0.2.99");
+        headers.insert(FORWARDED, HeaderValue::from_static("for=203.0.113.7"));
+
+        let client = config.client_ip("10.0.0.2".parse()?, &headers);
+
+        assert_eq!(client, "203.0.113.7".parse::<IpAddr>()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_spoofed_forwarded_ip_is_ignored_when_peer_is_untrusted() -> TestResult {
+        let client = config(&["10.0.0.0/8"]).client_ip(
+            "203.0.113.7".parse()?,
+            &headers(X_FORWARDED_FOR, "198.51.100.1"),
+        );
+
+        assert_eq!(client, "203.0.113.7".parse::<IpAddr>()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_spoofed_forwarded_ip_before_trusted_proxy_is_ignored() -> TestResult {
+        // The client sent its own X-Forwarded-For, which the trusted proxy appended to
+        let client = config(&["10.0.0.1"]).client_ip(
+            "10.0.0.1".parse()?,
+            &headers(X_FORWARDED_FOR, "192.0.2.99, 203.0.113.7"),
+        );
+
+        assert_eq!(client, "203.0.113.7".parse::<IpAddr>()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ipv4_mapped_peer_is_matched_as_ipv4() -> TestResult {
+        let client = config(&["10.0.0.0/8"]).client_ip(
+            "::ffff:10.0.0.2".parse()?,
+            &headers(X_FORWARDED_FOR, "198.51.100.1"),
+        );
+
+        assert_eq!(client, "198.51.100.1".parse::<IpAddr>()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ipv6_clients_are_keyed_by_network() -> TestResult {
+        assert_eq!(
+            rate_limit_key("2001:db8:1:2:3:4:5:6".parse()?),
+            "2001:db8:1:2::".parse::<IpAddr>()?
+        );
+        assert_eq!(
+            rate_limit_key("203.0.113.7".parse()?),
+            "203.0.113.7".parse::<IpAddr>()?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_proxy_is_rejected() {
+        assert!(parse_proxy("10.0.0.0/33").is_err());
+        assert!(parse_proxy("proxy.example.com").is_err());
+    }
+}
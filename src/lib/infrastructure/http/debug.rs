@@ -0,0 +1,108 @@
+//! Debugging aids for development and automated tests
+//!
+//! These leak things which must never leave a production server, so they are refused outright
+//! when `APP_ENV` is `production`.
+
+use clap::{ArgAction, Parser};
+use thiserror::Error;
+
+/// The `APP_ENV` marking a production server
+pub const PRODUCTION_ENVIRONMENT: &str = "production";
+
+/// Errors in the debugging configuration
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum DebugConfigError {
+    /// Confirmation links were to be exposed on a production server
+    #[error("EXPOSE_CONFIRMATION_LINKS can't be enabled when APP_ENV is production")]
+    ConfirmationLinksExposedInProduction,
+}
+
+/// Debugging configuration
+#[derive(Clone, Debug, PartialEq, Eq, Parser)]
+pub struct DebugConfig {
+    /// The environment the server is running in
+    #[arg(long = "app-env", env = "APP_ENV", default_value = "development")]
+    pub environment: String,
+
+    /// Whether email confirmation responses include the confirmation link, so it can be followed
+    /// without reading the email
+    #[arg(
+        long,
+        env = "EXPOSE_CONFIRMATION_LINKS",
+        default_value_t = false,
+        action = ArgAction::Set
+    )]
+    pub expose_confirmation_links: bool,
+}
+
+impl Default for DebugConfig {
+    fn default() -> Self {
+        Self {
+            environment: "development".to_string(),
+            expose_confirmation_links: false,
+        }
+    }
+}
+
+impl DebugConfig {
+    /// Whether the server is running in production
+    pub fn is_production(&self) -> bool {
+        self.environment
+            .trim()
+            .eq_ignore_ascii_case(PRODUCTION_ENVIRONMENT)
+    }
+
+    /// Checks no debugging aid is enabled in production
+    pub fn validate(&self) -> Result<(), DebugConfigError> {
+        if self.expose_confirmation_links && self.is_production() {
+            return Err(DebugConfigError::ConfirmationLinksExposedInProduction);
+        }
+
+        Ok(())
+    }
+
+    /// Whether confirmation links should be included in responses, which they never are in
+    /// production, whatever the configuration
+    pub fn exposes_confirmation_links(&self) -> bool {
+        self.expose_confirmation_links && !self.is_production()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(environment: &str, expose_confirmation_links: bool) -> DebugConfig {
+        DebugConfig {
+            environment: environment.to_string(),
+            expose_confirmation_links,
+        }
+    }
+
+    #[test]
+    fn test_confirmation_links_exposed_outside_production() {
+        let config = config("development", true);
+
+        assert_eq!(config.validate(), Ok(()));
+        assert!(config.exposes_confirmation_links());
+    }
+
+    #[test]
+    fn test_confirmation_links_refused_in_production() {
+        let config = config("Production", true);
+
+        assert_eq!(
+            config.validate(),
+            Err(DebugConfigError::ConfirmationLinksExposedInProduction)
+        );
+        assert!(!config.exposes_confirmation_links());
+    }
+
+    #[test]
+    fn test_production_without_debugging_aids_is_valid() {
+        let config = config("production", false);
+
+        assert_eq!(config.validate(), Ok(()));
+        assert!(!config.exposes_confirmation_links());
+    }
+}
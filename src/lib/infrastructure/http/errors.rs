@@ -13,14 +13,25 @@ use tracing::{debug, error};
 use utoipa::ToSchema;
 
 use crate::domain::{
-    auth::users::{
-        errors::{CreateUserError, GetUserByIdError, UpdateUserError},
-        PasswordError,
+    auth::{
+        tokens::AccessTokenError,
+        users::{
+            errors::{
+                CreateUserError, DeleteUserError, EmailExistsError, GetUserByIdError,
+                ListUsersError, UpdateUserError,
+            },
+            PasswordError, UsernameError,
+        },
     },
     communication::email_addresses::{EmailAddressError, EmailConfirmationError},
+    health::HealthError,
+    i18n::Locale,
+    idempotency::IdempotencyError,
 };
 
+use super::extractors::accept::ResponseFormat;
 use super::templates::errors::{
+    email_address_in_use::EmailAddressInUseErrorTemplate,
     internal_server_error::InternalServerErrorTemplate, not_found::NotFoundErrorTemplate,
     unprocessable_entity::UnprocessableEntityErrorTemplate,
 };
@@ -31,6 +42,156 @@ pub struct ErrorResponse {
     /// The error message
     #[schema(example = "Internal server error")]
     pub error: String,
+
+    /// A machine-readable error code, if there is one
+    #[schema(example = "forbidden")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+
+    /// The request fields which failed validation, if any did
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub validation_errors: Vec<ValidationError>,
+}
+
+/// A request field which failed validation
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+pub struct ValidationError {
+    /// The name of the field in the request body
+    #[schema(example = "password")]
+    pub field: String,
+
+    /// A machine-readable code for why the field failed
+    #[schema(example = "too_short")]
+    pub code: String,
+
+    /// Why the field failed
+    #[schema(example = "Password must be at least 8 characters long")]
+    pub message: String,
+
+    /// Suggestions for fixing the field, such as how to make a weak password stronger, which UIs
+    /// can list separately from the message
+    #[schema(example = json!(["Add another word or two. Uncommon words are better."]))]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suggestions: Vec<String>,
+}
+
+impl ValidationError {
+    /// The validation error for `field` failing with `err`
+    pub fn new(field: &str, err: &impl FieldError) -> Self {
+        Self {
+            field: field.to_string(),
+            code: err.code().to_string(),
+            message: err.message(),
+            suggestions: err.suggestions(),
+        }
+    }
+}
+
+/// An error in a single request field
+pub trait FieldError {
+    /// A machine-readable code for the error
+    fn code(&self) -> &'static str;
+
+    /// The message shown for the error
+    fn message(&self) -> String;
+
+    /// Suggestions for fixing the field, if there are any
+    fn suggestions(&self) -> Vec<String> {
+        vec![]
+    }
+}
+
+impl FieldError for EmailAddressError {
+    fn code(&self) -> &'static str {
+        match self {
+            EmailAddressError::EmptyEmailAddress => "empty",
+            EmailAddressError::InvalidEmailAddress => "invalid",
+            EmailAddressError::UndeliverableEmailAddress => "undeliverable",
+            EmailAddressError::DomainNotAllowed => "domain_not_allowed",
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            EmailAddressError::EmptyEmailAddress => "Please provide an email address".to_string(),
+            EmailAddressError::InvalidEmailAddress => {
+                "Please provide a valid email address".to_string()
+            }
+            EmailAddressError::UndeliverableEmailAddress => {
+                "Please provide an email address which can receive email".to_string()
+            }
+            EmailAddressError::DomainNotAllowed => {
+                "Email addresses from this domain can't be used to sign up".to_string()
+            }
+        }
+    }
+}
+
+impl FieldError for PasswordError {
+    fn code(&self) -> &'static str {
+        match self {
+            PasswordError::TooShort(_) => "too_short",
+            PasswordError::TooLong(_) => "too_long",
+            PasswordError::MissingCharacterClasses(_) => "missing_character_classes",
+            PasswordError::TooWeak(_) => "too_weak",
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            PasswordError::TooShort(min_length) => {
+                format!("Password must be at least {min_length} characters long")
+            }
+            PasswordError::TooLong(max_length) => {
+                format!("Password must be at most {max_length} bytes long")
+            }
+            PasswordError::MissingCharacterClasses(classes) => format!(
+                "Password must contain at least one of each: {}",
+                classes
+                    .iter()
+                    .map(|class| class.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            PasswordError::TooWeak(suggestions) => {
+                format!("Password is too weak: {}", suggestions.join(" "))
+            }
+        }
+    }
+
+    fn suggestions(&self) -> Vec<String> {
+        match self {
+            PasswordError::TooWeak(suggestions) => suggestions.clone(),
+            _ => vec![],
+        }
+    }
+}
+
+impl FieldError for UsernameError {
+    fn code(&self) -> &'static str {
+        match self {
+            UsernameError::TooShort(_) => "too_short",
+            UsernameError::TooLong(_) => "too_long",
+            UsernameError::InvalidCharacters => "invalid_characters",
+            UsernameError::Reserved => "reserved",
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            UsernameError::TooShort(min_length) => {
+                format!("Username must be at least {min_length} characters long")
+            }
+            UsernameError::TooLong(max_length) => {
+                format!("Username must be at most {max_length} characters long")
+            }
+            UsernameError::InvalidCharacters => {
+                "Username may only contain lowercase letters, digits, underscores and hyphens"
+                    .to_string()
+            }
+            UsernameError::Reserved => "That username is reserved".to_string(),
+        }
+    }
 }
 
 /// An error raised in the API
@@ -44,6 +205,14 @@ pub struct ApiError {
     /// The error message
     #[schema(example = "Internal server error")]
     pub message: String,
+
+    /// A machine-readable error code, if there is one
+    #[schema(example = "forbidden")]
+    #[serde(default)]
+    pub code: Option<String>,
+    /// The request fields which failed validation, if any did
+    #[serde(default)]
+    pub validation_errors: Vec<ValidationError>,
 }
 
 impl ApiError {
@@ -52,6 +221,28 @@ impl ApiError {
         Self {
             status,
             message: message.to_string(),
+            code: None,
+            validation_errors: vec![],
+        }
+    }
+
+    /// Create a new unauthorized error
+    pub fn new_401(message: &str) -> Self {
+        Self {
+            status: StatusCode::UNAUTHORIZED,
+            message: message.to_string(),
+            code: None,
+            validation_errors: vec![],
+        }
+    }
+
+    /// Create a new forbidden error
+    pub fn new_403(message: &str) -> Self {
+        Self {
+            status: StatusCode::FORBIDDEN,
+            message: message.to_string(),
+            code: Some("forbidden".to_string()),
+            validation_errors: vec![],
         }
     }
 
@@ -59,6 +250,8 @@ impl ApiError {
         Self {
             status: StatusCode::NOT_FOUND,
             message: message.to_string(),
+            code: None,
+            validation_errors: vec![],
         }
     }
 
@@ -66,6 +259,18 @@ impl ApiError {
         Self {
             status: StatusCode::CONFLICT,
             message: message.to_string(),
+            code: None,
+            validation_errors: vec![],
+        }
+    }
+
+    /// Create a new unsupported media type error
+    pub fn new_415(message: &str) -> Self {
+        Self {
+            status: StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            message: message.to_string(),
+            code: Some("unsupported_media_type".to_string()),
+            validation_errors: vec![],
         }
     }
 
@@ -74,6 +279,23 @@ impl ApiError {
         Self {
             status: StatusCode::UNPROCESSABLE_ENTITY,
             message: message.to_string(),
+            code: None,
+            validation_errors: vec![],
+        }
+    }
+
+    /// Create a new unprocessable entity error for request fields which failed validation, whose
+    /// messages are joined into the summary
+    pub fn new_validation(validation_errors: Vec<ValidationError>) -> Self {
+        Self {
+            status: StatusCode::UNPROCESSABLE_ENTITY,
+            message: validation_errors
+                .iter()
+                .map(|err| err.message.as_str())
+                .collect::<Vec<&str>>()
+                .join("; "),
+            code: Some("validation_failed".to_string()),
+            validation_errors,
         }
     }
 
@@ -82,6 +304,8 @@ impl ApiError {
         Self {
             status: StatusCode::INTERNAL_SERVER_ERROR,
             message: message.to_string(),
+            code: None,
+            validation_errors: vec![],
         }
     }
 }
@@ -92,54 +316,129 @@ impl fmt::Display for ApiError {
     }
 }
 
+impl From<ApiError> for ErrorResponse {
+    fn from(err: ApiError) -> Self {
+        Self {
+            error: err.message,
+            code: err.code,
+            validation_errors: err.validation_errors,
+        }
+    }
+}
+
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
-        (
-            self.status,
-            Json(ErrorResponse {
-                error: self.message,
-            }),
-        )
+        (self.status, Json(ErrorResponse::from(self))).into_response()
+    }
+}
+
+/// An error shown to a browser as a page in the requester's locale, or to an API client which
+/// asked for JSON as an [`ErrorResponse`]
+#[derive(Debug)]
+pub struct LocalizedError<E> {
+    /// The error
+    pub error: E,
+
+    /// The locale the page is written in
+    pub locale: Locale,
+
+    /// Whether the error is shown as a page or as JSON
+    pub format: ResponseFormat,
+}
+
+impl<E> LocalizedError<E> {
+    /// Create a new localized error, shown as a page
+    pub fn new(error: E, locale: Locale) -> Self {
+        Self {
+            error,
+            locale,
+            format: ResponseFormat::Html,
+        }
+    }
+
+    /// Show the error in `format` instead
+    pub fn with_format(mut self, format: ResponseFormat) -> Self {
+        self.format = format;
+        self
+    }
+}
+
+impl<E: Into<ApiError>> LocalizedError<E> {
+    /// Responds with the error as JSON if that's what was asked for, or otherwise with the page
+    /// `page` renders for it
+    fn negotiate(self, page: impl FnOnce(E, Locale) -> Response) -> Response {
+        match self.format {
+            ResponseFormat::Json => self.error.into().into_response(),
+            ResponseFormat::Html => page(self.error, self.locale),
+        }
+    }
+}
+
+impl IntoResponse for LocalizedError<EmailConfirmationError> {
+    fn into_response(self) -> Response {
+        self.negotiate(|error, locale| {
+            match error {
+                EmailConfirmationError::UserNotFound => (
+                    StatusCode::NOT_FOUND,
+                    NotFoundErrorTemplate { locale }.into_response(),
+                ),
+                EmailConfirmationError::ConfirmationTokenExpired
+                | EmailConfirmationError::ConfirmationTokenMismatch
+                | EmailConfirmationError::InvalidRecipient => (
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    UnprocessableEntityErrorTemplate { locale }.into_response(),
+                ),
+                EmailConfirmationError::EmailAlreadyConfirmed
+                | EmailConfirmationError::NoPendingEmailChange
+                | EmailConfirmationError::Conflict => (
+                    StatusCode::CONFLICT,
+                    UnprocessableEntityErrorTemplate { locale }.into_response(),
+                ),
+                EmailConfirmationError::EmailAddressInUse => (
+                    StatusCode::CONFLICT,
+                    EmailAddressInUseErrorTemplate { locale }.into_response(),
+                ),
+                EmailConfirmationError::MailerUnavailable => (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    InternalServerErrorTemplate { locale }.into_response(),
+                ),
+                _ => (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    InternalServerErrorTemplate { locale }.into_response(),
+                ),
+            }
             .into_response()
+        })
     }
 }
 
 impl IntoResponse for EmailConfirmationError {
     fn into_response(self) -> Response {
-        match self {
-            EmailConfirmationError::UserNotFound => {
-                (StatusCode::NOT_FOUND, NotFoundErrorTemplate.into_response())
+        LocalizedError::new(self, Locale::default()).into_response()
+    }
+}
+
+impl IntoResponse for LocalizedError<GetUserByIdError> {
+    fn into_response(self) -> Response {
+        self.negotiate(|error, locale| {
+            match error {
+                GetUserByIdError::UserNotFound => (
+                    StatusCode::NOT_FOUND,
+                    NotFoundErrorTemplate { locale }.into_response(),
+                ),
+                GetUserByIdError::UnknownError(_) => (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    InternalServerErrorTemplate { locale }.into_response(),
+                ),
             }
-            EmailConfirmationError::ConfirmationTokenExpired
-            | EmailConfirmationError::ConfirmationTokenMismatch => (
-                StatusCode::UNPROCESSABLE_ENTITY,
-                UnprocessableEntityErrorTemplate.into_response(),
-            ),
-            EmailConfirmationError::EmailAlreadyConfirmed => (
-                StatusCode::CONFLICT,
-                UnprocessableEntityErrorTemplate.into_response(),
-            ),
-            _ => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                InternalServerErrorTemplate.into_response(),
-            ),
-        }
-        .into_response()
+            .into_response()
+        })
     }
 }
 
 impl IntoResponse for GetUserByIdError {
     fn into_response(self) -> Response {
-        match self {
-            GetUserByIdError::UserNotFound => {
-                (StatusCode::NOT_FOUND, NotFoundErrorTemplate.into_response())
-            }
-            GetUserByIdError::UnknownError(_) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                InternalServerErrorTemplate.into_response(),
-            ),
-        }
-        .into_response()
+        LocalizedError::new(self, Locale::default()).into_response()
     }
 }
 
@@ -150,6 +449,8 @@ impl From<anyhow::Error> for ApiError {
         ApiError {
             status: StatusCode::INTERNAL_SERVER_ERROR,
             message: err.to_string(),
+            code: None,
+            validation_errors: vec![],
         }
     }
 }
@@ -158,14 +459,7 @@ impl From<EmailAddressError> for ApiError {
     fn from(err: EmailAddressError) -> Self {
         debug!("EmailAddressError -> ApiError");
 
-        match err {
-            EmailAddressError::EmptyEmailAddress => {
-                ApiError::new_422("Please provide an email address")
-            }
-            EmailAddressError::InvalidEmailAddress => {
-                ApiError::new_422("Please provide a valid email address")
-            }
-        }
+        ApiError::new_422(&err.message())
     }
 }
 
@@ -174,10 +468,17 @@ impl From<EmailConfirmationError> for ApiError {
         debug!("EmailConfirmationError {:#?} -> ApiError", err);
 
         match err {
-            EmailConfirmationError::UserNotFound => ApiError::new_404(&format!("User not found")),
+            EmailConfirmationError::UserNotFound => ApiError::new_404("User not found"),
             EmailConfirmationError::CouldNotSendEmail => {
                 ApiError::new_500("Could not send email confirmation email")
             }
+            EmailConfirmationError::InvalidRecipient => {
+                ApiError::new_422("That email address can't receive email")
+            }
+            EmailConfirmationError::MailerUnavailable => ApiError::new(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Email could not be sent, please try again later",
+            ),
             EmailConfirmationError::EmailAlreadyConfirmed => {
                 ApiError::new_409("Email is already confirmed")
             }
@@ -190,6 +491,10 @@ impl From<EmailConfirmationError> for ApiError {
             EmailConfirmationError::EmailAddressInUse => {
                 ApiError::new_409("Email is already in use")
             }
+            EmailConfirmationError::NoPendingEmailChange => {
+                ApiError::new_409("No email change is pending")
+            }
+            EmailConfirmationError::Conflict => version_conflict(),
             EmailConfirmationError::UnknownError(err) => unknown_error(Some(err.to_string())),
         }
     }
@@ -199,28 +504,55 @@ impl From<PasswordError> for ApiError {
     fn from(err: PasswordError) -> Self {
         debug!("PasswordError -> ApiError");
 
+        ApiError::new_422(&err.message())
+    }
+}
+
+impl From<IdempotencyError> for ApiError {
+    fn from(err: IdempotencyError) -> Self {
+        debug!("IdempotencyError -> ApiError");
+
         match err {
-            PasswordError::TooShort => {
-                ApiError::new_422("Password must be at least 8 characters long")
-            }
-            PasswordError::TooLong => {
-                ApiError::new_422("Password must be at most 100 characters long")
-            }
-            PasswordError::TooWeak(suggestions) => {
-                ApiError::new_422(&format!("Password is too weak: {}", suggestions.join(" ")))
+            IdempotencyError::UnknownError(err) => unknown_error(Some(err.to_string())),
+        }
+    }
+}
+
+impl From<HealthError> for ApiError {
+    fn from(err: HealthError) -> Self {
+        debug!("HealthError -> ApiError");
+
+        match err {
+            HealthError::UnknownError(err) => {
+                error!("database health check failed: {:?}", err);
+
+                ApiError::new(StatusCode::SERVICE_UNAVAILABLE, "Database is unavailable")
             }
         }
     }
 }
 
+impl From<UsernameError> for ApiError {
+    fn from(err: UsernameError) -> Self {
+        debug!("UsernameError -> ApiError");
+
+        ApiError::new_422(&err.message())
+    }
+}
+
 impl From<CreateUserError> for ApiError {
     fn from(err: CreateUserError) -> Self {
         debug!("CreateUserError -> ApiError");
 
         match err {
-            CreateUserError::DuplicateUser => {
-                ApiError::new_409("User already exists with that email address")
-            }
+            CreateUserError::DuplicateUser => ApiError {
+                code: Some("duplicate_email".to_string()),
+                ..ApiError::new_409("User already exists with that email address")
+            },
+            CreateUserError::DuplicateUsername => ApiError {
+                code: Some("duplicate_username".to_string()),
+                ..ApiError::new_409("User already exists with that username")
+            },
             CreateUserError::UnknownError(err) => unknown_error(Some(err.to_string())),
         }
     }
@@ -231,9 +563,16 @@ impl From<UpdateUserError> for ApiError {
         debug!("UpdateUserError -> ApiError");
 
         match err {
-            UpdateUserError::UserNotFound => ApiError::new_404(&format!("User not found")),
+            UpdateUserError::UserNotFound => ApiError::new_404("User not found"),
             UpdateUserError::UnknownError(err) => unknown_error(Some(err.to_string())),
             UpdateUserError::EmailAddressInUse => ApiError::new_409("Email is already in use"),
+            UpdateUserError::ConfirmationTokenMismatch => {
+                ApiError::new_422("Confirmation token does not match")
+            }
+            UpdateUserError::NoPendingEmailChange => {
+                ApiError::new_409("No email change is pending")
+            }
+            UpdateUserError::Conflict => version_conflict(),
         }
     }
 }
@@ -243,17 +582,78 @@ impl From<GetUserByIdError> for ApiError {
         debug!("GetUserByIdError -> ApiError");
 
         match err {
-            GetUserByIdError::UserNotFound => ApiError::new_404(&format!("User not found")),
+            GetUserByIdError::UserNotFound => ApiError::new_404("User not found"),
             GetUserByIdError::UnknownError(err) => unknown_error(Some(err.to_string())),
         }
     }
 }
 
+impl From<ListUsersError> for ApiError {
+    fn from(err: ListUsersError) -> Self {
+        debug!("ListUsersError -> ApiError");
+
+        match err {
+            ListUsersError::UnknownError(err) => unknown_error(Some(err.to_string())),
+        }
+    }
+}
+
+impl From<EmailExistsError> for ApiError {
+    fn from(err: EmailExistsError) -> Self {
+        debug!("EmailExistsError -> ApiError");
+
+        match err {
+            EmailExistsError::UnknownError(err) => unknown_error(Some(err.to_string())),
+        }
+    }
+}
+
+impl From<DeleteUserError> for ApiError {
+    fn from(err: DeleteUserError) -> Self {
+        debug!("DeleteUserError -> ApiError");
+
+        match err {
+            DeleteUserError::UserNotFound => ApiError::new_404("User not found"),
+            DeleteUserError::UnknownError(err) => unknown_error(Some(err.to_string())),
+        }
+    }
+}
+
+impl From<AccessTokenError> for ApiError {
+    fn from(err: AccessTokenError) -> Self {
+        debug!("AccessTokenError -> ApiError");
+
+        match err {
+            AccessTokenError::Expired => ApiError::new_401("Access token has expired"),
+            AccessTokenError::Invalid => ApiError::new_401("Access token is invalid"),
+            AccessTokenError::UnknownError(err) => unknown_error(Some(err.to_string())),
+        }
+    }
+}
+
 impl From<JsonRejection> for ApiError {
     fn from(rejection: JsonRejection) -> Self {
         debug!("JsonRejection -> ApiError");
 
-        ApiError::new(rejection.status(), &rejection.body_text())
+        match rejection {
+            JsonRejection::MissingJsonContentType(_) => ApiError::new_415(
+                "Expected a request body with the `Content-Type: application/json` header",
+            ),
+            JsonRejection::JsonSyntaxError(_) => ApiError {
+                code: Some("malformed_json".to_string()),
+                ..ApiError::new(rejection.status(), &rejection.body_text())
+            },
+            _ => ApiError::new(rejection.status(), &rejection.body_text()),
+        }
+    }
+}
+
+fn version_conflict() -> ApiError {
+    ApiError {
+        code: Some("version_conflict".to_string()),
+        ..ApiError::new_409(
+            "User has been updated since it was read, please fetch it and try again",
+        )
     }
 }
 
@@ -265,8 +665,6 @@ fn unknown_error(message: Option<String>) -> ApiError {
 
 #[cfg(test)]
 mod tests {
-    use std::usize;
-
     use anyhow::anyhow;
     use axum::{body::to_bytes, http::StatusCode, response::IntoResponse};
     use testresult::TestResult;
@@ -278,6 +676,8 @@ mod tests {
         let error = ApiError {
             status: StatusCode::INTERNAL_SERVER_ERROR,
             message: "Internal server error".to_string(),
+            code: None,
+            validation_errors: vec![],
         };
 
         let response = error.into_response();
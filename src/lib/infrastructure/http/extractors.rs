@@ -0,0 +1,13 @@
+//! Request extractors
+
+pub mod accept;
+pub mod accept_language;
+pub mod auth_user;
+pub mod client_origin;
+pub mod current_user;
+pub mod if_match;
+pub mod if_none_match;
+pub mod json;
+pub mod request_base_url;
+pub mod require_role;
+pub mod validated_json;
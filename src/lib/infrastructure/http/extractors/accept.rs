@@ -0,0 +1,118 @@
+//! Accept extractor
+
+use std::convert::Infallible;
+
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header::ACCEPT, request::Parts},
+};
+
+/// How a response is represented
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ResponseFormat {
+    /// An HTML page, for browsers
+    #[default]
+    Html,
+
+    /// JSON, for API clients
+    Json,
+}
+
+impl ResponseFormat {
+    /// The format an `Accept` header such as `application/json, text/html;q=0.9` prefers. HTML
+    /// wins ties, so browsers, which accept anything, are still shown pages.
+    pub fn from_accept(header: &str) -> Self {
+        let mut html: f32 = 0.0;
+        let mut json: f32 = 0.0;
+
+        for range in header.split(',') {
+            let mut params = range.split(';');
+            let media_type = params
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_ascii_lowercase();
+
+            let quality = params
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|quality| quality.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+
+            match media_type.as_str() {
+                "text/html" | "text/*" => html = html.max(quality),
+                "application/json" | "application/*" => json = json.max(quality),
+                "*/*" => {
+                    html = html.max(quality);
+                    json = json.max(quality);
+                }
+                _ => {}
+            }
+        }
+
+        if json > html {
+            Self::Json
+        } else {
+            Self::Html
+        }
+    }
+}
+
+/// The [`ResponseFormat`] the request's `Accept` header prefers, or HTML if it doesn't have one
+#[derive(Debug)]
+pub struct PreferredFormat(pub ResponseFormat);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for PreferredFormat
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let format = parts
+            .headers
+            .get(ACCEPT)
+            .and_then(|value| value.to_str().ok())
+            .map(ResponseFormat::from_accept)
+            .unwrap_or_default();
+
+        Ok(Self(format))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ResponseFormat;
+
+    #[test]
+    fn test_json_is_preferred_when_asked_for() {
+        assert_eq!(
+            ResponseFormat::from_accept("application/json"),
+            ResponseFormat::Json
+        );
+        assert_eq!(
+            ResponseFormat::from_accept("text/html;q=0.5, application/json"),
+            ResponseFormat::Json
+        );
+        assert_eq!(
+            ResponseFormat::from_accept("application/json, */*;q=0.1"),
+            ResponseFormat::Json
+        );
+    }
+
+    #[test]
+    fn test_html_is_preferred_by_browsers() {
+        assert_eq!(
+            ResponseFormat::from_accept(
+                "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8"
+            ),
+            ResponseFormat::Html
+        );
+        assert_eq!(ResponseFormat::from_accept("*/*"), ResponseFormat::Html);
+        assert_eq!(
+            ResponseFormat::from_accept("text/plain"),
+            ResponseFormat::Html
+        );
+    }
+}
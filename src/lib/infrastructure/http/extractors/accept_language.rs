@@ -0,0 +1,35 @@
+//! Accept-Language extractor
+
+use std::convert::Infallible;
+
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header::ACCEPT_LANGUAGE, request::Parts},
+};
+
+use crate::domain::i18n::Locale;
+
+/// The [`Locale`] the request's `Accept-Language` header prefers, or the default if it doesn't
+/// have one or doesn't accept any there are translations for
+#[derive(Debug)]
+pub struct AcceptLanguage(pub Locale);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for AcceptLanguage
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let locale = parts
+            .headers
+            .get(ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok())
+            .map(Locale::from_accept_language)
+            .unwrap_or_default();
+
+        Ok(Self(locale))
+    }
+}
@@ -0,0 +1,52 @@
+//! Authenticated user extractor
+
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header::AUTHORIZATION, request::Parts},
+};
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        auth::{tokens::validate_access_token, users::UserService},
+        communication::email_addresses::EmailAddressService,
+        health::DatabaseHealth,
+        idempotency::IdempotencyStore,
+    },
+    infrastructure::http::{errors::ApiError, state::AppState},
+};
+
+/// The user authenticated by the request's `Authorization: Bearer` access token
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuthUser {
+    /// The authenticated user's ID
+    pub id: Uuid,
+}
+
+#[async_trait]
+impl<U, E, I, H> FromRequestParts<AppState<U, E, I, H>> for AuthUser
+where
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState<U, E, I, H>,
+    ) -> Result<Self, Self::Rejection> {
+        let token = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| ApiError::new_401("Missing bearer token"))?;
+
+        let claims = validate_access_token(&state.config.auth, token.trim())?;
+
+        Ok(Self { id: claims.sub })
+    }
+}
@@ -0,0 +1,63 @@
+//! Client origin extractor
+
+use std::{convert::Infallible, net::SocketAddr};
+
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts},
+    http::{header::USER_AGENT, request::Parts},
+};
+
+use crate::{
+    domain::{
+        auth::users::UserService,
+        communication::email_addresses::{EmailAddressService, RequestOrigin},
+        health::DatabaseHealth,
+        idempotency::IdempotencyStore,
+    },
+    infrastructure::http::state::AppState,
+};
+
+/// The most characters of a `User-Agent` kept, so a client can't store arbitrarily long ones
+const MAX_USER_AGENT_CHARS: usize = 512;
+
+/// The client the request came from: its IP address, read from the forwarded headers behind a
+/// trusted proxy, and its `User-Agent`
+#[derive(Debug)]
+pub struct ClientOrigin(pub RequestOrigin);
+
+#[async_trait]
+impl<U, E, I, H> FromRequestParts<AppState<U, E, I, H>> for ClientOrigin
+where
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState<U, E, I, H>,
+    ) -> Result<Self, Self::Rejection> {
+        let ip = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(peer)| {
+                state
+                    .config
+                    .trusted_proxies
+                    .client_ip(peer.ip(), &parts.headers)
+            });
+
+        let user_agent = parts
+            .headers
+            .get(USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(|value| value.chars().take(MAX_USER_AGENT_CHARS).collect());
+
+        Ok(Self(RequestOrigin { ip, user_agent }))
+    }
+}
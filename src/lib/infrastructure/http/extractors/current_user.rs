@@ -0,0 +1,80 @@
+//! Current user extractor
+
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header::COOKIE, request::Parts},
+};
+
+use crate::{
+    domain::{
+        auth::{
+            tokens::validate_access_token,
+            users::{errors::GetUserByIdError, User, UserService},
+        },
+        communication::email_addresses::EmailAddressService,
+        health::DatabaseHealth,
+        idempotency::IdempotencyStore,
+    },
+    infrastructure::http::{errors::ApiError, extractors::auth_user::AuthUser, state::AppState},
+};
+
+/// The name of the cookie holding a session's access token
+pub const SESSION_COOKIE_NAME: &str = "session";
+
+/// The authenticated [`User`], whichever way the client authenticated.
+///
+/// The session cookie is tried first, then the `Authorization: Bearer` access token, so
+/// handlers don't need to care which one a client uses. Rejects with 401 only if neither is
+/// valid, or if the token's user no longer exists.
+#[derive(Debug)]
+pub struct CurrentUser(pub User);
+
+#[async_trait]
+impl<U, E, I, H> FromRequestParts<AppState<U, E, I, H>> for CurrentUser
+where
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState<U, E, I, H>,
+    ) -> Result<Self, Self::Rejection> {
+        let session_user_id = session_cookie(parts)
+            .and_then(|token| validate_access_token(&state.config.auth, token).ok())
+            .map(|claims| claims.sub);
+
+        let user_id = match session_user_id {
+            Some(user_id) => user_id,
+            None => AuthUser::from_request_parts(parts, state).await?.id,
+        };
+
+        let user = state
+            .users
+            .get_user_by_id(&user_id)
+            .await
+            .map_err(|err| match err {
+                GetUserByIdError::UserNotFound => ApiError::new_401("Access token is invalid"),
+                err => err.into(),
+            })?;
+
+        Ok(Self(user))
+    }
+}
+
+/// Returns the value of the session cookie, if the request has one
+fn session_cookie(parts: &Parts) -> Option<&str> {
+    parts
+        .headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(name, _)| *name == SESSION_COOKIE_NAME)
+        .map(|(_, value)| value)
+}
@@ -0,0 +1,108 @@
+//! If-Match extractor
+
+use std::convert::Infallible;
+
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header::IF_MATCH, request::Parts},
+};
+
+/// The entity tag for a resource at `version`. It is weak, as compression sends different bytes
+/// for the same version under it.
+pub fn etag(version: i32) -> String {
+    format!("W/\"{version}\"")
+}
+
+/// The entity tag for a resource at `version` with only `fields` of it sent, which differs from
+/// the full resource's so neither is mistaken for the other when cached. The fields are joined
+/// with `+`, as commas separate the tags in conditional headers.
+pub fn sparse_etag(version: i32, fields: &[&str]) -> String {
+    format!("W/\"{version};{}\"", fields.join("+"))
+}
+
+/// The opaque part of an entity tag, without its weak indicator
+pub(crate) fn opaque_tag(tag: &str) -> &str {
+    tag.strip_prefix("W/").unwrap_or(tag)
+}
+
+/// The version of the resource an entity tag we issued is for, whichever fields it covers
+fn tag_version(tag: &str) -> Option<i32> {
+    opaque_tag(tag)
+        .strip_prefix('"')?
+        .strip_suffix('"')?
+        .split(';')
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// The entity tags in the request's `If-Match` header, if it has one, which the resource being
+/// updated must still match
+#[derive(Debug, Default)]
+pub struct IfMatch(Option<String>);
+
+impl IfMatch {
+    /// Whether a resource at `version` matches, which it always does if there's no `If-Match`
+    /// header or it is `*`. The tags we issue are only weak because of compression, the version
+    /// in them still identifies the resource exactly, so they match despite `If-Match` otherwise
+    /// using the strong comparison, as do tags for only some of the resource's fields.
+    pub fn matches(&self, version: i32) -> bool {
+        let Some(tags) = &self.0 else {
+            return true;
+        };
+
+        tags.split(',')
+            .map(str::trim)
+            .any(|tag| tag == "*" || tag_version(tag) == Some(version))
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for IfMatch
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        // A header which isn't valid UTF-8 can't contain any tag we issued, so it matches nothing
+        Ok(Self(parts.headers.get(IF_MATCH).map(|value| {
+            value.to_str().unwrap_or_default().to_string()
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{etag, sparse_etag, IfMatch};
+
+    #[test]
+    fn test_missing_header_matches_any_version() {
+        assert!(IfMatch(None).matches(3));
+    }
+
+    #[test]
+    fn test_matches_tags_for_the_version() {
+        let if_match = IfMatch(Some("\"2\", W/\"3\"".to_string()));
+
+        assert!(if_match.matches(2));
+        assert!(if_match.matches(3));
+        assert!(!if_match.matches(4));
+        assert!(!IfMatch(Some("3".to_string())).matches(3));
+    }
+
+    #[test]
+    fn test_matches_sparse_tags_for_the_version() {
+        let if_match = IfMatch(Some(sparse_etag(2, &["email", "id"])));
+
+        assert!(if_match.matches(2));
+        assert!(!if_match.matches(3));
+        assert!(IfMatch(Some(etag(5))).matches(5));
+    }
+
+    #[test]
+    fn test_wildcard_matches_any_version() {
+        assert!(IfMatch(Some("*".to_string())).matches(7));
+    }
+}
@@ -0,0 +1,82 @@
+//! If-None-Match extractor
+
+use std::convert::Infallible;
+
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header::IF_NONE_MATCH, request::Parts},
+};
+
+use super::if_match::opaque_tag;
+
+/// The entity tags in the request's `If-None-Match` header, if it has one, naming the versions of
+/// a resource the client already has
+#[derive(Debug, Default)]
+pub struct IfNoneMatch(Option<String>);
+
+impl IfNoneMatch {
+    /// Whether the client already has the representation tagged `etag`, so it needn't be sent
+    /// again. Weak tags match too, as `If-None-Match` uses the weak comparison.
+    pub fn matches(&self, etag: &str) -> bool {
+        let Some(tags) = &self.0 else {
+            return false;
+        };
+
+        tags.split(',')
+            .map(str::trim)
+            .any(|tag| tag == "*" || opaque_tag(tag) == opaque_tag(etag))
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for IfNoneMatch
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        // A header which isn't valid UTF-8 can't contain any tag we issued, so it matches nothing
+        Ok(Self(parts.headers.get(IF_NONE_MATCH).map(|value| {
+            value.to_str().unwrap_or_default().to_string()
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::infrastructure::http::extractors::if_match::{etag, sparse_etag};
+
+    use super::IfNoneMatch;
+
+    #[test]
+    fn test_missing_header_matches_no_version() {
+        assert!(!IfNoneMatch(None).matches(&etag(3)));
+    }
+
+    #[test]
+    fn test_matches_strong_and_weak_tags_for_the_version() {
+        let if_none_match = IfNoneMatch(Some("\"2\", W/\"3\"".to_string()));
+
+        assert!(if_none_match.matches(&etag(2)));
+        assert!(if_none_match.matches(&etag(3)));
+        assert!(!if_none_match.matches(&etag(4)));
+        assert!(!IfNoneMatch(Some("3".to_string())).matches(&etag(3)));
+    }
+
+    #[test]
+    fn test_full_and_sparse_tags_do_not_match_each_other() {
+        let sparse = sparse_etag(2, &["email", "id"]);
+
+        assert!(!IfNoneMatch(Some(etag(2))).matches(&sparse));
+        assert!(!IfNoneMatch(Some(sparse.clone())).matches(&etag(2)));
+        assert!(!IfNoneMatch(Some(sparse.clone())).matches(&sparse_etag(2, &["id"])));
+        assert!(IfNoneMatch(Some(sparse.clone())).matches(&sparse));
+    }
+
+    #[test]
+    fn test_wildcard_matches_any_version() {
+        assert!(IfNoneMatch(Some("*".to_string())).matches(&etag(7)));
+    }
+}
@@ -0,0 +1,112 @@
+//! JSON body extractor
+
+use axum::{
+    async_trait,
+    extract::{FromRequest, Request},
+    Json,
+};
+use serde::de::DeserializeOwned;
+
+use crate::infrastructure::http::errors::ApiError;
+
+/// A JSON request body, which is rejected with an [`ApiError`] rather than axum's plain text:
+/// a 415 if the request isn't `Content-Type: application/json`, a 400 if the JSON is malformed,
+/// or a 422 if it doesn't match `T`
+#[derive(Debug)]
+pub struct JsonBody<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for JsonBody<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(body) = Json::<T>::from_request(request, state).await?;
+
+        Ok(Self(body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{http::StatusCode, routing::post, Router};
+    use axum_test::TestServer;
+    use serde::Deserialize;
+    use testresult::TestResult;
+
+    use crate::infrastructure::http::errors::ErrorResponse;
+
+    use super::*;
+
+    #[derive(Debug, Deserialize)]
+    struct Body {
+        name: String,
+    }
+
+    fn server() -> TestResult<TestServer> {
+        let router = Router::new().route(
+            "/",
+            post(|JsonBody(body): JsonBody<Body>| async move { body.name }),
+        );
+
+        Ok(TestServer::new(router)?)
+    }
+
+    #[tokio::test]
+    async fn test_json_body() -> TestResult {
+        let response = server()?
+            .post("/")
+            .json(&serde_json::json!({ "name": "jane" }))
+            .await;
+
+        response.assert_status_ok();
+        response.assert_text("jane");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_missing_content_type_is_unsupported() -> TestResult {
+        let response = server()?.post("/").text(r#"{"name":"jane"}"#).await;
+
+        response.assert_status(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let json = response.json::<ErrorResponse>();
+
+        assert_eq!(json.code.as_deref(), Some("unsupported_media_type"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_malformed_json_is_bad_request() -> TestResult {
+        let response = server()?
+            .post("/")
+            .bytes(r#"{"name":"#.into())
+            .content_type("application/json")
+            .await;
+
+        response.assert_status_bad_request();
+
+        let json = response.json::<ErrorResponse>();
+
+        assert_eq!(json.code.as_deref(), Some("malformed_json"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_mismatched_json_is_unprocessable() -> TestResult {
+        let response = server()?
+            .post("/")
+            .json(&serde_json::json!({ "nickname": "jane" }))
+            .await;
+
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+
+        Ok(())
+    }
+}
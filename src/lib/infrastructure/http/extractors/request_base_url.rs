@@ -0,0 +1,89 @@
+//! Request base URL extractor
+
+use std::{convert::Infallible, net::SocketAddr};
+
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts},
+    http::{header::HOST, request::Parts},
+};
+
+use crate::{
+    domain::{
+        auth::users::UserService, base_url::BaseUrl,
+        communication::email_addresses::EmailAddressService, health::DatabaseHealth,
+        idempotency::IdempotencyStore,
+    },
+    infrastructure::http::state::AppState,
+};
+
+/// The `X-Forwarded-Host` header
+const X_FORWARDED_HOST: &str = "x-forwarded-host";
+
+/// The `X-Forwarded-Proto` header
+const X_FORWARDED_PROTO: &str = "x-forwarded-proto";
+
+/// The base URL links in the response to this request should use.
+///
+/// That's the host the request was made to, if it's one of the allowed hosts, and the configured
+/// base URL otherwise. Behind a trusted proxy the host and scheme are read from the
+/// `X-Forwarded-Host` and `X-Forwarded-Proto` headers, which are ignored from anyone else.
+#[derive(Debug)]
+pub struct RequestBaseUrl(pub BaseUrl);
+
+#[async_trait]
+impl<U, E, I, H> FromRequestParts<AppState<U, E, I, H>> for RequestBaseUrl
+where
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState<U, E, I, H>,
+    ) -> Result<Self, Self::Rejection> {
+        let configured = &state.config.base_url;
+
+        let behind_trusted_proxy = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .is_some_and(|ConnectInfo(peer)| state.config.trusted_proxies.is_trusted(&peer.ip()));
+
+        let forwarded_host = behind_trusted_proxy
+            .then(|| first_value(parts, X_FORWARDED_HOST))
+            .flatten();
+
+        let host = forwarded_host
+            .or_else(|| first_value(parts, HOST.as_str()))
+            .or_else(|| parts.uri.authority().map(|authority| authority.as_str()));
+
+        let Some(host) = host else {
+            return Ok(Self(configured.clone()));
+        };
+
+        let scheme = behind_trusted_proxy
+            .then(|| first_value(parts, X_FORWARDED_PROTO))
+            .flatten();
+
+        Ok(Self(
+            state
+                .config
+                .allowed_hosts
+                .base_url_for(configured, host, scheme),
+        ))
+    }
+}
+
+/// The first of the comma separated values of the `name` header, as the one nearest the client
+fn first_value<'a>(parts: &'a Parts, name: &str) -> Option<&'a str> {
+    parts
+        .headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(',').next())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
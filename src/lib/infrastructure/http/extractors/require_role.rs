@@ -0,0 +1,64 @@
+//! Role-based authorization extractor
+
+use std::marker::PhantomData;
+
+use axum::{async_trait, extract::FromRequestParts, http::request::Parts};
+
+use crate::{
+    domain::{
+        auth::users::{Role, User, UserService},
+        communication::email_addresses::EmailAddressService,
+        health::DatabaseHealth,
+        idempotency::IdempotencyStore,
+    },
+    infrastructure::http::{
+        errors::ApiError, extractors::current_user::CurrentUser, state::AppState,
+    },
+};
+
+/// A role that can be required of the authenticated user
+pub trait RequiredRole: Send + Sync + 'static {
+    /// The required role
+    const ROLE: Role;
+}
+
+/// Requires the authenticated user to be an [`Role::Admin`]
+#[derive(Debug)]
+pub struct Admin;
+
+impl RequiredRole for Admin {
+    const ROLE: Role = Role::Admin;
+}
+
+/// The authenticated [`User`], guaranteed to have at least the role required by `R`.
+///
+/// Rejects with 401 if the request is not authenticated, and 403 if the user lacks the role.
+/// The role is read from the user record rather than the access token, so demoting a user
+/// takes effect immediately.
+#[derive(Debug)]
+pub struct RequireRole<R: RequiredRole>(pub User, PhantomData<R>);
+
+#[async_trait]
+impl<U, E, I, H, R> FromRequestParts<AppState<U, E, I, H>> for RequireRole<R>
+where
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+    R: RequiredRole,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState<U, E, I, H>,
+    ) -> Result<Self, Self::Rejection> {
+        let CurrentUser(user) = CurrentUser::from_request_parts(parts, state).await?;
+
+        if !user.role.satisfies(R::ROLE) {
+            return Err(ApiError::new_403("You do not have permission to do that"));
+        }
+
+        Ok(Self(user, PhantomData))
+    }
+}
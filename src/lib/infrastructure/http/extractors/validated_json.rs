@@ -0,0 +1,159 @@
+//! Validated JSON body extractor
+
+use std::fmt;
+
+use axum::{
+    async_trait,
+    extract::{FromRequest, Request},
+};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    domain::{
+        auth::users::UserService, communication::email_addresses::EmailAddressService,
+        health::DatabaseHealth, idempotency::IdempotencyStore,
+    },
+    infrastructure::http::{
+        errors::{ApiError, ValidationError},
+        extractors::json::JsonBody,
+        state::{AppConfig, AppState},
+    },
+};
+
+/// A request body which is validated into the value a handler works with
+pub trait Validate: DeserializeOwned {
+    /// The value the body is validated into
+    type Valid;
+
+    /// Validates the body against `config`.
+    ///
+    /// # Returns
+    /// A [`Result`] which is [`Ok`] containing the valid value, or an [`Err`] containing every
+    /// field which failed validation.
+    fn validate(self, config: &AppConfig) -> Result<Self::Valid, Vec<ValidationError>>;
+}
+
+/// A JSON request body, validated with its [`Validate`] implementation. Bodies which can't be
+/// extracted are rejected as by [`JsonBody`], and invalid ones with a 422 listing the fields.
+pub struct ValidatedJson<T: Validate>(pub T::Valid);
+
+impl<T> fmt::Debug for ValidatedJson<T>
+where
+    T: Validate,
+    T::Valid: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ValidatedJson").field(&self.0).finish()
+    }
+}
+
+#[async_trait]
+impl<T, U, E, I, H> FromRequest<AppState<U, E, I, H>> for ValidatedJson<T>
+where
+    T: Validate,
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(
+        request: Request,
+        state: &AppState<U, E, I, H>,
+    ) -> Result<Self, Self::Rejection> {
+        let JsonBody(body) = JsonBody::<T>::from_request(request, state).await?;
+
+        body.validate(&state.config)
+            .map(Self)
+            .map_err(ApiError::new_validation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{http::StatusCode, routing::post, Router};
+    use axum_test::TestServer;
+    use serde_json::json;
+    use testresult::TestResult;
+
+    use crate::infrastructure::http::{
+        errors::ErrorResponse, handlers::v1::auth::create_user::CreateUserBody,
+        state::tests::test_state,
+    };
+
+    use super::*;
+
+    fn server() -> TestResult<TestServer> {
+        let router = Router::new()
+            .route(
+                "/",
+                post(
+                    |ValidatedJson(user): ValidatedJson<CreateUserBody>| async move {
+                        user.build()
+                            .map(|user| user.email().to_string())
+                            .unwrap_or_default()
+                    },
+                ),
+            )
+            .with_state(test_state(None, None));
+
+        Ok(TestServer::new(router)?)
+    }
+
+    #[tokio::test]
+    async fn test_valid_body_is_validated() -> TestResult {
+        let response = server()?
+            .post("/")
+            .json(&json!({ "email": "email@example.com", "password": "correcthorsebatterystaple" }))
+            .await;
+
+        response.assert_status_ok();
+        response.assert_text("email@example.com");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_malformed_json_is_bad_request() -> TestResult {
+        let response = server()?
+            .post("/")
+            .bytes(r#"{"email": "#.into())
+            .content_type("application/json")
+            .await;
+
+        response.assert_status_bad_request();
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_missing_content_type_is_unsupported() -> TestResult {
+        let response = server()?
+            .post("/")
+            .text(r#"{"email": "email@example.com", "password": "correcthorsebatterystaple"}"#)
+            .await;
+
+        response.assert_status(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_body_is_unprocessable() -> TestResult {
+        let response = server()?
+            .post("/")
+            .json(&json!({ "email": "not an email", "password": "correcthorsebatterystaple" }))
+            .await;
+
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+
+        let json = response.json::<ErrorResponse>();
+
+        assert_eq!(json.code.as_deref(), Some("validation_failed"));
+        assert_eq!(json.validation_errors.len(), 1);
+        assert_eq!(json.validation_errors[0].field, "email");
+
+        Ok(())
+    }
+}
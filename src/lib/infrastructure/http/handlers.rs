@@ -4,12 +4,16 @@ use std::any::Any;
 
 use axum::{
     body::Body,
-    http::{Response, StatusCode},
+    http::{Response, StatusCode, Uri},
     response::IntoResponse,
     Json,
 };
 
-use super::errors::ErrorResponse;
+use super::{
+    errors::{ApiError, ErrorResponse},
+    extractors::accept_language::AcceptLanguage,
+    templates::errors::not_found::NotFoundErrorTemplate,
+};
 
 pub mod v1;
 
@@ -27,6 +31,8 @@ pub fn panic_handler(err: Box<dyn Any + Send + 'static>) -> Response<Body> {
 
     let error = ErrorResponse {
         error: "Internal server error".to_string(),
+        code: None,
+        validation_errors: vec![],
     };
 
     let response = Json(error).into_response();
@@ -34,12 +40,38 @@ pub fn panic_handler(err: Box<dyn Any + Send + 'static>) -> Response<Body> {
     (StatusCode::INTERNAL_SERVER_ERROR, response).into_response()
 }
 
+/// Respond to a request for an API path which doesn't exist
+pub async fn api_not_found() -> ApiError {
+    ApiError {
+        code: Some("not_found".to_string()),
+        ..ApiError::new_404("Not found")
+    }
+}
+
+/// Respond to a request for a path which doesn't exist, as JSON for API paths and with the not
+/// found page for any others
+pub async fn not_found(uri: Uri, AcceptLanguage(locale): AcceptLanguage) -> Response<Body> {
+    if uri.path() == "/api" || uri.path().starts_with("/api/") {
+        return api_not_found().await.into_response();
+    }
+
+    (
+        StatusCode::NOT_FOUND,
+        NotFoundErrorTemplate { locale }.into_response(),
+    )
+        .into_response()
+}
+
 #[cfg(test)]
 mod tests {
     use std::panic::{self, AssertUnwindSafe};
 
     use super::*;
-    use axum::body::to_bytes;
+    use axum::{body::to_bytes, http::header::CONTENT_TYPE};
+    use axum_test::TestServer;
+    use testresult::TestResult;
+
+    use crate::infrastructure::http::{servers::https::router, state::tests::test_state};
 
     #[tokio::test]
     async fn test_panic_handler() {
@@ -59,6 +91,37 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_unknown_api_path_is_json_not_found() -> TestResult {
+        let response = TestServer::new(router(test_state(None, None)))?
+            .get("/api/v1/nope")
+            .await;
+
+        response.assert_status_not_found();
+
+        let json = response.json::<ErrorResponse>();
+
+        assert_eq!(json.error, "Not found");
+        assert_eq!(json.code.as_deref(), Some("not_found"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unknown_web_path_is_html_not_found() -> TestResult {
+        let response = TestServer::new(router(test_state(None, None)))?
+            .get("/nope")
+            .await;
+
+        response.assert_status_not_found();
+        assert!(response
+            .header(CONTENT_TYPE)
+            .to_str()?
+            .starts_with("text/html"));
+
+        Ok(())
+    }
+
     fn simulate_panic() -> Box<dyn std::any::Any + Send + 'static> {
         let result = panic::catch_unwind(AssertUnwindSafe(|| {
             panic!("Panic message");
@@ -1,44 +1,128 @@
 //! Version 1 of the API
 
 use axum::{
-    routing::{get, post},
-    Router,
+    extract::State,
+    routing::{delete, get, post},
+    Json, Router,
 };
+use utoipa::openapi::OpenApi;
 
 use crate::{
-    domain::{auth::users::UserService, communication::email_addresses::EmailAddressService},
-    infrastructure::http::state::AppState,
+    domain::{
+        auth::users::UserService, communication::email_addresses::EmailAddressService,
+        health::DatabaseHealth, idempotency::IdempotencyStore,
+    },
+    infrastructure::http::{
+        handlers::api_not_found, open_api::ApiDocs, operation_id::WithOperationId, state::AppState,
+    },
 };
 
+pub mod admin;
 pub mod auth;
+pub mod health;
 pub mod stoplight;
 pub mod uptime;
 
 /// Create the router for version 1 of the API
-pub fn router<U: UserService, E: EmailAddressService>() -> Router<AppState<U, E>> {
-    #[allow(unused_mut)]
-    let mut router = Router::new()
+pub fn router<U: UserService, E: EmailAddressService, I: IdempotencyStore, H: DatabaseHealth>(
+) -> Router<AppState<U, E, I, H>> {
+    Router::new()
         .route("/", get(stoplight::handler))
-        .route("/uptime", get(uptime::handler))
-        .route("/users/:id", get(auth::get_user_by_id::handler))
+        .route("/openapi.json", get(open_api))
+        .route("/uptime", get(uptime::handler).operation_id("uptime"))
+        .route(
+            "/health",
+            get(health::status::handler).operation_id("get_health"),
+        )
+        .route(
+            "/health/ready",
+            get(health::ready::handler).operation_id("get_readiness"),
+        )
+        .route(
+            "/auth/password-policy",
+            get(auth::password_policy::handler).operation_id("get_password_policy"),
+        )
+        .route(
+            "/auth/me",
+            get(auth::me::handler).operation_id("get_current_user"),
+        )
+        .route(
+            "/auth/me",
+            delete(auth::erase_account::handler).operation_id("erase_current_user"),
+        )
+        .route(
+            "/users/:id",
+            get(auth::get_user_by_id::handler).operation_id("get_user_by_id"),
+        )
+        .route(
+            "/users/:id",
+            delete(auth::delete_user::handler).operation_id("delete_user"),
+        )
         .route(
             "/users/:id/email/confirmation",
-            post(auth::send_email_confirmation::handler),
+            post(auth::send_email_confirmation::handler).operation_id("send_email_confirmation"),
         )
         .route(
             "/users/:id/email/confirmation",
-            get(auth::confirm_email::handler),
+            get(auth::confirm_email::handler).operation_id("confirm_email"),
         )
-        .route("/users/:id/email/change", post(auth::change_email::handler))
-        .route("/users", post(auth::create_user::handler));
-
-    #[cfg(not(test))]
-    {
-        use crate::infrastructure::http::open_api::ApiDocs;
-        use axum::Json;
-        use utoipa::OpenApi;
-        router = router.route("/openapi.json", get(Json(ApiDocs::openapi())));
-    }
+        .route(
+            "/users/:id/email/confirm",
+            post(auth::confirm_email_json::handler).operation_id("confirm_email_json"),
+        )
+        .route(
+            "/users/:id/email/confirmation/status",
+            get(auth::email_confirmation_status::handler)
+                .operation_id("get_email_confirmation_status"),
+        )
+        .route(
+            "/users/:id/email/confirmation/validate",
+            get(auth::validate_email_confirmation::handler)
+                .operation_id("validate_email_confirmation"),
+        )
+        .route(
+            "/users/:id/email/change",
+            post(auth::change_email::handler).operation_id("send_change_email_confirmation"),
+        )
+        .route(
+            "/users/:id/email/change",
+            delete(auth::cancel_email_change::handler).operation_id("cancel_email_change"),
+        )
+        .route(
+            "/users",
+            post(auth::create_user::handler).operation_id("create_user"),
+        )
+        .route(
+            "/users",
+            get(auth::list_users::handler).operation_id("list_users"),
+        )
+        .route(
+            "/users/email-available",
+            get(auth::email_available::handler).operation_id("check_email_available"),
+        )
+        .route(
+            "/users/batch",
+            post(auth::create_users::handler).operation_id("create_users"),
+        )
+        .route(
+            "/admin/health",
+            get(admin::health::handler).operation_id("admin_get_health"),
+        )
+        .route(
+            "/admin/users/:id/email/confirm",
+            post(admin::confirm_email::handler).operation_id("admin_confirm_email"),
+        )
+        .fallback(api_not_found)
+}
 
-    router
+/// The OpenAPI spec
+async fn open_api<
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+>(
+    State(state): State<AppState<U, E, I, H>>,
+) -> Json<OpenApi> {
+    Json(ApiDocs::for_base_url(state.config.base_url.as_str()))
 }
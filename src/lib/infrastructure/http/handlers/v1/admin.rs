@@ -0,0 +1,4 @@
+//! Admin handlers
+
+pub mod confirm_email;
+pub mod health;
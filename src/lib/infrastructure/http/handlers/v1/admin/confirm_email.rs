@@ -0,0 +1,246 @@
+//! Force-confirm email handler
+
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        auth::users::UserService, communication::email_addresses::EmailAddressService,
+        health::DatabaseHealth, idempotency::IdempotencyStore,
+    },
+    infrastructure::http::{
+        errors::ApiError,
+        extractors::{
+            client_origin::ClientOrigin,
+            require_role::{Admin, RequireRole},
+        },
+        handlers::v1::auth::email_confirmation_status::EmailConfirmationStatusResponse,
+        state::AppState,
+    },
+};
+
+/// Confirm a user's email address without a token
+///
+/// For support staff helping users who can't receive the confirmation email. Confirming an
+/// address which is already confirmed succeeds without changing anything.
+#[utoipa::path(
+    post,
+    operation_id = "admin_confirm_email",
+    tag = "Admin",
+    path = "/api/v1/admin/users/{id}/email/confirm",
+    params(
+        ("id" = Uuid, Path, description = "The UUID of the user", example = "550e8400-e29b-41d4-a716-446655440000"),
+    ),
+    security(("bearerAuth" = [])),
+    responses(
+        (status = StatusCode::OK, description = "Email address confirmed", body = EmailConfirmationStatusResponse),
+        (status = StatusCode::UNAUTHORIZED, description = "Missing, invalid or expired access token", body = ErrorResponse),
+        (status = StatusCode::FORBIDDEN, description = "User is not an admin", body = ErrorResponse, example = json!({ "error": "You do not have permission to do that", "code": "forbidden" })),
+        (status = StatusCode::NOT_FOUND, description = "User not found", body = ErrorResponse),
+        (status = StatusCode::INTERNAL_SERVER_ERROR, description = "Internal Server Error", body = ErrorResponse),
+        (status = StatusCode::TOO_MANY_REQUESTS, description = "Too many requests", body = TooManyRequestsResponse),
+    )
+)]
+pub async fn handler<
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+>(
+    State(state): State<AppState<U, E, I, H>>,
+    admin: RequireRole<Admin>,
+    ClientOrigin(origin): ClientOrigin,
+    Path(id): Path<Uuid>,
+) -> Result<Json<EmailConfirmationStatusResponse>, ApiError> {
+    let user = state.users.get_user_by_id(&id).await?;
+
+    state
+        .email_addresses
+        .force_confirm_email(&user, &admin.0.id, &origin)
+        .await?;
+
+    let status = state.users.get_user_by_id(&id).await?.into();
+
+    Ok(Json(status))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{header::AUTHORIZATION, StatusCode};
+    use axum_test::{TestResponse, TestServer};
+    use chrono::Utc;
+    use mockall::predicate::eq;
+    use testresult::TestResult;
+    use uuid::Uuid;
+
+    use crate::{
+        domain::{
+            auth::users::{errors::GetUserByIdError, tests::MockUserService, Role, User},
+            communication::email_addresses::tests::MockEmailAddressService,
+        },
+        infrastructure::http::{
+            servers::https::router,
+            state::tests::{test_bearer_token, test_state},
+        },
+    };
+
+    fn user_with_role(role: Role) -> User {
+        User {
+            id: Uuid::now_v7(),
+            role,
+            ..User::default()
+        }
+    }
+
+    async fn confirm_as(
+        actor: User,
+        users: MockUserService,
+        email_addresses: MockEmailAddressService,
+        user_id: Uuid,
+    ) -> TestResult<TestResponse> {
+        let state = test_state(Some(users), Some(email_addresses));
+        let token = test_bearer_token(&state, &actor.id);
+
+        Ok(TestServer::new(router(state))?
+            .post(&format!("/api/v1/admin/users/{user_id}/email/confirm"))
+            .add_header(AUTHORIZATION, token.parse()?)
+            .await)
+    }
+
+    fn users_returning(admin: User, before: User, after: User) -> MockUserService {
+        let mut users = MockUserService::new();
+        let admin_id = admin.id;
+        let user_id = before.id;
+        let mut reads = vec![after, before];
+
+        users
+            .expect_get_user_by_id()
+            .with(eq(admin_id))
+            .returning(move |_| Ok(admin.clone()));
+
+        users
+            .expect_get_user_by_id()
+            .with(eq(user_id))
+            .times(2)
+            .returning(move |_| Ok(reads.pop().unwrap_or_default()));
+
+        users
+    }
+
+    #[tokio::test]
+    async fn test_admin_confirms_unconfirmed_user() -> TestResult {
+        let admin = user_with_role(Role::Admin);
+        let admin_id = admin.id;
+        let user = user_with_role(Role::User);
+        let user_id = user.id;
+
+        let confirmed = User {
+            email_confirmed_at: Some(Utc::now()),
+            ..user.clone()
+        };
+
+        let mut email_addresses = MockEmailAddressService::new();
+
+        email_addresses
+            .expect_force_confirm_email()
+            .times(1)
+            .withf(move |user, by, _| user.id == user_id && *by == admin_id)
+            .returning(|_, _, _| Ok(()));
+
+        let response = confirm_as(
+            admin.clone(),
+            users_returning(admin, user, confirmed),
+            email_addresses,
+            user_id,
+        )
+        .await?;
+
+        response.assert_status_ok();
+        assert_eq!(response.json::<serde_json::Value>()["confirmed"], true);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_admin_reconfirming_confirmed_user_succeeds() -> TestResult {
+        let admin = user_with_role(Role::Admin);
+        let user = User {
+            email_confirmed_at: Some(Utc::now()),
+            ..user_with_role(Role::User)
+        };
+        let user_id = user.id;
+
+        let mut email_addresses = MockEmailAddressService::new();
+
+        email_addresses
+            .expect_force_confirm_email()
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+
+        let response = confirm_as(
+            admin.clone(),
+            users_returning(admin, user.clone(), user),
+            email_addresses,
+            user_id,
+        )
+        .await?;
+
+        response.assert_status_ok();
+        assert_eq!(response.json::<serde_json::Value>()["confirmed"], true);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_admin_confirming_missing_user_is_not_found() -> TestResult {
+        let admin = user_with_role(Role::Admin);
+        let admin_id = admin.id;
+        let actor = admin.clone();
+
+        let mut users = MockUserService::new();
+
+        users
+            .expect_get_user_by_id()
+            .with(eq(admin_id))
+            .returning(move |_| Ok(admin.clone()));
+
+        users
+            .expect_get_user_by_id()
+            .returning(|_| Err(GetUserByIdError::UserNotFound));
+
+        let mut email_addresses = MockEmailAddressService::new();
+
+        email_addresses.expect_force_confirm_email().times(0);
+
+        let response = confirm_as(actor, users, email_addresses, Uuid::now_v7()).await?;
+
+        response.assert_status(StatusCode::NOT_FOUND);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_regular_user_cannot_force_confirm() -> TestResult {
+        let user = user_with_role(Role::User);
+        let actor = user.clone();
+
+        let mut users = MockUserService::new();
+
+        users
+            .expect_get_user_by_id()
+            .returning(move |_| Ok(user.clone()));
+
+        let mut email_addresses = MockEmailAddressService::new();
+
+        email_addresses.expect_force_confirm_email().times(0);
+
+        let response = confirm_as(actor, users, email_addresses, Uuid::now_v7()).await?;
+
+        response.assert_status(StatusCode::FORBIDDEN);
+
+        Ok(())
+    }
+}
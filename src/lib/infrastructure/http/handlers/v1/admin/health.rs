@@ -0,0 +1,151 @@
+//! Admin health handler
+
+use axum::{extract::State, http::StatusCode, Json};
+
+use crate::{
+    domain::{
+        auth::users::UserService,
+        communication::email_addresses::EmailAddressService,
+        health::{DatabaseCheck, DatabaseHealth, HealthChecks},
+        idempotency::IdempotencyStore,
+    },
+    infrastructure::http::{
+        extractors::require_role::{Admin, RequireRole},
+        handlers::v1::health::status::{respond, HealthResponse},
+        state::AppState,
+    },
+};
+
+/// Get the health of the application, including every dependency
+///
+/// Runs the same checks as `/api/v1/health`, along with those which probe other services, such
+/// as whether the SMTP servers accept connections. The checks are run afresh for every request.
+#[utoipa::path(
+    get,
+    operation_id = "admin_get_health",
+    tag = "Admin",
+    path = "/api/v1/admin/health",
+    security(("bearerAuth" = [])),
+    responses(
+        (status = StatusCode::OK, description = "Every required check is up", body = HealthResponse),
+        (status = StatusCode::UNAUTHORIZED, description = "Missing, invalid or expired access token", body = ErrorResponse),
+        (status = StatusCode::FORBIDDEN, description = "User is not an admin", body = ErrorResponse, example = json!({ "error": "You do not have permission to do that", "code": "forbidden" })),
+        (status = StatusCode::SERVICE_UNAVAILABLE, description = "A required check is down", body = HealthResponse),
+        (status = StatusCode::TOO_MANY_REQUESTS, description = "Too many requests", body = TooManyRequestsResponse),
+    )
+)]
+pub async fn handler<
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+>(
+    State(state): State<AppState<U, E, I, H>>,
+    _admin: RequireRole<Admin>,
+) -> (StatusCode, Json<HealthResponse>) {
+    let report = HealthChecks::new()
+        .with_required(DatabaseCheck::new(state.health.clone()))
+        .with_checks(&state.health_checks)
+        .with_checks(&state.admin_health_checks)
+        .run()
+        .await;
+
+    respond(&state, report)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::{
+        async_trait,
+        http::{header::AUTHORIZATION, StatusCode},
+    };
+    use axum_test::{TestResponse, TestServer};
+    use testresult::TestResult;
+    use uuid::Uuid;
+
+    use crate::{
+        domain::{
+            auth::users::{tests::MockUserService, Role, User},
+            health::{tests::MockDatabaseHealth, HealthCheck, HealthChecks, HealthStatus},
+        },
+        infrastructure::http::{
+            handlers::v1::health::status::HealthResponse,
+            servers::https::router,
+            state::tests::{test_bearer_token, test_state},
+        },
+    };
+
+    /// A check which always reports down
+    struct DownCheck;
+
+    #[async_trait]
+    impl HealthCheck for DownCheck {
+        fn name(&self) -> &str {
+            "smtp"
+        }
+
+        async fn check(&self) -> HealthStatus {
+            HealthStatus::Down
+        }
+    }
+
+    async fn get_health_as(role: Role, pings: usize) -> TestResult<TestResponse> {
+        let user = User {
+            id: Uuid::now_v7(),
+            role,
+            ..User::default()
+        };
+
+        let mut users = MockUserService::new();
+
+        users
+            .expect_get_user_by_id()
+            .returning(move |_| Ok(user.clone()));
+
+        let mut health = MockDatabaseHealth::new();
+
+        health.expect_ping().times(pings).returning(|| Ok(()));
+
+        let mut state = test_state(Some(users), None);
+        state.health = Arc::new(health);
+        state.admin_health_checks = HealthChecks::new().with_optional(DownCheck);
+
+        let token = test_bearer_token(&state, &Uuid::now_v7());
+
+        Ok(TestServer::new(router(state))?
+            .get("/api/v1/admin/health")
+            .add_header(AUTHORIZATION, token.parse()?)
+            .await)
+    }
+
+    #[tokio::test]
+    async fn test_admin_health_includes_admin_checks() -> TestResult {
+        let response = get_health_as(Role::Admin, 1).await?;
+
+        response.assert_status_ok();
+
+        let body = response.json::<HealthResponse>();
+
+        assert_eq!(body.status, HealthStatus::Degraded);
+        assert_eq!(
+            body.checks
+                .iter()
+                .map(|check| (check.name.as_str(), check.status))
+                .collect::<Vec<_>>(),
+            vec![("database", HealthStatus::Up), ("smtp", HealthStatus::Down)]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_admin_health_is_forbidden_to_users() -> TestResult {
+        let response = get_health_as(Role::User, 0).await?;
+
+        response.assert_status(StatusCode::FORBIDDEN);
+
+        Ok(())
+    }
+}
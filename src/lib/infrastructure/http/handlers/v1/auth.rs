@@ -1,7 +1,18 @@
 //! Auth handlers
 
+pub mod cancel_email_change;
 pub mod change_email;
 pub mod confirm_email;
+pub mod confirm_email_json;
 pub mod create_user;
+pub mod create_users;
+pub mod delete_user;
+pub mod email_available;
+pub mod email_confirmation_status;
+pub mod erase_account;
 pub mod get_user_by_id;
+pub mod list_users;
+pub mod me;
+pub mod password_policy;
 pub mod send_email_confirmation;
+pub mod validate_email_confirmation;
@@ -0,0 +1,196 @@
+//! Cancel email change handler
+
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        auth::users::{errors::UpdateUserError, UserService},
+        communication::email_addresses::{EmailAddress, EmailAddressService},
+        health::DatabaseHealth,
+        idempotency::IdempotencyStore,
+    },
+    infrastructure::http::{
+        errors::ApiError,
+        extractors::{auth_user::AuthUser, if_match::IfMatch},
+        state::AppState,
+    },
+};
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CancelEmailChangeResponse {
+    #[schema(value_type = String, example = "email@example.com")]
+    email: EmailAddress,
+}
+
+/// Cancel a pending email change
+#[utoipa::path(
+    delete,
+    operation_id = "cancel_email_change",
+    tag = "Auth",
+    path = "/api/v1/users/{id}/email/change",
+    params(
+        ("id" = Uuid, Path, description = "The UUID of the user", example = "550e8400-e29b-41d4-a716-446655440000"),
+        ("If-Match" = Option<String>, Header, description = "The user's `ETag`, to only cancel the change if they haven't been updated since", example = "W/\"2\""),
+    ),
+    security(("bearerAuth" = [])),
+    responses(
+        (status = StatusCode::OK, description = "Email change cancelled", body = CancelEmailChangeResponse),
+        (status = StatusCode::UNAUTHORIZED, description = "Missing, invalid or expired access token", body = ErrorResponse),
+        (status = StatusCode::FORBIDDEN, description = "Access token belongs to another user", body = ErrorResponse),
+        (status = StatusCode::NOT_FOUND, description = "User not found", body = ErrorResponse),
+        (status = StatusCode::CONFLICT, description = "No email change is pending, or the user has been updated since the `If-Match` version", body = ErrorResponse, example = json!({ "error": "No email change is pending" })),
+        (status = StatusCode::INTERNAL_SERVER_ERROR, description = "Internal Server Error", body = ErrorResponse),
+        (status = StatusCode::TOO_MANY_REQUESTS, description = "Too many requests", body = TooManyRequestsResponse),
+    )
+)]
+pub async fn handler<
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+>(
+    State(state): State<AppState<U, E, I, H>>,
+    auth_user: AuthUser,
+    Path(user_id): Path<Uuid>,
+    if_match: IfMatch,
+) -> Result<Json<CancelEmailChangeResponse>, ApiError> {
+    if auth_user.id != user_id {
+        return Err(ApiError::new_403("You may only access your own user"));
+    }
+
+    let user = state.users.get_user_by_id(&user_id).await?;
+
+    if !if_match.matches(user.version) {
+        return Err(UpdateUserError::Conflict.into());
+    }
+
+    state.email_addresses.cancel_email_change(&user).await?;
+
+    Ok(Json(CancelEmailChangeResponse { email: user.email }))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{header::AUTHORIZATION, StatusCode};
+    use axum_test::TestServer;
+    use testresult::TestResult;
+    use uuid::Uuid;
+
+    use crate::{
+        domain::{
+            auth::users::{tests::MockUserService, User},
+            communication::email_addresses::{
+                tests::MockEmailAddressService, EmailAddress, EmailConfirmationError,
+            },
+        },
+        infrastructure::http::{
+            errors::ErrorResponse,
+            servers::https::router,
+            state::tests::{test_bearer_token, test_state},
+        },
+    };
+
+    use super::CancelEmailChangeResponse;
+
+    #[tokio::test]
+    async fn test_cancel_email_change() -> TestResult {
+        let user = User {
+            id: Uuid::now_v7(),
+            email: EmailAddress::new_unchecked("email@example.com"),
+            new_email: Some(EmailAddress::new_unchecked("new@example.com")),
+            ..User::default()
+        };
+        let user_id = user.id;
+
+        let mut users = MockUserService::new();
+        let mut email_addresses = MockEmailAddressService::new();
+
+        users
+            .expect_get_user_by_id()
+            .withf(move |id| *id == user_id)
+            .returning(move |_| Ok(user.clone()));
+
+        email_addresses
+            .expect_cancel_email_change()
+            .times(1)
+            .withf(move |user| user.id == user_id)
+            .returning(|_| Ok(()));
+
+        let state = test_state(Some(users), Some(email_addresses));
+        let token = test_bearer_token(&state, &user_id);
+
+        let response = TestServer::new(router(state))?
+            .delete(&format!("/api/v1/users/{user_id}/email/change"))
+            .add_header(AUTHORIZATION, token.parse()?)
+            .await;
+
+        let json = response.json::<CancelEmailChangeResponse>();
+
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(json.email.to_string(), "email@example.com");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cancel_email_change_without_pending_change() -> TestResult {
+        let user = User {
+            id: Uuid::now_v7(),
+            ..User::default()
+        };
+        let user_id = user.id;
+
+        let mut users = MockUserService::new();
+        let mut email_addresses = MockEmailAddressService::new();
+
+        users
+            .expect_get_user_by_id()
+            .returning(move |_| Ok(user.clone()));
+
+        email_addresses
+            .expect_cancel_email_change()
+            .returning(|_| Err(EmailConfirmationError::NoPendingEmailChange));
+
+        let state = test_state(Some(users), Some(email_addresses));
+        let token = test_bearer_token(&state, &user_id);
+
+        let response = TestServer::new(router(state))?
+            .delete(&format!("/api/v1/users/{user_id}/email/change"))
+            .add_header(AUTHORIZATION, token.parse()?)
+            .await;
+
+        let json = response.json::<ErrorResponse>();
+
+        assert_eq!(response.status_code(), StatusCode::CONFLICT);
+        assert_eq!(json.error, "No email change is pending");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cancel_email_change_other_user() -> TestResult {
+        let mut users = MockUserService::new();
+        let mut email_addresses = MockEmailAddressService::new();
+
+        users.expect_get_user_by_id().times(0);
+        email_addresses.expect_cancel_email_change().times(0);
+
+        let state = test_state(Some(users), Some(email_addresses));
+        let token = test_bearer_token(&state, &Uuid::now_v7());
+
+        let response = TestServer::new(router(state))?
+            .delete(&format!("/api/v1/users/{}/email/change", Uuid::now_v7()))
+            .add_header(AUTHORIZATION, token.parse()?)
+            .await;
+
+        assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+        Ok(())
+    }
+}
@@ -12,23 +12,46 @@ use uuid::Uuid;
 
 use crate::{
     domain::{
-        auth::users::UserService,
+        auth::users::{errors::UpdateUserError, UserService},
         communication::email_addresses::{
             EmailAddress, EmailAddressService, EmailConfirmationType,
         },
+        health::DatabaseHealth,
+        idempotency::IdempotencyStore,
+    },
+    infrastructure::http::{
+        errors::{ApiError, ValidationError},
+        extractors::{
+            client_origin::ClientOrigin,
+            if_match::IfMatch,
+            validated_json::{Validate, ValidatedJson},
+        },
+        state::{AppConfig, AppState},
     },
-    infrastructure::http::{errors::ApiError, state::AppState},
 };
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ChangeEmailRequest {
-    #[schema(value_type = String, example = "email@example.com")]
-    email: EmailAddress,
+    #[schema(example = "email@example.com")]
+    email: String,
+}
+
+impl Validate for ChangeEmailRequest {
+    type Valid = EmailAddress;
+
+    fn validate(self, _config: &AppConfig) -> Result<EmailAddress, Vec<ValidationError>> {
+        EmailAddress::new(&self.email).map_err(|err| vec![ValidationError::new("email", &err)])
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ChangeEmailResponse {
     expires_at: DateTime<Utc>,
+
+    /// The confirmation link, only included when `EXPOSE_CONFIRMATION_LINKS` is enabled outside
+    /// production
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    confirmation_link: Option<String>,
 }
 
 /// Send a change email confirmation email
@@ -40,41 +63,75 @@ pub struct ChangeEmailResponse {
     request_body = ChangeEmailRequest,
     params(
         ("id" = Uuid, Path, description = "The UUID of the user", example = "550e8400-e29b-41d4-a716-446655440000"),
+        ("If-Match" = Option<String>, Header, description = "The user's `ETag`, to only change their email if they haven't been updated since", example = "W/\"1\""),
     ),
     responses(
         (status = StatusCode::ACCEPTED, description = "Email change confirmation sent", body = ChangeEmailResponse),
-        (status = StatusCode::UNPROCESSABLE_ENTITY, description = "Unprocessable entity", body = ErrorResponse),
+        (status = StatusCode::UNSUPPORTED_MEDIA_TYPE, description = "The body isn't JSON", body = ErrorResponse, example = json!({"error": "Expected a request body with the `Content-Type: application/json` header", "code": "unsupported_media_type"})),
+        (status = StatusCode::UNPROCESSABLE_ENTITY, description = "The email address is invalid", body = ErrorResponse, example = json!({"error": "Please provide a valid email address", "code": "validation_failed", "validation_errors": [{"field": "email", "code": "invalid", "message": "Please provide a valid email address"}]})),
         (status = StatusCode::NOT_FOUND, description = "User not found", body = ErrorResponse, example = json!({ "error": "User with id \"550e8400-e29b-41d4-a716-446655440000\" not found" })),
+        (status = StatusCode::CONFLICT, description = "The user has been updated since the `If-Match` version, or the email address is in use", body = ErrorResponse, example = json!({ "error": "User has been updated since it was read, please fetch it and try again", "code": "version_conflict" })),
         (status = StatusCode::INTERNAL_SERVER_ERROR, description = "Internal Server Error", body = ErrorResponse, example = json!({ "error": "Failed to send email confirmation: <error>" })),
+        (status = StatusCode::SERVICE_UNAVAILABLE, description = "Email delivery is temporarily unavailable", body = ErrorResponse, example = json!({ "error": "Email could not be sent, please try again later" })),
         (status = StatusCode::TOO_MANY_REQUESTS, description = "Too many requests", body = TooManyRequestsResponse),
     )
 )]
-pub async fn handler<U: UserService, E: EmailAddressService>(
-    State(state): State<AppState<U, E>>,
+pub async fn handler<
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+>(
+    State(state): State<AppState<U, E, I, H>>,
     Path(user_id): Path<Uuid>,
-    Json(request): Json<ChangeEmailRequest>,
+    if_match: IfMatch,
+    ClientOrigin(origin): ClientOrigin,
+    ValidatedJson(email): ValidatedJson<ChangeEmailRequest>,
 ) -> Result<(StatusCode, Json<ChangeEmailResponse>), ApiError> {
     let user = state.users.get_user_by_id(&user_id).await?;
 
-    let expires_at = state
+    if !if_match.matches(user.version) {
+        return Err(UpdateUserError::Conflict.into());
+    }
+
+    state.check_deliverable("email", &email).await?;
+
+    let sent = state
         .email_addresses
         .send_email_confirmation(
             &user,
-            EmailConfirmationType::NewEmail(request.email),
+            EmailConfirmationType::NewEmail(email),
             &state.config.base_url,
+            &origin,
         )
         .await?;
 
     Ok((
         StatusCode::ACCEPTED,
-        Json(ChangeEmailResponse { expires_at }),
+        Json(ChangeEmailResponse {
+            expires_at: sent.expires_at,
+            confirmation_link: state
+                .config
+                .debug
+                .exposes_confirmation_links()
+                .then_some(sent.link),
+        }),
     ))
 }
 
 #[cfg(test)]
 mod tests {
-    use axum::http::StatusCode;
-    use axum_test::TestServer;
+    use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+    use axum::{
+        extract::ConnectInfo,
+        http::{
+            header::{IF_MATCH, USER_AGENT},
+            HeaderName, StatusCode,
+        },
+        Extension,
+    };
+    use axum_test::{TestResponse, TestServer};
     use chrono::{Duration, Utc};
     use serde_json::json;
     use testresult::TestResult;
@@ -82,12 +139,18 @@ mod tests {
 
     use crate::{
         domain::{
-            auth::users::{tests::MockUserService, User},
+            auth::users::{tests::MockUserService, Role, User},
             communication::email_addresses::{
-                tests::MockEmailAddressService, EmailAddress, EmailConfirmationType,
+                tests::MockEmailAddressService, EmailAddress, EmailConfirmationType, RequestOrigin,
+                SentEmailConfirmation,
             },
+            i18n::Locale,
+            timestamps::Timestamps,
+        },
+        infrastructure::http::{
+            client_ip::TrustedProxiesConfig, errors::ErrorResponse, servers::https::router,
+            state::tests::test_state,
         },
-        infrastructure::http::{servers::https::router, state::tests::test_state},
     };
 
     use super::ChangeEmailResponse;
@@ -101,14 +164,19 @@ mod tests {
         let expected_confirmation_type = EmailConfirmationType::NewEmail(changed_email.clone());
 
         let user = User {
-            id: user_id.clone(),
+            id: user_id,
             email: EmailAddress::new_unchecked("email@example.com"),
             new_email: None,
             email_confirmed_at: None,
             email_confirmation_token: Some("token".to_string()),
-            email_confirmation_sent_at: Some(yesterday.clone()),
-            created_at: yesterday.clone(),
-            updated_at: yesterday.clone(),
+            email_confirmation_sent_at: Some(yesterday),
+            email_confirmation_expires_at: Some(yesterday + Duration::hours(24)),
+            role: Role::User,
+            terms_accepted_at: None,
+            terms_version: None,
+            locale: Locale::English,
+            timestamps: Timestamps::created(yesterday),
+            version: 1,
         };
 
         let expected_expiry = Utc::now() + Duration::days(1);
@@ -124,12 +192,17 @@ mod tests {
         email_addresses
             .expect_send_email_confirmation()
             .times(1)
-            .withf(move |user, confirmation_type, base_url| {
+            .withf(move |user, confirmation_type, base_url, _| {
                 *user == user.clone()
                     && *confirmation_type == expected_confirmation_type
-                    && base_url == "https://example.com"
+                    && base_url.as_str() == "https://example.com"
             })
-            .returning(move |_, _, _| Ok(expected_expiry.clone()));
+            .returning(move |_, _, _, _| {
+                Ok(SentEmailConfirmation {
+                    expires_at: expected_expiry,
+                    link: "https://example.com/confirm".to_string(),
+                })
+            });
 
         let state = test_state(Some(users), Some(email_addresses));
 
@@ -142,6 +215,158 @@ mod tests {
 
         assert_eq!(response.status_code(), StatusCode::ACCEPTED);
         assert_eq!(json.expires_at, expected_expiry);
+        assert_eq!(json.confirmation_link, None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_change_email_confirmation_records_client_origin() -> TestResult {
+        let user = User {
+            id: Uuid::now_v7(),
+            ..User::default()
+        };
+        let user_id = user.id;
+
+        let mut users = MockUserService::new();
+        let mut email_addresses = MockEmailAddressService::new();
+
+        users
+            .expect_get_user_by_id()
+            .returning(move |_| Ok(user.clone()));
+
+        let expected_origin = RequestOrigin {
+            ip: Some(IpAddr::V4(Ipv4Addr::new(203, 0, 113, 7))),
+            user_agent: Some("Mozilla/5.0".to_string()),
+        };
+
+        email_addresses
+            .expect_send_email_confirmation()
+            .times(1)
+            .withf(move |_, _, _, origin| *origin == expected_origin)
+            .returning(|_, _, _, _| {
+                Ok(SentEmailConfirmation {
+                    expires_at: Utc::now(),
+                    link: "https://example.com/confirm".to_string(),
+                })
+            });
+
+        let mut state = test_state(Some(users), Some(email_addresses));
+        state.config.trusted_proxies = TrustedProxiesConfig {
+            trusted_proxies: vec!["10.0.0.0/8".parse()?],
+            ..TrustedProxiesConfig::default()
+        };
+
+        let peer = SocketAddr::new(Ipv4Addr::new(10, 0, 0, 1).into(), 1234);
+
+        TestServer::new(router(state).layer(Extension(ConnectInfo(peer))))?
+            .post(&format!("/api/v1/users/{user_id}/email/change"))
+            .add_header(
+                HeaderName::from_static("x-forwarded-for"),
+                "203.0.113.7".parse()?,
+            )
+            .add_header(USER_AGENT, "Mozilla/5.0".parse()?)
+            .json(&json!({ "email": "new_email@example.com" }))
+            .await
+            .assert_status(StatusCode::ACCEPTED);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_change_email_confirmation_rejects_form_data() -> TestResult {
+        let state = test_state(None, None);
+
+        let response = TestServer::new(router(state))?
+            .post(&format!("/api/v1/users/{}/email/change", Uuid::now_v7()))
+            .form(&json!({ "email": "new_email@example.com" }))
+            .await;
+
+        response.assert_status(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let json = response.json::<ErrorResponse>();
+
+        assert_eq!(json.code.as_deref(), Some("unsupported_media_type"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_change_email_confirmation_reports_invalid_email() -> TestResult {
+        let state = test_state(None, None);
+
+        let response = TestServer::new(router(state))?
+            .post(&format!("/api/v1/users/{}/email/change", Uuid::now_v7()))
+            .json(&json!({ "email": "not an email" }))
+            .await;
+
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+
+        let json = response.json::<ErrorResponse>();
+
+        assert_eq!(json.error, "Please provide a valid email address");
+        assert_eq!(json.validation_errors.len(), 1);
+        assert_eq!(json.validation_errors[0].field, "email");
+        assert_eq!(json.validation_errors[0].code, "invalid");
+
+        Ok(())
+    }
+
+    async fn send_change_email_confirmation_if_match(
+        if_match: &str,
+        sends: usize,
+    ) -> TestResult<TestResponse> {
+        let user = User {
+            id: Uuid::now_v7(),
+            version: 2,
+            ..User::default()
+        };
+        let user_id = user.id;
+
+        let mut users = MockUserService::new();
+        let mut email_addresses = MockEmailAddressService::new();
+
+        users
+            .expect_get_user_by_id()
+            .returning(move |_| Ok(user.clone()));
+
+        email_addresses
+            .expect_send_email_confirmation()
+            .times(sends)
+            .returning(|_, _, _, _| {
+                Ok(SentEmailConfirmation {
+                    expires_at: Utc::now(),
+                    link: "https://example.com/confirm".to_string(),
+                })
+            });
+
+        let state = test_state(Some(users), Some(email_addresses));
+
+        Ok(TestServer::new(router(state))?
+            .post(&format!("/api/v1/users/{user_id}/email/change"))
+            .add_header(IF_MATCH, if_match.parse()?)
+            .json(&json!({ "email": "new_email@example.com" }))
+            .await)
+    }
+
+    #[tokio::test]
+    async fn test_send_change_email_confirmation_with_current_version() -> TestResult {
+        let response = send_change_email_confirmation_if_match("\"2\"", 1).await?;
+
+        response.assert_status(StatusCode::ACCEPTED);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_change_email_confirmation_with_stale_version() -> TestResult {
+        let response = send_change_email_confirmation_if_match("\"1\"", 0).await?;
+
+        response.assert_status(StatusCode::CONFLICT);
+
+        let json = response.json::<ErrorResponse>();
+
+        assert_eq!(json.code.as_deref(), Some("version_conflict"));
 
         Ok(())
     }
@@ -5,59 +5,219 @@ use axum::{
     response::{ErrorResponse, IntoResponse},
 };
 use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
 use crate::{
-    domain::{auth::users::UserService, communication::email_addresses::EmailAddressService},
+    domain::{
+        auth::users::UserService,
+        communication::email_addresses::{EmailAddressService, RequestOrigin},
+        health::DatabaseHealth,
+        i18n::Locale,
+        idempotency::IdempotencyStore,
+    },
     infrastructure::http::{
-        state::AppState, templates::auth::email_confirmed::EmailConfirmedTemplate,
+        errors::LocalizedError,
+        extractors::{
+            accept::{PreferredFormat, ResponseFormat},
+            accept_language::AcceptLanguage,
+            client_origin::ClientOrigin,
+        },
+        state::AppState,
+        templates::auth::email_confirmed::EmailConfirmedTemplate,
     },
 };
 
-#[derive(Debug, Serialize, Deserialize, ToSchema)]
+/// Confirm email query parameters
+#[derive(Debug, Serialize, Deserialize, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct ConfirmEmailParams {
+    /// The confirmation token from the email
+    #[schema(example = "5f0c6f8e0d1e4a8e9a7b2f6b1c9d3e4f")]
     pub token: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, ToSchema)]
-pub struct EmailConfirmedResponse {
-    success: bool,
-}
-
 /// Confirm a user's email address
-pub async fn handler<U: UserService, E: EmailAddressService>(
-    State(state): State<AppState<U, E>>,
+///
+/// This is the link in the confirmation email, so it responds with an HTML page in the language
+/// the browser prefers. Errors are JSON instead if the `Accept` header prefers it.
+#[utoipa::path(
+    get,
+    operation_id = "confirm_email",
+    tag = "Auth",
+    path = "/api/v1/users/{id}/email/confirmation",
+    params(
+        ("id" = Uuid, Path, description = "The UUID of the user", example = "550e8400-e29b-41d4-a716-446655440000"),
+        ConfirmEmailParams,
+        ("Accept-Language" = Option<String>, Header, description = "The language the page is written in, English if none of them have translations", example = "fr-CA, fr;q=0.9, en;q=0.8"),
+        ("Accept" = Option<String>, Header, description = "Errors are JSON if this prefers `application/json`, and pages otherwise", example = "application/json"),
+    ),
+    responses(
+        (status = StatusCode::OK, description = "Email address confirmed", body = String, content_type = "text/html"),
+        (status = StatusCode::NOT_FOUND, description = "User not found", content(("text/html" = String), ("application/json" = ErrorResponse))),
+        (status = StatusCode::CONFLICT, description = "Email already confirmed, no email change pending, or the email address is in use", content(("text/html" = String), ("application/json" = ErrorResponse))),
+        (status = StatusCode::UNPROCESSABLE_ENTITY, description = "Confirmation token has expired or does not match", content(("text/html" = String), ("application/json" = ErrorResponse))),
+        (status = StatusCode::INTERNAL_SERVER_ERROR, description = "Internal Server Error", content(("text/html" = String), ("application/json" = ErrorResponse))),
+        (status = StatusCode::SERVICE_UNAVAILABLE, description = "Email delivery is temporarily unavailable", content(("text/html" = String), ("application/json" = ErrorResponse))),
+        (status = StatusCode::TOO_MANY_REQUESTS, description = "Too many requests", body = TooManyRequestsResponse),
+    )
+)]
+pub async fn handler<
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+>(
+    State(state): State<AppState<U, E, I, H>>,
     Path(user_id): Path<Uuid>,
     Query(query): Query<ConfirmEmailParams>,
+    AcceptLanguage(locale): AcceptLanguage,
+    PreferredFormat(format): PreferredFormat,
+    ClientOrigin(origin): ClientOrigin,
 ) -> Result<impl IntoResponse, ErrorResponse> {
-    let user = state.users.get_user_by_id(&user_id).await?;
+    confirm(&state, &user_id, &query.token, &origin, locale, format).await?;
+
+    Ok((StatusCode::OK, EmailConfirmedTemplate { locale }))
+}
+
+/// Confirms the user's email address with `token`, however it was submitted, by the client at
+/// `origin`, responding to any error in `format`
+pub async fn confirm<
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+>(
+    state: &AppState<U, E, I, H>,
+    user_id: &Uuid,
+    token: &str,
+    origin: &RequestOrigin,
+    locale: Locale,
+    format: ResponseFormat,
+) -> Result<(), ErrorResponse> {
+    let user = state
+        .users
+        .get_user_by_id(user_id)
+        .await
+        .map_err(|err| LocalizedError::new(err, locale).with_format(format))?;
 
     state
         .email_addresses
-        .confirm_email(&user, &query.token)
-        .await?;
+        .confirm_email(&user, token, origin)
+        .await
+        .map_err(|err| LocalizedError::new(err, locale).with_format(format))?;
 
-    Ok((StatusCode::OK, EmailConfirmedTemplate))
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use axum::http::StatusCode;
-    use axum_test::TestServer;
+    use axum::http::{
+        header::{ACCEPT, ACCEPT_LANGUAGE, CONTENT_TYPE},
+        StatusCode,
+    };
+    use axum_test::{TestResponse, TestServer};
     use testresult::TestResult;
     use uuid::Uuid;
 
     use crate::{
         domain::{
-            auth::users::{tests::MockUserService, User},
+            auth::{
+                emails::confirm_email_address::confirmation_url,
+                users::{errors::GetUserByIdError, tests::MockUserService, User},
+            },
+            base_url::tests::example_base_url,
             communication::email_addresses::{
-                tests::MockEmailAddressService, EmailConfirmationError,
+                tests::MockEmailAddressService, EmailAddress, EmailConfirmationError,
             },
         },
-        infrastructure::http::{servers::https::router, state::tests::test_state},
+        infrastructure::http::{
+            errors::ErrorResponse, servers::https::router, state::tests::test_state,
+        },
     };
 
+    async fn confirm_unknown_user_accepting(accept: &str) -> TestResult<TestResponse> {
+        let mut users = MockUserService::new();
+
+        users
+            .expect_get_user_by_id()
+            .times(1)
+            .returning(|_| Err(GetUserByIdError::UserNotFound));
+
+        let state = test_state(Some(users), None);
+
+        Ok(TestServer::new(router(state))?
+            .get(&format!(
+                "/api/v1/users/{}/email/confirmation",
+                Uuid::now_v7()
+            ))
+            .add_query_param("token", "test-token")
+            .add_header(ACCEPT, accept.parse()?)
+            .await)
+    }
+
+    #[tokio::test]
+    async fn test_confirm_email_error_is_a_page_for_browsers() -> TestResult {
+        let response = confirm_unknown_user_accepting(
+            "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8",
+        )
+        .await?;
+
+        response.assert_status(StatusCode::NOT_FOUND);
+        assert!(response
+            .header(CONTENT_TYPE)
+            .to_str()?
+            .starts_with("text/html"));
+        response.assert_text_contains("Not found.");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_confirm_email_error_is_json_for_api_clients() -> TestResult {
+        let response = confirm_unknown_user_accepting("application/json").await?;
+
+        response.assert_status(StatusCode::NOT_FOUND);
+        assert_eq!(response.header(CONTENT_TYPE), "application/json");
+        assert_eq!(response.json::<ErrorResponse>().error, "User not found");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_confirm_email_invalid_token_is_json_for_api_clients() -> TestResult {
+        let mut users = MockUserService::new();
+        let mut email_addresses = MockEmailAddressService::new();
+
+        users
+            .expect_get_user_by_id()
+            .returning(|_| Ok(User::default()));
+
+        email_addresses
+            .expect_confirm_email()
+            .times(1)
+            .returning(|_, _, _| Err(EmailConfirmationError::ConfirmationTokenMismatch));
+
+        let state = test_state(Some(users), Some(email_addresses));
+
+        let response = TestServer::new(router(state))?
+            .get(&format!(
+                "/api/v1/users/{}/email/confirmation",
+                Uuid::now_v7()
+            ))
+            .add_query_param("token", "test-token")
+            .add_header(ACCEPT, "application/json".parse()?)
+            .await;
+
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            response.json::<ErrorResponse>().error,
+            "Confirmation token does not match"
+        );
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_confirm_email_success() -> TestResult {
         let user_id = Uuid::now_v7();
@@ -77,8 +237,8 @@ mod tests {
         email_addresses
             .expect_confirm_email()
             .times(1)
-            .withf(move |user, token| *user == expected_user && token == "test-token")
-            .returning(move |_, _| Ok(()));
+            .withf(move |user, token, _| *user == expected_user && token == "test-token")
+            .returning(move |_, _, _| Ok(()));
 
         let state = test_state(Some(users), Some(email_addresses));
 
@@ -96,6 +256,48 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_confirmation_url_is_handled_by_confirm_email() -> TestResult {
+        let user_id = Uuid::now_v7();
+
+        let mut users = MockUserService::new();
+        let mut email_addresses = MockEmailAddressService::new();
+
+        users
+            .expect_get_user_by_id()
+            .times(1)
+            .withf(move |id| *id == user_id)
+            .returning(move |_| Ok(User::default()));
+
+        email_addresses
+            .expect_confirm_email()
+            .times(1)
+            .withf(|_, token, _| token == "f9l4Cu5Mpwxu48ITlEfh3QNCgRrda_p23dtSx-ETfkY=")
+            .returning(move |_, _, _| Ok(()));
+
+        let state = test_state(Some(users), Some(email_addresses));
+
+        let base_url = example_base_url();
+        let url = confirmation_url(
+            &base_url,
+            &user_id,
+            "f9l4Cu5Mpwxu48ITlEfh3QNCgRrda_p23dtSx-ETfkY=",
+        );
+        let (path, query) = url
+            .strip_prefix(base_url.as_str())
+            .and_then(|url| url.split_once('?'))
+            .ok_or("confirmation URL has no path and query")?;
+
+        let response = TestServer::new(router(state))?
+            .get(path)
+            .add_raw_query_param(query)
+            .await;
+
+        response.assert_status(StatusCode::OK);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_confirm_email_user_not_found() -> TestResult {
         let user_id = Uuid::now_v7();
@@ -115,8 +317,8 @@ mod tests {
         email_addresses
             .expect_confirm_email()
             .times(1)
-            .withf(move |user, token| *user == expected_user && token == "test-token")
-            .returning(move |_, _| Err(EmailConfirmationError::UserNotFound));
+            .withf(move |user, token, _| *user == expected_user && token == "test-token")
+            .returning(move |_, _, _| Err(EmailConfirmationError::UserNotFound));
 
         let state = test_state(Some(users), Some(email_addresses));
 
@@ -153,8 +355,8 @@ mod tests {
         email_addresses
             .expect_confirm_email()
             .times(1)
-            .withf(move |user, token| *user == expected_user && token == "test-token")
-            .returning(move |_, _| Err(EmailConfirmationError::ConfirmationTokenMismatch));
+            .withf(move |user, token, _| *user == expected_user && token == "test-token")
+            .returning(move |_, _, _| Err(EmailConfirmationError::ConfirmationTokenMismatch));
 
         let state = test_state(Some(users), Some(email_addresses));
 
@@ -190,8 +392,8 @@ mod tests {
         email_addresses
             .expect_confirm_email()
             .times(1)
-            .withf(move |user, token| *user == expected_user && token == "test-token")
-            .returning(move |_, _| Err(EmailConfirmationError::EmailAlreadyConfirmed));
+            .withf(move |user, token, _| *user == expected_user && token == "test-token")
+            .returning(move |_, _, _| Err(EmailConfirmationError::EmailAlreadyConfirmed));
 
         let state = test_state(Some(users), Some(email_addresses));
 
@@ -207,4 +409,96 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_confirm_email_new_email_taken() -> TestResult {
+        let user_id = Uuid::now_v7();
+
+        let mut users = MockUserService::new();
+        let mut email_addresses = MockEmailAddressService::new();
+
+        let user = User {
+            new_email: Some(EmailAddress::new_unchecked("taken@example.com")),
+            ..User::default()
+        };
+
+        users
+            .expect_get_user_by_id()
+            .times(1)
+            .withf(move |id| *id == user_id)
+            .returning(move |_| Ok(user.clone()));
+
+        email_addresses
+            .expect_confirm_email()
+            .times(1)
+            .returning(move |_, _, _| Err(EmailConfirmationError::EmailAddressInUse));
+
+        let state = test_state(Some(users), Some(email_addresses));
+
+        let response = TestServer::new(router(state))?
+            .get(&format!("/api/v1/users/{}/email/confirmation", user_id))
+            .add_query_param("token", "test-token")
+            .await;
+
+        response.assert_status(StatusCode::CONFLICT);
+        response.assert_text_contains("already in use by another account");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_confirm_email_in_accepted_language() -> TestResult {
+        let user_id = Uuid::now_v7();
+
+        let mut users = MockUserService::new();
+        let mut email_addresses = MockEmailAddressService::new();
+
+        users
+            .expect_get_user_by_id()
+            .times(1)
+            .returning(move |_| Ok(User::default()));
+
+        email_addresses
+            .expect_confirm_email()
+            .times(1)
+            .returning(move |_, _, _| Ok(()));
+
+        let state = test_state(Some(users), Some(email_addresses));
+
+        let response = TestServer::new(router(state))?
+            .get(&format!("/api/v1/users/{}/email/confirmation", user_id))
+            .add_query_param("token", "test-token")
+            .add_header(ACCEPT_LANGUAGE, "fr-CA, fr;q=0.9, en;q=0.8".parse()?)
+            .await;
+
+        response.assert_status(StatusCode::OK);
+        response.assert_text_contains("Votre adresse e-mail a été confirmée.");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_confirm_email_error_in_accepted_language() -> TestResult {
+        let user_id = Uuid::now_v7();
+
+        let mut users = MockUserService::new();
+
+        users
+            .expect_get_user_by_id()
+            .times(1)
+            .returning(move |_| Err(GetUserByIdError::UserNotFound));
+
+        let state = test_state(Some(users), None);
+
+        let response = TestServer::new(router(state))?
+            .get(&format!("/api/v1/users/{}/email/confirmation", user_id))
+            .add_query_param("token", "test-token")
+            .add_header(ACCEPT_LANGUAGE, "fr".parse()?)
+            .await;
+
+        response.assert_status(StatusCode::NOT_FOUND);
+        response.assert_text_contains("Introuvable.");
+
+        Ok(())
+    }
 }
@@ -0,0 +1,181 @@
+//! Confirm email with a JSON body
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::ErrorResponse,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        auth::users::UserService, communication::email_addresses::EmailAddressService,
+        health::DatabaseHealth, i18n::Locale, idempotency::IdempotencyStore,
+    },
+    infrastructure::http::{
+        extractors::{accept::ResponseFormat, client_origin::ClientOrigin, json::JsonBody},
+        handlers::v1::auth::confirm_email::confirm,
+        state::AppState,
+    },
+};
+
+/// Confirm email request
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ConfirmEmailRequest {
+    /// The confirmation token from the email
+    #[schema(example = "5f0c6f8e0d1e4a8e9a7b2f6b1c9d3e4f")]
+    pub token: String,
+}
+
+/// Confirm a user's email address, with the token in the body
+///
+/// For API clients and front-ends, which can keep the token out of the URL, and so out of access
+/// logs and browser history. The link in the confirmation email uses `GET` on
+/// `/api/v1/users/{id}/email/confirmation` instead.
+#[utoipa::path(
+    post,
+    operation_id = "confirm_email_json",
+    tag = "Auth",
+    path = "/api/v1/users/{id}/email/confirm",
+    request_body = ConfirmEmailRequest,
+    params(
+        ("id" = Uuid, Path, description = "The UUID of the user", example = "550e8400-e29b-41d4-a716-446655440000"),
+    ),
+    responses(
+        (status = StatusCode::NO_CONTENT, description = "Email address confirmed"),
+        (status = StatusCode::UNSUPPORTED_MEDIA_TYPE, description = "The body isn't JSON", body = ErrorResponse, example = json!({"error": "Expected a request body with the `Content-Type: application/json` header", "code": "unsupported_media_type"})),
+        (status = StatusCode::NOT_FOUND, description = "User not found", body = ErrorResponse),
+        (status = StatusCode::CONFLICT, description = "Email already confirmed, no email change pending, or the email address is in use", body = ErrorResponse),
+        (status = StatusCode::UNPROCESSABLE_ENTITY, description = "Confirmation token has expired or does not match", body = ErrorResponse),
+        (status = StatusCode::INTERNAL_SERVER_ERROR, description = "Internal Server Error", body = ErrorResponse),
+        (status = StatusCode::SERVICE_UNAVAILABLE, description = "Email delivery is temporarily unavailable", body = ErrorResponse),
+        (status = StatusCode::TOO_MANY_REQUESTS, description = "Too many requests", body = TooManyRequestsResponse),
+    )
+)]
+pub async fn handler<
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+>(
+    State(state): State<AppState<U, E, I, H>>,
+    Path(user_id): Path<Uuid>,
+    ClientOrigin(origin): ClientOrigin,
+    JsonBody(body): JsonBody<ConfirmEmailRequest>,
+) -> Result<StatusCode, ErrorResponse> {
+    confirm(
+        &state,
+        &user_id,
+        &body.token,
+        &origin,
+        Locale::default(),
+        ResponseFormat::Json,
+    )
+    .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use axum_test::{TestResponse, TestServer};
+    use serde_json::json;
+    use testresult::TestResult;
+    use uuid::Uuid;
+
+    use crate::{
+        domain::{
+            auth::users::{tests::MockUserService, User},
+            communication::email_addresses::{
+                tests::MockEmailAddressService, EmailConfirmationError,
+            },
+        },
+        infrastructure::http::{
+            errors::ErrorResponse, servers::https::router, state::tests::test_state,
+        },
+    };
+
+    async fn confirm_returning(
+        result: Result<(), EmailConfirmationError>,
+    ) -> TestResult<TestResponse> {
+        let user_id = Uuid::now_v7();
+
+        let mut users = MockUserService::new();
+        let mut email_addresses = MockEmailAddressService::new();
+
+        users
+            .expect_get_user_by_id()
+            .times(1)
+            .withf(move |id| *id == user_id)
+            .returning(|_| Ok(User::default()));
+
+        let mut result = Some(result);
+
+        email_addresses
+            .expect_confirm_email()
+            .times(1)
+            .withf(|_, token, _| token == "test-token")
+            .returning(move |_, _, _| result.take().unwrap_or(Ok(())));
+
+        let state = test_state(Some(users), Some(email_addresses));
+
+        Ok(TestServer::new(router(state))?
+            .post(&format!("/api/v1/users/{user_id}/email/confirm"))
+            .json(&json!({ "token": "test-token" }))
+            .await)
+    }
+
+    #[tokio::test]
+    async fn test_confirm_email_json_success() -> TestResult {
+        let response = confirm_returning(Ok(())).await?;
+
+        response.assert_status(StatusCode::NO_CONTENT);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_confirm_email_json_token_mismatch() -> TestResult {
+        let response =
+            confirm_returning(Err(EmailConfirmationError::ConfirmationTokenMismatch)).await?;
+
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            response.json::<ErrorResponse>().error,
+            "Confirmation token does not match"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_confirm_email_json_token_expired() -> TestResult {
+        let response =
+            confirm_returning(Err(EmailConfirmationError::ConfirmationTokenExpired)).await?;
+
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            response.json::<ErrorResponse>().error,
+            "Confirmation token has expired"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_confirm_email_json_requires_a_token() -> TestResult {
+        let state = test_state(None, None);
+
+        let response = TestServer::new(router(state))?
+            .post(&format!("/api/v1/users/{}/email/confirm", Uuid::now_v7()))
+            .json(&json!({}))
+            .await;
+
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+
+        Ok(())
+    }
+}
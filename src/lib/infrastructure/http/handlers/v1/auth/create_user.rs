@@ -1,20 +1,31 @@
 //! Create user handler
 
 use axum::{
-    extract::{rejection::JsonRejection, State},
-    http::StatusCode,
+    extract::State,
+    http::{header::LOCATION, HeaderName, StatusCode},
     Json,
 };
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use uuid::Uuid;
 
 use crate::{
     domain::{
-        auth::users::{NewUser, Password, UserService},
+        auth::users::{NewUser, NewUserBuilder, Password, UserService, Username},
         communication::email_addresses::{EmailAddress, EmailAddressService},
+        health::DatabaseHealth,
+        idempotency::IdempotencyStore,
+    },
+    infrastructure::http::{
+        errors::{ApiError, ValidationError},
+        extractors::{
+            accept_language::AcceptLanguage,
+            client_origin::ClientOrigin,
+            validated_json::{Validate, ValidatedJson},
+        },
+        state::{AppConfig, AppState},
     },
-    infrastructure::http::{errors::ApiError, state::AppState},
 };
 
 /// Create user request body
@@ -27,17 +38,80 @@ pub struct CreateUserBody {
     /// The new user's password
     #[schema(example = "correcthorsebatterystaple")]
     pub password: String,
-}
 
-impl TryFrom<CreateUserBody> for NewUser {
-    type Error = ApiError;
+    /// The new user's username, if they want one
+    #[schema(example = "jane_doe")]
+    #[serde(default)]
+    pub username: Option<String>,
+
+    /// Whether the new user accepts the terms of service, which may be required to sign up
+    #[schema(example = true)]
+    #[serde(default)]
+    pub accepted_terms: bool,
+
+    /// The version of the terms of service the new user accepts
+    #[schema(example = "2024-08-01")]
+    #[serde(default)]
+    pub terms_version: Option<String>,
+}
 
-    fn try_from(body: CreateUserBody) -> Result<Self, Self::Error> {
-        Ok(Self::new(
-            Uuid::now_v7(),
-            EmailAddress::new(&body.email)?,
-            Password::new(&body.password)?,
-        ))
+impl Validate for CreateUserBody {
+    type Valid = NewUserBuilder;
+
+    /// Validate the request body against the password policy, signup email policy and signup
+    /// requirements, and start building a [`NewUser`] from it, which hashes the password once it
+    /// is built. Every invalid field is reported, not just the first.
+    fn validate(self, config: &AppConfig) -> Result<NewUserBuilder, Vec<ValidationError>> {
+        let email = EmailAddress::new(&self.email)
+            .and_then(|email| config.signup_email_policy.check(&email).map(|()| email))
+            .map_err(|err| ValidationError::new("email", &err));
+
+        let password = Password::new_with_policy(&self.password, &config.password_policy)
+            .map_err(|err| ValidationError::new("password", &err));
+
+        let username = self
+            .username
+            .as_deref()
+            .map(Username::new)
+            .transpose()
+            .map_err(|err| ValidationError::new("username", &err));
+
+        let terms = if config.signup.require_terms_acceptance && !self.accepted_terms {
+            Err(ValidationError {
+                field: "accepted_terms".to_string(),
+                code: "required".to_string(),
+                message: "You must accept the terms of service".to_string(),
+                suggestions: vec![],
+            })
+        } else {
+            Ok(())
+        };
+
+        let (email, password, username) = match (email, password, username, terms) {
+            (Ok(email), Ok(password), Ok(username), Ok(())) => (email, password, username),
+            (email, password, username, terms) => {
+                return Err([email.err(), password.err(), username.err(), terms.err()]
+                    .into_iter()
+                    .flatten()
+                    .collect());
+            }
+        };
+
+        let new_user = NewUser::builder()
+            .email(email)
+            .password(password)
+            .hashing(config.password_hashing.clone());
+
+        let new_user = match username {
+            Some(username) => new_user.username(username),
+            None => new_user,
+        };
+
+        Ok(if self.accepted_terms {
+            new_user.accepted_terms(self.terms_version)
+        } else {
+            new_user
+        })
     }
 }
 
@@ -49,6 +123,13 @@ pub struct CreateUserResponse {
 
     #[schema(example = "email@example.com", value_type = String)]
     email: EmailAddress,
+
+    #[schema(example = "jane_doe", value_type = Option<String>)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    username: Option<Username>,
+
+    #[schema(example = "2024-08-17T07:38:29Z")]
+    created_at: DateTime<Utc>,
 }
 
 /// Create a new user
@@ -58,47 +139,86 @@ pub struct CreateUserResponse {
     tag = "Auth",
     path = "/api/v1/users",
     request_body = CreateUserBody,
+    params(
+        ("Accept-Language" = Option<String>, Header, description = "The language emails to the new user are written in, English if none of them have translations", example = "fr-CA, fr;q=0.9, en;q=0.8"),
+        ("Idempotency-Key" = Option<String>, Header, description = "Makes the request safe to retry: repeats within 24 hours replay the original response", example = "5f0c6f8e-0d1e-4a8e-9a7b-2f6b1c9d3e4f"),
+    ),
     responses(
-        (status = StatusCode::CREATED, description = "User created", body = CreateUserResponse),
-        (status = StatusCode::UNPROCESSABLE_ENTITY, description = "Unprocessable entity", body = ErrorResponse),
-        (status = StatusCode::CONFLICT, description = "User already exists", body = ErrorResponse, example = json!({"message": "User with email \"email@example.com\" aleady exists"})),
+        (status = StatusCode::CREATED, description = "User created", body = CreateUserResponse, headers(
+            ("Location" = String, description = "The path of the new user, e.g. `/api/v1/users/497f6eca-6276-4993-bfeb-53cbbbba6f08`"),
+        )),
+        (status = StatusCode::UNSUPPORTED_MEDIA_TYPE, description = "The body isn't JSON", body = ErrorResponse, example = json!({"error": "Expected a request body with the `Content-Type: application/json` header", "code": "unsupported_media_type"})),
+        (status = StatusCode::UNPROCESSABLE_ENTITY, description = "One or more fields are invalid", body = ErrorResponse, example = json!({"error": "Please provide a valid email address; Password must be at least 8 characters long", "code": "validation_failed", "validation_errors": [{"field": "email", "code": "invalid", "message": "Please provide a valid email address"}, {"field": "password", "code": "too_short", "message": "Password must be at least 8 characters long"}]})),
+        (status = StatusCode::CONFLICT, description = "User already exists, or the Idempotency-Key is in use", body = ErrorResponse, example = json!({"message": "User with email \"email@example.com\" aleady exists"})),
         (status = StatusCode::TOO_MANY_REQUESTS, description = "Too many requests", body = TooManyRequestsResponse),
     )
 )]
-pub async fn handler<U: UserService, E: EmailAddressService>(
-    State(state): State<AppState<U, E>>,
-    request: Result<Json<CreateUserBody>, JsonRejection>,
-) -> Result<(StatusCode, Json<CreateUserResponse>), ApiError> {
-    let Json(request) = request?;
-    let email = request.email.clone();
-
-    let new_user: NewUser = request.try_into()?;
-
-    let id = state.users.create_user(&new_user).await?;
+pub async fn handler<
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+>(
+    State(state): State<AppState<U, E, I, H>>,
+    AcceptLanguage(locale): AcceptLanguage,
+    ClientOrigin(origin): ClientOrigin,
+    ValidatedJson(new_user): ValidatedJson<CreateUserBody>,
+) -> Result<
+    (
+        StatusCode,
+        [(HeaderName, String); 1],
+        Json<CreateUserResponse>,
+    ),
+    ApiError,
+> {
+    // Emails to the new user are written in the language they signed up in
+    let new_user = new_user
+        .locale(locale)
+        .build()
+        .map_err(anyhow::Error::from)?;
+
+    state.check_deliverable("email", new_user.email()).await?;
+
+    // The user is returned as they were stored
+    let user = state.users.create_user(&new_user, &origin).await?;
 
     Ok((
         StatusCode::CREATED,
+        [(LOCATION, format!("/api/v1/users/{}", user.id))],
         Json(CreateUserResponse {
-            id,
-            email: EmailAddress::new_unchecked(&email),
+            id: user.id,
+            email: user.email,
+            username: new_user.username().cloned(),
+            created_at: user.timestamps.created_at,
         }),
     ))
 }
 
 #[cfg(test)]
 mod tests {
-    use axum::http::StatusCode;
+    use std::sync::Arc;
+
+    use axum::http::{
+        header::{ACCEPT_LANGUAGE, LOCATION},
+        StatusCode,
+    };
     use axum_test::TestServer;
+    use chrono::Utc;
     use testresult::TestResult;
     use uuid::Uuid;
 
     use crate::{
         domain::{
-            auth::users::{errors::CreateUserError, tests::MockUserService},
-            communication::email_addresses::EmailAddress,
+            auth::users::{
+                errors::CreateUserError, tests::MockUserService, PasswordHashingConfig, User,
+                Username,
+            },
+            communication::email_addresses::{tests::MockMailDomainResolver, EmailAddress},
+            i18n::Locale,
+            timestamps::Timestamps,
         },
         infrastructure::http::{
-            errors::ErrorResponse,
+            errors::{ErrorResponse, ValidationError},
             handlers::v1::auth::create_user::{CreateUserBody, CreateUserResponse},
             servers::https::router,
             state::tests::test_state,
@@ -111,22 +231,45 @@ mod tests {
             Self {
                 email: email.to_string(),
                 password: password.to_string(),
+                username: None,
+                accepted_terms: false,
+                terms_version: None,
             }
         }
     }
 
+    /// The user as they were stored
+    fn created(id: Uuid) -> User {
+        User {
+            id,
+            email: EmailAddress::new_unchecked("email@example.com"),
+            ..User::default()
+        }
+    }
+
     #[tokio::test]
     async fn test_create_user_success() -> TestResult {
         let mut user_service = MockUserService::new();
         let user_id = Uuid::now_v7();
+        let created_at = Utc::now();
 
         let email = EmailAddress::new("email@example.com")?;
         let body = CreateUserBody::new(&email.to_string(), "correcthorsebatterystaple");
+        let stored_email = email.clone();
 
         user_service
             .expect_create_user()
-            .withf(move |user| user.email() == &email)
-            .returning(move |_| Ok(user_id.clone()));
+            .withf(move |user, _| user.email() == &email)
+            .returning(move |_, _| {
+                Ok(User {
+                    id: user_id,
+                    email: stored_email.clone(),
+                    timestamps: Timestamps::created(created_at),
+                    ..User::default()
+                })
+            });
+
+        user_service.expect_get_user_by_id().times(0);
 
         let state = test_state(Some(user_service), None);
 
@@ -139,6 +282,165 @@ mod tests {
 
         assert_eq!(response.status_code(), StatusCode::CREATED);
         assert_eq!(json.id, user_id);
+        assert_eq!(json.created_at, created_at);
+        assert_eq!(
+            response.header(LOCATION),
+            format!("/api/v1/users/{}", json.id)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_user_hashes_password_with_configured_parameters() -> TestResult {
+        let mut user_service = MockUserService::new();
+        let user_id = Uuid::now_v7();
+
+        user_service
+            .expect_create_user()
+            .times(1)
+            .withf(|user, _| user.password_hash().contains("m=8,t=1,p=1"))
+            .returning(move |_, _| Ok(created(user_id)));
+
+        let mut state = test_state(Some(user_service), None);
+        state.config.password_hashing = PasswordHashingConfig {
+            memory_kib: 8,
+            iterations: 1,
+            parallelism: 1,
+        };
+
+        let response = TestServer::new(router(state))?
+            .post("/api/v1/users")
+            .json(&CreateUserBody::new(
+                "email@example.com",
+                "correcthorsebatterystaple",
+            ))
+            .await;
+
+        assert_eq!(response.status_code(), StatusCode::CREATED);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_user_with_username() -> TestResult {
+        let mut user_service = MockUserService::new();
+        let user_id = Uuid::now_v7();
+
+        let body = CreateUserBody {
+            username: Some("Jane_Doe".to_string()),
+            ..CreateUserBody::new("email@example.com", "correcthorsebatterystaple")
+        };
+
+        user_service
+            .expect_create_user()
+            .withf(|user, _| user.username() == Some(&Username::new_unchecked("jane_doe")))
+            .returning(move |_, _| Ok(created(user_id)));
+
+        let state = test_state(Some(user_service), None);
+
+        let response = TestServer::new(router(state))?
+            .post("/api/v1/users")
+            .json(&body)
+            .await;
+
+        let json = response.json::<CreateUserResponse>();
+
+        assert_eq!(response.status_code(), StatusCode::CREATED);
+        assert_eq!(json.username, Some(Username::new_unchecked("jane_doe")));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_user_records_accepted_language() -> TestResult {
+        let mut user_service = MockUserService::new();
+        let user_id = Uuid::now_v7();
+
+        user_service
+            .expect_create_user()
+            .times(1)
+            .withf(|user, _| user.locale() == Locale::French)
+            .returning(move |_, _| Ok(created(user_id)));
+
+        let state = test_state(Some(user_service), None);
+
+        let response = TestServer::new(router(state))?
+            .post("/api/v1/users")
+            .add_header(ACCEPT_LANGUAGE, "fr-FR, en;q=0.5".parse()?)
+            .json(&CreateUserBody::new(
+                "email@example.com",
+                "correcthorsebatterystaple",
+            ))
+            .await;
+
+        assert_eq!(response.status_code(), StatusCode::CREATED);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_user_username_too_short() -> TestResult {
+        let state = test_state(None, None);
+
+        let response = TestServer::new(router(state))?
+            .post("/api/v1/users")
+            .json(&CreateUserBody {
+                username: Some("ab".to_string()),
+                ..CreateUserBody::new("email@example.com", "correcthorsebatterystaple")
+            })
+            .await;
+
+        let json = response.json::<ErrorResponse>();
+
+        assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(json.error, "Username must be at least 3 characters long");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_user_reserved_username() -> TestResult {
+        let state = test_state(None, None);
+
+        let response = TestServer::new(router(state))?
+            .post("/api/v1/users")
+            .json(&CreateUserBody {
+                username: Some("admin".to_string()),
+                ..CreateUserBody::new("email@example.com", "correcthorsebatterystaple")
+            })
+            .await;
+
+        let json = response.json::<ErrorResponse>();
+
+        assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(json.error, "That username is reserved");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_user_duplicate_username() -> TestResult {
+        let mut users = MockUserService::new();
+
+        users
+            .expect_create_user()
+            .returning(|_, _| Err(CreateUserError::DuplicateUsername));
+
+        let state = test_state(Some(users), None);
+
+        let response = TestServer::new(router(state))?
+            .post("/api/v1/users")
+            .json(&CreateUserBody {
+                username: Some("jane_doe".to_string()),
+                ..CreateUserBody::new("email@example.com", "correcthorsebatterystaple")
+            })
+            .await;
+
+        let json = response.json::<ErrorResponse>();
+
+        assert_eq!(response.status_code(), StatusCode::CONFLICT);
+        assert_eq!(json.error, "User already exists with that username");
 
         Ok(())
     }
@@ -163,6 +465,70 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_create_user_email_domain_not_allowed() -> TestResult {
+        let mut users = MockUserService::new();
+
+        users.expect_create_user().times(0);
+
+        let mut state = test_state(Some(users), None);
+        state.config.signup_email_policy.blocked_domains = vec!["mailinator.com".to_string()];
+
+        let response = TestServer::new(router(state))?
+            .post("/api/v1/users")
+            .json(&CreateUserBody::new(
+                "email@mailinator.com",
+                "correcthorsebatterystaple",
+            ))
+            .await;
+
+        let json = response.json::<ErrorResponse>();
+
+        assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            json.error,
+            "Email addresses from this domain can't be used to sign up"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_user_undeliverable_email_when_checked() -> TestResult {
+        let mut users = MockUserService::new();
+
+        users.expect_create_user().times(0);
+
+        let mut resolver = MockMailDomainResolver::new();
+
+        resolver
+            .expect_mail_exchangers()
+            .withf(|domain| domain == "example.invalid")
+            .returning(|_| Ok(vec![]));
+        resolver
+            .expect_has_address_records()
+            .returning(|_| Ok(false));
+
+        let mut state = test_state(Some(users), None);
+        state.mail_domains = Some(Arc::new(resolver));
+
+        let response = TestServer::new(router(state))?
+            .post("/api/v1/users")
+            .json(&CreateUserBody::new(
+                "email@example.invalid",
+                "correcthorsebatterystaple",
+            ))
+            .await;
+
+        let json = response.json::<ErrorResponse>();
+
+        assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(json.validation_errors[0].field, "email");
+        assert_eq!(json.validation_errors[0].code, "undeliverable");
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_create_user_password_error() -> TestResult {
         let state = test_state(None, None);
@@ -180,13 +546,61 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_create_user_weak_password_lists_suggestions() -> TestResult {
+        let state = test_state(None, None);
+
+        let response = TestServer::new(router(state))?
+            .post("/api/v1/users")
+            .json(&CreateUserBody::new("email@example.com", "password"))
+            .await;
+
+        let json = response.json::<ErrorResponse>();
+
+        assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let [error] = json.validation_errors.as_slice() else {
+            return Err(format!("expected one validation error: {json:?}").into());
+        };
+
+        assert_eq!(error.code, "too_weak");
+        assert!(!error.suggestions.is_empty());
+        assert_eq!(
+            error.message,
+            format!("Password is too weak: {}", error.suggestions.join(" "))
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_user_password_too_long_for_policy() -> TestResult {
+        let mut state = test_state(None, None);
+        state.config.password_policy.max_length = 20;
+
+        let response = TestServer::new(router(state))?
+            .post("/api/v1/users")
+            .json(&CreateUserBody::new(
+                "email@example.com",
+                "correcthorsebatterystaple",
+            ))
+            .await;
+
+        let json = response.json::<ErrorResponse>();
+
+        assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(json.error, "Password must be at most 20 bytes long");
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_create_user_duplicate_user() -> TestResult {
         let mut users = MockUserService::new();
 
         users
             .expect_create_user()
-            .returning(|_| Err(CreateUserError::DuplicateUser));
+            .returning(|_, _| Err(CreateUserError::DuplicateUser));
 
         let state = test_state(Some(users), None);
 
@@ -205,4 +619,155 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_create_user_without_accepting_required_terms() -> TestResult {
+        let mut users = MockUserService::new();
+
+        users.expect_create_user().times(0);
+
+        let mut state = test_state(Some(users), None);
+        state.config.signup.require_terms_acceptance = true;
+
+        let server = TestServer::new(router(state))?;
+
+        for accepted_terms in [None, Some(false)] {
+            let mut body = serde_json::to_value(CreateUserBody::new(
+                "email@example.com",
+                "correcthorsebatterystaple",
+            ))?;
+
+            match accepted_terms {
+                Some(accepted_terms) => body["accepted_terms"] = accepted_terms.into(),
+                None => {
+                    body.as_object_mut()
+                        .ok_or("body is not an object")?
+                        .remove("accepted_terms");
+                }
+            }
+
+            let response = server.post("/api/v1/users").json(&body).await;
+
+            let json = response.json::<ErrorResponse>();
+
+            assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+            assert_eq!(json.error, "You must accept the terms of service");
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_user_records_accepted_terms_version() -> TestResult {
+        let mut users = MockUserService::new();
+        let user_id = Uuid::now_v7();
+
+        users
+            .expect_create_user()
+            .withf(|user, _| user.accepted_terms() && user.terms_version() == Some("2024-08-01"))
+            .returning(move |_, _| Ok(created(user_id)));
+
+        let mut state = test_state(Some(users), None);
+        state.config.signup.require_terms_acceptance = true;
+
+        let response = TestServer::new(router(state))?
+            .post("/api/v1/users")
+            .json(&CreateUserBody {
+                accepted_terms: true,
+                terms_version: Some("2024-08-01".to_string()),
+                ..CreateUserBody::new("email@example.com", "correcthorsebatterystaple")
+            })
+            .await;
+
+        assert_eq!(response.status_code(), StatusCode::CREATED);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_user_rejects_form_data() -> TestResult {
+        let mut user_service = MockUserService::new();
+
+        user_service.expect_create_user().times(0);
+
+        let state = test_state(Some(user_service), None);
+
+        let response = TestServer::new(router(state))?
+            .post("/api/v1/users")
+            .form(&CreateUserBody::new(
+                "email@example.com",
+                "correcthorsebatterystaple",
+            ))
+            .await;
+
+        assert_eq!(response.status_code(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let json = response.json::<ErrorResponse>();
+
+        assert_eq!(json.code.as_deref(), Some("unsupported_media_type"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_user_rejects_malformed_json() -> TestResult {
+        let state = test_state(None, None);
+
+        let response = TestServer::new(router(state))?
+            .post("/api/v1/users")
+            .bytes(r#"{"email": "#.into())
+            .content_type("application/json")
+            .await;
+
+        assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+
+        let json = response.json::<ErrorResponse>();
+
+        assert_eq!(json.code.as_deref(), Some("malformed_json"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_user_reports_every_invalid_field() -> TestResult {
+        let mut user_service = MockUserService::new();
+
+        user_service.expect_create_user().times(0);
+
+        let state = test_state(Some(user_service), None);
+
+        let response = TestServer::new(router(state))?
+            .post("/api/v1/users")
+            .json(&CreateUserBody::new("not an email", "short"))
+            .await;
+
+        assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let json = response.json::<ErrorResponse>();
+
+        assert_eq!(json.code.as_deref(), Some("validation_failed"));
+        assert_eq!(
+            json.error,
+            "Please provide a valid email address; Password must be at least 8 characters long"
+        );
+        assert_eq!(
+            json.validation_errors,
+            vec![
+                ValidationError {
+                    field: "email".to_string(),
+                    code: "invalid".to_string(),
+                    message: "Please provide a valid email address".to_string(),
+                    suggestions: vec![],
+                },
+                ValidationError {
+                    field: "password".to_string(),
+                    code: "too_short".to_string(),
+                    message: "Password must be at least 8 characters long".to_string(),
+                    suggestions: vec![],
+                },
+            ]
+        );
+
+        Ok(())
+    }
 }
@@ -0,0 +1,432 @@
+//! Create users in bulk handler
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        auth::users::{NewUser, UserService},
+        communication::email_addresses::{EmailAddressService, RequestOrigin},
+        health::DatabaseHealth,
+        idempotency::IdempotencyStore,
+    },
+    infrastructure::http::{
+        errors::{ApiError, ErrorResponse, ValidationError},
+        extractors::{
+            client_origin::ClientOrigin,
+            json::JsonBody,
+            require_role::{Admin, RequireRole},
+            validated_json::Validate,
+        },
+        handlers::v1::auth::create_user::CreateUserBody,
+        state::AppState,
+    },
+};
+
+/// The most users which can be created in one batch, which bounds how long hashing their
+/// passwords ties up a blocking thread for
+const MAX_BATCH_SIZE: usize = 500;
+
+/// Create users query parameters
+#[derive(Debug, Default, Serialize, Deserialize, IntoParams)]
+pub struct CreateUsersParams {
+    /// Create every user or, if any of them can't be, none, rather than each independently
+    #[param(example = false)]
+    #[serde(default)]
+    atomic: bool,
+}
+
+/// The result of creating one user in a batch
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateUsersResult {
+    /// The status creating the user on its own would have responded with
+    #[schema(example = 201)]
+    status: u16,
+
+    /// The new user's ID, if they were created
+    #[schema(example = "497f6eca-6276-4993-bfeb-53cbbbba6f08")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<Uuid>,
+
+    /// Why the user wasn't created, if they weren't
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<ErrorResponse>,
+}
+
+impl CreateUsersResult {
+    fn created(id: Uuid) -> Self {
+        Self {
+            status: StatusCode::CREATED.as_u16(),
+            id: Some(id),
+            error: None,
+        }
+    }
+
+    fn failed(err: impl Into<ApiError>) -> Self {
+        let err = err.into();
+
+        Self {
+            status: err.status.as_u16(),
+            id: None,
+            error: Some(err.into()),
+        }
+    }
+}
+
+/// Create users response body
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateUsersResponse {
+    /// The result of creating each user, in the order they were given
+    results: Vec<CreateUsersResult>,
+}
+
+/// Create users in bulk
+#[utoipa::path(
+    post,
+    operation_id = "create_users",
+    tag = "Auth",
+    path = "/api/v1/users/batch",
+    request_body = Vec<CreateUserBody>,
+    params(CreateUsersParams),
+    security(("bearerAuth" = [])),
+    responses(
+        (status = StatusCode::OK, description = "The result of creating each user", body = CreateUsersResponse, example = json!({"results": [{"status": 201, "id": "497f6eca-6276-4993-bfeb-53cbbbba6f08"}, {"status": 409, "error": {"error": "User already exists with that email address", "code": "duplicate_email"}}]})),
+        (status = StatusCode::UNAUTHORIZED, description = "Missing, invalid or expired access token", body = ErrorResponse),
+        (status = StatusCode::FORBIDDEN, description = "User is not an admin", body = ErrorResponse, example = json!({ "error": "You do not have permission to do that", "code": "forbidden" })),
+        (status = StatusCode::CONFLICT, description = "A user already exists, when creating them atomically", body = ErrorResponse),
+        (status = StatusCode::UNSUPPORTED_MEDIA_TYPE, description = "The body isn't JSON", body = ErrorResponse),
+        (status = StatusCode::UNPROCESSABLE_ENTITY, description = "The batch is too large, or a user is invalid when creating them atomically", body = ErrorResponse, example = json!({"error": "A batch may contain at most 500 users", "code": "batch_too_large"})),
+        (status = StatusCode::TOO_MANY_REQUESTS, description = "Too many requests", body = TooManyRequestsResponse),
+    )
+)]
+pub async fn handler<
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+>(
+    State(state): State<AppState<U, E, I, H>>,
+    _: RequireRole<Admin>,
+    Query(params): Query<CreateUsersParams>,
+    ClientOrigin(origin): ClientOrigin,
+    JsonBody(bodies): JsonBody<Vec<CreateUserBody>>,
+) -> Result<Json<CreateUsersResponse>, ApiError> {
+    if bodies.len() > MAX_BATCH_SIZE {
+        return Err(ApiError {
+            code: Some("batch_too_large".to_string()),
+            ..ApiError::new_422(&format!(
+                "A batch may contain at most {MAX_BATCH_SIZE} users"
+            ))
+        });
+    }
+
+    // Building a user hashes their password, which is too slow to do on the async executor
+    let config = state.config.clone();
+    let validated: Vec<Result<NewUser, Vec<ValidationError>>> =
+        tokio::task::spawn_blocking(move || {
+            bodies
+                .into_iter()
+                .map(|body| match body.validate(&config) {
+                    Ok(user) => user.build().map(Ok),
+                    Err(errors) => Ok(Err(errors)),
+                })
+                .collect::<Result<_, _>>()
+        })
+        .await
+        .map_err(anyhow::Error::from)?
+        .map_err(anyhow::Error::from)?;
+
+    let results = if params.atomic {
+        create_atomically(&state, validated, &origin).await?
+    } else {
+        create_independently(&state, validated, &origin).await
+    };
+
+    Ok(Json(CreateUsersResponse { results }))
+}
+
+/// Creates every user, or none of them if any are invalid or can't be created
+async fn create_atomically<
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+>(
+    state: &AppState<U, E, I, H>,
+    validated: Vec<Result<NewUser, Vec<ValidationError>>>,
+    origin: &RequestOrigin,
+) -> Result<Vec<CreateUsersResult>, ApiError> {
+    let mut users = Vec::with_capacity(validated.len());
+    let mut validation_errors = vec![];
+
+    for (index, result) in validated.into_iter().enumerate() {
+        match result {
+            Ok(user) => users.push(user),
+            Err(errors) => {
+                validation_errors.extend(errors.into_iter().map(|err| ValidationError {
+                    field: format!("[{index}].{}", err.field),
+                    ..err
+                }))
+            }
+        }
+    }
+
+    if !validation_errors.is_empty() {
+        return Err(ApiError::new_validation(validation_errors));
+    }
+
+    let ids = state.users.create_users(&users, origin).await?;
+
+    Ok(ids.into_iter().map(CreateUsersResult::created).collect())
+}
+
+/// Creates each valid user independently, reporting the rest as failed
+async fn create_independently<
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+>(
+    state: &AppState<U, E, I, H>,
+    validated: Vec<Result<NewUser, Vec<ValidationError>>>,
+    origin: &RequestOrigin,
+) -> Vec<CreateUsersResult> {
+    let users: Vec<NewUser> = validated
+        .iter()
+        .filter_map(|result| result.as_ref().ok().cloned())
+        .collect();
+
+    let mut created = state
+        .users
+        .create_users_independently(&users, origin)
+        .await
+        .into_iter();
+
+    validated
+        .into_iter()
+        .map(|result| match result {
+            Ok(_) => match created.next() {
+                Some(Ok(id)) => CreateUsersResult::created(id),
+                Some(Err(err)) => CreateUsersResult::failed(err),
+                None => CreateUsersResult::failed(ApiError::new_500(
+                    "An unknown error occurred, please try again",
+                )),
+            },
+            Err(errors) => CreateUsersResult::failed(ApiError::new_validation(errors)),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{header::AUTHORIZATION, StatusCode};
+    use axum_test::TestServer;
+    use mockall::predicate::eq;
+    use serde_json::json;
+    use testresult::TestResult;
+    use uuid::Uuid;
+
+    use crate::{
+        domain::auth::users::{errors::CreateUserError, tests::MockUserService, Role, User},
+        infrastructure::http::{
+            errors::ErrorResponse,
+            servers::https::router,
+            state::tests::{test_bearer_token, test_state},
+        },
+    };
+
+    use super::{CreateUsersResponse, MAX_BATCH_SIZE};
+
+    /// A user service which authenticates `admin`
+    fn users_with_admin(role: Role) -> (MockUserService, Uuid) {
+        let admin = User {
+            id: Uuid::now_v7(),
+            role,
+            ..User::default()
+        };
+        let admin_id = admin.id;
+
+        let mut users = MockUserService::new();
+
+        users
+            .expect_get_user_by_id()
+            .with(eq(admin_id))
+            .returning(move |_| Ok(admin.clone()));
+
+        (users, admin_id)
+    }
+
+    fn user(email: &str) -> serde_json::Value {
+        json!({ "email": email, "password": "correcthorsebatterystaple" })
+    }
+
+    #[tokio::test]
+    async fn test_create_users_reports_each_result() -> TestResult {
+        let (mut users, admin_id) = users_with_admin(Role::Admin);
+
+        users
+            .expect_create_users_independently()
+            .times(1)
+            .withf(|users, _| {
+                users
+                    .iter()
+                    .map(|user| user.email().to_string())
+                    .collect::<Vec<String>>()
+                    == vec!["a@example.com", "taken@example.com", "c@example.com"]
+            })
+            .returning(|users, _| {
+                users
+                    .iter()
+                    .map(|user| match user.email().to_string().as_str() {
+                        "taken@example.com" => Err(CreateUserError::DuplicateUser),
+                        _ => Ok(*user.id()),
+                    })
+                    .collect()
+            });
+
+        let state = test_state(Some(users), None);
+        let token = test_bearer_token(&state, &admin_id);
+
+        let response = TestServer::new(router(state))?
+            .post("/api/v1/users/batch")
+            .add_header(AUTHORIZATION, token.parse()?)
+            .json(&json!([
+                user("a@example.com"),
+                user("taken@example.com"),
+                user("not an email"),
+                user("c@example.com"),
+            ]))
+            .await;
+
+        response.assert_status_ok();
+
+        let json = response.json::<CreateUsersResponse>();
+
+        let statuses: Vec<u16> = json.results.iter().map(|result| result.status).collect();
+
+        assert_eq!(statuses, vec![201, 409, 422, 201]);
+        assert!(json.results[0].id.is_some());
+        assert!(json.results[3].id.is_some());
+        assert_eq!(
+            json.results[1]
+                .error
+                .as_ref()
+                .and_then(|err| err.code.as_deref()),
+            Some("duplicate_email")
+        );
+        assert_eq!(
+            json.results[2]
+                .error
+                .as_ref()
+                .map(|err| err.validation_errors[0].field.as_str()),
+            Some("email")
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_users_atomically() -> TestResult {
+        let (mut users, admin_id) = users_with_admin(Role::Admin);
+
+        users.expect_create_users_independently().times(0);
+        users
+            .expect_create_users()
+            .times(1)
+            .returning(|_, _| Err(CreateUserError::DuplicateUser));
+
+        let state = test_state(Some(users), None);
+        let token = test_bearer_token(&state, &admin_id);
+
+        let response = TestServer::new(router(state))?
+            .post("/api/v1/users/batch")
+            .add_query_param("atomic", true)
+            .add_header(AUTHORIZATION, token.parse()?)
+            .json(&json!([user("a@example.com"), user("taken@example.com")]))
+            .await;
+
+        response.assert_status(StatusCode::CONFLICT);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_users_atomically_reports_invalid_users() -> TestResult {
+        let (mut users, admin_id) = users_with_admin(Role::Admin);
+
+        users.expect_create_users().times(0);
+
+        let state = test_state(Some(users), None);
+        let token = test_bearer_token(&state, &admin_id);
+
+        let response = TestServer::new(router(state))?
+            .post("/api/v1/users/batch")
+            .add_query_param("atomic", true)
+            .add_header(AUTHORIZATION, token.parse()?)
+            .json(&json!([user("a@example.com"), user("not an email")]))
+            .await;
+
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+
+        let json = response.json::<ErrorResponse>();
+
+        assert_eq!(json.validation_errors[0].field, "[1].email");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_users_rejects_large_batches() -> TestResult {
+        let (mut users, admin_id) = users_with_admin(Role::Admin);
+
+        users.expect_create_users_independently().times(0);
+
+        let state = test_state(Some(users), None);
+        let token = test_bearer_token(&state, &admin_id);
+
+        let batch: Vec<serde_json::Value> = (0..=MAX_BATCH_SIZE)
+            .map(|i| user(&format!("user{i}@example.com")))
+            .collect();
+
+        let response = TestServer::new(router(state))?
+            .post("/api/v1/users/batch")
+            .add_header(AUTHORIZATION, token.parse()?)
+            .json(&batch)
+            .await;
+
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+
+        assert_eq!(
+            response.json::<ErrorResponse>().code.as_deref(),
+            Some("batch_too_large")
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_users_requires_admin() -> TestResult {
+        let (mut users, user_id) = users_with_admin(Role::User);
+
+        users.expect_create_users_independently().times(0);
+
+        let state = test_state(Some(users), None);
+        let token = test_bearer_token(&state, &user_id);
+
+        let response = TestServer::new(router(state))?
+            .post("/api/v1/users/batch")
+            .add_header(AUTHORIZATION, token.parse()?)
+            .json(&json!([user("a@example.com")]))
+            .await;
+
+        response.assert_status_forbidden();
+
+        Ok(())
+    }
+}
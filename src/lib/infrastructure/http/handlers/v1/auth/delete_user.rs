@@ -0,0 +1,165 @@
+//! Delete user handler
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+};
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        auth::users::UserService, communication::email_addresses::EmailAddressService,
+        health::DatabaseHealth, idempotency::IdempotencyStore,
+    },
+    infrastructure::http::{
+        errors::ApiError,
+        extractors::require_role::{Admin, RequireRole},
+        state::AppState,
+    },
+};
+
+/// Delete a user
+#[utoipa::path(
+    delete,
+    operation_id = "delete_user",
+    tag = "Auth",
+    path = "/api/v1/users/{id}",
+    params(
+        ("id" = Uuid, Path, description = "The UUID of the user", example = "550e8400-e29b-41d4-a716-446655440000"),
+    ),
+    security(("bearerAuth" = [])),
+    responses(
+        (status = StatusCode::NO_CONTENT, description = "User deleted"),
+        (status = StatusCode::UNAUTHORIZED, description = "Missing, invalid or expired access token", body = ErrorResponse),
+        (status = StatusCode::FORBIDDEN, description = "User is not an admin", body = ErrorResponse, example = json!({ "error": "You do not have permission to do that", "code": "forbidden" })),
+        (status = StatusCode::NOT_FOUND, description = "User not found", body = ErrorResponse),
+        (status = StatusCode::INTERNAL_SERVER_ERROR, description = "Internal Server Error", body = ErrorResponse),
+        (status = StatusCode::TOO_MANY_REQUESTS, description = "Too many requests", body = TooManyRequestsResponse),
+    )
+)]
+pub async fn handler<
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+>(
+    State(state): State<AppState<U, E, I, H>>,
+    _: RequireRole<Admin>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    state.users.delete_user(&id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{header::AUTHORIZATION, StatusCode};
+    use axum_test::TestServer;
+    use mockall::predicate::eq;
+    use testresult::TestResult;
+    use uuid::Uuid;
+
+    use crate::{
+        domain::auth::users::{errors::DeleteUserError, tests::MockUserService, Role, User},
+        infrastructure::http::{
+            servers::https::router,
+            state::tests::{test_bearer_token, test_state},
+        },
+    };
+
+    fn user_with_role(role: Role) -> User {
+        User {
+            id: Uuid::now_v7(),
+            role,
+            ..User::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_delete_user_as_admin() -> TestResult {
+        let admin = user_with_role(Role::Admin);
+        let admin_id = admin.id;
+        let user_id = Uuid::now_v7();
+
+        let mut users = MockUserService::new();
+
+        users
+            .expect_get_user_by_id()
+            .with(eq(admin_id))
+            .returning(move |_| Ok(admin.clone()));
+
+        users
+            .expect_delete_user()
+            .times(1)
+            .with(eq(user_id))
+            .returning(|_| Ok(()));
+
+        let state = test_state(Some(users), None);
+        let token = test_bearer_token(&state, &admin_id);
+
+        let response = TestServer::new(router(state))?
+            .delete(&format!("/api/v1/users/{user_id}"))
+            .add_header(AUTHORIZATION, token.parse()?)
+            .await;
+
+        assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_delete_user_not_found() -> TestResult {
+        let admin = user_with_role(Role::Admin);
+        let admin_id = admin.id;
+
+        let mut users = MockUserService::new();
+
+        users
+            .expect_get_user_by_id()
+            .returning(move |_| Ok(admin.clone()));
+
+        users
+            .expect_delete_user()
+            .times(1)
+            .returning(|_| Err(DeleteUserError::UserNotFound));
+
+        let state = test_state(Some(users), None);
+        let token = test_bearer_token(&state, &admin_id);
+
+        let response = TestServer::new(router(state))?
+            .delete(&format!("/api/v1/users/{}", Uuid::now_v7()))
+            .add_header(AUTHORIZATION, token.parse()?)
+            .await;
+
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_delete_user_as_regular_user() -> TestResult {
+        let user = user_with_role(Role::User);
+        let user_id = user.id;
+
+        let mut users = MockUserService::new();
+
+        users
+            .expect_get_user_by_id()
+            .returning(move |_| Ok(user.clone()));
+
+        users.expect_delete_user().times(0);
+
+        let state = test_state(Some(users), None);
+        let token = test_bearer_token(&state, &user_id);
+
+        let response = TestServer::new(router(state))?
+            .delete(&format!("/api/v1/users/{}", Uuid::now_v7()))
+            .add_header(AUTHORIZATION, token.parse()?)
+            .await;
+
+        assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+        Ok(())
+    }
+}
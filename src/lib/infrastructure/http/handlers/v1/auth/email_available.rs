@@ -0,0 +1,185 @@
+//! Email address availability handler
+
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    domain::{
+        auth::users::UserService,
+        communication::email_addresses::{EmailAddress, EmailAddressService},
+        health::DatabaseHealth,
+        idempotency::IdempotencyStore,
+    },
+    infrastructure::http::{
+        errors::{ApiError, ValidationError},
+        state::AppState,
+    },
+};
+
+/// Email address availability query parameters
+#[derive(Debug, Default, Serialize, Deserialize, IntoParams)]
+pub struct EmailAvailableParams {
+    /// The email address to check
+    #[param(example = "email@example.com")]
+    #[serde(default)]
+    email: String,
+}
+
+/// Email address availability response body
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct EmailAvailableResponse {
+    /// Whether a new user could sign up with the email address
+    available: bool,
+}
+
+/// Check whether an email address is available to sign up with
+///
+/// Addresses are compared by their normalized form, exactly as they are when a user is created.
+/// This tells anyone whether an address has an account, so the endpoint is rate limited more
+/// strictly than the rest of the API.
+#[utoipa::path(
+    get,
+    operation_id = "check_email_available",
+    tag = "Auth",
+    path = "/api/v1/users/email-available",
+    params(EmailAvailableParams),
+    responses(
+        (status = StatusCode::OK, description = "Whether the email address is available", body = EmailAvailableResponse),
+        (status = StatusCode::UNPROCESSABLE_ENTITY, description = "The email address is invalid", body = ErrorResponse, example = json!({"error": "Please provide a valid email address", "code": "validation_failed", "validation_errors": [{"field": "email", "code": "invalid", "message": "Please provide a valid email address"}]})),
+        (status = StatusCode::INTERNAL_SERVER_ERROR, description = "Internal Server Error", body = ErrorResponse),
+        (status = StatusCode::TOO_MANY_REQUESTS, description = "Too many requests", body = TooManyRequestsResponse),
+    )
+)]
+pub async fn handler<
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+>(
+    State(state): State<AppState<U, E, I, H>>,
+    Query(params): Query<EmailAvailableParams>,
+) -> Result<Json<EmailAvailableResponse>, ApiError> {
+    let email = EmailAddress::new(&params.email)
+        .map_err(|err| ApiError::new_validation(vec![ValidationError::new("email", &err)]))?;
+
+    let exists = state.users.email_exists(&email).await?;
+
+    Ok(Json(EmailAvailableResponse { available: !exists }))
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+    use axum::http::StatusCode;
+    use axum_test::TestServer;
+    use testresult::TestResult;
+
+    use crate::{
+        domain::auth::users::{errors::EmailExistsError, tests::MockUserService},
+        infrastructure::http::{
+            errors::ErrorResponse, servers::https::router, state::tests::test_state,
+        },
+    };
+
+    use super::EmailAvailableResponse;
+
+    #[tokio::test]
+    async fn test_existing_email_is_not_available() -> TestResult {
+        let mut users = MockUserService::new();
+
+        users
+            .expect_email_exists()
+            .times(1)
+            .withf(|email| email.to_string() == "taken@example.com")
+            .returning(|_| Ok(true));
+
+        let response = TestServer::new(router(test_state(Some(users), None)))?
+            .get("/api/v1/users/email-available")
+            .add_query_param("email", "taken@example.com")
+            .await;
+
+        response.assert_status_ok();
+        assert!(!response.json::<EmailAvailableResponse>().available);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_free_email_is_available() -> TestResult {
+        let mut users = MockUserService::new();
+
+        users
+            .expect_email_exists()
+            .times(1)
+            .returning(|_| Ok(false));
+
+        let response = TestServer::new(router(test_state(Some(users), None)))?
+            .get("/api/v1/users/email-available")
+            .add_query_param("email", "free@example.com")
+            .await;
+
+        response.assert_status_ok();
+        assert!(response.json::<EmailAvailableResponse>().available);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_email_is_unprocessable() -> TestResult {
+        let mut users = MockUserService::new();
+
+        users.expect_email_exists().times(0);
+
+        let response = TestServer::new(router(test_state(Some(users), None)))?
+            .get("/api/v1/users/email-available")
+            .add_query_param("email", "not an email")
+            .await;
+
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+
+        let json = response.json::<ErrorResponse>();
+
+        assert_eq!(json.validation_errors.len(), 1);
+        assert_eq!(json.validation_errors[0].field, "email");
+        assert_eq!(json.validation_errors[0].code, "invalid");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_missing_email_is_unprocessable() -> TestResult {
+        let response = TestServer::new(router(test_state(None, None)))?
+            .get("/api/v1/users/email-available")
+            .await;
+
+        response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            response.json::<ErrorResponse>().validation_errors[0].code,
+            "empty"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unknown_error() -> TestResult {
+        let mut users = MockUserService::new();
+
+        users
+            .expect_email_exists()
+            .returning(|_| Err(EmailExistsError::UnknownError(anyhow!("boom"))));
+
+        let response = TestServer::new(router(test_state(Some(users), None)))?
+            .get("/api/v1/users/email-available")
+            .add_query_param("email", "email@example.com")
+            .await;
+
+        response.assert_status(StatusCode::INTERNAL_SERVER_ERROR);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,231 @@
+//! Email confirmation status
+
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        auth::users::{User, UserService},
+        communication::email_addresses::EmailAddressService,
+        health::DatabaseHealth,
+        idempotency::IdempotencyStore,
+    },
+    infrastructure::http::{errors::ApiError, extractors::auth_user::AuthUser, state::AppState},
+};
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct EmailConfirmationStatusResponse {
+    /// Whether the user's current email address has been confirmed
+    confirmed: bool,
+
+    /// The new email address awaiting confirmation, if the user is changing their email
+    #[schema(example = "new@example.com")]
+    pending_new_email: Option<String>,
+
+    /// When the outstanding confirmation token expires, if there is one
+    expires_at: Option<DateTime<Utc>>,
+}
+
+impl From<User> for EmailConfirmationStatusResponse {
+    fn from(user: User) -> Self {
+        Self {
+            confirmed: user.email_confirmed_at.is_some(),
+            pending_new_email: user.new_email.map(|email| email.to_string()),
+            expires_at: user
+                .email_confirmation_token
+                .and(user.email_confirmation_expires_at),
+        }
+    }
+}
+
+/// Get whether a user's email address has been confirmed
+#[utoipa::path(
+    get,
+    operation_id = "get_email_confirmation_status",
+    tag = "Auth",
+    path = "/api/v1/users/{id}/email/confirmation/status",
+    params(
+        ("id" = Uuid, Path, description = "The UUID of the user", example = "550e8400-e29b-41d4-a716-446655440000"),
+    ),
+    security(("bearerAuth" = [])),
+    responses(
+        (status = StatusCode::OK, description = "Email confirmation status", body = EmailConfirmationStatusResponse),
+        (status = StatusCode::UNAUTHORIZED, description = "Missing, invalid or expired access token", body = ErrorResponse),
+        (status = StatusCode::FORBIDDEN, description = "Access token belongs to another user", body = ErrorResponse),
+        (status = StatusCode::NOT_FOUND, description = "User not found", body = ErrorResponse),
+        (status = StatusCode::INTERNAL_SERVER_ERROR, description = "Internal Server Error", body = ErrorResponse),
+        (status = StatusCode::TOO_MANY_REQUESTS, description = "Too many requests", body = TooManyRequestsResponse),
+    )
+)]
+pub async fn handler<
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+>(
+    State(state): State<AppState<U, E, I, H>>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<EmailConfirmationStatusResponse>, ApiError> {
+    if auth_user.id != id {
+        return Err(ApiError::new_403("You may only access your own user"));
+    }
+
+    let status = state.users.get_user_by_id(&id).await?.into();
+
+    Ok(Json(status))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{header::AUTHORIZATION, StatusCode};
+    use axum_test::TestServer;
+    use chrono::{Duration, Utc};
+    use testresult::TestResult;
+    use uuid::Uuid;
+
+    use crate::{
+        domain::{
+            auth::users::{tests::MockUserService, User},
+            communication::email_addresses::{hash_confirmation_token, EmailAddress},
+        },
+        infrastructure::http::{
+            errors::ErrorResponse,
+            servers::https::router,
+            state::tests::{test_bearer_token, test_state},
+        },
+    };
+
+    use super::EmailConfirmationStatusResponse;
+
+    async fn get_status(user: User) -> TestResult<EmailConfirmationStatusResponse> {
+        let user_id = user.id;
+        let mut users = MockUserService::new();
+
+        users
+            .expect_get_user_by_id()
+            .withf(move |id| *id == user_id)
+            .returning(move |_| Ok(user.clone()));
+
+        let state = test_state(Some(users), None);
+        let token = test_bearer_token(&state, &user_id);
+
+        let response = TestServer::new(router(state))?
+            .get(&format!(
+                "/api/v1/users/{user_id}/email/confirmation/status"
+            ))
+            .add_header(AUTHORIZATION, token.parse()?)
+            .await;
+
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert!(!response.text().contains("token"));
+
+        Ok(response.json::<EmailConfirmationStatusResponse>())
+    }
+
+    #[tokio::test]
+    async fn test_email_confirmation_status_confirmed() -> TestResult {
+        let status = get_status(User {
+            id: Uuid::now_v7(),
+            email_confirmed_at: Some(Utc::now() - Duration::days(1)),
+            ..User::default()
+        })
+        .await?;
+
+        assert!(status.confirmed);
+        assert_eq!(status.pending_new_email, None);
+        assert_eq!(status.expires_at, None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_email_confirmation_status_unconfirmed_with_live_token() -> TestResult {
+        let expires_at = Utc::now() + Duration::hours(1);
+
+        let status = get_status(User {
+            id: Uuid::now_v7(),
+            email_confirmation_token: Some(hash_confirmation_token("token")),
+            email_confirmation_sent_at: Some(Utc::now()),
+            email_confirmation_expires_at: Some(expires_at),
+            ..User::default()
+        })
+        .await?;
+
+        assert!(!status.confirmed);
+        assert_eq!(status.pending_new_email, None);
+        assert_eq!(status.expires_at, Some(expires_at));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_email_confirmation_status_unconfirmed_with_expired_token() -> TestResult {
+        let expires_at = Utc::now() - Duration::hours(1);
+
+        let status = get_status(User {
+            id: Uuid::now_v7(),
+            email_confirmation_token: Some(hash_confirmation_token("token")),
+            email_confirmation_sent_at: Some(Utc::now() - Duration::days(1)),
+            email_confirmation_expires_at: Some(expires_at),
+            ..User::default()
+        })
+        .await?;
+
+        assert!(!status.confirmed);
+        assert_eq!(status.expires_at, Some(expires_at));
+        assert!(status.expires_at < Some(Utc::now()));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_email_confirmation_status_pending_new_email() -> TestResult {
+        let status = get_status(User {
+            id: Uuid::now_v7(),
+            new_email: Some(EmailAddress::new_unchecked("new@example.com")),
+            email_confirmed_at: Some(Utc::now() - Duration::days(1)),
+            email_confirmation_token: Some(hash_confirmation_token("token")),
+            email_confirmation_expires_at: Some(Utc::now() + Duration::hours(1)),
+            ..User::default()
+        })
+        .await?;
+
+        assert!(status.confirmed);
+        assert_eq!(status.pending_new_email.as_deref(), Some("new@example.com"));
+        assert!(status.expires_at.is_some());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_email_confirmation_status_other_user() -> TestResult {
+        let mut users = MockUserService::new();
+
+        users.expect_get_user_by_id().times(0);
+
+        let state = test_state(Some(users), None);
+        let token = test_bearer_token(&state, &Uuid::now_v7());
+
+        let response = TestServer::new(router(state))?
+            .get(&format!(
+                "/api/v1/users/{}/email/confirmation/status",
+                Uuid::now_v7()
+            ))
+            .add_header(AUTHORIZATION, token.parse()?)
+            .await;
+
+        let json = response.json::<ErrorResponse>();
+
+        assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+        assert_eq!(json.error, "You may only access your own user");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+//! Erase account handler
+
+use axum::{extract::State, http::StatusCode};
+
+use crate::{
+    domain::{
+        auth::users::UserService, communication::email_addresses::EmailAddressService,
+        health::DatabaseHealth, idempotency::IdempotencyStore,
+    },
+    infrastructure::http::{
+        errors::ApiError,
+        extractors::{client_origin::ClientOrigin, current_user::CurrentUser},
+        state::AppState,
+    },
+};
+
+/// Erase the authenticated user's account
+///
+/// Irreversibly scrubs the user's email addresses, password, username and any outstanding
+/// confirmation token. Their ID, role, locale, timestamps and acceptance of the terms are kept
+/// for legal reasons, as is the audit log, which refers to them only by ID.
+#[utoipa::path(
+    delete,
+    operation_id = "erase_current_user",
+    tag = "Auth",
+    path = "/api/v1/auth/me",
+    security(("bearerAuth" = [])),
+    responses(
+        (status = StatusCode::NO_CONTENT, description = "Account erased"),
+        (status = StatusCode::UNAUTHORIZED, description = "Missing, invalid or expired access token", body = ErrorResponse),
+        (status = StatusCode::INTERNAL_SERVER_ERROR, description = "Internal Server Error", body = ErrorResponse),
+        (status = StatusCode::TOO_MANY_REQUESTS, description = "Too many requests", body = TooManyRequestsResponse),
+    )
+)]
+pub async fn handler<
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+>(
+    State(state): State<AppState<U, E, I, H>>,
+    CurrentUser(user): CurrentUser,
+    ClientOrigin(origin): ClientOrigin,
+) -> Result<StatusCode, ApiError> {
+    state.users.erase_user(&user.id, &origin).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::http::{header::AUTHORIZATION, StatusCode};
+    use axum_test::TestServer;
+    use mockall::predicate::{always, eq};
+    use testresult::TestResult;
+    use uuid::Uuid;
+
+    use crate::{
+        domain::auth::users::{errors::GetUserByIdError, tests::MockUserService, User},
+        infrastructure::http::{
+            servers::https::router,
+            state::tests::{test_bearer_token, test_state},
+        },
+    };
+
+    #[tokio::test]
+    async fn test_erased_user_is_no_longer_found() -> TestResult {
+        let user_id = Uuid::now_v7();
+        let erased = Arc::new(Mutex::new(false));
+
+        let mut users = MockUserService::new();
+
+        users.expect_get_user_by_id().returning({
+            let erased = erased.clone();
+
+            move |id| match *erased.lock().unwrap() {
+                true => Err(GetUserByIdError::UserNotFound),
+                false => Ok(User {
+                    id: *id,
+                    ..User::default()
+                }),
+            }
+        });
+        users
+            .expect_erase_user()
+            .times(1)
+            .with(eq(user_id), always())
+            .returning(move |_, _| {
+                *erased.lock().unwrap() = true;
+
+                Ok(())
+            });
+
+        let state = test_state(Some(users), None);
+        let token = test_bearer_token(&state, &user_id);
+        let server = TestServer::new(router(state))?;
+
+        server
+            .delete("/api/v1/auth/me")
+            .add_header(AUTHORIZATION, token.parse()?)
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+
+        server
+            .get(&format!("/api/v1/users/{user_id}"))
+            .add_header(AUTHORIZATION, token.parse()?)
+            .await
+            .assert_status_not_found();
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_erase_account_unauthenticated() -> TestResult {
+        let mut users = MockUserService::new();
+
+        users.expect_erase_user().times(0);
+
+        let response = TestServer::new(router(test_state(Some(users), None)))?
+            .delete("/api/v1/auth/me")
+            .await;
+
+        response.assert_status(StatusCode::UNAUTHORIZED);
+
+        Ok(())
+    }
+}
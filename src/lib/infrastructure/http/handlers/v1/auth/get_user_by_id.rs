@@ -1,20 +1,33 @@
 //! Get User by ID
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
+    http::{header::ETAG, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use utoipa::ToSchema;
 use uuid::Uuid;
 
 use crate::{
     domain::{
-        auth::users::{User, UserService},
+        auth::users::{Role, User, UserService},
         communication::email_addresses::EmailAddressService,
+        health::DatabaseHealth,
+        idempotency::IdempotencyStore,
+    },
+    infrastructure::http::{
+        errors::ApiError,
+        extractors::{
+            auth_user::AuthUser,
+            if_match::{etag, sparse_etag},
+            if_none_match::IfNoneMatch,
+        },
+        state::AppState,
     },
-    infrastructure::http::{errors::ApiError, state::AppState},
 };
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -25,18 +38,75 @@ pub struct GetUserByIdResponse {
     #[schema(example = "email@example.com")]
     email: String,
     email_confirmed_at: Option<DateTime<Utc>>,
+
+    #[schema(example = "user", value_type = String)]
+    role: Role,
+    terms_accepted_at: Option<DateTime<Utc>>,
+
+    #[schema(example = "2024-08-01")]
+    terms_version: Option<String>,
     created_at: DateTime<Utc>,
     updated_at: DateTime<Utc>,
 }
 
+/// The fields of a [`GetUserByIdResponse`], which sparse fieldsets are chosen from
+const FIELDS: [&str; 8] = [
+    "id",
+    "email",
+    "email_confirmed_at",
+    "role",
+    "terms_accepted_at",
+    "terms_version",
+    "created_at",
+    "updated_at",
+];
+
+/// Get user by ID query parameters
+#[derive(Debug, Default, Deserialize)]
+pub struct GetUserByIdParams {
+    /// The comma-separated fields to return, or every field if not given
+    fields: Option<String>,
+}
+
+impl GetUserByIdParams {
+    /// The fields asked for, in a fixed order, ignoring unknown field names but always including
+    /// the ID, or [`None`] if every field was
+    fn fields(&self) -> Option<Vec<&'static str>> {
+        let requested: Vec<&str> = self.fields.as_ref()?.split(',').map(str::trim).collect();
+
+        Some(
+            FIELDS
+                .into_iter()
+                .filter(|field| *field == "id" || requested.contains(field))
+                .collect(),
+        )
+    }
+}
+
+/// Drops the fields of `user` which aren't in `fields`, if only some were asked for
+fn select(mut user: Value, fields: Option<&[&str]>) -> Value {
+    let Some(fields) = fields else {
+        return user;
+    };
+
+    if let Value::Object(user) = &mut user {
+        user.retain(|field, _| fields.contains(&field.as_str()));
+    }
+
+    user
+}
+
 impl From<User> for GetUserByIdResponse {
     fn from(user: User) -> Self {
         Self {
             id: user.id,
             email: user.email.to_string(),
             email_confirmed_at: user.email_confirmed_at,
-            created_at: user.created_at,
-            updated_at: user.updated_at,
+            role: user.role,
+            terms_accepted_at: user.terms_accepted_at,
+            terms_version: user.terms_version,
+            created_at: user.timestamps.created_at,
+            updated_at: user.timestamps.updated_at,
         }
     }
 }
@@ -49,39 +119,87 @@ impl From<User> for GetUserByIdResponse {
     path = "/api/v1/users/{id}",
     params(
         ("id" = Uuid, Path, description = "The UUID of the user", example = "550e8400-e29b-41d4-a716-446655440000"),
+        ("fields" = Option<String>, Query, description = "The comma-separated fields to return, instead of every field. `id` is always returned, and unknown fields are ignored", example = "id,email"),
+        ("If-None-Match" = Option<String>, Header, description = "The `ETag` of the user the client already has, to not send them again if they haven't changed", example = "W/\"1\""),
     ),
+    security(("bearerAuth" = [])),
     responses(
-        (status = StatusCode::OK, description = "User found", body = GetUserByIdResponse),
+        (status = StatusCode::OK, description = "User found", body = GetUserByIdResponse, headers(
+            ("ETag" = String, description = "The user's current version, to send as `If-Match` when updating them"),
+        )),
+        (status = StatusCode::NOT_MODIFIED, description = "The user hasn't changed since the `If-None-Match` version", headers(
+            ("ETag" = String, description = "The user's current version"),
+        )),
+        (status = StatusCode::UNAUTHORIZED, description = "Missing, invalid or expired access token", body = ErrorResponse),
+        (status = StatusCode::FORBIDDEN, description = "Access token belongs to another user", body = ErrorResponse),
         (status = StatusCode::NOT_FOUND, description = "User not found", body = ErrorResponse),
         (status = StatusCode::INTERNAL_SERVER_ERROR, description = "Internal Server Error", body = ErrorResponse),
         (status = StatusCode::TOO_MANY_REQUESTS, description = "Too many requests", body = TooManyRequestsResponse),
     )
 )]
-pub async fn handler<U: UserService, E: EmailAddressService>(
-    State(state): State<AppState<U, E>>,
+pub async fn handler<
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+>(
+    State(state): State<AppState<U, E, I, H>>,
+    auth_user: AuthUser,
     Path(id): Path<Uuid>,
-) -> Result<Json<GetUserByIdResponse>, ApiError> {
-    let user = state.users.get_user_by_id(&id).await?.into();
+    Query(params): Query<GetUserByIdParams>,
+    if_none_match: IfNoneMatch,
+) -> Result<Response, ApiError> {
+    if auth_user.id != id {
+        return Err(ApiError::new_403("You may only access your own user"));
+    }
 
-    Ok(Json(user))
+    let user = state.users.get_user_by_id(&id).await?;
+    let fields = params.fields();
+
+    // Sparse responses are tagged by their fields too, so a cached one is never reused in place
+    // of another set of fields
+    let etag = match &fields {
+        Some(fields) => sparse_etag(user.version, fields),
+        None => etag(user.version),
+    };
+
+    if if_none_match.matches(&etag) {
+        return Ok((StatusCode::NOT_MODIFIED, [(ETAG, etag)]).into_response());
+    }
+
+    let user =
+        serde_json::to_value(GetUserByIdResponse::from(user)).map_err(anyhow::Error::from)?;
+
+    Ok(([(ETAG, etag)], Json(select(user, fields.as_deref()))).into_response())
 }
 
 #[cfg(test)]
 mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::http::header::{AUTHORIZATION, ETAG, IF_NONE_MATCH};
     use axum::http::StatusCode;
     use axum_test::TestServer;
     use chrono::Utc;
+    use serde_json::{json, Value};
     use testresult::TestResult;
     use uuid::Uuid;
 
     use crate::{
         domain::{
-            auth::users::{errors::GetUserByIdError, tests::MockUserService, User},
+            auth::{
+                tokens::{issue_access_token, AuthConfig},
+                users::{errors::GetUserByIdError, tests::MockUserService, Role, User},
+            },
             communication::email_addresses::EmailAddress,
+            i18n::Locale,
+            timestamps::Timestamps,
         },
         infrastructure::http::{
-            errors::ErrorResponse, handlers::v1::auth::get_user_by_id::GetUserByIdResponse,
-            servers::https::router, state::tests::test_state,
+            errors::ErrorResponse,
+            handlers::v1::auth::get_user_by_id::{GetUserByIdResponse, FIELDS},
+            servers::https::router,
+            state::tests::{test_bearer_token, test_state},
         },
     };
 
@@ -89,14 +207,19 @@ mod tests {
     async fn test_get_user_by_id_success() -> TestResult {
         let user_id = Uuid::now_v7();
         let user = User {
-            id: user_id.clone(),
+            id: user_id,
             email: EmailAddress::new_unchecked("email@example.com"),
             new_email: None,
             email_confirmed_at: None,
             email_confirmation_token: None,
             email_confirmation_sent_at: None,
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
+            email_confirmation_expires_at: None,
+            role: Role::User,
+            terms_accepted_at: None,
+            terms_version: None,
+            locale: Locale::English,
+            timestamps: Timestamps::created(Utc::now()),
+            version: 4,
         };
 
         let mut users = MockUserService::new();
@@ -107,9 +230,11 @@ mod tests {
             .returning(move |_| Ok(user.clone()));
 
         let state = test_state(Some(users), None);
+        let token = test_bearer_token(&state, &user_id);
 
         let response = TestServer::new(router(state))?
-            .get(&format!("/api/v1/users/{}", user_id.clone()))
+            .get(&format!("/api/v1/users/{}", user_id))
+            .add_header(AUTHORIZATION, token.parse()?)
             .await;
 
         let json = response.json::<GetUserByIdResponse>();
@@ -117,6 +242,166 @@ mod tests {
         assert_eq!(response.status_code(), StatusCode::OK);
 
         assert_eq!(user_id.to_string(), json.id.to_string());
+        assert_eq!(response.header(ETAG), "W/\"4\"");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_user_by_id_not_modified_until_updated() -> TestResult {
+        let user_id = Uuid::now_v7();
+        let version = Arc::new(Mutex::new(1));
+
+        let mut users = MockUserService::new();
+
+        users.expect_get_user_by_id().returning({
+            let version = version.clone();
+
+            move |id| {
+                Ok(User {
+                    id: *id,
+                    version: *version.lock().unwrap(),
+                    ..User::default()
+                })
+            }
+        });
+
+        let state = test_state(Some(users), None);
+        let token = test_bearer_token(&state, &user_id);
+        let server = TestServer::new(router(state))?;
+        let path = format!("/api/v1/users/{user_id}");
+
+        let first = server
+            .get(&path)
+            .add_header(AUTHORIZATION, token.parse()?)
+            .await;
+        first.assert_status_ok();
+        let etag = first.header(ETAG);
+
+        let unchanged = server
+            .get(&path)
+            .add_header(AUTHORIZATION, token.parse()?)
+            .add_header(IF_NONE_MATCH, etag.clone())
+            .await;
+        unchanged.assert_status(StatusCode::NOT_MODIFIED);
+        assert_eq!(unchanged.header(ETAG), etag);
+        assert!(unchanged.as_bytes().is_empty());
+
+        *version.lock().unwrap() = 2;
+
+        let updated = server
+            .get(&path)
+            .add_header(AUTHORIZATION, token.parse()?)
+            .add_header(IF_NONE_MATCH, etag.clone())
+            .await;
+        updated.assert_status_ok();
+        assert_eq!(updated.header(ETAG), "W/\"2\"");
+        assert_eq!(updated.json::<GetUserByIdResponse>().id, user_id);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_user_by_id_with_sparse_fieldset() -> TestResult {
+        let user_id = Uuid::now_v7();
+        let mut users = MockUserService::new();
+
+        users.expect_get_user_by_id().returning(|id| {
+            Ok(User {
+                id: *id,
+                email: EmailAddress::new_unchecked("email@example.com"),
+                ..User::default()
+            })
+        });
+
+        let state = test_state(Some(users), None);
+        let token = test_bearer_token(&state, &user_id);
+        let server = TestServer::new(router(state))?;
+
+        let response = server
+            .get(&format!("/api/v1/users/{user_id}"))
+            .add_query_param("fields", "email,not_a_field")
+            .add_header(AUTHORIZATION, token.parse()?)
+            .await;
+
+        response.assert_status_ok();
+        assert_eq!(
+            response.json::<Value>(),
+            json!({ "id": user_id, "email": "email@example.com" })
+        );
+        assert_eq!(response.header(ETAG), "W/\"0;id+email\"");
+
+        let response = server
+            .get(&format!("/api/v1/users/{user_id}"))
+            .add_header(AUTHORIZATION, token.parse()?)
+            .await;
+
+        assert!(response.json::<Value>()["created_at"].is_string());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_user_by_id_sparse_etag_does_not_revalidate_full_user() -> TestResult {
+        let user_id = Uuid::now_v7();
+        let mut users = MockUserService::new();
+
+        users.expect_get_user_by_id().returning(|id| {
+            Ok(User {
+                id: *id,
+                version: 3,
+                ..User::default()
+            })
+        });
+
+        let state = test_state(Some(users), None);
+        let token = test_bearer_token(&state, &user_id);
+        let server = TestServer::new(router(state))?;
+        let path = format!("/api/v1/users/{user_id}");
+
+        let sparse = server
+            .get(&path)
+            .add_query_param("fields", "email")
+            .add_header(AUTHORIZATION, token.parse()?)
+            .await;
+        let etag = sparse.header(ETAG);
+
+        let full = server
+            .get(&path)
+            .add_header(AUTHORIZATION, token.parse()?)
+            .add_header(IF_NONE_MATCH, etag.clone())
+            .await;
+        full.assert_status_ok();
+        assert_eq!(full.header(ETAG), "W/\"3\"");
+        assert!(full.json::<Value>()["created_at"].is_string());
+
+        let same_fields = server
+            .get(&path)
+            .add_query_param("fields", "id, email")
+            .add_header(AUTHORIZATION, token.parse()?)
+            .add_header(IF_NONE_MATCH, etag)
+            .await;
+        same_fields.assert_status(StatusCode::NOT_MODIFIED);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fields_match_the_response() -> TestResult {
+        let user = serde_json::to_value(GetUserByIdResponse::from(User::default()))?;
+
+        let mut fields: Vec<&str> = user
+            .as_object()
+            .into_iter()
+            .flat_map(|user| user.keys())
+            .map(String::as_str)
+            .collect();
+        let mut expected = FIELDS.to_vec();
+
+        fields.sort_unstable();
+        expected.sort_unstable();
+
+        assert_eq!(fields, expected);
 
         Ok(())
     }
@@ -132,9 +417,11 @@ mod tests {
             .returning(move |_| Err(GetUserByIdError::UserNotFound));
 
         let state = test_state(Some(users), None);
+        let token = test_bearer_token(&state, &user_id);
 
         let response = TestServer::new(router(state))?
             .get(&format!("/api/v1/users/{user_id}"))
+            .add_header(AUTHORIZATION, token.parse()?)
             .await;
 
         let json = response.json::<ErrorResponse>();
@@ -144,4 +431,109 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_get_user_by_id_missing_token() -> TestResult {
+        let mut users = MockUserService::new();
+
+        users.expect_get_user_by_id().times(0);
+
+        let state = test_state(Some(users), None);
+
+        let response = TestServer::new(router(state))?
+            .get(&format!("/api/v1/users/{}", Uuid::now_v7()))
+            .await;
+
+        let json = response.json::<ErrorResponse>();
+
+        assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+        assert_eq!(json.error, "Missing bearer token");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_user_by_id_expired_token() -> TestResult {
+        let user_id = Uuid::now_v7();
+        let mut users = MockUserService::new();
+
+        users.expect_get_user_by_id().times(0);
+
+        let state = test_state(Some(users), None);
+
+        let expired_config = AuthConfig {
+            access_token_ttl_seconds: -3600,
+            ..state.config.auth.clone()
+        };
+        let access_token = issue_access_token(&expired_config, &user_id)?;
+
+        let response = TestServer::new(router(state))?
+            .get(&format!("/api/v1/users/{user_id}"))
+            .add_header(
+                AUTHORIZATION,
+                format!("Bearer {}", access_token.token).parse()?,
+            )
+            .await;
+
+        let json = response.json::<ErrorResponse>();
+
+        assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+        assert_eq!(json.error, "Access token has expired");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_user_by_id_tampered_token() -> TestResult {
+        let user_id = Uuid::now_v7();
+        let mut users = MockUserService::new();
+
+        users.expect_get_user_by_id().times(0);
+
+        let state = test_state(Some(users), None);
+
+        let forged_config = AuthConfig {
+            signing_key: "not-the-real-signing-key".to_string(),
+            ..state.config.auth.clone()
+        };
+        let access_token = issue_access_token(&forged_config, &user_id)?;
+
+        let response = TestServer::new(router(state))?
+            .get(&format!("/api/v1/users/{user_id}"))
+            .add_header(
+                AUTHORIZATION,
+                format!("Bearer {}", access_token.token).parse()?,
+            )
+            .await;
+
+        let json = response.json::<ErrorResponse>();
+
+        assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+        assert_eq!(json.error, "Access token is invalid");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_user_by_id_other_user() -> TestResult {
+        let user_id = Uuid::now_v7();
+        let mut users = MockUserService::new();
+
+        users.expect_get_user_by_id().times(0);
+
+        let state = test_state(Some(users), None);
+        let token = test_bearer_token(&state, &Uuid::now_v7());
+
+        let response = TestServer::new(router(state))?
+            .get(&format!("/api/v1/users/{user_id}"))
+            .add_header(AUTHORIZATION, token.parse()?)
+            .await;
+
+        let json = response.json::<ErrorResponse>();
+
+        assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+        assert_eq!(json.error, "You may only access your own user");
+
+        Ok(())
+    }
 }
@@ -0,0 +1,422 @@
+//! List users handler
+
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
+
+use crate::{
+    domain::{
+        auth::users::{UserCursor, UserFilter, UserService},
+        communication::email_addresses::EmailAddressService,
+        health::DatabaseHealth,
+        idempotency::IdempotencyStore,
+    },
+    infrastructure::http::{
+        errors::ApiError,
+        extractors::require_role::{Admin, RequireRole},
+        handlers::v1::auth::get_user_by_id::GetUserByIdResponse,
+        pagination::{encode_user_cursor, parse_user_cursor, Page},
+        state::AppState,
+    },
+};
+
+const DEFAULT_LIMIT: i64 = 50;
+const MAX_LIMIT: i64 = 100;
+
+/// List users query parameters
+#[derive(Debug, Default, Serialize, Deserialize, IntoParams)]
+pub struct ListUsersParams {
+    /// The maximum number of users to return, at most 100
+    #[param(example = 50)]
+    limit: Option<i64>,
+
+    /// The number of users to skip
+    #[param(example = 0)]
+    offset: Option<i64>,
+
+    /// The `next_cursor` of the previous page, to continue from instead of `offset`. Unlike an
+    /// offset, it isn't shifted by users being created or deleted between pages
+    #[param(example = "MTcyMjQ3MDQwMDAwMDAwMDowMTkxMGI3Yy0wMDAwLTdhYmMtOGRlZi0wMTIzNDU2Nzg5YWI")]
+    cursor: Option<String>,
+
+    /// Only users created after this date
+    #[param(example = "2024-01-01T00:00:00Z")]
+    created_after: Option<DateTime<Utc>>,
+
+    /// Only users created before this date
+    #[param(example = "2025-01-01T00:00:00Z")]
+    created_before: Option<DateTime<Utc>>,
+
+    /// Only users whose email address has, or hasn't, been confirmed
+    #[param(example = true)]
+    confirmed: Option<bool>,
+}
+
+impl ListUsersParams {
+    fn filter(&self) -> UserFilter {
+        UserFilter {
+            created_after: self.created_after,
+            created_before: self.created_before,
+            confirmed: self.confirmed,
+        }
+    }
+}
+
+/// List users
+#[utoipa::path(
+    get,
+    operation_id = "list_users",
+    tag = "Auth",
+    path = "/api/v1/users",
+    params(ListUsersParams),
+    security(("bearerAuth" = [])),
+    responses(
+        (status = StatusCode::OK, description = "Users, oldest first", body = UsersPage),
+        (status = StatusCode::UNAUTHORIZED, description = "Missing, invalid or expired access token", body = ErrorResponse),
+        (status = StatusCode::FORBIDDEN, description = "User is not an admin", body = ErrorResponse, example = json!({ "error": "You do not have permission to do that", "code": "forbidden" })),
+        (status = StatusCode::UNPROCESSABLE_ENTITY, description = "Invalid pagination cursor", body = ErrorResponse),
+        (status = StatusCode::INTERNAL_SERVER_ERROR, description = "Internal Server Error", body = ErrorResponse),
+        (status = StatusCode::TOO_MANY_REQUESTS, description = "Too many requests", body = TooManyRequestsResponse),
+    )
+)]
+pub async fn handler<
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+>(
+    State(state): State<AppState<U, E, I, H>>,
+    _: RequireRole<Admin>,
+    Query(params): Query<ListUsersParams>,
+) -> Result<Json<Page<GetUserByIdResponse>>, ApiError> {
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let (after, offset) = match &params.cursor {
+        Some(cursor) => (Some(parse_user_cursor(cursor)?), 0),
+        None => (None, params.offset.unwrap_or(0).max(0)),
+    };
+
+    // One more than a page is fetched to tell whether there's a next page
+    let users = state
+        .users
+        .list_users(&params.filter(), after, limit + 1, offset)
+        .await?;
+
+    Ok(Json(
+        Page::from_items(users, limit, |user| {
+            encode_user_cursor(&UserCursor::from(user))
+        })
+        .map(Into::into),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{
+        header::{AUTHORIZATION, COOKIE},
+        StatusCode,
+    };
+    use axum_test::TestServer;
+    use chrono::Utc;
+    use mockall::predicate::eq;
+    use serde_json::Value;
+    use testresult::TestResult;
+    use uuid::Uuid;
+
+    use crate::{
+        domain::{
+            auth::users::{tests::MockUserService, Role, User, UserCursor, UserFilter},
+            timestamps::Timestamps,
+        },
+        infrastructure::http::{
+            errors::ErrorResponse,
+            pagination::{encode_user_cursor, parse_user_cursor},
+            servers::https::router,
+            state::tests::{test_bearer_token, test_session_cookie, test_state},
+        },
+    };
+
+    #[tokio::test]
+    async fn test_list_users_as_admin() -> TestResult {
+        let admin = User {
+            id: Uuid::now_v7(),
+            role: Role::Admin,
+            ..User::default()
+        };
+        let admin_id = admin.id;
+        let listed_admin = admin.clone();
+
+        let mut users = MockUserService::new();
+
+        users
+            .expect_get_user_by_id()
+            .with(eq(admin_id))
+            .returning(move |_| Ok(admin.clone()));
+
+        users
+            .expect_list_users()
+            .times(1)
+            .with(eq(UserFilter::default()), eq(None), eq(101), eq(0))
+            .returning(move |_, _, _, _| Ok(vec![listed_admin.clone(), User::default()]));
+
+        let state = test_state(Some(users), None);
+        let token = test_bearer_token(&state, &admin_id);
+
+        let response = TestServer::new(router(state))?
+            .get("/api/v1/users")
+            .add_query_param("limit", 1000)
+            .add_header(AUTHORIZATION, token.parse()?)
+            .await;
+
+        response.assert_status_ok();
+
+        let json = response.json::<Value>();
+
+        assert_eq!(json["items"].as_array().map(Vec::len), Some(2));
+        assert_eq!(json["has_more"], false);
+
+        Ok(())
+    }
+
+    async fn list_users_with_filter(query: &str, expected: UserFilter) -> TestResult {
+        let admin = User {
+            id: Uuid::now_v7(),
+            role: Role::Admin,
+            ..User::default()
+        };
+        let admin_id = admin.id;
+
+        let mut users = MockUserService::new();
+
+        users
+            .expect_get_user_by_id()
+            .with(eq(admin_id))
+            .returning(move |_| Ok(admin.clone()));
+
+        users
+            .expect_list_users()
+            .times(1)
+            .with(eq(expected), eq(None), eq(51), eq(0))
+            .returning(|_, _, _, _| Ok(vec![]));
+
+        let state = test_state(Some(users), None);
+        let token = test_bearer_token(&state, &admin_id);
+
+        TestServer::new(router(state))?
+            .get(&format!("/api/v1/users?{query}"))
+            .add_header(AUTHORIZATION, token.parse()?)
+            .await
+            .assert_status_ok();
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_list_users_pages_with_cursor() -> TestResult {
+        let admin = User {
+            id: Uuid::now_v7(),
+            role: Role::Admin,
+            ..User::default()
+        };
+        let admin_id = admin.id;
+
+        let mut users = MockUserService::new();
+
+        users
+            .expect_get_user_by_id()
+            .with(eq(admin_id))
+            .returning(move |_| Ok(admin.clone()));
+
+        let listed: Vec<User> = (0..3)
+            .map(|_| User {
+                id: Uuid::now_v7(),
+                timestamps: Timestamps::created(Utc::now()),
+                ..User::default()
+            })
+            .collect();
+        let last_on_page = UserCursor::from(&listed[1]);
+
+        let after = UserCursor {
+            created_at: Utc::now(),
+            id: Uuid::now_v7(),
+        };
+        let cursor = encode_user_cursor(&after);
+        let after = parse_user_cursor(&cursor)?;
+
+        users
+            .expect_list_users()
+            .times(1)
+            .with(eq(UserFilter::default()), eq(Some(after)), eq(3), eq(0))
+            .returning(move |_, _, _, _| Ok(listed.clone()));
+
+        let state = test_state(Some(users), None);
+        let token = test_bearer_token(&state, &admin_id);
+
+        let response = TestServer::new(router(state))?
+            .get(&format!("/api/v1/users?limit=2&offset=5&cursor={cursor}"))
+            .add_header(AUTHORIZATION, token.parse()?)
+            .await;
+
+        response.assert_status_ok();
+
+        let json = response.json::<Value>();
+
+        assert_eq!(json["items"].as_array().map(Vec::len), Some(2));
+        assert_eq!(json["next_cursor"], encode_user_cursor(&last_on_page));
+        assert_eq!(json["has_more"], true);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_list_users_passes_confirmed_filter_through() -> TestResult {
+        list_users_with_filter(
+            "confirmed=false",
+            UserFilter {
+                confirmed: Some(false),
+                ..UserFilter::default()
+            },
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_list_users_combines_confirmed_with_date_range() -> TestResult {
+        list_users_with_filter(
+            "confirmed=true&created_after=2024-01-01T00:00:00Z&created_before=2025-01-01T00:00:00Z",
+            UserFilter {
+                created_after: Some("2024-01-01T00:00:00Z".parse()?),
+                created_before: Some("2025-01-01T00:00:00Z".parse()?),
+                confirmed: Some(true),
+            },
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_list_users_as_admin_with_session_cookie() -> TestResult {
+        let admin = User {
+            id: Uuid::now_v7(),
+            role: Role::Admin,
+            ..User::default()
+        };
+        let admin_id = admin.id;
+
+        let mut users = MockUserService::new();
+
+        users
+            .expect_get_user_by_id()
+            .with(eq(admin_id))
+            .returning(move |_| Ok(admin.clone()));
+
+        users
+            .expect_list_users()
+            .times(1)
+            .returning(|_, _, _, _| Ok(vec![User::default()]));
+
+        let state = test_state(Some(users), None);
+        let cookie = test_session_cookie(&state, &admin_id);
+
+        let response = TestServer::new(router(state))?
+            .get("/api/v1/users")
+            .add_header(COOKIE, format!("theme=dark; {cookie}").parse()?)
+            .await;
+
+        response.assert_status_ok();
+
+        assert_eq!(
+            response.json::<Value>()["items"].as_array().map(Vec::len),
+            Some(1)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_list_users_with_invalid_session_cookie_falls_back_to_bearer_token() -> TestResult
+    {
+        let admin = User {
+            id: Uuid::now_v7(),
+            role: Role::Admin,
+            ..User::default()
+        };
+        let admin_id = admin.id;
+
+        let mut users = MockUserService::new();
+
+        users
+            .expect_get_user_by_id()
+            .with(eq(admin_id))
+            .returning(move |_| Ok(admin.clone()));
+
+        users
+            .expect_list_users()
+            .times(1)
+            .returning(|_, _, _, _| Ok(vec![]));
+
+        let state = test_state(Some(users), None);
+        let token = test_bearer_token(&state, &admin_id);
+
+        let response = TestServer::new(router(state))?
+            .get("/api/v1/users")
+            .add_header(COOKIE, "session=not-a-token".parse()?)
+            .add_header(AUTHORIZATION, token.parse()?)
+            .await;
+
+        response.assert_status_ok();
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_list_users_as_regular_user() -> TestResult {
+        let user = User {
+            id: Uuid::now_v7(),
+            role: Role::User,
+            ..User::default()
+        };
+        let user_id = user.id;
+
+        let mut users = MockUserService::new();
+
+        users
+            .expect_get_user_by_id()
+            .with(eq(user_id))
+            .returning(move |_| Ok(user.clone()));
+
+        users.expect_list_users().times(0);
+
+        let state = test_state(Some(users), None);
+        let token = test_bearer_token(&state, &user_id);
+
+        let response = TestServer::new(router(state))?
+            .get("/api/v1/users")
+            .add_header(AUTHORIZATION, token.parse()?)
+            .await;
+
+        let json = response.json::<ErrorResponse>();
+
+        assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+        assert_eq!(json.code, Some("forbidden".to_string()));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_list_users_unauthenticated() -> TestResult {
+        let mut users = MockUserService::new();
+
+        users.expect_list_users().times(0);
+
+        let state = test_state(Some(users), None);
+
+        let response = TestServer::new(router(state))?.get("/api/v1/users").await;
+
+        assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,133 @@
+//! Get the current user
+
+use axum::Json;
+
+use crate::infrastructure::http::{
+    extractors::current_user::CurrentUser, handlers::v1::auth::get_user_by_id::GetUserByIdResponse,
+};
+
+/// Get the user the access token or session belongs to
+#[utoipa::path(
+    get,
+    operation_id = "get_current_user",
+    tag = "Auth",
+    path = "/api/v1/auth/me",
+    security(("bearerAuth" = [])),
+    responses(
+        (status = StatusCode::OK, description = "The authenticated user", body = GetUserByIdResponse),
+        (status = StatusCode::UNAUTHORIZED, description = "Missing, invalid or expired access token", body = ErrorResponse),
+        (status = StatusCode::INTERNAL_SERVER_ERROR, description = "Internal Server Error", body = ErrorResponse),
+        (status = StatusCode::TOO_MANY_REQUESTS, description = "Too many requests", body = TooManyRequestsResponse),
+    )
+)]
+pub async fn handler(CurrentUser(user): CurrentUser) -> Json<GetUserByIdResponse> {
+    Json(GetUserByIdResponse::from(user))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{header::AUTHORIZATION, StatusCode};
+    use axum_test::TestServer;
+    use serde_json::Value;
+    use testresult::TestResult;
+    use uuid::Uuid;
+
+    use crate::{
+        domain::{
+            auth::{
+                tokens::{issue_access_token, AuthConfig},
+                users::{tests::MockUserService, User},
+            },
+            communication::email_addresses::EmailAddress,
+        },
+        infrastructure::http::{
+            errors::ErrorResponse,
+            servers::https::router,
+            state::tests::{test_bearer_token, test_state},
+        },
+    };
+
+    #[tokio::test]
+    async fn test_get_current_user_returns_token_user() -> TestResult {
+        let user_id = Uuid::now_v7();
+        let mut users = MockUserService::new();
+
+        users
+            .expect_get_user_by_id()
+            .withf(move |id| *id == user_id)
+            .returning(|id| {
+                Ok(User {
+                    id: *id,
+                    email: EmailAddress::new_unchecked("email@example.com"),
+                    ..User::default()
+                })
+            });
+
+        let state = test_state(Some(users), None);
+        let token = test_bearer_token(&state, &user_id);
+
+        let response = TestServer::new(router(state))?
+            .get("/api/v1/auth/me")
+            .add_header(AUTHORIZATION, token.parse()?)
+            .await;
+
+        response.assert_status_ok();
+
+        let json = response.json::<Value>();
+
+        assert_eq!(json["id"], user_id.to_string());
+        assert_eq!(json["email"], "email@example.com");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_current_user_missing_token() -> TestResult {
+        let mut users = MockUserService::new();
+
+        users.expect_get_user_by_id().times(0);
+
+        let response = TestServer::new(router(test_state(Some(users), None)))?
+            .get("/api/v1/auth/me")
+            .await;
+
+        response.assert_status(StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            response.json::<ErrorResponse>().error,
+            "Missing bearer token"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_current_user_expired_token() -> TestResult {
+        let mut users = MockUserService::new();
+
+        users.expect_get_user_by_id().times(0);
+
+        let state = test_state(Some(users), None);
+
+        let expired_config = AuthConfig {
+            access_token_ttl_seconds: -3600,
+            ..state.config.auth.clone()
+        };
+        let access_token = issue_access_token(&expired_config, &Uuid::now_v7())?;
+
+        let response = TestServer::new(router(state))?
+            .get("/api/v1/auth/me")
+            .add_header(
+                AUTHORIZATION,
+                format!("Bearer {}", access_token.token).parse()?,
+            )
+            .await;
+
+        response.assert_status(StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            response.json::<ErrorResponse>().error,
+            "Access token has expired"
+        );
+
+        Ok(())
+    }
+}
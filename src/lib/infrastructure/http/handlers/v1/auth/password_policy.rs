@@ -0,0 +1,115 @@
+//! Password policy handler
+
+use axum::{extract::State, Json};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{
+    domain::{
+        auth::users::{CharacterClass, PasswordPolicy, UserService},
+        communication::email_addresses::EmailAddressService,
+        health::DatabaseHealth,
+        idempotency::IdempotencyStore,
+    },
+    infrastructure::http::state::AppState,
+};
+
+/// The password policy enforced when creating users
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PasswordPolicyResponse {
+    /// The minimum password length, in characters
+    #[schema(example = 8)]
+    pub min_length: usize,
+
+    /// The maximum password length, in UTF-8 bytes
+    #[schema(example = 100)]
+    pub max_length: usize,
+
+    /// The minimum zxcvbn strength score, from 0 (weakest) to 4 (strongest)
+    #[schema(example = 3)]
+    pub min_score: u8,
+
+    /// The character classes a password must contain at least one of each of
+    #[schema(value_type = Vec<String>, example = json!(["lowercase", "uppercase", "digit", "symbol"]))]
+    pub required_classes: Vec<CharacterClass>,
+}
+
+impl From<PasswordPolicy> for PasswordPolicyResponse {
+    fn from(policy: PasswordPolicy) -> Self {
+        Self {
+            min_length: policy.min_length,
+            max_length: policy.max_length,
+            min_score: policy.min_score,
+            required_classes: policy.required_classes,
+        }
+    }
+}
+
+/// Get the password policy
+#[utoipa::path(
+    get,
+    operation_id = "get_password_policy",
+    tag = "Auth",
+    path = "/api/v1/auth/password-policy",
+    responses(
+        (status = StatusCode::OK, description = "Password policy", body = PasswordPolicyResponse),
+        (status = StatusCode::TOO_MANY_REQUESTS, description = "Too many requests", body = TooManyRequestsResponse),
+    )
+)]
+pub async fn handler<
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+>(
+    State(state): State<AppState<U, E, I, H>>,
+) -> Json<PasswordPolicyResponse> {
+    Json(state.config.password_policy.clone().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum_test::TestServer;
+    use testresult::TestResult;
+
+    use crate::{
+        domain::auth::users::{CharacterClass, PasswordPolicy},
+        infrastructure::http::{servers::https::router, state::tests::test_state},
+    };
+
+    use super::PasswordPolicyResponse;
+
+    #[tokio::test]
+    async fn test_password_policy_reflects_config() -> TestResult {
+        let mut state = test_state(None, None);
+        state.config.password_policy = PasswordPolicy {
+            min_length: 12,
+            max_length: 64,
+            min_score: 4,
+            required_classes: vec![CharacterClass::Uppercase, CharacterClass::Symbol],
+        };
+
+        let response = TestServer::new(router(state))?
+            .get("/api/v1/auth/password-policy")
+            .await;
+
+        response.assert_status_ok();
+
+        let json = response.json::<PasswordPolicyResponse>();
+
+        assert_eq!(json.min_length, 12);
+        assert_eq!(json.max_length, 64);
+        assert_eq!(json.min_score, 4);
+        assert_eq!(
+            json.required_classes,
+            vec![CharacterClass::Uppercase, CharacterClass::Symbol]
+        );
+
+        assert_eq!(
+            response.json::<serde_json::Value>()["required_classes"],
+            serde_json::json!(["uppercase", "symbol"])
+        );
+
+        Ok(())
+    }
+}
@@ -14,16 +14,30 @@ use crate::{
     domain::{
         auth::users::UserService,
         communication::email_addresses::{EmailAddressService, EmailConfirmationType},
+        health::DatabaseHealth,
+        idempotency::IdempotencyStore,
+    },
+    infrastructure::http::{
+        errors::ApiError,
+        extractors::{client_origin::ClientOrigin, request_base_url::RequestBaseUrl},
+        state::AppState,
     },
-    infrastructure::http::{errors::ApiError, state::AppState},
 };
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct SendEmailConfirmationResponse {
     expires_at: DateTime<Utc>,
+
+    /// The confirmation link, only included when `EXPOSE_CONFIRMATION_LINKS` is enabled outside
+    /// production
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    confirmation_link: Option<String>,
 }
 
 /// Send an email confirmation email
+///
+/// The confirmation link points back at the host the request was made to if it's one of the
+/// allowed hosts, and at the configured base URL otherwise.
 #[utoipa::path(
     post,
     operation_id = "send_email_confirmation",
@@ -35,34 +49,60 @@ pub struct SendEmailConfirmationResponse {
     responses(
         (status = StatusCode::ACCEPTED, description = "Email confirmation sent", body = SendEmailConfirmationResponse),
         (status = StatusCode::NOT_FOUND, description = "User not found", body = ErrorResponse, example = json!({ "error": "User with id \"550e8400-e29b-41d4-a716-446655440000\" not found" })),
+        (status = StatusCode::UNPROCESSABLE_ENTITY, description = "The email address can't receive email", body = ErrorResponse, example = json!({ "error": "That email address can't receive email" })),
         (status = StatusCode::INTERNAL_SERVER_ERROR, description = "Internal Server Error", body = ErrorResponse, example = json!({ "error": "Failed to send email confirmation: <error>" })),
+        (status = StatusCode::SERVICE_UNAVAILABLE, description = "Email delivery is temporarily unavailable", body = ErrorResponse, example = json!({ "error": "Email could not be sent, please try again later" })),
         (status = StatusCode::TOO_MANY_REQUESTS, description = "Too many requests", body = TooManyRequestsResponse),
     )
 )]
-pub async fn handler<U: UserService, E: EmailAddressService>(
-    State(state): State<AppState<U, E>>,
+pub async fn handler<
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+>(
+    State(state): State<AppState<U, E, I, H>>,
     Path(user_id): Path<Uuid>,
+    RequestBaseUrl(base_url): RequestBaseUrl,
+    ClientOrigin(origin): ClientOrigin,
 ) -> Result<(StatusCode, Json<SendEmailConfirmationResponse>), ApiError> {
     let user = state.users.get_user_by_id(&user_id).await?;
 
-    let expires_at = state
+    let sent = state
         .email_addresses
         .send_email_confirmation(
             &user,
             EmailConfirmationType::CurrentEmail,
-            &state.config.base_url,
+            &base_url,
+            &origin,
         )
         .await?;
 
     Ok((
         StatusCode::ACCEPTED,
-        Json(SendEmailConfirmationResponse { expires_at }),
+        Json(SendEmailConfirmationResponse {
+            expires_at: sent.expires_at,
+            confirmation_link: state
+                .config
+                .debug
+                .exposes_confirmation_links()
+                .then_some(sent.link),
+        }),
     ))
 }
 
 #[cfg(test)]
 mod tests {
-    use axum::http::StatusCode;
+    use std::{
+        net::{Ipv4Addr, SocketAddr},
+        sync::{Arc, Mutex},
+    };
+
+    use axum::{
+        extract::ConnectInfo,
+        http::{header::USER_AGENT, HeaderName, StatusCode},
+        Extension,
+    };
     use axum_test::TestServer;
     use chrono::{Duration, Utc};
     use testresult::TestResult;
@@ -70,10 +110,16 @@ mod tests {
 
     use crate::{
         domain::{
-            auth::users::{errors::GetUserByIdError, tests::MockUserService, User},
-            communication::email_addresses::{tests::MockEmailAddressService, EmailAddress},
+            auth::users::{errors::GetUserByIdError, tests::MockUserService, Role, User},
+            communication::email_addresses::{
+                tests::MockEmailAddressService, EmailAddress, EmailConfirmationError,
+                RequestOrigin, SentEmailConfirmation,
+            },
+            i18n::Locale,
+            timestamps::Timestamps,
         },
         infrastructure::http::{
+            allowed_hosts::AllowedHostsConfig, client_ip::TrustedProxiesConfig, debug::DebugConfig,
             errors::ErrorResponse,
             handlers::v1::auth::send_email_confirmation::SendEmailConfirmationResponse,
             servers::https::router, state::tests::test_state,
@@ -86,14 +132,19 @@ mod tests {
         let yesterday = Utc::now() - Duration::days(1);
 
         let user = User {
-            id: user_id.clone(),
+            id: user_id,
             email: EmailAddress::new_unchecked("email@example.com"),
             new_email: None,
             email_confirmed_at: None,
             email_confirmation_token: Some("token".to_string()),
-            email_confirmation_sent_at: Some(yesterday.clone()),
-            created_at: yesterday.clone(),
-            updated_at: yesterday.clone(),
+            email_confirmation_sent_at: Some(yesterday),
+            email_confirmation_expires_at: Some(yesterday + Duration::hours(24)),
+            role: Role::User,
+            terms_accepted_at: None,
+            terms_version: None,
+            locale: Locale::English,
+            timestamps: Timestamps::created(yesterday),
+            version: 1,
         };
 
         let expected_expiry = Utc::now() + Duration::days(1);
@@ -109,10 +160,15 @@ mod tests {
         email_addresses
             .expect_send_email_confirmation()
             .times(1)
-            .withf(move |user, _, base_url| {
-                *user == user.clone() && base_url == "https://example.com"
+            .withf(move |user, _, base_url, _| {
+                *user == user.clone() && base_url.as_str() == "https://example.com"
             })
-            .returning(move |_, _, _| Ok(expected_expiry.clone()));
+            .returning(move |_, _, _, _| {
+                Ok(SentEmailConfirmation {
+                    expires_at: expected_expiry,
+                    link: "https://example.com/confirm".to_string(),
+                })
+            });
 
         let state = test_state(Some(users), Some(email_addresses));
 
@@ -127,6 +183,55 @@ mod tests {
 
         assert_eq!(response.status_code(), StatusCode::ACCEPTED);
         assert_eq!(json.expires_at, expected_expiry);
+        assert_eq!(json.confirmation_link, None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_email_confirmation_records_client_origin() -> TestResult {
+        let user = User {
+            id: Uuid::now_v7(),
+            ..User::default()
+        };
+        let user_id = user.id;
+
+        let mut users = MockUserService::new();
+        let mut email_addresses = MockEmailAddressService::new();
+
+        users
+            .expect_get_user_by_id()
+            .returning(move |_| Ok(user.clone()));
+
+        let expected_origin = RequestOrigin {
+            ip: Some(Ipv4Addr::new(203, 0, 113, 7).into()),
+            user_agent: Some("Mozilla/5.0".to_string()),
+        };
+
+        email_addresses
+            .expect_send_email_confirmation()
+            .times(1)
+            .withf(move |_, _, _, origin| *origin == expected_origin)
+            .returning(|_, _, _, _| {
+                Ok(SentEmailConfirmation {
+                    expires_at: Utc::now() + Duration::days(1),
+                    link: "https://example.com/confirm".to_string(),
+                })
+            });
+
+        // The peer isn't a trusted proxy, so the address it forwards for is ignored
+        let state = test_state(Some(users), Some(email_addresses));
+        let peer = SocketAddr::new(Ipv4Addr::new(203, 0, 113, 7).into(), 1234);
+
+        TestServer::new(router(state).layer(Extension(ConnectInfo(peer))))?
+            .post(&format!("/api/v1/users/{user_id}/email/confirmation"))
+            .add_header(
+                HeaderName::from_static("x-forwarded-for"),
+                "198.51.100.1".parse()?,
+            )
+            .add_header(USER_AGENT, "Mozilla/5.0".parse()?)
+            .await
+            .assert_status(StatusCode::ACCEPTED);
 
         Ok(())
     }
@@ -161,4 +266,221 @@ mod tests {
 
         Ok(())
     }
+
+    async fn send_email_confirmation_failing_with(
+        error: EmailConfirmationError,
+    ) -> TestResult<(StatusCode, ErrorResponse)> {
+        let user = User {
+            id: Uuid::now_v7(),
+            ..User::default()
+        };
+        let user_id = user.id;
+
+        let mut users = MockUserService::new();
+        let mut email_addresses = MockEmailAddressService::new();
+
+        users
+            .expect_get_user_by_id()
+            .returning(move |_| Ok(user.clone()));
+
+        let mut error = Some(error);
+
+        email_addresses
+            .expect_send_email_confirmation()
+            .times(1)
+            .returning(move |_, _, _, _| Err(error.take().expect("called twice")));
+
+        let state = test_state(Some(users), Some(email_addresses));
+
+        let response = TestServer::new(router(state))?
+            .post(&format!("/api/v1/users/{user_id}/email/confirmation"))
+            .await;
+
+        Ok((response.status_code(), response.json::<ErrorResponse>()))
+    }
+
+    #[tokio::test]
+    async fn test_send_email_confirmation_invalid_recipient() -> TestResult {
+        let (status, json) =
+            send_email_confirmation_failing_with(EmailConfirmationError::InvalidRecipient).await?;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(json.error, "That email address can't receive email");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_email_confirmation_mailer_unavailable() -> TestResult {
+        let (status, json) =
+            send_email_confirmation_failing_with(EmailConfirmationError::MailerUnavailable).await?;
+
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            json.error,
+            "Email could not be sent, please try again later"
+        );
+
+        Ok(())
+    }
+
+    async fn send_email_confirmation_with_debug(
+        debug: DebugConfig,
+    ) -> TestResult<SendEmailConfirmationResponse> {
+        let user = User {
+            id: Uuid::now_v7(),
+            ..User::default()
+        };
+        let user_id = user.id;
+
+        let mut users = MockUserService::new();
+        let mut email_addresses = MockEmailAddressService::new();
+
+        users
+            .expect_get_user_by_id()
+            .returning(move |_| Ok(user.clone()));
+
+        email_addresses
+            .expect_send_email_confirmation()
+            .times(1)
+            .returning(|_, _, _, _| {
+                Ok(SentEmailConfirmation {
+                    expires_at: Utc::now() + Duration::days(1),
+                    link: "https://example.com/confirm?token=abc".to_string(),
+                })
+            });
+
+        let mut state = test_state(Some(users), Some(email_addresses));
+        state.config.debug = debug;
+
+        let response = TestServer::new(router(state))?
+            .post(&format!("/api/v1/users/{user_id}/email/confirmation"))
+            .await;
+
+        response.assert_status(StatusCode::ACCEPTED);
+
+        Ok(response.json::<SendEmailConfirmationResponse>())
+    }
+
+    #[tokio::test]
+    async fn test_send_email_confirmation_exposes_link_when_enabled() -> TestResult {
+        let json = send_email_confirmation_with_debug(DebugConfig {
+            expose_confirmation_links: true,
+            ..DebugConfig::default()
+        })
+        .await?;
+
+        assert_eq!(
+            json.confirmation_link.as_deref(),
+            Some("https://example.com/confirm?token=abc")
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_email_confirmation_never_exposes_link_in_production() -> TestResult {
+        let json = send_email_confirmation_with_debug(DebugConfig {
+            environment: "production".to_string(),
+            expose_confirmation_links: true,
+        })
+        .await?;
+
+        assert_eq!(json.confirmation_link, None);
+
+        Ok(())
+    }
+
+    /// Sends a confirmation from `peer` with `X-Forwarded-Host: forwarded_host`, allowing
+    /// `*.preview.example.com` behind proxies in 10.0.0.0/8, and returns the base URL the link was
+    /// built from
+    async fn confirmation_base_url(peer: Ipv4Addr, forwarded_host: &str) -> TestResult<String> {
+        let user = User {
+            id: Uuid::now_v7(),
+            ..User::default()
+        };
+        let user_id = user.id;
+
+        let mut users = MockUserService::new();
+        let mut email_addresses = MockEmailAddressService::new();
+
+        users
+            .expect_get_user_by_id()
+            .returning(move |_| Ok(user.clone()));
+
+        let base_url = Arc::new(Mutex::new(String::new()));
+        let captured = base_url.clone();
+
+        email_addresses
+            .expect_send_email_confirmation()
+            .times(1)
+            .returning(move |_, _, base_url, _| {
+                *captured.lock().unwrap() = base_url.to_string();
+
+                Ok(SentEmailConfirmation {
+                    expires_at: Utc::now() + Duration::days(1),
+                    link: format!("{base_url}/confirm"),
+                })
+            });
+
+        let mut state = test_state(Some(users), Some(email_addresses));
+        state.config.allowed_hosts = AllowedHostsConfig {
+            allowed_hosts: vec!["*.preview.example.com".to_string()],
+        };
+        state.config.trusted_proxies = TrustedProxiesConfig {
+            trusted_proxies: vec!["10.0.0.0/8".parse()?],
+            ..TrustedProxiesConfig::default()
+        };
+
+        let router =
+            router(state).layer(Extension(ConnectInfo(SocketAddr::new(peer.into(), 1234))));
+
+        TestServer::new(router)?
+            .post(&format!("/api/v1/users/{user_id}/email/confirmation"))
+            .add_header(
+                HeaderName::from_static("x-forwarded-host"),
+                forwarded_host.parse()?,
+            )
+            .add_header(
+                HeaderName::from_static("x-forwarded-proto"),
+                "https".parse()?,
+            )
+            .await
+            .assert_status(StatusCode::ACCEPTED);
+
+        let base_url = base_url.lock().unwrap().clone();
+
+        Ok(base_url)
+    }
+
+    #[tokio::test]
+    async fn test_send_email_confirmation_uses_allowed_forwarded_host() -> TestResult {
+        let base_url =
+            confirmation_base_url(Ipv4Addr::new(10, 0, 0, 1), "pr-42.preview.example.com").await?;
+
+        assert_eq!(base_url, "https://pr-42.preview.example.com");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_email_confirmation_ignores_disallowed_forwarded_host() -> TestResult {
+        let base_url = confirmation_base_url(Ipv4Addr::new(10, 0, 0, 1), "evil.com").await?;
+
+        assert_eq!(base_url, "https://example.com");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_email_confirmation_ignores_forwarded_host_from_untrusted_peer() -> TestResult
+    {
+        let base_url =
+            confirmation_base_url(Ipv4Addr::new(203, 0, 113, 7), "pr-42.preview.example.com")
+                .await?;
+
+        assert_eq!(base_url, "https://example.com");
+
+        Ok(())
+    }
 }
@@ -0,0 +1,231 @@
+//! Email confirmation token validation
+
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        auth::users::UserService,
+        communication::email_addresses::{EmailAddressService, EmailConfirmationError},
+        health::DatabaseHealth,
+        idempotency::IdempotencyStore,
+    },
+    infrastructure::http::{
+        errors::ApiError, handlers::v1::auth::confirm_email::ConfirmEmailParams, state::AppState,
+    },
+};
+
+/// Why a confirmation token wouldn't confirm the user's email address
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum InvalidConfirmationTokenReason {
+    /// The token has expired
+    Expired,
+
+    /// The token doesn't match the one last sent, or has already been used
+    Mismatch,
+
+    /// The email address has already been confirmed
+    AlreadyConfirmed,
+}
+
+/// Whether a confirmation token is valid
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ValidateEmailConfirmationResponse {
+    /// Whether following the confirmation link would confirm the user's email address
+    valid: bool,
+
+    /// Why the token isn't valid, if it isn't
+    reason: Option<InvalidConfirmationTokenReason>,
+}
+
+impl ValidateEmailConfirmationResponse {
+    fn invalid(reason: InvalidConfirmationTokenReason) -> Self {
+        Self {
+            valid: false,
+            reason: Some(reason),
+        }
+    }
+}
+
+/// Check whether a confirmation token is valid, without using it up
+///
+/// The token is checked exactly as confirming the email address would check it, so front-ends can
+/// tell the user a link has expired before they follow it.
+#[utoipa::path(
+    get,
+    operation_id = "validate_email_confirmation",
+    tag = "Auth",
+    path = "/api/v1/users/{id}/email/confirmation/validate",
+    params(
+        ("id" = Uuid, Path, description = "The UUID of the user", example = "550e8400-e29b-41d4-a716-446655440000"),
+        ConfirmEmailParams,
+    ),
+    responses(
+        (status = StatusCode::OK, description = "Whether the token is valid", body = ValidateEmailConfirmationResponse),
+        (status = StatusCode::NOT_FOUND, description = "User not found", body = ErrorResponse),
+        (status = StatusCode::INTERNAL_SERVER_ERROR, description = "Internal Server Error", body = ErrorResponse),
+        (status = StatusCode::TOO_MANY_REQUESTS, description = "Too many requests", body = TooManyRequestsResponse),
+    )
+)]
+pub async fn handler<
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+>(
+    State(state): State<AppState<U, E, I, H>>,
+    Path(user_id): Path<Uuid>,
+    Query(query): Query<ConfirmEmailParams>,
+) -> Result<Json<ValidateEmailConfirmationResponse>, ApiError> {
+    let user = state.users.get_user_by_id(&user_id).await?;
+
+    let response = match state
+        .email_addresses
+        .validate_confirmation_token(&user, &query.token)
+    {
+        Ok(()) => ValidateEmailConfirmationResponse {
+            valid: true,
+            reason: None,
+        },
+        Err(EmailConfirmationError::ConfirmationTokenExpired) => {
+            ValidateEmailConfirmationResponse::invalid(InvalidConfirmationTokenReason::Expired)
+        }
+        Err(EmailConfirmationError::ConfirmationTokenMismatch) => {
+            ValidateEmailConfirmationResponse::invalid(InvalidConfirmationTokenReason::Mismatch)
+        }
+        Err(EmailConfirmationError::EmailAlreadyConfirmed) => {
+            ValidateEmailConfirmationResponse::invalid(
+                InvalidConfirmationTokenReason::AlreadyConfirmed,
+            )
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    Ok(Json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use axum_test::TestServer;
+    use testresult::TestResult;
+    use uuid::Uuid;
+
+    use crate::{
+        domain::{
+            auth::users::{errors::GetUserByIdError, tests::MockUserService, User},
+            communication::email_addresses::{
+                tests::MockEmailAddressService, EmailConfirmationError,
+            },
+        },
+        infrastructure::http::{servers::https::router, state::tests::test_state},
+    };
+
+    use super::{InvalidConfirmationTokenReason, ValidateEmailConfirmationResponse};
+
+    async fn validate(
+        result: fn() -> Result<(), EmailConfirmationError>,
+    ) -> TestResult<ValidateEmailConfirmationResponse> {
+        let user_id = Uuid::now_v7();
+
+        let mut users = MockUserService::new();
+        let mut email_addresses = MockEmailAddressService::new();
+
+        users
+            .expect_get_user_by_id()
+            .times(1)
+            .withf(move |id| *id == user_id)
+            .returning(move |_| {
+                Ok(User {
+                    id: user_id,
+                    ..User::default()
+                })
+            });
+
+        email_addresses
+            .expect_validate_confirmation_token()
+            .times(1)
+            .withf(move |user, token| user.id == user_id && token == "test-token")
+            .returning(move |_, _| result());
+
+        email_addresses.expect_confirm_email().times(0);
+
+        let state = test_state(Some(users), Some(email_addresses));
+
+        let response = TestServer::new(router(state))?
+            .get(&format!(
+                "/api/v1/users/{user_id}/email/confirmation/validate"
+            ))
+            .add_raw_query_param("token=test-token")
+            .await;
+
+        response.assert_status_ok();
+
+        Ok(response.json::<ValidateEmailConfirmationResponse>())
+    }
+
+    #[tokio::test]
+    async fn test_validate_email_confirmation_valid() -> TestResult {
+        let response = validate(|| Ok(())).await?;
+
+        assert!(response.valid);
+        assert_eq!(response.reason, None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_validate_email_confirmation_expired() -> TestResult {
+        let response = validate(|| Err(EmailConfirmationError::ConfirmationTokenExpired)).await?;
+
+        assert!(!response.valid);
+        assert_eq!(
+            response.reason,
+            Some(InvalidConfirmationTokenReason::Expired)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_validate_email_confirmation_mismatch() -> TestResult {
+        let response = validate(|| Err(EmailConfirmationError::ConfirmationTokenMismatch)).await?;
+
+        assert!(!response.valid);
+        assert_eq!(
+            response.reason,
+            Some(InvalidConfirmationTokenReason::Mismatch)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_validate_email_confirmation_user_not_found() -> TestResult {
+        let mut users = MockUserService::new();
+
+        users
+            .expect_get_user_by_id()
+            .times(1)
+            .returning(|_| Err(GetUserByIdError::UserNotFound));
+
+        let state = test_state(Some(users), None);
+
+        TestServer::new(router(state))?
+            .get(&format!(
+                "/api/v1/users/{}/email/confirmation/validate",
+                Uuid::now_v7()
+            ))
+            .add_raw_query_param("token=test-token")
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,4 @@
+//! Health handlers
+
+pub mod ready;
+pub mod status;
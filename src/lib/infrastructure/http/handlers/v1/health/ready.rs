@@ -0,0 +1,160 @@
+//! Readiness handler
+
+use axum::{extract::State, http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{
+    domain::{
+        auth::users::UserService, communication::email_addresses::EmailAddressService,
+        health::DatabaseHealth, idempotency::IdempotencyStore,
+    },
+    infrastructure::http::{errors::ApiError, state::AppState},
+};
+
+/// The readiness response
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ReadinessResponse {
+    /// The readiness of the application
+    #[schema(example = "ready")]
+    pub status: String,
+}
+
+/// Check the application is ready to serve traffic
+///
+/// The application is ready once the database can be reached and every migration embedded in
+/// the binary has been applied to it.
+#[utoipa::path(
+    get,
+    operation_id = "get_readiness",
+    tag = "System",
+    path = "/api/v1/health/ready",
+    responses(
+        (status = StatusCode::OK, description = "Ready to serve traffic", body = ReadinessResponse),
+        (status = StatusCode::SERVICE_UNAVAILABLE, description = "Database is unavailable or has pending migrations", body = ErrorResponse, example = json!({ "error": "Database has pending migrations: 20240814140000" })),
+        (status = StatusCode::TOO_MANY_REQUESTS, description = "Too many requests", body = TooManyRequestsResponse),
+    )
+)]
+pub async fn handler<
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+>(
+    State(state): State<AppState<U, E, I, H>>,
+) -> Result<Json<ReadinessResponse>, ApiError> {
+    state.health.ping().await?;
+
+    let pending = state.health.pending_migrations().await?;
+
+    if !pending.is_empty() {
+        let versions = pending
+            .iter()
+            .map(i64::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        return Err(ApiError::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            &format!("Database has pending migrations: {versions}"),
+        ));
+    }
+
+    Ok(Json(ReadinessResponse {
+        status: "ready".to_string(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use anyhow::anyhow;
+    use axum::http::StatusCode;
+    use axum_test::TestServer;
+    use testresult::TestResult;
+
+    use crate::{
+        domain::health::{tests::MockDatabaseHealth, HealthError},
+        infrastructure::http::{
+            errors::ErrorResponse, servers::https::router, state::tests::test_state,
+        },
+    };
+
+    use super::ReadinessResponse;
+
+    #[tokio::test]
+    async fn test_ready_when_migrations_are_applied() -> TestResult {
+        let mut health = MockDatabaseHealth::new();
+
+        health.expect_ping().times(1).returning(|| Ok(()));
+        health
+            .expect_pending_migrations()
+            .times(1)
+            .returning(|| Ok(vec![]));
+
+        let mut state = test_state(None, None);
+        state.health = Arc::new(health);
+
+        let response = TestServer::new(router(state))?
+            .get("/api/v1/health/ready")
+            .await;
+
+        response.assert_status_ok();
+        assert_eq!(response.json::<ReadinessResponse>().status, "ready");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_not_ready_when_a_migration_is_missing() -> TestResult {
+        let mut health = MockDatabaseHealth::new();
+
+        health.expect_ping().times(1).returning(|| Ok(()));
+        health
+            .expect_pending_migrations()
+            .times(1)
+            .returning(|| Ok(vec![20240814140000]));
+
+        let mut state = test_state(None, None);
+        state.health = Arc::new(health);
+
+        let response = TestServer::new(router(state))?
+            .get("/api/v1/health/ready")
+            .await;
+
+        assert_eq!(response.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            response.json::<ErrorResponse>().error,
+            "Database has pending migrations: 20240814140000"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_not_ready_when_database_is_unavailable() -> TestResult {
+        let mut health = MockDatabaseHealth::new();
+
+        health
+            .expect_ping()
+            .times(1)
+            .returning(|| Err(HealthError::UnknownError(anyhow!("connection refused"))));
+        health.expect_pending_migrations().times(0);
+
+        let mut state = test_state(None, None);
+        state.health = Arc::new(health);
+
+        let response = TestServer::new(router(state))?
+            .get("/api/v1/health/ready")
+            .await;
+
+        assert_eq!(response.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            response.json::<ErrorResponse>().error,
+            "Database is unavailable"
+        );
+
+        Ok(())
+    }
+}
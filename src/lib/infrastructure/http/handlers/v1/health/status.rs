@@ -0,0 +1,286 @@
+//! Health status handler
+
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{
+    domain::{
+        auth::users::UserService,
+        communication::email_addresses::EmailAddressService,
+        health::{
+            CheckResult, DatabaseCheck, DatabaseHealth, HealthChecks, HealthReport, HealthStatus,
+        },
+        idempotency::IdempotencyStore,
+    },
+    infrastructure::http::state::AppState,
+};
+
+/// The result of a single health check
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct HealthCheckResponse {
+    /// The name of the check
+    #[schema(example = "database")]
+    pub name: String,
+
+    /// How the check went
+    #[schema(example = "up", value_type = String)]
+    pub status: HealthStatus,
+
+    /// Whether the application is down when this check is
+    pub required: bool,
+}
+
+impl From<CheckResult> for HealthCheckResponse {
+    fn from(result: CheckResult) -> Self {
+        Self {
+            name: result.name,
+            status: result.status,
+            required: result.required,
+        }
+    }
+}
+
+/// The health response
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct HealthResponse {
+    /// The overall health of the application: `down` if any required check is, `degraded` if any
+    /// other check isn't `up`, and `up` otherwise
+    #[schema(example = "up", value_type = String)]
+    pub status: HealthStatus,
+
+    /// The version of the application
+    #[schema(example = "0.1.0")]
+    pub version: String,
+
+    /// The commit the application was built from
+    #[schema(example = "1a9d18b")]
+    pub git_sha: String,
+
+    /// The uptime of the application in seconds
+    #[schema(example = 123)]
+    pub uptime: i64,
+
+    /// The results of each health check
+    pub checks: Vec<HealthCheckResponse>,
+}
+
+/// Get the health of the application
+///
+/// Runs a check against the database, which is required, and each other dependency, which may be
+/// optional. Responds with 503 Service Unavailable if a required check is down, and 200 OK
+/// otherwise, reporting any other failed checks as a degraded status. The checks run at most once
+/// every few seconds, and those probing other services are only reported at
+/// `/api/v1/admin/health`. Use `/api/v1/health/ready` to decide whether to route traffic to the
+/// application.
+#[utoipa::path(
+    get,
+    operation_id = "get_health",
+    tag = "System",
+    path = "/api/v1/health",
+    responses(
+        (status = StatusCode::OK, description = "Every required check is up", body = HealthResponse),
+        (status = StatusCode::SERVICE_UNAVAILABLE, description = "A required check is down", body = HealthResponse),
+        (status = StatusCode::TOO_MANY_REQUESTS, description = "Too many requests", body = TooManyRequestsResponse),
+    )
+)]
+pub async fn handler<
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+>(
+    State(state): State<AppState<U, E, I, H>>,
+) -> (StatusCode, Json<HealthResponse>) {
+    let checks = HealthChecks::new()
+        .with_required(DatabaseCheck::new(state.health.clone()))
+        .with_checks(&state.health_checks);
+
+    let report = state.health_report.get_or_run(&checks).await;
+
+    respond(&state, report)
+}
+
+/// The response reporting `report`: 503 Service Unavailable if it is down, and 200 OK otherwise
+pub(crate) fn respond<U, E, I, H>(
+    state: &AppState<U, E, I, H>,
+    report: HealthReport,
+) -> (StatusCode, Json<HealthResponse>)
+where
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+{
+    let status_code = match report.status {
+        HealthStatus::Down => StatusCode::SERVICE_UNAVAILABLE,
+        HealthStatus::Up | HealthStatus::Degraded => StatusCode::OK,
+    };
+
+    (
+        status_code,
+        Json(HealthResponse {
+            status: report.status,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_sha: env!("GIT_SHA").to_string(),
+            uptime: Utc::now().timestamp() - state.start_time.timestamp(),
+            checks: report.checks.into_iter().map(Into::into).collect(),
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use anyhow::anyhow;
+    use axum::{async_trait, http::StatusCode};
+    use axum_test::TestServer;
+    use serde_json::json;
+    use testresult::TestResult;
+
+    use crate::{
+        domain::health::{
+            tests::MockDatabaseHealth, HealthCheck, HealthChecks, HealthError, HealthStatus,
+        },
+        infrastructure::http::{servers::https::router, state::tests::test_state},
+    };
+
+    use super::HealthResponse;
+
+    /// A check which always reports down
+    struct DownCheck;
+
+    #[async_trait]
+    impl HealthCheck for DownCheck {
+        fn name(&self) -> &str {
+            "smtp"
+        }
+
+        async fn check(&self) -> HealthStatus {
+            HealthStatus::Down
+        }
+    }
+
+    fn database(up: bool) -> MockDatabaseHealth {
+        let mut health = MockDatabaseHealth::new();
+
+        health.expect_ping().times(1).returning(move || match up {
+            true => Ok(()),
+            false => Err(HealthError::UnknownError(anyhow!("connection refused"))),
+        });
+        health.expect_pending_migrations().times(0);
+
+        health
+    }
+
+    #[tokio::test]
+    async fn test_health_when_every_check_passes() -> TestResult {
+        let mut state = test_state(None, None);
+        state.health = Arc::new(database(true));
+
+        let response = TestServer::new(router(state))?.get("/api/v1/health").await;
+
+        response.assert_status_ok();
+
+        let body = response.json::<HealthResponse>();
+
+        assert_eq!(body.status, HealthStatus::Up);
+        assert_eq!(body.checks.len(), 1);
+        assert_eq!(body.checks[0].name, "database");
+        assert_eq!(body.checks[0].status, HealthStatus::Up);
+        assert!(body.checks[0].required);
+        assert_eq!(body.version, env!("CARGO_PKG_VERSION"));
+        assert!(!body.git_sha.is_empty());
+        assert!(body.uptime >= 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_health_is_down_when_database_is_unavailable() -> TestResult {
+        let mut state = test_state(None, None);
+        state.health = Arc::new(database(false));
+
+        let response = TestServer::new(router(state))?.get("/api/v1/health").await;
+
+        response.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+
+        let body = response.json::<serde_json::Value>();
+
+        assert_eq!(body["status"], json!("down"));
+        assert_eq!(
+            body["checks"],
+            json!([{ "name": "database", "status": "down", "required": true }])
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_health_is_degraded_when_optional_check_is_down() -> TestResult {
+        let mut state = test_state(None, None);
+        state.health = Arc::new(database(true));
+        state.health_checks = HealthChecks::new().with_optional(DownCheck);
+
+        let response = TestServer::new(router(state))?.get("/api/v1/health").await;
+
+        response.assert_status_ok();
+
+        let body = response.json::<serde_json::Value>();
+
+        assert_eq!(body["status"], json!("degraded"));
+        assert_eq!(
+            body["checks"],
+            json!([
+                { "name": "database", "status": "up", "required": true },
+                { "name": "smtp", "status": "down", "required": false },
+            ])
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_health_report_is_reused_between_requests() -> TestResult {
+        let mut state = test_state(None, None);
+        state.health = Arc::new(database(true));
+
+        let server = TestServer::new(router(state))?;
+
+        // The database is only expected to be pinged once
+        server.get("/api/v1/health").await.assert_status_ok();
+        server.get("/api/v1/health").await.assert_status_ok();
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_health_leaves_out_admin_checks() -> TestResult {
+        let mut state = test_state(None, None);
+        state.health = Arc::new(database(true));
+        state.admin_health_checks = HealthChecks::new().with_required(DownCheck);
+
+        let response = TestServer::new(router(state))?.get("/api/v1/health").await;
+
+        response.assert_status_ok();
+        assert_eq!(response.json::<HealthResponse>().checks.len(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_health_is_down_when_required_check_is_down() -> TestResult {
+        let mut state = test_state(None, None);
+        state.health = Arc::new(database(true));
+        state.health_checks = HealthChecks::new().with_required(DownCheck);
+
+        let response = TestServer::new(router(state))?.get("/api/v1/health").await;
+
+        response.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.json::<HealthResponse>().status, HealthStatus::Down);
+
+        Ok(())
+    }
+}
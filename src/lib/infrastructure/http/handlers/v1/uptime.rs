@@ -8,6 +8,8 @@ use utoipa::ToSchema;
 use crate::{
     domain::auth::users::UserService,
     domain::communication::email_addresses::EmailAddressService,
+    domain::health::DatabaseHealth,
+    domain::idempotency::IdempotencyStore,
     infrastructure::http::{errors::ApiError, state::AppState},
 };
 
@@ -27,11 +29,16 @@ pub struct UptimeResponse {
     path = "/api/v1/uptime",
     responses(
         (status = StatusCode::OK, description = "Uptime response", body = UptimeResponse),
-        (status = StatusCode::TOO_MANY_REQUESTS, description = "Too many requests"),
+        (status = StatusCode::TOO_MANY_REQUESTS, description = "Too many requests", body = TooManyRequestsResponse),
     )
 )]
-pub async fn handler<U: UserService, E: EmailAddressService>(
-    State(state): State<AppState<U, E>>,
+pub async fn handler<
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+>(
+    State(state): State<AppState<U, E, I, H>>,
 ) -> Result<Json<UptimeResponse>, ApiError> {
     let uptime = Utc::now().timestamp() - state.start_time.timestamp();
 
@@ -40,6 +47,7 @@ pub async fn handler<U: UserService, E: EmailAddressService>(
 
 #[cfg(test)]
 mod tests {
+    use axum::http::StatusCode;
     use axum_test::TestServer;
     use chrono::Utc;
     use testresult::TestResult;
@@ -51,7 +59,7 @@ mod tests {
     #[tokio::test]
     async fn test_uptime_handler() -> TestResult {
         let state = test_state(None, None);
-        let start_time = state.start_time.clone();
+        let start_time = state.start_time;
 
         let response = TestServer::new(router(state))?.get("/api/v1/uptime").await;
 
@@ -67,4 +75,35 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_uptime_handler_with_trailing_slash() -> TestResult {
+        let state = test_state(None, None);
+
+        let response = TestServer::new(router(state))?.get("/api/v1/uptime/").await;
+
+        let json = response.json::<UptimeResponse>();
+
+        response.assert_status_ok();
+        assert!(json.uptime >= 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_uptime_handler_with_trailing_slash_when_strict() -> TestResult {
+        let mut state = test_state(None, None);
+        state.config.strict_trailing_slash = true;
+
+        let server = TestServer::new(router(state))?;
+
+        server.get("/api/v1/uptime").await.assert_status_ok();
+
+        assert_eq!(
+            server.get("/api/v1/uptime/").await.status_code(),
+            StatusCode::NOT_FOUND
+        );
+
+        Ok(())
+    }
 }
@@ -0,0 +1,144 @@
+//! Request header limits
+
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use clap::Parser;
+use tracing::debug;
+
+use super::errors::ApiError;
+
+/// Request header limits configuration
+#[derive(Clone, Debug, PartialEq, Eq, Parser)]
+pub struct HeaderLimitsConfig {
+    /// The maximum combined size of all header names and values, in bytes
+    #[arg(long, env = "MAX_HEADER_BYTES", default_value = "16384")]
+    pub max_header_bytes: usize,
+
+    /// The maximum number of headers
+    #[arg(long, env = "MAX_HEADER_COUNT", default_value = "64")]
+    pub max_header_count: usize,
+}
+
+impl Default for HeaderLimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_header_bytes: 16384,
+            max_header_count: 64,
+        }
+    }
+}
+
+/// Rejects requests whose headers exceed the configured limits with a 431
+pub async fn header_limits(
+    State(config): State<HeaderLimitsConfig>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let headers = request.headers();
+
+    if headers.len() > config.max_header_count {
+        debug!("rejecting request with {} headers", headers.len());
+
+        return too_large("Too many request headers");
+    }
+
+    let header_bytes: usize = headers
+        .iter()
+        .map(|(name, value)| name.as_str().len() + value.len())
+        .sum();
+
+    if header_bytes > config.max_header_bytes {
+        debug!("rejecting request with {} bytes of headers", header_bytes);
+
+        return too_large("Request headers are too large");
+    }
+
+    next.run(request).await
+}
+
+fn too_large(message: &str) -> Response {
+    ApiError::new(StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE, message).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{HeaderName, StatusCode};
+    use axum_test::TestServer;
+    use testresult::TestResult;
+
+    use crate::infrastructure::http::{
+        errors::ErrorResponse, servers::https::router, state::tests::test_state,
+    };
+
+    use super::HeaderLimitsConfig;
+
+    fn limited_server(max_header_bytes: usize, max_header_count: usize) -> TestResult<TestServer> {
+        let mut state = test_state(None, None);
+        state.config.header_limits = HeaderLimitsConfig {
+            max_header_bytes,
+            max_header_count,
+        };
+
+        Ok(TestServer::new(router(state))?)
+    }
+
+    #[tokio::test]
+    async fn test_request_within_limits() -> TestResult {
+        let response = limited_server(1024, 16)?
+            .get("/api/v1/uptime")
+            .add_header(HeaderName::from_static("x-custom"), "value".parse()?)
+            .await;
+
+        response.assert_status_ok();
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_oversized_header_is_rejected() -> TestResult {
+        let response = limited_server(1024, 16)?
+            .get("/api/v1/uptime")
+            .add_header(
+                HeaderName::from_static("x-custom"),
+                "a".repeat(2048).parse()?,
+            )
+            .await;
+
+        let json = response.json::<ErrorResponse>();
+
+        assert_eq!(
+            response.status_code(),
+            StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE
+        );
+        assert_eq!(json.error, "Request headers are too large");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_too_many_headers_are_rejected() -> TestResult {
+        let mut request = limited_server(1024, 4)?.get("/api/v1/uptime");
+
+        for i in 0..5 {
+            request = request.add_header(
+                HeaderName::try_from(format!("x-custom-{i}"))?,
+                "value".parse()?,
+            );
+        }
+
+        let response = request.await;
+        let json = response.json::<ErrorResponse>();
+
+        assert_eq!(
+            response.status_code(),
+            StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE
+        );
+        assert_eq!(json.error, "Too many request headers");
+
+        Ok(())
+    }
+}
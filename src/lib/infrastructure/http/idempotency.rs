@@ -0,0 +1,565 @@
+//! Idempotency key middleware
+
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{
+        header::{CONNECTION, CONTENT_LENGTH, CONTENT_TYPE, TRANSFER_ENCODING},
+        HeaderName, HeaderValue, Method, StatusCode,
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+use tracing::{debug, error};
+
+use crate::domain::{
+    auth::users::UserService,
+    communication::email_addresses::EmailAddressService,
+    health::DatabaseHealth,
+    idempotency::{IdempotencyStore, IdempotentRequest, RecordedResponse, StartOutcome},
+};
+
+use super::{errors::ApiError, state::AppState};
+
+/// The header clients send an idempotency key in
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// The header set on responses replayed from an earlier request
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// The only path whose `POST` requests are made idempotent. Replays are answered before the
+/// handler's extractors run, so routes which authenticate their caller must never be included,
+/// or anyone repeating the key and body would be sent another caller's response.
+const IDEMPOTENT_PATH: &str = "/api/v1/users";
+
+/// The maximum length of an idempotency key
+const MAX_KEY_LENGTH: usize = 255;
+
+/// The maximum size of a request or response body which can be recorded
+const MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// Headers which describe how a response was transferred rather than the response itself, so
+/// aren't recorded
+const UNRECORDED_HEADERS: [HeaderName; 3] = [CONNECTION, CONTENT_LENGTH, TRANSFER_ENCODING];
+
+/// Releases a started request's key when dropped, unless its response was recorded first, so a
+/// handler panicking or the client disconnecting doesn't leave the key in progress until it
+/// expires
+struct ReleaseOnDrop<I: IdempotencyStore> {
+    store: Arc<I>,
+    request: Option<IdempotentRequest>,
+}
+
+impl<I: IdempotencyStore> ReleaseOnDrop<I> {
+    fn new(store: Arc<I>, request: IdempotentRequest) -> Self {
+        Self {
+            store,
+            request: Some(request),
+        }
+    }
+
+    /// The started request
+    fn request(&self) -> &IdempotentRequest {
+        self.request
+            .as_ref()
+            .expect("the request is only taken when the guard is disarmed or dropped")
+    }
+
+    /// Keeps the key from being released, once it has been completed or released already
+    fn disarm(mut self) {
+        self.request = None;
+    }
+}
+
+impl<I: IdempotencyStore> Drop for ReleaseOnDrop<I> {
+    fn drop(&mut self) {
+        let Some(request) = self.request.take() else {
+            return;
+        };
+
+        let store = self.store.clone();
+
+        // Dropping can't wait on the store, so the release is left to finish on its own
+        tokio::spawn(async move {
+            if let Err(err) = store.release(&request).await {
+                error!("failed to release abandoned idempotency key: {:?}", err);
+            }
+        });
+    }
+}
+
+/// Makes `POST /api/v1/users` requests carrying an `Idempotency-Key` header safe to retry.
+///
+/// The first request with a key is executed and its response recorded. Repeats of the same
+/// request are answered with the recorded response without being executed, a different request
+/// reusing the key is rejected with 409, as is any request made while the first is still being
+/// executed. Server errors aren't recorded, so a request which failed with one can be retried, and
+/// nor are requests abandoned by a panic or the client disconnecting.
+pub async fn idempotency<U, E, I, H>(
+    State(state): State<AppState<U, E, I, H>>,
+    request: Request,
+    next: Next,
+) -> Response
+where
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+{
+    if request.method() != Method::POST || request.uri().path() != IDEMPOTENT_PATH {
+        return next.run(request).await;
+    }
+
+    let Some(key) = request.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return next.run(request).await;
+    };
+
+    let key = match key.to_str() {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LENGTH => key.to_string(),
+        _ => {
+            return ApiError::new_422(&format!(
+                "Idempotency-Key must be between 1 and {MAX_KEY_LENGTH} visible ASCII characters"
+            ))
+            .into_response()
+        }
+    };
+
+    let (parts, body) = request.into_parts();
+
+    let Ok(body) = to_bytes(body, MAX_BODY_BYTES).await else {
+        return ApiError::new(StatusCode::PAYLOAD_TOO_LARGE, "Request body is too large")
+            .into_response();
+    };
+
+    let idempotent_request = IdempotentRequest {
+        key,
+        route: format!("{} {}", parts.method, parts.uri.path()),
+        fingerprint: fingerprint(parts.uri.query(), &body),
+    };
+
+    match state.idempotency.start(&idempotent_request).await {
+        Ok(StartOutcome::Started) => {}
+        Ok(StartOutcome::Completed(recorded)) => {
+            debug!("replaying response for {:?}", idempotent_request);
+
+            return replay(recorded);
+        }
+        Ok(StartOutcome::InProgress) => {
+            return ApiError::new_409("A request with this Idempotency-Key is already in progress")
+                .into_response()
+        }
+        Ok(StartOutcome::FingerprintMismatch) => {
+            return ApiError::new_409(
+                "This Idempotency-Key has already been used for a different request",
+            )
+            .into_response()
+        }
+        Err(err) => return ApiError::from(err).into_response(),
+    }
+
+    let guard = ReleaseOnDrop::new(state.idempotency.clone(), idempotent_request);
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+
+    record(&state, guard, response).await
+}
+
+/// Records a response against a started request, releasing the key instead if it is a server
+/// error or can't be recorded
+async fn record<U, E, I, H>(
+    state: &AppState<U, E, I, H>,
+    guard: ReleaseOnDrop<I>,
+    response: Response,
+) -> Response
+where
+    U: UserService,
+    E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
+{
+    let (parts, body) = response.into_parts();
+    let request = guard.request();
+
+    let body = match to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(body) if !parts.status.is_server_error() => body,
+        result => {
+            if let Err(err) = state.idempotency.release(request).await {
+                error!("failed to release idempotency key: {:?}", err);
+            }
+
+            guard.disarm();
+
+            return match result {
+                Ok(body) => Response::from_parts(parts, Body::from(body)),
+                Err(_) => ApiError::new_500("Response body is too large").into_response(),
+            };
+        }
+    };
+
+    let recorded = RecordedResponse {
+        status: parts.status.as_u16(),
+        headers: parts
+            .headers
+            .iter()
+            .filter(|(name, _)| !UNRECORDED_HEADERS.contains(name))
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect(),
+        body: body.to_vec(),
+    };
+
+    if let Err(err) = state.idempotency.complete(request, &recorded).await {
+        error!("failed to record idempotent response: {:?}", err);
+    }
+
+    guard.disarm();
+
+    Response::from_parts(parts, Body::from(body))
+}
+
+/// Hashes everything which distinguishes one request to the route from another, so a key reused
+/// with a different query string or body is rejected rather than replayed
+fn fingerprint(query: Option<&str>, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+
+    hasher.update(query.unwrap_or_default().as_bytes());
+    hasher.update([0]);
+    hasher.update(body);
+
+    format!("{:x}", hasher.finalize())
+}
+
+/// Rebuilds a recorded response
+fn replay(recorded: RecordedResponse) -> Response {
+    let status = StatusCode::from_u16(recorded.status).unwrap_or(StatusCode::OK);
+
+    let mut response = (status, recorded.body).into_response();
+
+    let headers = response.headers_mut();
+
+    headers.remove(CONTENT_TYPE);
+
+    for (name, value) in recorded.headers {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(&value),
+        ) {
+            headers.append(name, value);
+        }
+    }
+
+    headers.insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use axum::http::{HeaderName, StatusCode};
+    use axum_test::TestServer;
+    use serde_json::json;
+    use testresult::TestResult;
+    use uuid::Uuid;
+
+    use crate::{
+        domain::{
+            auth::users::{tests::MockUserService, User},
+            idempotency::{tests::MockIdempotencyStore, RecordedResponse, StartOutcome},
+        },
+        infrastructure::http::{
+            errors::ErrorResponse, servers::https::router, state::tests::test_state,
+        },
+    };
+
+    use super::{fingerprint, IDEMPOTENCY_KEY_HEADER};
+
+    fn body() -> serde_json::Value {
+        json!({
+            "email": "email@example.com",
+            "password": "correcthorsebatterystaple",
+        })
+    }
+
+    #[tokio::test]
+    async fn test_first_request_is_executed_and_recorded() -> TestResult {
+        let user_id = Uuid::now_v7();
+
+        let mut users = MockUserService::new();
+
+        users.expect_create_user().times(1).returning(move |_, _| {
+            Ok(User {
+                id: user_id,
+                ..User::default()
+            })
+        });
+
+        let mut store = MockIdempotencyStore::new();
+
+        store
+            .expect_start()
+            .times(1)
+            .withf(|request| request.key == "key-1" && request.route == "POST /api/v1/users")
+            .returning(|_| Ok(StartOutcome::Started));
+
+        store
+            .expect_complete()
+            .times(1)
+            .withf(move |_, response| {
+                response.status == 201
+                    && response
+                        .headers
+                        .contains(&("location".to_string(), format!("/api/v1/users/{user_id}")))
+                    && !response
+                        .headers
+                        .iter()
+                        .any(|(name, _)| name == "content-length")
+            })
+            .returning(|_, _| Ok(()));
+
+        let mut state = test_state(Some(users), None);
+        state.idempotency = Arc::new(store);
+
+        let response = TestServer::new(router(state))?
+            .post("/api/v1/users")
+            .add_header(
+                HeaderName::from_static(IDEMPOTENCY_KEY_HEADER),
+                "key-1".parse()?,
+            )
+            .json(&body())
+            .await;
+
+        assert_eq!(response.status_code(), StatusCode::CREATED);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_repeated_request_replays_recorded_response() -> TestResult {
+        let recorded_body = json!({ "id": Uuid::now_v7(), "email": "email@example.com" });
+        let recorded = RecordedResponse {
+            status: 201,
+            headers: vec![
+                ("content-type".to_string(), "application/json".to_string()),
+                ("location".to_string(), "/api/v1/users/1".to_string()),
+            ],
+            body: serde_json::to_vec(&recorded_body)?,
+        };
+
+        let mut users = MockUserService::new();
+
+        users.expect_create_user().times(0);
+
+        let mut store = MockIdempotencyStore::new();
+
+        store
+            .expect_start()
+            .times(1)
+            .returning(move |_| Ok(StartOutcome::Completed(recorded.clone())));
+
+        store.expect_complete().times(0);
+
+        let mut state = test_state(Some(users), None);
+        state.idempotency = Arc::new(store);
+
+        let response = TestServer::new(router(state))?
+            .post("/api/v1/users")
+            .add_header(
+                HeaderName::from_static(IDEMPOTENCY_KEY_HEADER),
+                "key-1".parse()?,
+            )
+            .json(&body())
+            .await;
+
+        assert_eq!(response.status_code(), StatusCode::CREATED);
+        assert_eq!(response.header("content-type"), "application/json");
+        assert_eq!(response.header("location"), "/api/v1/users/1");
+        assert_eq!(response.header("idempotent-replayed"), "true");
+        assert_eq!(response.json::<serde_json::Value>(), recorded_body);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_panicking_handler_releases_key() -> TestResult {
+        let mut users = MockUserService::new();
+
+        users
+            .expect_create_user()
+            .times(1)
+            .returning(|_, _| panic!("handler panicked"));
+
+        let (released, mut on_release) = tokio::sync::mpsc::unbounded_channel();
+
+        let mut store = MockIdempotencyStore::new();
+
+        store
+            .expect_start()
+            .times(1)
+            .returning(|_| Ok(StartOutcome::Started));
+
+        store.expect_complete().times(0);
+
+        store
+            .expect_release()
+            .times(1)
+            .withf(|request| request.key == "key-1")
+            .returning(move |_| {
+                released.send(()).ok();
+                Ok(())
+            });
+
+        let mut state = test_state(Some(users), None);
+        state.idempotency = Arc::new(store);
+
+        let response = TestServer::new(router(state))?
+            .post("/api/v1/users")
+            .add_header(
+                HeaderName::from_static(IDEMPOTENCY_KEY_HEADER),
+                "key-1".parse()?,
+            )
+            .json(&body())
+            .await;
+
+        assert_eq!(response.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        tokio::time::timeout(Duration::from_secs(5), on_release.recv())
+            .await?
+            .ok_or("key was never released")?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reused_key_with_different_body_conflicts() -> TestResult {
+        let mut users = MockUserService::new();
+
+        users.expect_create_user().times(0);
+
+        let mut store = MockIdempotencyStore::new();
+
+        store
+            .expect_start()
+            .times(1)
+            .returning(|_| Ok(StartOutcome::FingerprintMismatch));
+
+        let mut state = test_state(Some(users), None);
+        state.idempotency = Arc::new(store);
+
+        let response = TestServer::new(router(state))?
+            .post("/api/v1/users")
+            .add_header(
+                HeaderName::from_static(IDEMPOTENCY_KEY_HEADER),
+                "key-1".parse()?,
+            )
+            .json(&body())
+            .await;
+
+        let json = response.json::<ErrorResponse>();
+
+        assert_eq!(response.status_code(), StatusCode::CONFLICT);
+        assert_eq!(
+            json.error,
+            "This Idempotency-Key has already been used for a different request"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_request_conflicts() -> TestResult {
+        let mut users = MockUserService::new();
+
+        users.expect_create_user().times(0);
+
+        let mut store = MockIdempotencyStore::new();
+
+        store
+            .expect_start()
+            .times(1)
+            .returning(|_| Ok(StartOutcome::InProgress));
+
+        let mut state = test_state(Some(users), None);
+        state.idempotency = Arc::new(store);
+
+        let response = TestServer::new(router(state))?
+            .post("/api/v1/users")
+            .add_header(
+                HeaderName::from_static(IDEMPOTENCY_KEY_HEADER),
+                "key-1".parse()?,
+            )
+            .json(&body())
+            .await;
+
+        assert_eq!(response.status_code(), StatusCode::CONFLICT);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_other_routes_are_not_recorded() -> TestResult {
+        let mut store = MockIdempotencyStore::new();
+
+        store.expect_start().times(0);
+
+        let mut state = test_state(None, None);
+        state.idempotency = Arc::new(store);
+
+        let response = TestServer::new(router(state))?
+            .post("/api/v1/users/batch")
+            .add_header(
+                HeaderName::from_static(IDEMPOTENCY_KEY_HEADER),
+                "key-1".parse()?,
+            )
+            .json(&json!({ "users": [body()] }))
+            .await;
+
+        assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.maybe_header("idempotent-replayed"), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fingerprint_covers_query_string() {
+        let body = br#"{"email":"email@example.com"}"#;
+
+        assert_eq!(fingerprint(None, body), fingerprint(Some(""), body));
+        assert_ne!(
+            fingerprint(Some("atomic=true"), body),
+            fingerprint(Some("atomic=false"), body)
+        );
+        assert_ne!(fingerprint(None, body), fingerprint(None, b"{}"));
+    }
+
+    #[tokio::test]
+    async fn test_request_without_key_is_not_recorded() -> TestResult {
+        let mut users = MockUserService::new();
+
+        users.expect_create_user().times(1).returning(|_, _| {
+            Ok(User {
+                id: Uuid::now_v7(),
+                ..User::default()
+            })
+        });
+
+        let mut store = MockIdempotencyStore::new();
+
+        store.expect_start().times(0);
+
+        let mut state = test_state(Some(users), None);
+        state.idempotency = Arc::new(store);
+
+        let response = TestServer::new(router(state))?
+            .post("/api/v1/users")
+            .json(&body())
+            .await;
+
+        assert_eq!(response.status_code(), StatusCode::CREATED);
+
+        Ok(())
+    }
+}
@@ -1,30 +1,219 @@
 //! OpenAPI module
 
-use utoipa::OpenApi;
+use utoipa::{
+    openapi::{
+        security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+        OpenApi as OpenApiSpec, Server,
+    },
+    Modify, OpenApi,
+};
 
 use crate::infrastructure::http::rate_limit::TooManyRequestsResponse;
-use crate::infrastructure::http::{errors::ErrorResponse, handlers::v1::*};
+use crate::infrastructure::http::{
+    errors::{ApiError, ErrorResponse, ValidationError},
+    handlers::v1::*,
+    pagination::UsersPage,
+};
 
 #[derive(Debug, OpenApi)]
 #[openapi(
     info(title = "SaaS Starter"),
     paths(
         auth::create_user::handler,
+        auth::create_users::handler,
         auth::get_user_by_id::handler,
+        auth::me::handler,
+        auth::erase_account::handler,
+        auth::list_users::handler,
+        auth::email_available::handler,
+        auth::delete_user::handler,
         auth::change_email::handler,
+        auth::cancel_email_change::handler,
         auth::send_email_confirmation::handler,
+        auth::confirm_email::handler,
+        auth::confirm_email_json::handler,
+        auth::email_confirmation_status::handler,
+        auth::validate_email_confirmation::handler,
+        auth::password_policy::handler,
+        admin::confirm_email::handler,
+        admin::health::handler,
+        health::status::handler,
+        health::ready::handler,
         uptime::handler
     ),
     components(schemas(
         auth::create_user::CreateUserBody,
         auth::create_user::CreateUserResponse,
+        auth::create_users::CreateUsersResult,
+        auth::create_users::CreateUsersResponse,
         auth::get_user_by_id::GetUserByIdResponse,
+        UsersPage,
+        auth::email_available::EmailAvailableResponse,
         auth::change_email::ChangeEmailRequest,
         auth::change_email::ChangeEmailResponse,
+        auth::cancel_email_change::CancelEmailChangeResponse,
         auth::send_email_confirmation::SendEmailConfirmationResponse,
+        auth::confirm_email::ConfirmEmailParams,
+        auth::confirm_email_json::ConfirmEmailRequest,
+        auth::email_confirmation_status::EmailConfirmationStatusResponse,
+        auth::validate_email_confirmation::InvalidConfirmationTokenReason,
+        auth::validate_email_confirmation::ValidateEmailConfirmationResponse,
+        auth::password_policy::PasswordPolicyResponse,
+        health::status::HealthResponse,
+        health::status::HealthCheckResponse,
+        health::ready::ReadinessResponse,
         uptime::UptimeResponse,
+        ApiError,
         ErrorResponse,
+        ValidationError,
         TooManyRequestsResponse
-    ))
+    )),
+    modifiers(&SecurityAddon)
 )]
 pub struct ApiDocs;
+
+impl ApiDocs {
+    /// The OpenAPI spec, with the server at `base_url` so the docs' "try it" sends requests there
+    pub fn for_base_url(base_url: &str) -> OpenApiSpec {
+        let mut spec = Self::openapi();
+
+        spec.servers = Some(vec![Server::new(base_url)]);
+
+        spec
+    }
+}
+
+/// Registers the `bearerAuth` scheme the protected endpoints require
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut OpenApiSpec) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+
+        components.add_security_scheme(
+            "bearerAuth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum_test::TestServer;
+    use serde_json::Value;
+    use testresult::TestResult;
+    use utoipa::OpenApi;
+
+    use crate::infrastructure::http::{servers::https::router, state::tests::test_state};
+
+    use super::ApiDocs;
+
+    #[tokio::test]
+    async fn test_spec_has_servers_and_bearer_auth() -> TestResult {
+        let server = TestServer::new(router(test_state(None, None)))?;
+
+        let response = server.get("/api/v1/openapi.json").await;
+
+        response.assert_status_ok();
+
+        let spec = response.json::<Value>();
+
+        assert_eq!(spec["servers"][0]["url"], "https://example.com");
+        assert_eq!(
+            spec["components"]["securitySchemes"]["bearerAuth"]["scheme"],
+            "bearer"
+        );
+        assert_eq!(
+            spec["paths"]["/api/v1/users"]["get"]["security"][0]["bearerAuth"],
+            serde_json::json!([])
+        );
+        assert!(spec["paths"]["/api/v1/users"]["post"]
+            .get("security")
+            .is_none());
+
+        Ok(())
+    }
+
+    /// Every `$ref` in `value`
+    fn references(value: &Value) -> Vec<String> {
+        match value {
+            Value::Object(object) => object
+                .iter()
+                .flat_map(|(key, value)| match (key.as_str(), value) {
+                    ("$ref", Value::String(reference)) => vec![reference.clone()],
+                    _ => references(value),
+                })
+                .collect(),
+            Value::Array(values) => values.iter().flat_map(references).collect(),
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn test_every_operation_documents_its_responses() -> TestResult {
+        let spec = serde_json::to_value(ApiDocs::openapi())?;
+
+        let paths = spec["paths"].as_object().ok_or("spec has no paths")?;
+
+        assert!(paths.contains_key("/api/v1/users/{id}/email/confirmation"));
+
+        for (path, operations) in paths {
+            for (method, operation) in operations.as_object().ok_or("path has no operations")? {
+                let responses = operation["responses"]
+                    .as_object()
+                    .ok_or_else(|| format!("{method} {path} has no responses"))?;
+
+                let documents = |status: &str| responses.contains_key(status);
+
+                assert!(
+                    responses.keys().any(|status| status.starts_with('2')),
+                    "{method} {path} documents no success response"
+                );
+                assert!(documents("429"), "{method} {path} doesn't document 429");
+
+                if operation.get("security").is_some() {
+                    assert!(documents("401"), "{method} {path} doesn't document 401");
+                }
+
+                if operation.get("requestBody").is_some() {
+                    assert!(documents("415"), "{method} {path} doesn't document 415");
+                    assert!(documents("422"), "{method} {path} doesn't document 422");
+                }
+
+                if path.contains("{id}") {
+                    assert!(documents("404"), "{method} {path} doesn't document 404");
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_every_schema_reference_is_registered() -> TestResult {
+        let spec = serde_json::to_value(ApiDocs::openapi())?;
+
+        for reference in references(&spec) {
+            let name = reference
+                .strip_prefix("#/components/schemas/")
+                .ok_or_else(|| format!("unexpected reference {reference}"))?;
+
+            assert!(
+                spec["components"]["schemas"].get(name).is_some(),
+                "{name} is referenced but not registered"
+            );
+        }
+
+        assert!(spec["components"]["schemas"].get("ApiError").is_some());
+        assert!(spec["components"]["schemas"]
+            .get("ConfirmEmailParams")
+            .is_some());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+//! OpenAPI operation IDs in traces
+//!
+//! Each documented route is tagged with the `operation_id` from its `#[utoipa::path]`, which is
+//! recorded on the request's trace span so logs can be filtered by logical operation rather than
+//! by method and path.
+
+use axum::{
+    extract::{Request, State},
+    middleware::{self, Next},
+    response::Response,
+    routing::MethodRouter,
+};
+use tracing::Span;
+
+/// The name of the request span field the operation ID is recorded in
+pub const OPERATION_ID_FIELD: &str = "operation_id";
+
+/// Records the route's operation ID on the current request span
+pub async fn record_operation_id(
+    State(operation_id): State<&'static str>,
+    request: Request,
+    next: Next,
+) -> Response {
+    Span::current().record(OPERATION_ID_FIELD, operation_id);
+
+    next.run(request).await
+}
+
+/// Tags a route with its OpenAPI operation ID
+pub trait WithOperationId {
+    /// Records `operation_id` on the span of every request the route handles
+    fn operation_id(self, operation_id: &'static str) -> Self;
+}
+
+impl<S> WithOperationId for MethodRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    fn operation_id(self, operation_id: &'static str) -> Self {
+        self.layer(middleware::from_fn_with_state(
+            operation_id,
+            record_operation_id,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum_test::TestServer;
+    use testresult::TestResult;
+    use tracing::{
+        field::{Field, Visit},
+        span::{Attributes, Id, Record},
+        Subscriber,
+    };
+    use tracing_subscriber::{layer::Context, prelude::*, registry::LookupSpan, Layer};
+
+    use crate::infrastructure::http::{servers::https::router, state::tests::test_state};
+
+    use super::OPERATION_ID_FIELD;
+
+    /// Collects every operation ID recorded on a span
+    #[derive(Clone, Default)]
+    struct OperationIds(Arc<Mutex<Vec<String>>>);
+
+    impl Visit for OperationIds {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            if field.name() == OPERATION_ID_FIELD {
+                self.0.lock().unwrap().push(value.to_string());
+            }
+        }
+
+        fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+    }
+
+    impl<S> Layer<S> for OperationIds
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+            attrs.record(&mut self.clone());
+        }
+
+        fn on_record(&self, _id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+            values.record(&mut self.clone());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_span_records_operation_id() -> TestResult {
+        let operation_ids = OperationIds::default();
+        let _guard = tracing::subscriber::set_default(
+            tracing_subscriber::registry().with(operation_ids.clone()),
+        );
+
+        let server = TestServer::new(router(test_state(None, None)))?;
+
+        server.get("/api/v1/uptime").await.assert_status_ok();
+
+        assert_eq!(*operation_ids.0.lock().unwrap(), vec!["uptime"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_undocumented_route_records_no_operation_id() -> TestResult {
+        let operation_ids = OperationIds::default();
+        let _guard = tracing::subscriber::set_default(
+            tracing_subscriber::registry().with(operation_ids.clone()),
+        );
+
+        let server = TestServer::new(router(test_state(None, None)))?;
+
+        server.get("/api/v1/nonexistent").await;
+
+        assert!(operation_ids.0.lock().unwrap().is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,170 @@
+//! Pagination module
+//!
+//! Every list endpoint responds with a [`Page`], so clients page through any of them the same
+//! way: by passing a page's `next_cursor` back as the `cursor` of the next request, until
+//! `has_more` is false.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    domain::auth::users::UserCursor,
+    infrastructure::http::{
+        errors::ApiError, handlers::v1::auth::get_user_by_id::GetUserByIdResponse,
+    },
+};
+
+/// A page of a list
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[aliases(UsersPage = Page<GetUserByIdResponse>)]
+pub struct Page<T> {
+    /// The items on this page
+    items: Vec<T>,
+
+    /// The cursor to request the next page with, if there is one
+    #[schema(example = "MTcyMjQ3MDQwMDAwMDAwMDowMTkxMGI3Yy0wMDAwLTdhYmMtOGRlZi0wMTIzNDU2Nzg5YWI")]
+    next_cursor: Option<String>,
+
+    /// Whether there are more items after this page
+    has_more: bool,
+}
+
+impl<T> Page<T> {
+    /// Create a new page of `items`, followed by more items if there's a `next_cursor`
+    pub fn new(items: Vec<T>, next_cursor: Option<String>) -> Self {
+        Self {
+            has_more: next_cursor.is_some(),
+            items,
+            next_cursor,
+        }
+    }
+
+    /// Create a page from up to `limit + 1` items, the extra item showing that there's another
+    /// page, which continues from the `cursor` of the last item on this one
+    pub fn from_items(mut items: Vec<T>, limit: i64, cursor: impl FnOnce(&T) -> String) -> Self {
+        let next_cursor = (items.len() as i64 > limit)
+            .then(|| {
+                items.truncate(limit as usize);
+
+                items.last().map(cursor)
+            })
+            .flatten();
+
+        Self::new(items, next_cursor)
+    }
+
+    /// Map the items on the page, keeping its cursor
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page::new(self.items.into_iter().map(f).collect(), self.next_cursor)
+    }
+}
+
+/// Encodes where a listing of users continues from as an opaque cursor
+pub fn encode_user_cursor(cursor: &UserCursor) -> String {
+    URL_SAFE_NO_PAD.encode(format!(
+        "{}:{}",
+        cursor.created_at.timestamp_micros(),
+        cursor.id
+    ))
+}
+
+/// Reads a cursor from [`encode_user_cursor`]
+pub fn parse_user_cursor(cursor: &str) -> Result<UserCursor, ApiError> {
+    let parse = || {
+        let cursor = String::from_utf8(URL_SAFE_NO_PAD.decode(cursor).ok()?).ok()?;
+        let (created_at, id) = cursor.split_once(':')?;
+
+        Some(UserCursor {
+            created_at: DateTime::from_timestamp_micros(created_at.parse().ok()?)?,
+            id: Uuid::parse_str(id).ok()?,
+        })
+    };
+
+    parse().ok_or_else(|| ApiError::new_422("Invalid pagination cursor"))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+    use testresult::TestResult;
+
+    use crate::domain::auth::users::User;
+
+    use super::*;
+
+    #[test]
+    fn test_page_of_users_serializes_items_and_cursor() -> TestResult {
+        let page = Page::new(
+            vec![GetUserByIdResponse::from(User::default())],
+            Some("50".to_string()),
+        );
+
+        let json = serde_json::to_value(&page)?;
+
+        assert_eq!(
+            json.as_object()
+                .map(|page| page.keys().cloned().collect::<Vec<_>>()),
+            Some(vec![
+                "has_more".to_string(),
+                "items".to_string(),
+                "next_cursor".to_string()
+            ])
+        );
+        assert_eq!(json["items"].as_array().map(Vec::len), Some(1));
+        assert_eq!(json["next_cursor"], "50");
+        assert_eq!(json["has_more"], true);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_last_page_has_no_more() -> TestResult {
+        let page = Page::<GetUserByIdResponse>::new(vec![], None);
+
+        assert_eq!(
+            serde_json::to_value(&page)?,
+            json!({ "items": [], "next_cursor": null, "has_more": false })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_page_from_items_drops_extra_item() -> TestResult {
+        let page = serde_json::to_value(Page::from_items(vec![1, 2, 3], 2, i32::to_string))?;
+
+        assert_eq!(
+            page,
+            json!({ "items": [1, 2], "next_cursor": "2", "has_more": true })
+        );
+
+        let page = serde_json::to_value(Page::from_items(vec![1, 2], 2, i32::to_string))?;
+
+        assert_eq!(page["has_more"], Value::Bool(false));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_user_cursor_round_trips() -> TestResult {
+        let cursor = UserCursor {
+            created_at: DateTime::from_timestamp_micros(1_722_470_400_123_456)
+                .ok_or("invalid timestamp")?,
+            id: Uuid::now_v7(),
+        };
+
+        assert_eq!(parse_user_cursor(&encode_user_cursor(&cursor))?, cursor);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_cursor_is_rejected() {
+        assert!(parse_user_cursor("12").is_err());
+        assert!(parse_user_cursor("not base64!").is_err());
+        assert!(parse_user_cursor(&URL_SAFE_NO_PAD.encode("12:not-a-uuid")).is_err());
+    }
+}
@@ -1,53 +1,824 @@
+//! Rate limiting
+//!
+//! Clients are limited by their address, as found by [`ClientIpKeyExtractor`]. Limits can be
+//! enforced, or only logged while working out what they should be, or turned off entirely.
+//! Sensitive paths, such as those which reveal whether an account exists, have a stricter limit
+//! of their own, kept separately from the general one.
+//!
+//! Each client's limit is kept in a [`RateLimitStore`]: in memory by default, so every instance
+//! limits clients separately, or in Redis, so the limit is shared between them.
+
+mod errors;
+mod memory;
+#[cfg(feature = "redis")]
+mod redis;
+
+pub use errors::{RateLimitConfigError, RateLimitStoreError};
+pub use memory::MemoryRateLimitStore;
+#[cfg(feature = "redis")]
+pub use redis::RedisRateLimitStore;
+
+use std::{
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
 use axum::{
     body::Body,
-    http::{Response, StatusCode},
+    extract::{Request, State},
+    http::{header::RETRY_AFTER, HeaderValue, Response, StatusCode},
+    middleware::{self, Next},
     response::IntoResponse,
+    Router,
 };
+use clap::{Parser, ValueEnum};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use tower_governor::GovernorError;
+use tower_governor::{key_extractor::KeyExtractor, GovernorError};
+use tracing::{error, warn};
 use utoipa::ToSchema;
 
-use super::errors::ApiError;
+use super::{
+    client_ip::{ClientIpKeyExtractor, TrustedProxiesConfig},
+    errors::ApiError,
+};
 
-#[derive(Debug)]
+/// What happens to requests over the rate limit
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitMode {
+    /// Requests over the limit are refused with a 429
+    Enforce,
+
+    /// Requests over the limit are logged, but still served
+    Warn,
+
+    /// Requests aren't rate limited
+    Off,
+}
+
+/// Where each client's rate limit is kept
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitBackend {
+    /// In this process, so each instance limits clients separately
+    Memory,
+
+    /// In Redis, so limits are shared between instances
+    Redis,
+}
+
+/// Rate limiting configuration
+#[derive(Clone, Debug, PartialEq, Eq, Parser)]
 pub struct RateLimitConfig {
-    /// The number of requests allowed per second
+    /// What happens to requests over the limit
+    #[arg(
+        long = "rate-limit-mode",
+        env = "RATE_LIMIT_MODE",
+        default_value = "enforce"
+    )]
+    pub mode: RateLimitMode,
+
+    /// The number of seconds it takes for a client to be allowed another request
+    #[arg(
+        long = "rate-limit-per-second",
+        env = "RATE_LIMIT_PER_SECOND",
+        default_value = "2",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
     pub per_second: u64,
 
     /// The number of requests allowed in a burst
+    #[arg(
+        long = "rate-limit-burst-size",
+        env = "RATE_LIMIT_BURST_SIZE",
+        default_value = "5",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
     pub burst_size: u32,
+
+    /// Where each client's rate limit is kept
+    #[arg(
+        long = "rate-limit-backend",
+        env = "RATE_LIMIT_BACKEND",
+        default_value = "memory"
+    )]
+    pub backend: RateLimitBackend,
+
+    /// The Redis server rate limits are kept in, when the backend is `redis`
+    #[arg(long = "rate-limit-redis-url", env = "RATE_LIMIT_REDIS_URL")]
+    pub redis_url: Option<String>,
+
+    /// Paths limited by the strict limit instead of the general one
+    #[arg(
+        long = "rate-limit-strict-paths",
+        env = "RATE_LIMIT_STRICT_PATHS",
+        value_delimiter = ',',
+        default_value = "/api/v1/users/email-available"
+    )]
+    pub strict_paths: Vec<String>,
+
+    /// The number of seconds it takes for a client to be allowed another request to a strict path
+    #[arg(
+        long = "rate-limit-strict-per-second",
+        env = "RATE_LIMIT_STRICT_PER_SECOND",
+        default_value = "60",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub strict_per_second: u64,
+
+    /// The number of requests to strict paths allowed in a burst
+    #[arg(
+        long = "rate-limit-strict-burst-size",
+        env = "RATE_LIMIT_STRICT_BURST_SIZE",
+        default_value = "5",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub strict_burst_size: u32,
 }
 
 impl Default for RateLimitConfig {
     fn default() -> Self {
         Self {
+            mode: RateLimitMode::Enforce,
             per_second: 2,
             burst_size: 5,
+            backend: RateLimitBackend::Memory,
+            redis_url: None,
+            strict_paths: vec!["/api/v1/users/email-available".to_string()],
+            strict_per_second: 60,
+            strict_burst_size: 5,
+        }
+    }
+}
+
+impl RateLimitConfig {
+    /// Checks the configured backend can be used
+    pub fn validate(&self) -> Result<(), RateLimitConfigError> {
+        match self.backend {
+            RateLimitBackend::Memory => Ok(()),
+            RateLimitBackend::Redis => self.store(GENERAL_NAMESPACE).map(|_| ()),
         }
     }
+
+    /// The configuration of the strict limit, which is kept in the same backend
+    fn strict(&self) -> Self {
+        Self {
+            per_second: self.strict_per_second,
+            burst_size: self.strict_burst_size,
+            ..self.clone()
+        }
+    }
+
+    /// Creates the store rate limits are kept in, with each client's limit under `namespace`, so
+    /// separate limits sharing a backend don't count each other's requests
+    #[cfg_attr(not(feature = "redis"), allow(unused_variables))]
+    fn store(&self, namespace: &str) -> Result<Arc<dyn RateLimitStore>, RateLimitConfigError> {
+        match self.backend {
+            RateLimitBackend::Memory => {
+                let store = MemoryRateLimitStore::new(self);
+                store.spawn_cleanup();
+
+                Ok(Arc::new(store))
+            }
+            #[cfg(feature = "redis")]
+            RateLimitBackend::Redis => {
+                let url = self
+                    .redis_url
+                    .as_deref()
+                    .ok_or(RateLimitConfigError::RedisUrlMissing)?;
+
+                let store = RedisRateLimitStore::new(url, namespace, self)
+                    .map_err(|err| RateLimitConfigError::InvalidRedisUrl(err.to_string()))?;
+
+                Ok(Arc::new(store))
+            }
+            #[cfg(not(feature = "redis"))]
+            RateLimitBackend::Redis => Err(RateLimitConfigError::RedisUnsupported),
+        }
+    }
+}
+
+/// Whether a client's request is within their rate limit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RateLimitDecision {
+    /// The request is allowed
+    Allowed {
+        /// How many more requests the client can make straight away
+        remaining: u32,
+    },
+
+    /// The request is over the limit
+    Limited {
+        /// How long until the client is allowed another request
+        retry_after: Duration,
+    },
+}
+
+/// Storage for each client's rate limit
+#[async_trait]
+pub trait RateLimitStore: Send + Sync + 'static {
+    /// Records a request from the client with `key`, and decides whether it's allowed
+    ///
+    /// # Arguments
+    /// * `key` - The address the client is limited by.
+    ///
+    /// # Returns
+    /// A [`Result`] which is [`Ok`] containing the [`RateLimitDecision`], or an [`Err`]
+    /// containing a [`RateLimitStoreError`] if the store couldn't be reached.
+    async fn check(&self, key: IpAddr) -> Result<RateLimitDecision, RateLimitStoreError>;
+}
+
+/// The namespace of the general limit's store
+const GENERAL_NAMESPACE: &str = "rate_limit";
+
+/// The namespace of the strict limit's store
+const STRICT_NAMESPACE: &str = "rate_limit:strict";
+
+/// The shortest time between logged refusals
+const REJECTION_LOG_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The shortest time between logged failures to reach the store
+const STORE_ERROR_LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How long the store has to decide whether a request is allowed, before it's let through
+const STORE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Decides which of a stream of repeated events are logged, at most once per interval so that an
+/// attack or an outage doesn't flood the logs. Events in between are counted, and reported with
+/// the next one logged
+#[derive(Debug)]
+struct SampledLog {
+    interval: Duration,
+    state: Mutex<SampledLogState>,
+}
+
+#[derive(Debug, Default)]
+struct SampledLogState {
+    last_logged: Option<Instant>,
+    suppressed: u64,
+}
+
+impl SampledLog {
+    fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Whether an event should be logged, returning how many were suppressed since the last one
+    /// if so, or [`None`] if one was logged within the interval
+    fn sample(&self) -> Option<u64> {
+        let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+
+        match state.last_logged {
+            Some(last_logged) if last_logged.elapsed() < self.interval => {
+                state.suppressed += 1;
+
+                None
+            }
+            _ => {
+                state.last_logged = Some(Instant::now());
+
+                Some(std::mem::take(&mut state.suppressed))
+            }
+        }
+    }
+}
+
+/// A limit, and the store each client's use of it is kept in
+#[derive(Clone)]
+struct Bucket {
+    burst_size: u32,
+    store: Arc<dyn RateLimitStore>,
+}
+
+impl Bucket {
+    fn new(config: &RateLimitConfig, namespace: &str) -> Self {
+        Self {
+            burst_size: config.burst_size,
+            store: config
+                .store(namespace)
+                .expect("rate limit config is validated at startup"),
+        }
+    }
+}
+
+/// What requests are checked against, and what's done with those over the limit
+#[derive(Clone)]
+struct Limiter {
+    mode: RateLimitMode,
+    key_extractor: ClientIpKeyExtractor,
+    general: Bucket,
+    strict: Bucket,
+    strict_paths: Arc<[String]>,
+    store_timeout: Duration,
+    rejections: Arc<SampledLog>,
+    store_errors: Arc<SampledLog>,
+}
+
+impl Limiter {
+    /// The limit requests to `path` are checked against
+    fn bucket(&self, path: &str) -> &Bucket {
+        if self.strict_paths.iter().any(|strict| strict == path) {
+            &self.strict
+        } else {
+            &self.general
+        }
+    }
+}
+
+/// Layers rate limiting onto `router`, in the mode given by `config`
+pub fn rate_limit(
+    router: Router,
+    config: &RateLimitConfig,
+    trusted_proxies: TrustedProxiesConfig,
+) -> Router {
+    if config.mode == RateLimitMode::Off {
+        return router;
+    }
+
+    router.layer(middleware::from_fn_with_state(
+        Limiter {
+            mode: config.mode,
+            key_extractor: ClientIpKeyExtractor::new(trusted_proxies),
+            general: Bucket::new(config, GENERAL_NAMESPACE),
+            strict: Bucket::new(&config.strict(), STRICT_NAMESPACE),
+            strict_paths: config.strict_paths.clone().into(),
+            store_timeout: STORE_TIMEOUT,
+            rejections: Arc::new(SampledLog::new(REJECTION_LOG_INTERVAL)),
+            store_errors: Arc::new(SampledLog::new(STORE_ERROR_LOG_INTERVAL)),
+        },
+        limit,
+    ))
+}
+
+/// Checks each request against the client's rate limit, refusing or logging those over it. If the
+/// store can't be reached in time, requests are let through rather than the whole API going down
+/// with it.
+async fn limit(
+    State(limiter): State<Limiter>,
+    request: Request,
+    next: Next,
+) -> axum::response::Response {
+    let enforce = limiter.mode == RateLimitMode::Enforce;
+
+    let key = match limiter.key_extractor.extract(&request) {
+        Ok(key) => key,
+        Err(err) if enforce => return rate_limit_error_handler(err),
+        Err(_) => return next.run(request).await,
+    };
+
+    let bucket = limiter.bucket(request.uri().path());
+
+    let decision = match tokio::time::timeout(limiter.store_timeout, bucket.store.check(key))
+        .await
+        .unwrap_or(Err(RateLimitStoreError::Timeout))
+    {
+        Ok(decision) => decision,
+        Err(err) => {
+            if let Some(suppressed) = limiter.store_errors.sample() {
+                error!(
+                    suppressed,
+                    "failed to check rate limit, allowing the request: {err}"
+                );
+            }
+
+            return next.run(request).await;
+        }
+    };
+
+    match decision {
+        RateLimitDecision::Limited { retry_after } if enforce => {
+            let wait_time = whole_seconds(retry_after);
+
+            if let Some(suppressed) = limiter.rejections.sample() {
+                warn!(
+                    key = %key,
+                    path = request.uri().path(),
+                    wait_time,
+                    suppressed,
+                    "rate limited request refused"
+                );
+            }
+
+            rate_limit_error_handler(GovernorError::TooManyRequests {
+                wait_time,
+                headers: None,
+            })
+        }
+        RateLimitDecision::Limited { .. } => {
+            warn!(key = %key, path = request.uri().path(), "rate limit exceeded");
+
+            next.run(request).await
+        }
+        RateLimitDecision::Allowed { remaining } => {
+            let mut response = next.run(request).await;
+
+            if enforce {
+                let headers = response.headers_mut();
+                headers.insert("x-ratelimit-limit", HeaderValue::from(bucket.burst_size));
+                headers.insert("x-ratelimit-remaining", HeaderValue::from(remaining));
+            }
+
+            response
+        }
+    }
+}
+
+/// `duration` in whole seconds, rounded up so clients retrying on time aren't limited again
+fn whole_seconds(duration: Duration) -> u64 {
+    duration.as_secs() + u64::from(duration.subsec_nanos() > 0)
 }
 
+/// The body of a rate limited response
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct TooManyRequestsResponse {
+    /// The number of seconds until another request is allowed
     pub retry_after: u64,
 }
 
 /// Rate limit error handler
+///
+/// Rate limited responses say how long to wait both in the body and in the standard
+/// `Retry-After` header, which many clients and proxies honour.
 pub fn rate_limit_error_handler(err: GovernorError) -> Response<Body> {
     match err {
-        GovernorError::TooManyRequests { wait_time, .. } => {
+        GovernorError::TooManyRequests { wait_time, headers } => {
             let body = json!(TooManyRequestsResponse {
                 retry_after: wait_time
             })
             .to_string();
-            Response::builder()
+            let mut response = Response::builder()
                 .status(StatusCode::TOO_MANY_REQUESTS)
                 .header("Content-Type", "application/json")
                 .body(Body::from(body))
-                .unwrap()
+                .unwrap();
+            response.headers_mut().extend(headers.unwrap_or_default());
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(wait_time));
+            response
         }
         _ => ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error")
             .into_response(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{IpAddr, SocketAddr},
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use async_trait::async_trait;
+    use axum::{
+        body::to_bytes,
+        extract::ConnectInfo,
+        http::{header::RETRY_AFTER, StatusCode},
+        middleware,
+        routing::get,
+        Extension, Router,
+    };
+    use axum_test::TestServer;
+    use chrono::Utc;
+    use testresult::TestResult;
+    use tower_governor::GovernorError;
+    use tracing::{
+        field::{Field, Visit},
+        Event, Level, Subscriber,
+    };
+    use tracing_subscriber::{layer::Context, prelude::*, Layer};
+    use uuid::Uuid;
+
+    use crate::{
+        domain::{
+            auth::users::{tests::MockUserService, User},
+            communication::email_addresses::{
+                tests::MockEmailAddressService, SentEmailConfirmation,
+            },
+        },
+        infrastructure::http::{
+            client_ip::{ClientIpKeyExtractor, TrustedProxiesConfig},
+            servers::https::router,
+            state::tests::test_state,
+        },
+    };
+
+    use super::{
+        limit, rate_limit_error_handler, whole_seconds, Bucket, Limiter, RateLimitBackend,
+        RateLimitConfig, RateLimitConfigError, RateLimitDecision, RateLimitMode, RateLimitStore,
+        RateLimitStoreError, SampledLog, TooManyRequestsResponse, REJECTION_LOG_INTERVAL,
+    };
+
+    /// Collects the path of every warning logged
+    #[derive(Clone, Default)]
+    struct WarnedPaths(Arc<Mutex<Vec<String>>>);
+
+    impl Visit for WarnedPaths {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            if field.name() == "path" {
+                self.0.lock().unwrap().push(value.to_string());
+            }
+        }
+
+        fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+    }
+
+    impl<S: Subscriber> Layer<S> for WarnedPaths {
+        fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+            if *event.metadata().level() == Level::WARN {
+                event.record(&mut self.clone());
+            }
+        }
+    }
+
+    fn server(mode: RateLimitMode) -> TestResult<TestServer> {
+        let mut state = test_state(None, None);
+        state.config.rate_limit = RateLimitConfig {
+            mode,
+            per_second: 60,
+            burst_size: 2,
+            ..RateLimitConfig::default()
+        };
+
+        let peer = SocketAddr::from(([203, 0, 113, 7], 443));
+        let router = router(state).layer(Extension(ConnectInfo(peer)));
+
+        Ok(TestServer::new(router)?)
+    }
+
+    #[tokio::test]
+    async fn test_enforce_mode_refuses_requests_over_the_limit() -> TestResult {
+        let server = server(RateLimitMode::Enforce)?;
+
+        let first = server.get("/api/v1/uptime").await;
+        first.assert_status_ok();
+        assert_eq!(first.header("x-ratelimit-limit"), "2");
+        assert_eq!(first.header("x-ratelimit-remaining"), "1");
+
+        server.get("/api/v1/uptime").await.assert_status_ok();
+
+        let limited = server.get("/api/v1/uptime").await;
+        limited.assert_status(StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(limited.header("retry-after"), "60");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_refused_requests_are_logged_without_flooding() -> TestResult {
+        let warned = WarnedPaths::default();
+        let _guard =
+            tracing::subscriber::set_default(tracing_subscriber::registry().with(warned.clone()));
+
+        let server = server(RateLimitMode::Enforce)?;
+
+        for _ in 0..2 {
+            server.get("/api/v1/uptime").await.assert_status_ok();
+        }
+
+        for _ in 0..3 {
+            let limited = server.get("/api/v1/uptime").await;
+            limited.assert_status(StatusCode::TOO_MANY_REQUESTS);
+            assert_eq!(limited.json::<TooManyRequestsResponse>().retry_after, 60);
+        }
+
+        assert_eq!(*warned.0.lock().unwrap(), vec!["/api/v1/uptime"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sampled_log_logs_once_per_interval() {
+        let every = SampledLog::new(Duration::ZERO);
+
+        assert_eq!(every.sample(), Some(0));
+        assert_eq!(every.sample(), Some(0));
+
+        let sampled = SampledLog::new(Duration::from_secs(3600));
+
+        assert_eq!(sampled.sample(), Some(0));
+        assert_eq!(sampled.sample(), None);
+        assert_eq!(sampled.sample(), None);
+        assert_eq!(sampled.state.lock().unwrap().suppressed, 2);
+    }
+
+    /// A store which never decides
+    struct HangingStore;
+
+    #[async_trait]
+    impl RateLimitStore for HangingStore {
+        async fn check(&self, _key: IpAddr) -> Result<RateLimitDecision, RateLimitStoreError> {
+            std::future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_slow_store_times_out_and_lets_requests_through() -> TestResult {
+        let bucket = Bucket {
+            burst_size: 2,
+            store: Arc::new(HangingStore),
+        };
+
+        let limiter = Limiter {
+            mode: RateLimitMode::Enforce,
+            key_extractor: ClientIpKeyExtractor::new(TrustedProxiesConfig::default()),
+            general: bucket.clone(),
+            strict: bucket,
+            strict_paths: Arc::new([]),
+            store_timeout: Duration::from_millis(10),
+            rejections: Arc::new(SampledLog::new(REJECTION_LOG_INTERVAL)),
+            store_errors: Arc::new(SampledLog::new(Duration::from_secs(3600))),
+        };
+
+        let peer = SocketAddr::from(([203, 0, 113, 7], 443));
+        let router = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(middleware::from_fn_with_state(limiter.clone(), limit))
+            .layer(Extension(ConnectInfo(peer)));
+
+        let server = TestServer::new(router)?;
+
+        for _ in 0..2 {
+            server.get("/").await.assert_status_ok();
+        }
+
+        // Only the first failure was logged
+        assert_eq!(limiter.store_errors.state.lock().unwrap().suppressed, 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_strict_paths_have_a_separate_stricter_limit() -> TestResult {
+        let mut users = MockUserService::new();
+        users.expect_email_exists().returning(|_| Ok(false));
+
+        let mut state = test_state(Some(users), None);
+        state.config.rate_limit = RateLimitConfig {
+            mode: RateLimitMode::Enforce,
+            per_second: 60,
+            burst_size: 2,
+            strict_per_second: 60,
+            strict_burst_size: 1,
+            ..RateLimitConfig::default()
+        };
+
+        let peer = SocketAddr::from(([203, 0, 113, 7], 443));
+        let server = TestServer::new(router(state).layer(Extension(ConnectInfo(peer))))?;
+
+        let strict = server
+            .get("/api/v1/users/email-available")
+            .add_query_param("email", "email@example.com")
+            .await;
+        strict.assert_status_ok();
+        assert_eq!(strict.header("x-ratelimit-limit"), "1");
+        assert_eq!(strict.header("x-ratelimit-remaining"), "0");
+
+        server
+            .get("/api/v1/users/email-available")
+            .add_query_param("email", "email@example.com")
+            .await
+            .assert_status(StatusCode::TOO_MANY_REQUESTS);
+
+        let general = server.get("/api/v1/uptime").await;
+        general.assert_status_ok();
+        assert_eq!(general.header("x-ratelimit-remaining"), "1");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_throttled_resend_sets_retry_after_matching_body() -> TestResult {
+        let user_id = Uuid::now_v7();
+        let resend = format!("/api/v1/users/{user_id}/email/confirmation");
+
+        let mut users = MockUserService::new();
+        users.expect_get_user_by_id().returning(|id| {
+            Ok(User {
+                id: *id,
+                ..User::default()
+            })
+        });
+
+        let mut email_addresses = MockEmailAddressService::new();
+        email_addresses
+            .expect_send_email_confirmation()
+            .times(1)
+            .returning(|_, _, _, _| {
+                Ok(SentEmailConfirmation {
+                    expires_at: Utc::now(),
+                    link: "https://example.com/confirm".to_string(),
+                })
+            });
+
+        let mut state = test_state(Some(users), Some(email_addresses));
+        state.config.rate_limit = RateLimitConfig {
+            mode: RateLimitMode::Enforce,
+            strict_paths: vec![resend.clone()],
+            strict_per_second: 90,
+            strict_burst_size: 1,
+            ..RateLimitConfig::default()
+        };
+
+        let peer = SocketAddr::from(([203, 0, 113, 7], 443));
+        let server = TestServer::new(router(state).layer(Extension(ConnectInfo(peer))))?;
+
+        server
+            .post(&resend)
+            .await
+            .assert_status(StatusCode::ACCEPTED);
+
+        let limited = server.post(&resend).await;
+
+        limited.assert_status(StatusCode::TOO_MANY_REQUESTS);
+
+        let retry_after = limited.json::<TooManyRequestsResponse>().retry_after;
+
+        assert!(retry_after > 0 && retry_after <= 90);
+        assert_eq!(limited.header(RETRY_AFTER), retry_after.to_string());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_error_handler_sets_retry_after_header() -> TestResult {
+        let response = rate_limit_error_handler(GovernorError::TooManyRequests {
+            wait_time: 7,
+            headers: None,
+        });
+
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[RETRY_AFTER], "7");
+
+        let body: TooManyRequestsResponse =
+            serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await?)?;
+
+        assert_eq!(body.retry_after, 7);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wait_time_is_rounded_up_to_whole_seconds() {
+        assert_eq!(whole_seconds(Duration::from_secs(3)), 3);
+        assert_eq!(whole_seconds(Duration::from_millis(3001)), 4);
+        assert_eq!(whole_seconds(Duration::from_millis(1)), 1);
+        assert_eq!(whole_seconds(Duration::ZERO), 0);
+    }
+
+    #[tokio::test]
+    async fn test_warn_mode_serves_requests_over_the_limit() -> TestResult {
+        let warned = WarnedPaths::default();
+        let _guard =
+            tracing::subscriber::set_default(tracing_subscriber::registry().with(warned.clone()));
+
+        let server = server(RateLimitMode::Warn)?;
+
+        for _ in 0..5 {
+            server.get("/api/v1/uptime").await.assert_status_ok();
+        }
+
+        assert_eq!(*warned.0.lock().unwrap(), vec!["/api/v1/uptime"; 3]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_off_mode_serves_every_request() -> TestResult {
+        let server = server(RateLimitMode::Off)?;
+
+        for _ in 0..5 {
+            server.get("/api/v1/uptime").await.assert_status_ok();
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_memory_backend_is_valid() {
+        assert_eq!(RateLimitConfig::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_redis_backend_requires_a_url() {
+        let config = RateLimitConfig {
+            backend: RateLimitBackend::Redis,
+            ..RateLimitConfig::default()
+        };
+
+        let expected = if cfg!(feature = "redis") {
+            RateLimitConfigError::RedisUrlMissing
+        } else {
+            RateLimitConfigError::RedisUnsupported
+        };
+
+        assert_eq!(config.validate(), Err(expected));
+    }
+}
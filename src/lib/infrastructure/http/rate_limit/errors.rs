@@ -0,0 +1,30 @@
+use thiserror::Error;
+
+/// Errors in the rate limiting configuration
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum RateLimitConfigError {
+    /// The Redis backend was chosen without a URL to reach Redis at
+    #[error("RATE_LIMIT_REDIS_URL must be set when RATE_LIMIT_BACKEND is redis")]
+    RedisUrlMissing,
+
+    /// The Redis URL couldn't be parsed
+    #[error("invalid RATE_LIMIT_REDIS_URL: {0}")]
+    InvalidRedisUrl(String),
+
+    /// The Redis backend was chosen, but the server was built without the `redis` feature
+    #[error("RATE_LIMIT_BACKEND=redis requires the server to be built with the redis feature")]
+    RedisUnsupported,
+}
+
+/// Errors checking a client's rate limit
+#[derive(Debug, Error)]
+pub enum RateLimitStoreError {
+    /// The store didn't decide in time
+    #[error("timed out checking the rate limit")]
+    Timeout,
+
+    /// The Redis server couldn't be reached, or the script failed
+    #[cfg(feature = "redis")]
+    #[error(transparent)]
+    Redis(#[from] redis::RedisError),
+}
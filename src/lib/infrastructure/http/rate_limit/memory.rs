@@ -0,0 +1,129 @@
+//! In-process rate limit storage
+
+use std::{net::IpAddr, num::NonZeroU32, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use governor::{
+    clock::{Clock, DefaultClock},
+    middleware::StateInformationMiddleware,
+    state::keyed::DefaultKeyedStateStore,
+    Quota, RateLimiter,
+};
+use tracing::info;
+
+use super::{RateLimitConfig, RateLimitDecision, RateLimitStore, RateLimitStoreError};
+
+type KeyedRateLimiter =
+    RateLimiter<IpAddr, DefaultKeyedStateStore<IpAddr>, DefaultClock, StateInformationMiddleware>;
+
+/// Keeps each client's rate limit in this process, so every instance limits clients separately
+#[derive(Clone, Debug)]
+pub struct MemoryRateLimitStore {
+    limiter: Arc<KeyedRateLimiter>,
+}
+
+impl MemoryRateLimitStore {
+    /// Create a new in-memory store, allowing `config.burst_size` requests at once, and another
+    /// every `config.per_second` seconds
+    pub fn new(config: &RateLimitConfig) -> Self {
+        let quota = Quota::with_period(Duration::from_secs(config.per_second.max(1)))
+            .expect("rate limit period is at least a second")
+            .allow_burst(NonZeroU32::new(config.burst_size).unwrap_or(NonZeroU32::MIN));
+
+        Self {
+            limiter: Arc::new(RateLimiter::keyed(quota).with_middleware()),
+        }
+    }
+
+    /// Periodically forgets clients which haven't made a request recently, so the storage doesn't
+    /// grow forever
+    pub fn spawn_cleanup(&self) {
+        let limiter = self.limiter.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(60));
+            loop {
+                interval.tick().await;
+                info!("rate limiting storage size: {}", limiter.len());
+                limiter.retain_recent();
+            }
+        });
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for MemoryRateLimitStore {
+    async fn check(&self, key: IpAddr) -> Result<RateLimitDecision, RateLimitStoreError> {
+        Ok(match self.limiter.check_key(&key) {
+            Ok(snapshot) => RateLimitDecision::Allowed {
+                remaining: snapshot.remaining_burst_capacity(),
+            },
+            Err(not_until) => RateLimitDecision::Limited {
+                retry_after: not_until.wait_time_from(DefaultClock::default().now()),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use testresult::TestResult;
+
+    use super::*;
+
+    fn store(burst_size: u32) -> MemoryRateLimitStore {
+        MemoryRateLimitStore::new(&RateLimitConfig {
+            per_second: 60,
+            burst_size,
+            ..RateLimitConfig::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_requests_within_burst_are_allowed() -> TestResult {
+        let store = store(2);
+        let key = "203.0.113.7".parse()?;
+
+        assert_eq!(
+            store.check(key).await?,
+            RateLimitDecision::Allowed { remaining: 1 }
+        );
+        assert_eq!(
+            store.check(key).await?,
+            RateLimitDecision::Allowed { remaining: 0 }
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_requests_over_burst_are_limited() -> TestResult {
+        let store = store(1);
+        let key = "203.0.113.7".parse()?;
+
+        store.check(key).await?;
+
+        let RateLimitDecision::Limited { retry_after } = store.check(key).await? else {
+            panic!("expected the request to be limited");
+        };
+
+        assert!(retry_after > Duration::ZERO);
+        assert!(retry_after <= Duration::from_secs(60));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_clients_are_limited_separately() -> TestResult {
+        let store = store(1);
+
+        store.check("203.0.113.7".parse()?).await?;
+
+        assert_eq!(
+            store.check("203.0.113.8".parse()?).await?,
+            RateLimitDecision::Allowed { remaining: 0 }
+        );
+
+        Ok(())
+    }
+}
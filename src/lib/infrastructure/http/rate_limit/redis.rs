@@ -0,0 +1,179 @@
+//! Rate limit storage shared between instances through Redis
+
+use std::{fmt, net::IpAddr, time::Duration};
+
+use async_trait::async_trait;
+use redis::{aio::ConnectionManager, Client, Script};
+use tokio::sync::OnceCell;
+
+use super::{RateLimitConfig, RateLimitDecision, RateLimitStore, RateLimitStoreError};
+
+/// The same generic cell rate algorithm as the in-memory store, run atomically in Redis. Each
+/// client's key holds the time, in milliseconds, at which their bucket will next be full, and
+/// expires then. Redis's clock is used rather than each instance's, so they can't disagree.
+///
+/// Returns `{1, remaining}` if the request is allowed, or `{0, retry_after_ms}` if it isn't.
+const GCRA_SCRIPT: &str = r"
+local emission_interval = tonumber(ARGV[1])
+local burst_size = tonumber(ARGV[2])
+
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+
+local full_at = tonumber(redis.call('GET', KEYS[1])) or now
+if full_at < now then
+    full_at = now
+end
+
+local new_full_at = full_at + emission_interval
+local allow_at = new_full_at - burst_size * emission_interval
+
+if now < allow_at then
+    return {0, allow_at - now}
+end
+
+redis.call('SET', KEYS[1], new_full_at, 'PX', new_full_at - now)
+
+return {1, math.floor((now - allow_at) / emission_interval)}
+";
+
+/// Keeps each client's rate limit in Redis, so they're limited across every instance
+pub struct RedisRateLimitStore {
+    client: Client,
+    connection: OnceCell<ConnectionManager>,
+    script: Script,
+    namespace: String,
+    emission_interval_ms: u64,
+    burst_size: u32,
+}
+
+impl fmt::Debug for RedisRateLimitStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RedisRateLimitStore")
+            .field("connected", &self.connection.initialized())
+            .field("namespace", &self.namespace)
+            .field("emission_interval_ms", &self.emission_interval_ms)
+            .field("burst_size", &self.burst_size)
+            .finish()
+    }
+}
+
+impl RedisRateLimitStore {
+    /// Create a new Redis store, which connects to `url` when it's first used and keeps each
+    /// client's limit under `namespace`
+    pub fn new(
+        url: &str,
+        namespace: &str,
+        config: &RateLimitConfig,
+    ) -> Result<Self, redis::RedisError> {
+        Ok(Self {
+            client: Client::open(url)?,
+            connection: OnceCell::new(),
+            script: Script::new(GCRA_SCRIPT),
+            namespace: namespace.to_string(),
+            emission_interval_ms: config.per_second.max(1) * 1000,
+            burst_size: config.burst_size.max(1),
+        })
+    }
+
+    async fn connection(&self) -> Result<ConnectionManager, redis::RedisError> {
+        self.connection
+            .get_or_try_init(|| ConnectionManager::new(self.client.clone()))
+            .await
+            .cloned()
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for RedisRateLimitStore {
+    async fn check(&self, key: IpAddr) -> Result<RateLimitDecision, RateLimitStoreError> {
+        let mut connection = self.connection().await?;
+
+        let (allowed, value): (u8, u64) = self
+            .script
+            .key(format!("{}:{key}", self.namespace))
+            .arg(self.emission_interval_ms)
+            .arg(self.burst_size)
+            .invoke_async(&mut connection)
+            .await?;
+
+        Ok(if allowed == 1 {
+            RateLimitDecision::Allowed {
+                remaining: u32::try_from(value).unwrap_or(u32::MAX),
+            }
+        } else {
+            RateLimitDecision::Limited {
+                retry_after: Duration::from_millis(value),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use testresult::TestResult;
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn store(burst_size: u32) -> TestResult<RedisRateLimitStore> {
+        let url = std::env::var("REDIS_URL")?;
+
+        Ok(RedisRateLimitStore::new(
+            &url,
+            "rate_limit",
+            &RateLimitConfig {
+                per_second: 60,
+                burst_size,
+                ..RateLimitConfig::default()
+            },
+        )?)
+    }
+
+    /// A client address no other test run has used, so earlier runs' limits don't carry over
+    fn unique_key() -> IpAddr {
+        IpAddr::V6(Uuid::now_v7().as_u128().into())
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Redis server at REDIS_URL"]
+    async fn test_redis_store_limits_requests_over_burst() -> TestResult {
+        let store = store(2)?;
+        let key = unique_key();
+
+        assert_eq!(
+            store.check(key).await?,
+            RateLimitDecision::Allowed { remaining: 1 }
+        );
+        assert_eq!(
+            store.check(key).await?,
+            RateLimitDecision::Allowed { remaining: 0 }
+        );
+
+        let RateLimitDecision::Limited { retry_after } = store.check(key).await? else {
+            panic!("expected the request to be limited");
+        };
+
+        assert!(retry_after > Duration::ZERO);
+        assert!(retry_after <= Duration::from_secs(60));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Redis server at REDIS_URL"]
+    async fn test_redis_store_shares_limits_between_instances() -> TestResult {
+        let first = store(1)?;
+        let second = store(1)?;
+        let key = unique_key();
+
+        first.check(key).await?;
+
+        assert!(matches!(
+            second.check(key).await?,
+            RateLimitDecision::Limited { .. }
+        ));
+
+        Ok(())
+    }
+}
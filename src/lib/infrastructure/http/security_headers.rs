@@ -0,0 +1,164 @@
+//! Security headers
+//!
+//! Every HTTPS response is hardened against MIME sniffing, framing and referrer leaks, and given
+//! a `Content-Security-Policy`. The default policy allows the API documentation's scripts and
+//! styles from unpkg.com, so API-only deployments may want to tighten it.
+
+use axum::{
+    extract::{Request, State},
+    http::{
+        header::{
+            CONTENT_SECURITY_POLICY, REFERRER_POLICY, X_CONTENT_TYPE_OPTIONS, X_FRAME_OPTIONS,
+        },
+        HeaderValue,
+    },
+    middleware::Next,
+    response::Response,
+};
+use clap::Parser;
+
+/// The default `Content-Security-Policy`, which allows the API documentation to load
+pub const DEFAULT_CONTENT_SECURITY_POLICY: &str = "default-src 'self'; \
+    script-src 'self' https://unpkg.com; \
+    style-src 'self' 'unsafe-inline' https://unpkg.com; \
+    img-src 'self' data:; \
+    font-src 'self' data:; \
+    frame-ancestors 'none'";
+
+/// Security headers configuration
+#[derive(Clone, Debug, PartialEq, Eq, Parser)]
+pub struct SecurityHeadersConfig {
+    /// The `Content-Security-Policy` sent with every response
+    #[arg(
+        long,
+        env = "CONTENT_SECURITY_POLICY",
+        default_value = DEFAULT_CONTENT_SECURITY_POLICY,
+        value_parser = parse_policy
+    )]
+    pub content_security_policy: String,
+}
+
+impl Default for SecurityHeadersConfig {
+    fn default() -> Self {
+        Self {
+            content_security_policy: DEFAULT_CONTENT_SECURITY_POLICY.to_string(),
+        }
+    }
+}
+
+/// Checks a policy can be sent as a header
+fn parse_policy(value: &str) -> Result<String, String> {
+    HeaderValue::from_str(value)
+        .map(|_| value.to_string())
+        .map_err(|_| "the policy contains characters which aren't allowed in a header".to_string())
+}
+
+/// The security headers added to every response
+#[derive(Clone, Debug)]
+pub struct SecurityHeaders {
+    content_security_policy: HeaderValue,
+}
+
+impl SecurityHeaders {
+    /// Create the security headers from `config`
+    pub fn new(config: &SecurityHeadersConfig) -> Self {
+        Self {
+            content_security_policy: HeaderValue::from_str(&config.content_security_policy)
+                .expect("the content security policy was checked when it was parsed"),
+        }
+    }
+}
+
+/// Adds the security headers to every response, unless the handler set its own
+pub async fn security_headers(
+    State(headers): State<SecurityHeaders>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let mut response = next.run(request).await;
+    let response_headers = response.headers_mut();
+
+    response_headers
+        .entry(X_CONTENT_TYPE_OPTIONS)
+        .or_insert(HeaderValue::from_static("nosniff"));
+    response_headers
+        .entry(X_FRAME_OPTIONS)
+        .or_insert(HeaderValue::from_static("DENY"));
+    response_headers
+        .entry(REFERRER_POLICY)
+        .or_insert(HeaderValue::from_static("no-referrer"));
+    response_headers
+        .entry(CONTENT_SECURITY_POLICY)
+        .or_insert(headers.content_security_policy);
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use axum_test::{TestResponse, TestServer};
+    use testresult::TestResult;
+
+    use crate::infrastructure::http::{servers::https::router, state::tests::test_state};
+
+    use super::*;
+
+    fn assert_security_headers(response: &TestResponse, content_security_policy: &str) {
+        assert_eq!(response.header(X_CONTENT_TYPE_OPTIONS), "nosniff");
+        assert_eq!(response.header(X_FRAME_OPTIONS), "DENY");
+        assert_eq!(response.header(REFERRER_POLICY), "no-referrer");
+        assert_eq!(
+            response.header(CONTENT_SECURITY_POLICY),
+            content_security_policy
+        );
+    }
+
+    #[tokio::test]
+    async fn test_security_headers_on_json_response() -> TestResult {
+        let server = TestServer::new(router(test_state(None, None)))?;
+
+        let response = server.get("/api/v1/uptime").await;
+
+        response.assert_status_ok();
+        assert_security_headers(&response, DEFAULT_CONTENT_SECURITY_POLICY);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_security_headers_on_docs() -> TestResult {
+        let server = TestServer::new(router(test_state(None, None)))?;
+
+        let response = server.get("/api/v1").await;
+
+        response.assert_status_ok();
+        assert_security_headers(&response, DEFAULT_CONTENT_SECURITY_POLICY);
+        assert!(DEFAULT_CONTENT_SECURITY_POLICY.contains("script-src 'self' https://unpkg.com"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_content_security_policy_is_configurable() -> TestResult {
+        let mut state = test_state(None, None);
+        state.config.security_headers.content_security_policy = "default-src 'none'".to_string();
+
+        let server = TestServer::new(router(state))?;
+
+        let response = server.get("/api/v1/uptime").await;
+
+        assert_security_headers(&response, "default-src 'none'");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_content_security_policy_is_rejected() {
+        assert!(SecurityHeadersConfig::try_parse_from([
+            "server",
+            "--content-security-policy",
+            "default-src 'self'\n",
+        ])
+        .is_err());
+    }
+}
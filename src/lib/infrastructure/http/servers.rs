@@ -1,4 +1,47 @@
 //! HTTP(S) server implementation modules.
 
+use std::net::{SocketAddr, TcpListener};
+
+use anyhow::{Context, Result};
+
 pub mod http;
 pub mod https;
+pub mod tls_health;
+
+/// Binds a listener for the `protocol` server to `address`, naming both if it can't, so it's clear
+/// which of the servers failed to start
+fn bind(protocol: &str, address: SocketAddr) -> Result<TcpListener> {
+    TcpListener::bind(address).with_context(|| {
+        format!(
+            "failed to bind the {protocol} listener on port {} ({address})",
+            address.port()
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr, TcpListener};
+
+    use testresult::TestResult;
+
+    use super::bind;
+
+    #[test]
+    fn test_bind_error_names_the_protocol_and_port() -> TestResult {
+        let taken = TcpListener::bind(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0))?;
+        let address = taken.local_addr()?;
+
+        let err = bind("HTTP", address).expect_err("the port is already in use");
+
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "failed to bind the HTTP listener on port {} ({address})",
+                address.port()
+            )
+        );
+
+        Ok(())
+    }
+}
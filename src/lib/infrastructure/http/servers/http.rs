@@ -1,30 +1,51 @@
 //! HTTP application server
 
-use std::net::{SocketAddr, TcpListener};
+use std::{
+    net::{SocketAddr, TcpListener},
+    time::Duration,
+};
 
 use anyhow::{Context, Result};
 use axum::{async_trait, extract::State, http::Uri, response::Redirect, routing::get, Router};
 use axum_server::Handle;
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, info};
 
-use crate::infrastructure::http::{shutdown_signal, Server};
+use crate::{
+    domain::base_url::BaseUrl,
+    infrastructure::http::{graceful_shutdown, servers::bind, Server},
+};
 
 /// The application's HTTP server
 #[derive(Debug)]
 pub struct HttpServer {
     router: Router,
     listener: TcpListener,
+    shutdown: CancellationToken,
+    grace_period: Duration,
 }
 
 impl HttpServer {
-    /// Returns a new HTTP server bound to the port specified in `config`.
-    pub async fn new(address: SocketAddr, base_url: &str) -> Result<Self> {
-        let router = router(base_url);
-
-        let listener = TcpListener::bind(address)
-            .with_context(|| format!("failed to listen on {}", address))?;
-
-        Ok(Self { router, listener })
+    /// Returns a new HTTP server bound to the port specified in `config`, which redirects to
+    /// `base_url` permanently if `permanent_redirect` is set, and stops once `shutdown` is
+    /// cancelled, giving in-flight requests `grace_period` to finish.
+    pub async fn new(
+        address: SocketAddr,
+        base_url: &BaseUrl,
+        permanent_redirect: bool,
+        shutdown: CancellationToken,
+        grace_period: Duration,
+    ) -> Result<Self> {
+        let router = router(base_url, permanent_redirect);
+
+        let listener = bind("HTTP", address)?;
+
+        Ok(Self {
+            router,
+            listener,
+            shutdown,
+            grace_period,
+        })
     }
 }
 
@@ -42,34 +63,53 @@ impl Server for HttpServer {
 
         let handle = Handle::new();
 
-        let server = axum_server::from_tcp(self.listener)
-            .handle(handle.clone())
-            .serve(self.router.into_make_service());
+        tokio::spawn(graceful_shutdown(
+            self.shutdown,
+            handle.clone(),
+            self.grace_period,
+        ));
+
+        axum_server::from_tcp(self.listener)
+            .handle(handle)
+            .serve(self.router.into_make_service())
+            .await
+            .context("server error")?;
 
-        tokio::select! {
-            result = server => result.context("server error")?,
-            _ = shutdown_signal(Some(handle)) => {
-                info!("Shutting down HTTP server");
-            }
-        }
+        info!("HTTP server stopped");
 
         Ok(())
     }
 }
 
+/// Where, and how, HTTP requests are redirected
+#[derive(Clone, Debug)]
+struct RedirectConfig {
+    base_url: BaseUrl,
+    permanent: bool,
+}
+
 /// The HTTP handler
-async fn http_handler(State(base_url): State<String>, uri: Uri) -> Redirect {
-    debug!("redirecting to HTTPS: {}{}", base_url, uri.path());
-    let uri = format!("{}{}", base_url, uri.path());
+async fn http_handler(State(config): State<RedirectConfig>, uri: Uri) -> Redirect {
+    let path_and_query = uri.path_and_query().map_or("/", |path| path.as_str());
+    let uri = format!("{}{}", config.base_url, path_and_query);
 
-    Redirect::temporary(&uri)
+    debug!("redirecting to HTTPS: {}", uri);
+
+    if config.permanent {
+        Redirect::permanent(&uri)
+    } else {
+        Redirect::temporary(&uri)
+    }
 }
 
 /// Create the router for the HTTP server
-pub fn router(base_url: &str) -> Router {
+pub fn router(base_url: &BaseUrl, permanent_redirect: bool) -> Router {
     Router::new()
         .route("/*path", get(http_handler))
-        .with_state(base_url.to_string())
+        .with_state(RedirectConfig {
+            base_url: base_url.clone(),
+            permanent: permanent_redirect,
+        })
 }
 
 #[cfg(test)]
@@ -79,11 +119,11 @@ mod tests {
     use axum_test::TestServer;
     use testresult::TestResult;
 
+    use crate::domain::base_url::tests::example_base_url;
+
     #[tokio::test]
     async fn test_http_server_redirect() -> TestResult {
-        let base_url = "https://example.com";
-
-        let router = super::router(base_url);
+        let router = super::router(&example_base_url(), false);
 
         let response = TestServer::new(router)?.get("/abc/def").await;
 
@@ -95,7 +135,54 @@ mod tests {
             .ok_or_else(|| anyhow!("missing location header"))?
             .to_str()?;
 
-        assert_eq!(location, format!("{}{}", base_url, "/abc/def"));
+        assert_eq!(location, "https://example.com/abc/def");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_http_server_redirect_keeps_query_string() -> TestResult {
+        let router = super::router(&example_base_url(), false);
+
+        let response = TestServer::new(router)?
+            .get("/abc/def?token=123&next=%2Fhome")
+            .await;
+
+        response.assert_status(StatusCode::TEMPORARY_REDIRECT);
+        assert_eq!(
+            response.header("location"),
+            "https://example.com/abc/def?token=123&next=%2Fhome"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_http_server_permanent_redirect() -> TestResult {
+        let router = super::router(&example_base_url(), true);
+
+        let response = TestServer::new(router)?.get("/abc/def").await;
+
+        response.assert_status(StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(response.header("location"), "https://example.com/abc/def");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_http_server_redirect_keeps_confirmation_token() -> TestResult {
+        let router = super::router(&example_base_url(), false);
+
+        let response = TestServer::new(router)?
+            .get("/api/v1/users/0192a9f1-7c2e-7d3a-9b4f-1e2d3c4b5a69/email/confirmation?token=abc")
+            .await;
+
+        response.assert_status(StatusCode::TEMPORARY_REDIRECT);
+
+        assert_eq!(
+            response.header("location"),
+            "https://example.com/api/v1/users/0192a9f1-7c2e-7d3a-9b4f-1e2d3c4b5a69/email/confirmation?token=abc"
+        );
 
         Ok(())
     }
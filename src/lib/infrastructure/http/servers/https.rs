@@ -1,89 +1,354 @@
 //! HTTPS application server
 
-use std::net::SocketAddr;
+use std::{
+    fmt,
+    net::{SocketAddr, TcpListener},
+    sync::Arc,
+    time::Duration,
+};
 
 use anyhow::{Context, Result};
-use axum::{async_trait, extract::Request, Router};
+use axum::{async_trait, extract::Request, middleware, Router};
 use axum_server::{tls_rustls::RustlsConfig, Handle};
-use tower_http::{catch_panic::CatchPanicLayer, compression::CompressionLayer, trace::TraceLayer};
-use tracing::{debug, info, info_span};
+use clap::ValueEnum;
+use rustls::crypto::{ring, CryptoProvider};
+use tokio_util::sync::CancellationToken;
+use tower_http::{
+    catch_panic::CatchPanicLayer, compression::CompressionLayer,
+    normalize_path::NormalizePathLayer, trace::TraceLayer,
+};
+use tower_layer::Layer;
+use tracing::{debug, error, field, info, info_span};
 
 use crate::{
-    domain::{auth::users::UserService, communication::email_addresses::EmailAddressService},
+    domain::{
+        auth::users::UserService, communication::email_addresses::EmailAddressService,
+        health::DatabaseHealth, idempotency::IdempotencyStore,
+    },
     infrastructure::http::{
-        handlers::{panic_handler, v1},
-        shutdown_signal,
+        access_log::{access_log, AccessLog},
+        body_log::body_log,
+        graceful_shutdown,
+        handlers::{not_found, panic_handler, v1},
+        header_limits::header_limits,
+        idempotency::idempotency,
+        rate_limit::rate_limit,
+        security_headers::{security_headers, SecurityHeaders},
+        servers::{bind, tls_health::TlsFilesHealthCheck},
         state::AppState,
+        transport_security::hsts,
         Server,
     },
 };
 
+/// A protocol the HTTPS server advertises through ALPN
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum AlpnProtocol {
+    /// HTTP/2
+    #[value(name = "h2")]
+    H2,
+
+    /// HTTP/1.1
+    #[value(name = "http/1.1")]
+    Http11,
+}
+
+impl AlpnProtocol {
+    /// The protocol's ALPN identifier
+    pub fn id(&self) -> &'static [u8] {
+        match self {
+            Self::H2 => b"h2",
+            Self::Http11 => b"http/1.1",
+        }
+    }
+}
+
+/// Where the HTTPS server's certificate and key are loaded from
+#[derive(Clone, PartialEq, Eq)]
+pub enum TlsSource {
+    /// PEM files, which are reloaded on SIGHUP
+    Files {
+        /// The path to the certificate file
+        cert: String,
+
+        /// The path to the key file
+        key: String,
+    },
+
+    /// PEM encoded strings, such as from environment variables or a secret manager
+    Pem {
+        /// The PEM encoded certificate chain
+        cert: String,
+
+        /// The PEM encoded key
+        key: String,
+    },
+}
+
+impl TlsSource {
+    /// A health check of the certificate and key files, if they're loaded from files
+    pub fn health_check(&self) -> Option<TlsFilesHealthCheck> {
+        match self {
+            Self::Files { cert, key } => Some(TlsFilesHealthCheck::new(cert, key)),
+            Self::Pem { .. } => None,
+        }
+    }
+
+    /// Loads the certificate and key, advertising `alpn_protocols`, in order of preference
+    pub async fn load(&self, alpn_protocols: &[AlpnProtocol]) -> Result<RustlsConfig> {
+        let loaded = match self {
+            Self::Files { cert, key } => RustlsConfig::from_pem_file(cert, key).await,
+            Self::Pem { cert, key } => {
+                RustlsConfig::from_pem(cert.as_bytes().to_vec(), key.as_bytes().to_vec()).await
+            }
+        }
+        .context("failed to load TLS config")?;
+
+        let mut server_config = (*loaded.get_inner()).clone();
+        server_config.alpn_protocols = alpn_protocols
+            .iter()
+            .map(|protocol| protocol.id().to_vec())
+            .collect();
+
+        Ok(RustlsConfig::from_config(Arc::new(server_config)))
+    }
+}
+
+impl fmt::Debug for TlsSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Files { cert, key } => f
+                .debug_struct("Files")
+                .field("cert", cert)
+                .field("key", key)
+                .finish(),
+            Self::Pem { cert, .. } => f
+                .debug_struct("Pem")
+                .field("cert", cert)
+                .field("key", &"********")
+                .finish(),
+        }
+    }
+}
+
 /// The application's HTTPS server
 #[derive(Debug)]
 pub struct HttpsServer {
     router: Router,
-    address: SocketAddr,
+    listener: TcpListener,
     tls_config: RustlsConfig,
+    tls_source: TlsSource,
+    alpn_protocols: Vec<AlpnProtocol>,
+    shutdown: CancellationToken,
+    grace_period: Duration,
 }
 
 impl HttpsServer {
-    /// Returns a new HTTPS server bound to the port specified in `config`.
+    /// Returns a new HTTPS server bound to `address`, serving the certificate from `tls_source`
+    /// and advertising `alpn_protocols`, which stops once `shutdown` is cancelled, giving
+    /// in-flight requests `grace_period` to finish.
     pub async fn new(
         address: SocketAddr,
-        cert_path: &str,
-        key_path: &str,
-        state: AppState<impl UserService, impl EmailAddressService>,
+        tls_source: TlsSource,
+        alpn_protocols: Vec<AlpnProtocol>,
+        state: AppState<
+            impl UserService,
+            impl EmailAddressService,
+            impl IdempotencyStore,
+            impl DatabaseHealth,
+        >,
+        shutdown: CancellationToken,
+        grace_period: Duration,
     ) -> Result<Self> {
-        let tls_config = RustlsConfig::from_pem_file(cert_path, key_path)
-            .await
-            .context("failed to load TLS config")?;
+        ensure_crypto_provider()?;
+
+        let tls_config = tls_source.load(&alpn_protocols).await?;
 
         let router = router(state);
 
+        let listener = bind("HTTPS", address)?;
+
         Ok(Self {
             router,
-            address,
+            listener,
             tls_config,
+            tls_source,
+            alpn_protocols,
+            shutdown,
+            grace_period,
         })
     }
+
+    /// The address the server is listening on
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        self.listener
+            .local_addr()
+            .context("failed to get local address")
+    }
 }
 
 #[async_trait]
 impl Server for HttpsServer {
     async fn run(self) -> Result<()> {
-        debug!("HTTPS Server listening on {}", self.address);
+        debug!("HTTPS Server listening on {}", self.local_addr()?);
 
         let handle = Handle::new();
 
-        let server = axum_server::bind_rustls(self.address, self.tls_config)
-            .handle(handle.clone())
+        // Certificates given as strings can't have changed, so only files are reloaded
+        if let TlsSource::Files { .. } = self.tls_source {
+            tokio::spawn(reload_on_hangup(
+                self.tls_config.clone(),
+                self.tls_source,
+                self.alpn_protocols,
+                self.shutdown.clone(),
+            ));
+        }
+
+        tokio::spawn(graceful_shutdown(
+            self.shutdown,
+            handle.clone(),
+            self.grace_period,
+        ));
+
+        axum_server::from_tcp_rustls(self.listener, self.tls_config)
+            .handle(handle)
             .serve(
                 self.router
                     .into_make_service_with_connect_info::<SocketAddr>(),
-            );
+            )
+            .await
+            .context("server error")?;
+
+        info!("HTTPS server stopped");
 
-        tokio::select! {
-            result = server => result.context("server error")?,
-            _ = shutdown_signal(Some(handle)) => {
-                info!("Shutting down HTTPS server");
+        Ok(())
+    }
+}
+
+/// Reloads the certificate and key whenever the process receives SIGHUP, so renewed certificates
+/// are served without a restart, until `shutdown` is cancelled
+#[mutants::skip]
+async fn reload_on_hangup(
+    tls_config: RustlsConfig,
+    tls_source: TlsSource,
+    alpn_protocols: Vec<AlpnProtocol>,
+    shutdown: CancellationToken,
+) {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut hangup = match signal(SignalKind::hangup()) {
+            Ok(hangup) => hangup,
+            Err(err) => {
+                error!("failed to install SIGHUP handler, certificates won't be reloaded: {err}");
+                return;
+            }
+        };
+
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = hangup.recv() => {
+                    reload_certificate(&tls_config, &tls_source, &alpn_protocols).await;
+                }
             }
         }
+    }
 
-        Ok(())
+    #[cfg(not(unix))]
+    {
+        let _ = (tls_config, tls_source, alpn_protocols, shutdown);
     }
 }
 
+/// Reloads the certificate and key from `tls_source`, still advertising `alpn_protocols`. If they
+/// can't be loaded, the ones already loaded carry on being served.
+///
+/// # Returns
+/// Whether the certificate was reloaded.
+pub async fn reload_certificate(
+    tls_config: &RustlsConfig,
+    tls_source: &TlsSource,
+    alpn_protocols: &[AlpnProtocol],
+) -> bool {
+    match tls_source.load(alpn_protocols).await {
+        Ok(reloaded) => {
+            tls_config.reload_from_config(reloaded.get_inner());
+            info!(source = ?tls_source, "reloaded TLS certificate");
+
+            true
+        }
+        Err(err) => {
+            error!(
+                source = ?tls_source,
+                "failed to reload TLS certificate, keeping the current one: {err:#}"
+            );
+
+            false
+        }
+    }
+}
+
+/// Installs ring as the process's rustls crypto provider, unless one already has been, so servers
+/// can be created more than once in the same process
+pub fn ensure_crypto_provider() -> Result<()> {
+    if ring::default_provider().install_default().is_err()
+        && CryptoProvider::get_default().is_none()
+    {
+        anyhow::bail!("failed to install rustls crypto provider");
+    }
+
+    Ok(())
+}
+
 /// Create the router for the HTTPS server
-pub fn router<U: UserService, E: EmailAddressService>(state: AppState<U, E>) -> Router {
-    let trace_layer = TraceLayer::new_for_http().make_span_with(|request: &Request<_>| {
-        let uri = request.uri().to_string();
-        info_span!("http_request", method = ?request.method(), uri)
-    });
-
-    #[allow(unused_mut)]
-    let mut router = Router::new()
-        .layer(trace_layer)
+pub fn router<U: UserService, E: EmailAddressService, I: IdempotencyStore, H: DatabaseHealth>(
+    state: AppState<U, E, I, H>,
+) -> Router {
+    // Responses are logged by the access log instead, which can leave out noisy routes
+    let trace_layer = TraceLayer::new_for_http()
+        .make_span_with(|request: &Request<_>| {
+            let uri = request.uri().to_string();
+            info_span!(
+                "http_request",
+                method = ?request.method(),
+                uri,
+                operation_id = field::Empty,
+            )
+        })
+        .on_request(())
+        .on_response(());
+
+    let access_log_layer =
+        middleware::from_fn_with_state(AccessLog::new(state.config.access_log.clone()), access_log);
+
+    let body_log_layer =
+        middleware::from_fn_with_state(Arc::new(state.config.body_log.clone()), body_log);
+
+    let header_limits_layer =
+        middleware::from_fn_with_state(state.config.header_limits.clone(), header_limits);
+
+    let hsts_layer =
+        middleware::from_fn_with_state(state.config.transport_security.hsts_header(), hsts);
+
+    let security_headers_layer = middleware::from_fn_with_state(
+        SecurityHeaders::new(&state.config.security_headers),
+        security_headers,
+    );
+
+    let strict_trailing_slash = state.config.strict_trailing_slash;
+
+    let rate_limit_config = state.config.rate_limit.clone();
+    let trusted_proxies = state.config.trusted_proxies.clone();
+
+    let router = Router::new()
         .nest("/api/v1", v1::router())
+        .fallback(not_found)
+        .layer(body_log_layer)
+        .layer(access_log_layer)
+        .layer(trace_layer)
+        .layer(middleware::from_fn_with_state(state.clone(), idempotency))
         .layer(
             CompressionLayer::new()
                 .br(true)
@@ -92,48 +357,197 @@ pub fn router<U: UserService, E: EmailAddressService>(state: AppState<U, E>) ->
                 .zstd(true),
         )
         .with_state(state)
+        .layer(header_limits_layer)
         .layer(CatchPanicLayer::custom(panic_handler));
 
-    // Configure the rate limiting only if not compiling for tests
-    #[cfg(not(test))]
-    {
-        use crate::infrastructure::http::rate_limit::RateLimitConfig;
-        use std::{sync::Arc, time::Duration};
-        use tower_governor::{
-            governor::GovernorConfigBuilder, key_extractor::SmartIpKeyExtractor, GovernorLayer,
-        };
+    let router = rate_limit(router, &rate_limit_config, trusted_proxies);
+
+    // Outermost, so rate limited and panicked responses carry them too
+    let router = router.layer(security_headers_layer).layer(hsts_layer);
+
+    if strict_trailing_slash {
+        return router;
+    }
+
+    // Paths have to be normalized before routing, which layers on the router itself run after,
+    // so the router is wrapped as the fallback of an otherwise empty one
+    Router::new().fallback_service(NormalizePathLayer::trim_trailing_slash().layer(router))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{Ipv4Addr, SocketAddr},
+        path::PathBuf,
+        sync::Arc,
+        time::Duration,
+    };
+
+    use rustls::crypto::CryptoProvider;
+    use testresult::TestResult;
+    use tokio_util::sync::CancellationToken;
+    use uuid::Uuid;
+
+    use crate::infrastructure::http::{state::tests::test_state, Server};
+
+    use super::{ensure_crypto_provider, reload_certificate, AlpnProtocol, HttpsServer, TlsSource};
+
+    const ALPN_PROTOCOLS: [AlpnProtocol; 2] = [AlpnProtocol::H2, AlpnProtocol::Http11];
+
+    fn embedded_certificate() -> TlsSource {
+        TlsSource::Pem {
+            cert: include_str!("testdata/cert.pem").to_string(),
+            key: include_str!("testdata/key.pem").to_string(),
+        }
+    }
+
+    /// Copies the test certificate and key to a directory of their own, so they can be changed
+    fn certificate_files() -> TestResult<TlsSource> {
+        let testdata = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("src/lib/infrastructure/http/servers/testdata");
+        let dir = std::env::temp_dir().join(format!("certs-{}", Uuid::now_v7()));
+
+        std::fs::create_dir_all(&dir)?;
+
+        let cert_path = dir.join("cert.pem");
+        let key_path = dir.join("key.pem");
+
+        std::fs::copy(testdata.join("cert.pem"), &cert_path)?;
+        std::fs::copy(testdata.join("key.pem"), &key_path)?;
+
+        Ok(TlsSource::Files {
+            cert: cert_path.to_string_lossy().into_owned(),
+            key: key_path.to_string_lossy().into_owned(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_reload_certificate_replaces_served_config() -> TestResult {
+        ensure_crypto_provider()?;
+
+        let tls_source = certificate_files()?;
+        let tls_config = tls_source.load(&ALPN_PROTOCOLS).await?;
+        let before = tls_config.get_inner();
+
+        assert!(reload_certificate(&tls_config, &tls_source, &ALPN_PROTOCOLS).await);
+        assert!(!Arc::ptr_eq(&before, &tls_config.get_inner()));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reload_certificate_keeps_current_config_on_failure() -> TestResult {
+        ensure_crypto_provider()?;
+
+        let tls_source = certificate_files()?;
+        let tls_config = tls_source.load(&ALPN_PROTOCOLS).await?;
+        let before = tls_config.get_inner();
+
+        if let TlsSource::Files { cert, .. } = &tls_source {
+            std::fs::write(cert, "not a certificate")?;
+        }
 
-        use crate::infrastructure::http::rate_limit::rate_limit_error_handler;
-
-        let rate_limit = RateLimitConfig::default();
-        let governor_conf = Arc::new(
-            GovernorConfigBuilder::default()
-                .key_extractor(SmartIpKeyExtractor)
-                .per_second(rate_limit.per_second)
-                .burst_size(rate_limit.burst_size)
-                .use_headers()
-                .error_handler(rate_limit_error_handler)
-                .finish()
-                .expect("failed to create governor config"),
+        assert!(!reload_certificate(&tls_config, &tls_source, &ALPN_PROTOCOLS).await);
+        assert!(Arc::ptr_eq(&before, &tls_config.get_inner()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ensure_crypto_provider_is_idempotent() -> TestResult {
+        ensure_crypto_provider()?;
+        ensure_crypto_provider()?;
+
+        assert!(CryptoProvider::get_default().is_some());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_https_server_from_pem_strings_binds() -> TestResult {
+        let shutdown = CancellationToken::new();
+
+        let server = HttpsServer::new(
+            SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0),
+            embedded_certificate(),
+            ALPN_PROTOCOLS.to_vec(),
+            test_state(None, None),
+            shutdown.clone(),
+            Duration::from_secs(1),
+        )
+        .await?;
+
+        let address = server.local_addr()?;
+        let running = tokio::spawn(server.run());
+
+        tokio::net::TcpStream::connect(address).await?;
+
+        shutdown.cancel();
+        running.await??;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_https_server_on_a_used_port_names_the_port() -> TestResult {
+        let taken = std::net::TcpListener::bind(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0))?;
+        let port = taken.local_addr()?.port();
+
+        let err = HttpsServer::new(
+            SocketAddr::new(Ipv4Addr::LOCALHOST.into(), port),
+            embedded_certificate(),
+            ALPN_PROTOCOLS.to_vec(),
+            test_state(None, None),
+            CancellationToken::new(),
+            Duration::from_secs(1),
+        )
+        .await
+        .expect_err("the port is already in use");
+
+        assert!(err.to_string().starts_with(&format!(
+            "failed to bind the HTTPS listener on port {port} "
+        )));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_alpn_protocols_are_advertised() -> TestResult {
+        ensure_crypto_provider()?;
+
+        let both = embedded_certificate().load(&ALPN_PROTOCOLS).await?;
+        let http1_only = embedded_certificate().load(&[AlpnProtocol::Http11]).await?;
+
+        assert_eq!(
+            both.get_inner().alpn_protocols,
+            vec![b"h2".to_vec(), b"http/1.1".to_vec()]
+        );
+        assert_eq!(
+            http1_only.get_inner().alpn_protocols,
+            vec![b"http/1.1".to_vec()]
         );
 
-        let governor_limiter = governor_conf.limiter().clone();
+        Ok(())
+    }
 
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(Duration::from_secs(60));
-            loop {
-                interval.tick().await;
-                tracing::info!("rate limiting storage size: {}", governor_limiter.len());
-                governor_limiter.retain_recent();
-            }
-        });
+    #[tokio::test]
+    async fn test_reload_certificate_keeps_alpn_protocols() -> TestResult {
+        ensure_crypto_provider()?;
 
-        let governor_layer = GovernorLayer {
-            config: governor_conf,
-        };
+        let tls_source = certificate_files()?;
+        let tls_config = tls_source.load(&[AlpnProtocol::H2]).await?;
 
-        router = router.layer(governor_layer);
+        assert!(reload_certificate(&tls_config, &tls_source, &[AlpnProtocol::H2]).await);
+        assert_eq!(tls_config.get_inner().alpn_protocols, vec![b"h2".to_vec()]);
+
+        Ok(())
     }
 
-    router
+    #[test]
+    fn test_pem_key_is_not_debug_printed() {
+        let debug = format!("{:?}", embedded_certificate());
+
+        assert!(debug.contains("BEGIN CERTIFICATE"));
+        assert!(!debug.contains("PRIVATE KEY"));
+    }
 }
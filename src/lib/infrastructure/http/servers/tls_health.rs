@@ -0,0 +1,122 @@
+//! TLS certificate files health check
+
+use std::path::{Path, PathBuf};
+
+use axum::async_trait;
+use tracing::warn;
+
+use crate::domain::health::{HealthCheck, HealthStatus};
+
+/// The least free space, in bytes, the certificate files' disk should have for renewed ones to
+/// be written to it
+const MIN_FREE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// A [`HealthCheck`] of the certificate and key files the HTTPS server reloads on SIGHUP.
+///
+/// Down if either can't be read, so the next reload would fail, degraded if the disk they're on
+/// is nearly full, so renewing them might, and up otherwise.
+#[derive(Clone, Debug)]
+pub struct TlsFilesHealthCheck {
+    cert: PathBuf,
+    key: PathBuf,
+}
+
+impl TlsFilesHealthCheck {
+    /// Create a new check of the `cert` and `key` files
+    pub fn new(cert: impl Into<PathBuf>, key: impl Into<PathBuf>) -> Self {
+        Self {
+            cert: cert.into(),
+            key: key.into(),
+        }
+    }
+}
+
+/// Whether `path` is a file which can be read
+async fn readable(path: &Path) -> bool {
+    match tokio::fs::File::open(path).await {
+        Ok(_) => true,
+        Err(err) => {
+            warn!("TLS file {} can't be read: {err}", path.display());
+            false
+        }
+    }
+}
+
+/// The bytes free to unprivileged users on the disk `path` is on, if they can be found
+#[cfg(unix)]
+fn free_bytes(path: &Path) -> Option<u64> {
+    let directory = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+
+    match rustix::fs::statvfs(directory) {
+        Ok(stats) => Some(stats.f_bavail.saturating_mul(stats.f_frsize)),
+        Err(err) => {
+            warn!("Can't find the free space for {}: {err}", path.display());
+            None
+        }
+    }
+}
+
+/// The free space can't be found off Unix, so it's assumed to be enough
+#[cfg(not(unix))]
+fn free_bytes(_path: &Path) -> Option<u64> {
+    None
+}
+
+#[async_trait]
+impl HealthCheck for TlsFilesHealthCheck {
+    fn name(&self) -> &str {
+        "tls_files"
+    }
+
+    async fn check(&self) -> HealthStatus {
+        if !readable(&self.cert).await || !readable(&self.key).await {
+            return HealthStatus::Down;
+        }
+
+        match free_bytes(&self.cert) {
+            Some(free) if free < MIN_FREE_BYTES => {
+                warn!(
+                    free_bytes = free,
+                    "The disk the TLS certificate is on is nearly full"
+                );
+                HealthStatus::Degraded
+            }
+            _ => HealthStatus::Up,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CERT: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/src/lib/infrastructure/http/servers/testdata/cert.pem"
+    );
+    const KEY: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/src/lib/infrastructure/http/servers/testdata/key.pem"
+    );
+
+    #[tokio::test]
+    async fn test_readable_files_are_up() {
+        assert_eq!(
+            TlsFilesHealthCheck::new(CERT, KEY).check().await,
+            HealthStatus::Up
+        );
+    }
+
+    #[tokio::test]
+    async fn test_missing_file_is_down() {
+        assert_eq!(
+            TlsFilesHealthCheck::new(CERT, "does/not/exist.pem")
+                .check()
+                .await,
+            HealthStatus::Down
+        );
+    }
+}
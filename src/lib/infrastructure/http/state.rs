@@ -7,19 +7,87 @@ use std::fmt;
 use chrono::{DateTime, Utc};
 
 use crate::domain::{
-    auth::users::UserService, communication::email_addresses::EmailAddressService,
+    auth::{
+        tokens::AuthConfig,
+        users::{
+            PasswordHashingConfig, PasswordPolicy, SignupConfig, SignupEmailPolicy, UserService,
+        },
+    },
+    base_url::BaseUrl,
+    communication::email_addresses::{EmailAddress, EmailAddressService, MailDomainResolver},
+    health::{DatabaseHealth, HealthChecks, HealthReportCache},
+    idempotency::IdempotencyStore,
+};
+
+use super::{
+    access_log::AccessLogConfig,
+    allowed_hosts::AllowedHostsConfig,
+    body_log::BodyLogConfig,
+    client_ip::TrustedProxiesConfig,
+    debug::DebugConfig,
+    errors::{ApiError, ValidationError},
+    header_limits::HeaderLimitsConfig,
+    rate_limit::RateLimitConfig,
+    security_headers::SecurityHeadersConfig,
+    transport_security::TransportSecurityConfig,
 };
 
 /// Application configuration
 #[derive(Clone, Debug)]
 pub struct AppConfig {
     /// The base URL of the application
-    pub base_url: String,
+    pub base_url: BaseUrl,
+
+    /// The hosts links can point back at instead of the base URL
+    pub allowed_hosts: AllowedHostsConfig,
+
+    /// The policy new passwords are validated against
+    pub password_policy: PasswordPolicy,
+
+    /// The parameters new passwords are hashed with
+    pub password_hashing: PasswordHashingConfig,
+
+    /// The access token configuration
+    pub auth: AuthConfig,
+
+    /// The signup requirements
+    pub signup: SignupConfig,
+
+    /// The email domains new users may sign up with
+    pub signup_email_policy: SignupEmailPolicy,
+
+    /// The request header limits
+    pub header_limits: HeaderLimitsConfig,
+
+    /// The access log configuration
+    pub access_log: AccessLogConfig,
+
+    /// The request and response body logging configuration
+    pub body_log: BodyLogConfig,
+
+    /// The rate limiting configuration
+    pub rate_limit: RateLimitConfig,
+
+    /// The proxies whose forwarded client addresses are trusted
+    pub trusted_proxies: TrustedProxiesConfig,
+
+    /// The HTTPS redirect and HSTS configuration
+    pub transport_security: TransportSecurityConfig,
+
+    /// The security headers configuration
+    pub security_headers: SecurityHeadersConfig,
+
+    /// The debugging configuration
+    pub debug: DebugConfig,
+
+    /// Whether routes only match exactly, rather than ignoring a trailing slash
+    pub strict_trailing_slash: bool,
 }
 
 /// Global application state
 #[derive(Clone)]
-pub struct AppState<U: UserService, E: EmailAddressService> {
+pub struct AppState<U: UserService, E: EmailAddressService, I: IdempotencyStore, H: DatabaseHealth>
+{
     /// The time the server started
     pub start_time: DateTime<Utc>,
 
@@ -31,29 +99,76 @@ pub struct AppState<U: UserService, E: EmailAddressService> {
 
     /// Email address service
     pub email_addresses: Arc<E>,
+
+    /// Idempotency key store
+    pub idempotency: Arc<I>,
+
+    /// Database health checks
+    pub health: Arc<H>,
+
+    /// The health checks of dependencies besides the database, which is always checked
+    pub health_checks: HealthChecks,
+
+    /// Health checks only reported to admins, such as ones probing other services, which
+    /// anonymous clients shouldn't be able to trigger
+    pub admin_health_checks: HealthChecks,
+
+    /// The public health report, reused briefly between requests
+    pub health_report: HealthReportCache,
+
+    /// The resolver email addresses' domains are checked with before users sign up with them or
+    /// change to them, if deliverability checks are on
+    pub mail_domains: Option<Arc<dyn MailDomainResolver>>,
 }
 
 /// Implementation of the application state
-impl<U, E> AppState<U, E>
+impl<U, E, I, H> AppState<U, E, I, H>
 where
     U: UserService,
     E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
 {
-    /// Create a new application state
-    pub fn new(config: AppConfig, users: U, email_addresses: E) -> Self {
+    /// Create a new application state, without any health checks besides the database
+    pub fn new(config: AppConfig, users: U, email_addresses: E, idempotency: I, health: H) -> Self {
         Self {
             config,
             start_time: Utc::now(),
             users: Arc::new(users),
             email_addresses: Arc::new(email_addresses),
+            idempotency: Arc::new(idempotency),
+            health: Arc::new(health),
+            health_checks: HealthChecks::new(),
+            admin_health_checks: HealthChecks::new(),
+            health_report: HealthReportCache::default(),
+            mail_domains: None,
         }
     }
+
+    /// Checks that `email`'s domain can receive email, if deliverability checks are on,
+    /// reporting it as an invalid `field` if it can't
+    pub async fn check_deliverable(
+        &self,
+        field: &str,
+        email: &EmailAddress,
+    ) -> Result<(), ApiError> {
+        let Some(resolver) = &self.mail_domains else {
+            return Ok(());
+        };
+
+        email
+            .validate_deliverable(resolver.as_ref())
+            .await
+            .map_err(|err| ApiError::new_validation(vec![ValidationError::new(field, &err)]))
+    }
 }
 
-impl<U, E> fmt::Debug for AppState<U, E>
+impl<U, E, I, H> fmt::Debug for AppState<U, E, I, H>
 where
     U: UserService,
     E: EmailAddressService,
+    I: IdempotencyStore,
+    H: DatabaseHealth,
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("AppState")
@@ -61,23 +176,44 @@ where
             .field("config", &self.config)
             .field("users", &"UserService")
             .field("email_addresses", &"EmailAddressService")
+            .field("idempotency", &"IdempotencyStore")
+            .field("health", &"DatabaseHealth")
+            .field("health_checks", &self.health_checks)
+            .field("admin_health_checks", &self.admin_health_checks)
+            .field(
+                "mail_domains",
+                &self.mail_domains.as_ref().map(|_| "MailDomainResolver"),
+            )
             .finish()
     }
 }
 
+/// Test helpers for the application state
 #[cfg(test)]
 pub mod tests {
-    use crate::domain::{
-        auth::users::tests::MockUserService,
-        communication::email_addresses::tests::MockEmailAddressService,
+    use uuid::Uuid;
+
+    use crate::{
+        domain::{
+            auth::{tokens::issue_access_token, users::tests::MockUserService},
+            base_url::tests::example_base_url,
+            communication::email_addresses::tests::MockEmailAddressService,
+            health::tests::MockDatabaseHealth,
+            idempotency::tests::MockIdempotencyStore,
+        },
+        infrastructure::http::{
+            extractors::current_user::SESSION_COOKIE_NAME, rate_limit::RateLimitMode,
+        },
     };
 
     use super::*;
 
+    /// Create an application state backed by mock services
     pub fn test_state(
         users: Option<MockUserService>,
         email_addresses: Option<MockEmailAddressService>,
-    ) -> AppState<MockUserService, MockEmailAddressService> {
+    ) -> AppState<MockUserService, MockEmailAddressService, MockIdempotencyStore, MockDatabaseHealth>
+    {
         let users = users
             .map(Arc::new)
             .unwrap_or_else(|| Arc::new(MockUserService::new()));
@@ -87,7 +223,29 @@ pub mod tests {
             .unwrap_or_else(|| Arc::new(MockEmailAddressService::new()));
 
         let config = AppConfig {
-            base_url: "https://example.com".to_string(),
+            base_url: example_base_url(),
+            allowed_hosts: AllowedHostsConfig::default(),
+            password_policy: PasswordPolicy::default(),
+            password_hashing: PasswordHashingConfig::default(),
+            auth: AuthConfig {
+                signing_key: "test-signing-key".to_string(),
+                issuer: "test-issuer".to_string(),
+                access_token_ttl_seconds: 900,
+            },
+            signup: SignupConfig::default(),
+            signup_email_policy: SignupEmailPolicy::default(),
+            header_limits: HeaderLimitsConfig::default(),
+            access_log: AccessLogConfig::default(),
+            body_log: BodyLogConfig::default(),
+            rate_limit: RateLimitConfig {
+                mode: RateLimitMode::Off,
+                ..RateLimitConfig::default()
+            },
+            trusted_proxies: TrustedProxiesConfig::default(),
+            transport_security: TransportSecurityConfig::default(),
+            security_headers: SecurityHeadersConfig::default(),
+            debug: DebugConfig::default(),
+            strict_trailing_slash: false,
         };
 
         AppState {
@@ -95,6 +253,44 @@ pub mod tests {
             config,
             users,
             email_addresses,
+            idempotency: Arc::new(MockIdempotencyStore::new()),
+            health: Arc::new(MockDatabaseHealth::new()),
+            health_checks: HealthChecks::new(),
+            admin_health_checks: HealthChecks::new(),
+            health_report: HealthReportCache::default(),
+            mail_domains: None,
         }
     }
+
+    /// Create an `Authorization` header value for the given user, signed with the state's config
+    pub fn test_bearer_token<
+        U: UserService,
+        E: EmailAddressService,
+        I: IdempotencyStore,
+        H: DatabaseHealth,
+    >(
+        state: &AppState<U, E, I, H>,
+        user_id: &Uuid,
+    ) -> String {
+        let access_token =
+            issue_access_token(&state.config.auth, user_id).expect("failed to issue access token");
+
+        format!("Bearer {}", access_token.token)
+    }
+
+    /// Returns a `Cookie` header value holding a session for the given user
+    pub fn test_session_cookie<
+        U: UserService,
+        E: EmailAddressService,
+        I: IdempotencyStore,
+        H: DatabaseHealth,
+    >(
+        state: &AppState<U, E, I, H>,
+        user_id: &Uuid,
+    ) -> String {
+        let access_token =
+            issue_access_token(&state.config.auth, user_id).expect("failed to issue access token");
+
+        format!("{SESSION_COOKIE_NAME}={}", access_token.token)
+    }
 }
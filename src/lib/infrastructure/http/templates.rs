@@ -1,2 +1,64 @@
 pub mod auth;
 pub mod errors;
+
+/// Snapshots of each page's full rendered HTML, to catch accidental template changes.
+///
+/// When a template is changed on purpose, run `cargo insta test --review` (or `cargo test`
+/// followed by `cargo insta review`) and accept the new snapshots, which are checked in under
+/// `snapshots/`.
+#[cfg(test)]
+mod tests {
+    use askama::Template;
+    use insta::assert_snapshot;
+    use testresult::TestResult;
+
+    use crate::domain::i18n::Locale;
+
+    use super::{
+        auth::email_confirmed::EmailConfirmedTemplate,
+        errors::{
+            internal_server_error::InternalServerErrorTemplate, not_found::NotFoundErrorTemplate,
+            unprocessable_entity::UnprocessableEntityErrorTemplate,
+        },
+    };
+
+    #[test]
+    fn test_email_confirmed_snapshot() -> TestResult {
+        assert_snapshot!(EmailConfirmedTemplate {
+            locale: Locale::English
+        }
+        .render()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_not_found_snapshot() -> TestResult {
+        assert_snapshot!(NotFoundErrorTemplate {
+            locale: Locale::English
+        }
+        .render()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_internal_server_error_snapshot() -> TestResult {
+        assert_snapshot!(InternalServerErrorTemplate {
+            locale: Locale::English
+        }
+        .render()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unprocessable_entity_snapshot() -> TestResult {
+        assert_snapshot!(UnprocessableEntityErrorTemplate {
+            locale: Locale::English
+        }
+        .render()?);
+
+        Ok(())
+    }
+}
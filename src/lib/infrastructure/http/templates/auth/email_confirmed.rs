@@ -1,5 +1,9 @@
 use askama::Template;
 
+use crate::domain::i18n::Locale;
+
 #[derive(Debug, Template)]
 #[template(path = "auth/email_confirmed.html")]
-pub struct EmailConfirmedTemplate;
+pub struct EmailConfirmedTemplate {
+    pub locale: Locale,
+}
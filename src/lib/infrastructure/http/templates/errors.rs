@@ -1,3 +1,4 @@
+pub mod email_address_in_use;
 pub mod internal_server_error;
 pub mod not_found;
 pub mod unprocessable_entity;
@@ -0,0 +1,9 @@
+use askama::Template;
+
+use crate::domain::i18n::Locale;
+
+#[derive(Debug, Template)]
+#[template(path = "errors/email_address_in_use.html")]
+pub struct EmailAddressInUseErrorTemplate {
+    pub locale: Locale,
+}
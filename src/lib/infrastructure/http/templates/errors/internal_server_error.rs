@@ -1,5 +1,9 @@
 use askama::Template;
 
+use crate::domain::i18n::Locale;
+
 #[derive(Debug, Template)]
 #[template(path = "errors/internal_server_error.html")]
-pub struct InternalServerErrorTemplate;
+pub struct InternalServerErrorTemplate {
+    pub locale: Locale,
+}
@@ -1,5 +1,9 @@
 use askama::Template;
 
+use crate::domain::i18n::Locale;
+
 #[derive(Debug, Template)]
 #[template(path = "errors/not_found.html")]
-pub struct NotFoundErrorTemplate;
+pub struct NotFoundErrorTemplate {
+    pub locale: Locale,
+}
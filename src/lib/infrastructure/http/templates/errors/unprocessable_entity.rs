@@ -1,5 +1,9 @@
 use askama::Template;
 
+use crate::domain::i18n::Locale;
+
 #[derive(Debug, Template)]
 #[template(path = "errors/unprocessable.html")]
-pub struct UnprocessableEntityErrorTemplate;
+pub struct UnprocessableEntityErrorTemplate {
+    pub locale: Locale,
+}
@@ -0,0 +1,137 @@
+//! Transport security
+//!
+//! Plain HTTP requests are redirected to HTTPS, and HTTPS responses carry a
+//! `Strict-Transport-Security` header so browsers skip the redirect next time.
+
+use axum::{
+    extract::{Request, State},
+    http::{header::STRICT_TRANSPORT_SECURITY, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use clap::{ArgAction, Parser};
+
+/// Transport security configuration
+#[derive(Clone, Debug, PartialEq, Eq, Parser)]
+pub struct TransportSecurityConfig {
+    /// Redirect HTTP to HTTPS with a permanent 308 rather than a temporary 307, so browsers and
+    /// search engines remember it
+    #[arg(
+        long,
+        env = "PERMANENT_HTTPS_REDIRECT",
+        default_value_t = false,
+        action = ArgAction::Set
+    )]
+    pub permanent_redirect: bool,
+
+    /// How many seconds browsers should only use HTTPS for, or 0 to have them forget
+    #[arg(long, env = "HSTS_MAX_AGE", default_value = "31536000")]
+    pub hsts_max_age: u64,
+
+    /// Whether HSTS also applies to subdomains
+    #[arg(
+        long,
+        env = "HSTS_INCLUDE_SUBDOMAINS",
+        default_value_t = false,
+        action = ArgAction::Set
+    )]
+    pub hsts_include_subdomains: bool,
+
+    /// Whether the domain may be included in browsers' HSTS preload lists
+    #[arg(
+        long,
+        env = "HSTS_PRELOAD",
+        default_value_t = false,
+        action = ArgAction::Set
+    )]
+    pub hsts_preload: bool,
+}
+
+impl Default for TransportSecurityConfig {
+    fn default() -> Self {
+        Self {
+            permanent_redirect: false,
+            hsts_max_age: 31536000,
+            hsts_include_subdomains: false,
+            hsts_preload: false,
+        }
+    }
+}
+
+impl TransportSecurityConfig {
+    /// The `Strict-Transport-Security` header value
+    pub fn hsts_header(&self) -> HeaderValue {
+        let mut value = format!("max-age={}", self.hsts_max_age);
+
+        if self.hsts_include_subdomains {
+            value.push_str("; includeSubDomains");
+        }
+
+        if self.hsts_preload {
+            value.push_str("; preload");
+        }
+
+        HeaderValue::from_str(&value).expect("HSTS header is always valid")
+    }
+}
+
+/// Adds the `Strict-Transport-Security` header to every response
+pub async fn hsts(State(header): State<HeaderValue>, request: Request, next: Next) -> Response {
+    let mut response = next.run(request).await;
+
+    response
+        .headers_mut()
+        .insert(STRICT_TRANSPORT_SECURITY, header);
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use axum_test::TestServer;
+    use testresult::TestResult;
+
+    use crate::infrastructure::http::{servers::https::router, state::tests::test_state};
+
+    use super::*;
+
+    #[test]
+    fn test_hsts_header_defaults_to_a_year() {
+        assert_eq!(
+            TransportSecurityConfig::default().hsts_header(),
+            "max-age=31536000"
+        );
+    }
+
+    #[test]
+    fn test_hsts_header_directives() {
+        let config = TransportSecurityConfig {
+            hsts_max_age: 63072000,
+            hsts_include_subdomains: true,
+            hsts_preload: true,
+            ..TransportSecurityConfig::default()
+        };
+
+        assert_eq!(
+            config.hsts_header(),
+            "max-age=63072000; includeSubDomains; preload"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hsts_header_on_https_responses() -> TestResult {
+        let mut state = test_state(None, None);
+        state.config.transport_security.hsts_include_subdomains = true;
+
+        let server = TestServer::new(router(state))?;
+
+        let response = server.get("/api/v1/uptime").await;
+
+        assert_eq!(
+            response.header(STRICT_TRANSPORT_SECURITY),
+            "max-age=31536000; includeSubDomains"
+        );
+
+        Ok(())
+    }
+}
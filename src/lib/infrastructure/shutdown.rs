@@ -0,0 +1,127 @@
+//! Shutdown coordination
+//!
+//! The servers and the work they start share a [`CancellationToken`], and are drained in order
+//! once it is cancelled: the servers stop accepting connections and finish their in-flight
+//! requests, then queued background work is flushed, and only then is the database pool closed,
+//! so nothing still running loses the connections it needs.
+
+use std::future::Future;
+
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+
+/// Coordinates an orderly shutdown
+#[derive(Clone, Debug, Default)]
+pub struct ShutdownCoordinator {
+    token: CancellationToken,
+}
+
+impl ShutdownCoordinator {
+    /// Create a new shutdown coordinator
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a token which is cancelled when shutdown starts, for servers to stop accepting
+    /// connections on
+    pub fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    /// Starts shutting down, and waits for each stage to drain before starting the next.
+    ///
+    /// # Arguments
+    /// * `servers` - Completes once every server has finished its in-flight requests.
+    /// * `queue` - Flushes queued background work, abandoning what is still running after its
+    ///   grace period so a stuck task can't hold up the rest of shutdown.
+    /// * `pool` - Closes the database pool.
+    pub async fn shutdown<S, Q, P>(&self, servers: S, queue: Q, pool: P)
+    where
+        S: Future<Output = ()>,
+        Q: Future<Output = ()>,
+        P: Future<Output = ()>,
+    {
+        info!("shutting down: no longer accepting connections");
+        self.token.cancel();
+
+        servers.await;
+        info!("shutting down: in-flight requests finished");
+
+        queue.await;
+        info!("shutting down: background work flushed");
+
+        pool.await;
+        info!("shutting down: database pool closed");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use tokio_util::task::TaskTracker;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_pool_is_closed_only_after_queue_is_flushed() {
+        let coordinator = ShutdownCoordinator::new();
+        let events = Arc::new(Mutex::new(Vec::new()));
+
+        let server = {
+            let token = coordinator.token();
+            let events = events.clone();
+
+            tokio::spawn(async move {
+                token.cancelled().await;
+                events.lock().unwrap().push("server stopped");
+            })
+        };
+
+        let queue = TaskTracker::new();
+
+        queue.spawn({
+            let events = events.clone();
+
+            async move {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                events.lock().unwrap().push("queue flushed");
+            }
+        });
+
+        coordinator
+            .shutdown(
+                async {
+                    server.await.unwrap();
+                },
+                async {
+                    queue.close();
+                    queue.wait().await;
+                },
+                async {
+                    events.lock().unwrap().push("pool closed");
+                },
+            )
+            .await;
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec!["server stopped", "queue flushed", "pool closed"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_token_is_cancelled_on_shutdown() {
+        let coordinator = ShutdownCoordinator::new();
+        let token = coordinator.token();
+
+        assert!(!token.is_cancelled());
+
+        coordinator.shutdown(async {}, async {}, async {}).await;
+
+        assert!(token.is_cancelled());
+    }
+}
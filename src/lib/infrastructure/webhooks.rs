@@ -0,0 +1,3 @@
+//! Webhooks module
+
+pub mod http;
@@ -0,0 +1,255 @@
+//! HTTP webhook notifier implementation
+
+use std::{fmt, time::Duration};
+
+use anyhow::anyhow;
+use axum::async_trait;
+use clap::Parser;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::domain::communication::webhooks::{UserEvent, WebhookError, WebhookNotifier};
+
+/// The header carrying the payload's signature
+pub const SIGNATURE_HEADER: &str = "X-Signature";
+
+/// Webhook configuration
+#[derive(Clone, PartialEq, Eq, Parser)]
+pub struct WebhookConfig {
+    /// The URL user lifecycle events are POSTed to. Webhooks are disabled if unset
+    #[clap(long = "webhook-url", env = "WEBHOOK_URL", requires = "secret")]
+    pub url: Option<String>,
+
+    /// The secret used to sign webhook payloads (HMAC-SHA256)
+    #[clap(long = "webhook-secret", env = "WEBHOOK_SECRET")]
+    pub secret: Option<String>,
+
+    /// How many seconds a delivery is given to complete, including connecting
+    #[clap(
+        long = "webhook-timeout-seconds",
+        env = "WEBHOOK_TIMEOUT_SECONDS",
+        default_value = "10"
+    )]
+    pub timeout_seconds: u64,
+
+    /// How many seconds a delivery is given to connect to the endpoint
+    #[clap(
+        long = "webhook-connect-timeout-seconds",
+        env = "WEBHOOK_CONNECT_TIMEOUT_SECONDS",
+        default_value = "5"
+    )]
+    pub connect_timeout_seconds: u64,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            secret: None,
+            timeout_seconds: 10,
+            connect_timeout_seconds: 5,
+        }
+    }
+}
+
+impl fmt::Debug for WebhookConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebhookConfig")
+            .field("url", &self.url)
+            .field("secret", &self.secret.as_ref().map(|_| "********"))
+            .field("timeout_seconds", &self.timeout_seconds)
+            .field("connect_timeout_seconds", &self.connect_timeout_seconds)
+            .finish()
+    }
+}
+
+/// HTTP webhook notifier
+///
+/// POSTs each event as JSON to the configured URL, with an `X-Signature: sha256=<hex>` header
+/// holding the HMAC-SHA256 of the body, keyed with the configured secret.
+#[derive(Debug, Clone)]
+pub struct HttpWebhookNotifier {
+    config: WebhookConfig,
+    client: reqwest::Client,
+}
+
+impl HttpWebhookNotifier {
+    /// Create a new HTTP webhook notifier
+    pub fn new(config: WebhookConfig) -> Self {
+        // A slow endpoint would otherwise hold its delivery's background task, and shutdown, open
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_seconds))
+            .connect_timeout(Duration::from_secs(config.connect_timeout_seconds))
+            .build()
+            .expect("the webhook client's TLS backend can be initialized");
+
+        Self { config, client }
+    }
+}
+
+/// Signs a payload, returning the value of the [`SIGNATURE_HEADER`]
+pub fn sign(secret: &str, payload: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(payload);
+
+    let signature: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+
+    format!("sha256={signature}")
+}
+
+#[async_trait]
+impl WebhookNotifier for HttpWebhookNotifier {
+    async fn notify(&self, event: UserEvent) -> Result<(), WebhookError> {
+        let Some(url) = &self.config.url else {
+            return Ok(());
+        };
+
+        let payload = serde_json::to_vec(&event).map_err(|err| anyhow!(err))?;
+        let signature = sign(self.config.secret.as_deref().unwrap_or_default(), &payload);
+
+        let response = self
+            .client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, signature)
+            .body(payload)
+            .send()
+            .await
+            .map_err(|err| anyhow!(err))?;
+
+        if !response.status().is_success() {
+            return Err(WebhookError::UnexpectedStatus(response.status().as_u16()));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::Bytes, http::HeaderMap, routing::post, Router};
+    use chrono::Utc;
+    use serde_json::Value;
+    use testresult::TestResult;
+    use tokio::{net::TcpListener, sync::mpsc};
+    use uuid::Uuid;
+
+    use crate::domain::communication::email_addresses::EmailAddress;
+
+    use super::*;
+
+    /// Starts a server which records the headers and body of each request it receives
+    async fn mock_endpoint() -> TestResult<(String, mpsc::UnboundedReceiver<(HeaderMap, Bytes)>)> {
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        let app = Router::new().route(
+            "/webhooks",
+            post(move |headers: HeaderMap, body: Bytes| async move {
+                let _ = tx.send((headers, body));
+            }),
+        );
+
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}/webhooks", listener.local_addr()?);
+
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        Ok((url, rx))
+    }
+
+    #[test]
+    fn test_sign() {
+        assert_eq!(
+            sign("secret", b"payload"),
+            "sha256=b82fcb791acec57859b989b430a826488ce2e479fdf92326bd0a2e8375a42ba4"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_notify_posts_signed_payload() -> TestResult {
+        let (url, mut requests) = mock_endpoint().await?;
+
+        let notifier = HttpWebhookNotifier::new(WebhookConfig {
+            url: Some(url),
+            secret: Some("webhook-secret".to_string()),
+            ..WebhookConfig::default()
+        });
+
+        let user_id = Uuid::now_v7();
+
+        notifier
+            .notify(UserEvent::EmailConfirmed {
+                user_id,
+                email: EmailAddress::new("email@example.com")?,
+                occurred_at: Utc::now(),
+            })
+            .await?;
+
+        let (headers, body) = requests.recv().await.ok_or("no request received")?;
+        let json: Value = serde_json::from_slice(&body)?;
+
+        assert_eq!(headers["content-type"], "application/json");
+        assert_eq!(
+            headers["x-signature"],
+            sign("webhook-secret", &body).as_str()
+        );
+        assert_eq!(json["event"], "email_confirmed");
+        assert_eq!(json["user_id"], user_id.to_string());
+        assert_eq!(json["email"], "email@example.com");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_notify_times_out_on_slow_endpoint() -> TestResult {
+        let app = Router::new().route(
+            "/webhooks",
+            post(|| tokio::time::sleep(Duration::from_secs(60))),
+        );
+
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}/webhooks", listener.local_addr()?);
+
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let notifier = HttpWebhookNotifier::new(WebhookConfig {
+            url: Some(url),
+            secret: Some("webhook-secret".to_string()),
+            timeout_seconds: 1,
+            ..WebhookConfig::default()
+        });
+
+        let result = notifier
+            .notify(UserEvent::UserCreated {
+                user_id: Uuid::now_v7(),
+                email: EmailAddress::new("email@example.com")?,
+                occurred_at: Utc::now(),
+            })
+            .await;
+
+        assert!(result.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_notify_without_url_does_nothing() -> TestResult {
+        let notifier = HttpWebhookNotifier::new(WebhookConfig::default());
+
+        notifier
+            .notify(UserEvent::UserCreated {
+                user_id: Uuid::now_v7(),
+                email: EmailAddress::new("email@example.com")?,
+                occurred_at: Utc::now(),
+            })
+            .await?;
+
+        Ok(())
+    }
+}